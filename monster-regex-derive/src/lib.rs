@@ -0,0 +1,247 @@
+//! The `FromRegex` derive macro for [`monster-regex`](https://docs.rs/monster-regex),
+//! re-exported from there under its `derive` feature.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Span, TokenStream, TokenTree};
+
+/// Gives a struct with named fields a `parse(text: &str) -> Option<Self>`
+/// constructor that fills each field from the named capture group of the same name.
+///
+/// The pattern comes from a `#[regex(pattern = "...")]` attribute, which can also
+/// set flags as in a substitution command, like `flags = "i"`. The pattern is
+/// compiled on first use and panics then if it is invalid.
+///
+/// `parse` searches the text like `Regex::captures`, so the pattern should be
+/// anchored to match the whole text. Each group's text is converted with
+/// [`FromStr`](std::str::FromStr), and `parse` returns `None` if there is no match,
+/// a conversion fails, or a group didn't take part in the match. `Option` fields
+/// are `None` instead when their group didn't take part.
+///
+/// ```
+/// use monster_regex_derive::FromRegex;
+///
+/// #[derive(FromRegex)]
+/// #[regex(pattern = r"^(?<level>\a+) (?<code>\d+)(?: (?<msg>.*))?$")]
+/// struct LogLine {
+///     level: String,
+///     code: u32,
+///     msg: Option<String>,
+/// }
+///
+/// let line = LogLine::parse("WARN 42 disk almost full").unwrap();
+/// assert_eq!((line.level.as_str(), line.code), ("WARN", 42));
+/// assert_eq!(line.msg.as_deref(), Some("disk almost full"));
+/// assert!(LogLine::parse("WARN 42").unwrap().msg.is_none());
+/// assert!(LogLine::parse("WARN x").is_none());
+/// ```
+#[proc_macro_derive(FromRegex, attributes(regex))]
+pub fn derive_from_regex(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(output) => output,
+        Err((message, span)) => compile_error(&message, span),
+    }
+}
+
+type Error = (String, Span);
+
+struct Field {
+    name: Ident,
+    optional: bool,
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, Error> {
+    let mut tokens = input.into_iter().peekable();
+    let mut pattern = None;
+    let mut flags = None;
+
+    // Attributes, visibility and `struct`, up to the name
+    let name = loop {
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => {
+                if let Some(TokenTree::Group(group)) = tokens.next() {
+                    parse_attribute(&group, &mut pattern, &mut flags)?;
+                }
+            }
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => match tokens.next() {
+                Some(TokenTree::Ident(name)) => break name,
+                _ => return Err(("expected a struct name".to_string(), ident.span())),
+            },
+            Some(TokenTree::Ident(ident))
+                if matches!(ident.to_string().as_str(), "enum" | "union") =>
+            {
+                return Err((
+                    "FromRegex can only be derived for structs".to_string(),
+                    ident.span(),
+                ));
+            }
+            Some(_) => {}
+            None => return Err(("expected a struct".to_string(), Span::call_site())),
+        }
+    };
+
+    let fields = match tokens.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            parse_fields(group.stream())?
+        }
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '<' => {
+            return Err((
+                "FromRegex can't be derived for generic structs".to_string(),
+                punct.span(),
+            ));
+        }
+        _ => {
+            return Err((
+                "FromRegex can only be derived for structs with named fields".to_string(),
+                name.span(),
+            ));
+        }
+    };
+    let pattern = pattern.ok_or_else(|| {
+        (
+            "missing #[regex(pattern = \"...\")] attribute".to_string(),
+            name.span(),
+        )
+    })?;
+    let flags = flags.unwrap_or_else(|| Literal::string(""));
+
+    let mut initializers = String::new();
+    for field in &fields {
+        let name = field.name.to_string();
+        let group = Literal::string(name.strip_prefix("r#").unwrap_or(&name));
+        let value = if field.optional {
+            format!(
+                "match captures.as_str_named(text, {group}) {{ \
+                    ::core::option::Option::Some(value) => \
+                        ::core::option::Option::Some(value.parse().ok()?), \
+                    ::core::option::Option::None => ::core::option::Option::None, \
+                }}"
+            )
+        } else {
+            format!("captures.as_str_named(text, {group})?.parse().ok()?")
+        };
+        initializers.push_str(&format!("{}: {},", field.name, value));
+    }
+
+    let invalid = Literal::string(&format!("invalid #[regex] attribute on {}", name));
+    let output = format!(
+        "impl {name} {{
+            /// Parses the text with the struct's pattern, filling each field from the
+            /// named capture group of the same name.
+            pub fn parse(text: &str) -> ::core::option::Option<Self> {{
+                static REGEX: ::std::sync::OnceLock<::monster_regex::Regex> =
+                    ::std::sync::OnceLock::new();
+                let regex = REGEX.get_or_init(|| {{
+                    let flags: ::monster_regex::Flags = {flags}.parse().expect({invalid});
+                    ::monster_regex::Regex::new({pattern}, flags).expect({invalid})
+                }});
+                let captures = regex.captures(text)?;
+                ::core::option::Option::Some({name} {{ {initializers} }})
+            }}
+        }}"
+    );
+    Ok(output.parse().expect("generated code should parse"))
+}
+
+// Reads `pattern` and `flags` from a `regex(...)` attribute, ignoring other attributes
+fn parse_attribute(
+    group: &Group,
+    pattern: &mut Option<Literal>,
+    flags: &mut Option<Literal>,
+) -> Result<(), Error> {
+    let mut tokens = group.stream().into_iter();
+    match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "regex" => {}
+        _ => return Ok(()),
+    }
+    let arguments = match tokens.next() {
+        Some(TokenTree::Group(arguments)) if arguments.delimiter() == Delimiter::Parenthesis => {
+            arguments
+        }
+        _ => {
+            return Err((
+                "expected #[regex(pattern = \"...\")]".to_string(),
+                group.span(),
+            ));
+        }
+    };
+
+    let mut tokens = arguments.stream().into_iter();
+    while let Some(token) = tokens.next() {
+        let key = match token {
+            TokenTree::Ident(key) => key,
+            TokenTree::Punct(punct) if punct.as_char() == ',' => continue,
+            other => return Err(("expected `pattern` or `flags`".to_string(), other.span())),
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+            _ => return Err((format!("expected `=` after `{}`", key), key.span())),
+        }
+        let value = match tokens.next() {
+            Some(TokenTree::Literal(value)) if value.to_string().contains('"') => value,
+            _ => return Err((format!("expected a string for `{}`", key), key.span())),
+        };
+        match key.to_string().as_str() {
+            "pattern" => *pattern = Some(value),
+            "flags" => *flags = Some(value),
+            _ => return Err((format!("unknown argument `{}`", key), key.span())),
+        }
+    }
+    Ok(())
+}
+
+fn parse_fields(stream: TokenStream) -> Result<Vec<Field>, Error> {
+    let mut fields = vec![];
+    let mut tokens = stream.into_iter().peekable();
+    while tokens.peek().is_some() {
+        // Attributes and visibility come before the name
+        let name = loop {
+            match tokens.next() {
+                Some(TokenTree::Ident(ident)) if ident.to_string() == "pub" => {
+                    tokens.next_if(|token| matches!(token, TokenTree::Group(_)));
+                }
+                Some(TokenTree::Ident(ident)) => break ident,
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => {
+                    tokens.next();
+                }
+                Some(other) => return Err(("expected a field name".to_string(), other.span())),
+                None => return Ok(fields),
+            }
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ':' => {}
+            _ => return Err(("expected `:` after the field name".to_string(), name.span())),
+        }
+
+        // The type runs to the next comma outside angle brackets
+        let mut ty = vec![];
+        let mut depth = 0usize;
+        for token in tokens.by_ref() {
+            if let TokenTree::Punct(punct) = &token {
+                match punct.as_char() {
+                    ',' if depth == 0 => break,
+                    '<' => depth += 1,
+                    '>' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            ty.push(token);
+        }
+        let optional = matches!(
+            ty.as_slice(),
+            [TokenTree::Ident(ident), TokenTree::Punct(punct), ..]
+                if ident.to_string() == "Option" && punct.as_char() == '<'
+        );
+        fields.push(Field { name, optional });
+    }
+    Ok(fields)
+}
+
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    let mut output: TokenStream = "::core::compile_error!".parse().unwrap();
+    let mut arguments = Group::new(Delimiter::Parenthesis, TokenTree::Literal(message).into());
+    arguments.set_span(span);
+    output.extend([TokenTree::Group(arguments)]);
+    output.extend("; ".parse::<TokenStream>());
+    output
+}
@@ -0,0 +1,45 @@
+use monster_regex_derive::FromRegex;
+
+#[derive(Debug, PartialEq, FromRegex)]
+#[regex(pattern = r"^(?<key>\w+)\s*=\s*(?<value>-?\d+)$")]
+struct Setting {
+    key: String,
+    value: i64,
+}
+
+/// Doc comments and other attributes are left alone.
+#[derive(Debug, FromRegex)]
+#[regex(pattern = "^(?<name>[a-z]+)(?:@(?<port>\\d+))?$", flags = "i")]
+pub(crate) struct Host {
+    pub name: String,
+    #[allow(dead_code)]
+    pub(crate) port: Option<u16>,
+    r#type: Option<String>,
+}
+
+#[test]
+fn test_parse_fields() {
+    assert_eq!(
+        Setting::parse("width = -12"),
+        Some(Setting {
+            key: "width".to_string(),
+            value: -12,
+        })
+    );
+    // No match, and a group that doesn't convert
+    assert_eq!(Setting::parse("width"), None);
+    assert_eq!(Setting::parse("width = 99999999999999999999"), None);
+}
+
+#[test]
+fn test_optional_fields_and_flags() {
+    let host = Host::parse("LocalHost@8080").unwrap();
+    assert_eq!(host.name, "LocalHost");
+    assert_eq!(host.port, Some(8080));
+    // A field without a group is always `None`
+    assert_eq!(host.r#type, None);
+
+    let host = Host::parse("example").unwrap();
+    assert_eq!(host.port, None);
+    assert!(Host::parse("example@http").is_none());
+}
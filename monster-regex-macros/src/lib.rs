@@ -0,0 +1,139 @@
+//! Compile-time checked patterns for [`monster-regex`](https://docs.rs/monster-regex).
+//!
+//! This crate depends on `monster-regex` to check patterns as they're compiled, so
+//! it can't be re-exported from there; depend on both crates.
+
+use proc_macro::{Delimiter, Group, Literal, Span, TokenStream, TokenTree};
+
+use monster_regex::{Regex, parse_rift_format};
+
+/// Checks a pattern in rift format (`pattern/flags`) while compiling, and expands
+/// to a `&'static Regex` compiled on first use.
+///
+/// A pattern that doesn't parse or compile is a compile error, and each use of
+/// the macro compiles its pattern only once, however often the code runs.
+///
+/// ```
+/// use monster_regex_macros::rift;
+///
+/// fn is_version(text: &str) -> bool {
+///     rift!(r"^\d+\.\d+$/").is_match(text)
+/// }
+///
+/// assert!(is_version("1.10"));
+/// assert!(!is_version("1.x"));
+/// assert!(rift!("HELLO/i").is_match("hello"));
+/// ```
+///
+/// ```compile_fail
+/// use monster_regex_macros::rift;
+///
+/// // The group is never closed
+/// let re = rift!("(abc/");
+/// ```
+#[proc_macro]
+pub fn rift(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(output) => output,
+        Err((message, span)) => compile_error(&message, span),
+    }
+}
+
+type Error = (String, Span);
+
+fn expand(input: TokenStream) -> Result<TokenStream, Error> {
+    let mut tokens = input.into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal,
+        // Literals passed through another macro arrive wrapped in an invisible group
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::None => {
+            return expand(group.stream());
+        }
+        (Some(token), _) => {
+            return Err((
+                "expected a single string literal like \"pattern/flags\"".to_string(),
+                token.span(),
+            ));
+        }
+        (None, _) => {
+            return Err((
+                "expected a string literal like \"pattern/flags\"".to_string(),
+                Span::call_site(),
+            ));
+        }
+    };
+    let input = string_value(&literal.to_string())
+        .ok_or_else(|| ("expected a string literal".to_string(), literal.span()))?;
+
+    let (pattern, flags) = parse_rift_format(&input)
+        .map_err(|err| (format!("invalid rift format: {:?}", err), literal.span()))?;
+    Regex::new(&pattern, flags)
+        .map_err(|err| (format!("invalid pattern: {:?}", err), literal.span()))?;
+
+    let output = format!(
+        "{{
+            static REGEX: ::std::sync::OnceLock<::monster_regex::Regex> =
+                ::std::sync::OnceLock::new();
+            REGEX.get_or_init(|| {{
+                let (pattern, flags) = ::monster_regex::parse_rift_format({literal})
+                    .expect(\"checked while compiling\");
+                ::monster_regex::Regex::new(&pattern, flags).expect(\"checked while compiling\")
+            }})
+        }}"
+    );
+    Ok(output.parse().expect("generated code should parse"))
+}
+
+// Returns the value of a string literal as written in source, or `None` for other
+// literals
+fn string_value(source: &str) -> Option<String> {
+    if let Some(raw) = source.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let body = raw[hashes..].strip_prefix('"')?;
+        let body = body.strip_suffix(&"#".repeat(hashes))?.strip_suffix('"')?;
+        return Some(body.to_string());
+    }
+
+    let body = source.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '0' => value.push('\0'),
+            '\\' => value.push('\\'),
+            '\'' => value.push('\''),
+            '"' => value.push('"'),
+            'x' => {
+                let digits: String = chars.by_ref().take(2).collect();
+                value.push(char::from(u8::from_str_radix(&digits, 16).ok()?));
+            }
+            'u' => {
+                chars.next_if_eq(&'{')?;
+                let digits: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let code = u32::from_str_radix(&digits.replace('_', ""), 16).ok()?;
+                value.push(char::from_u32(code)?);
+            }
+            // A line continuation skips the line break and the next line's indent
+            '\n' | '\r' => while chars.next_if(|c| c.is_whitespace()).is_some() {},
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    let mut output: TokenStream = "::core::compile_error!".parse().unwrap();
+    let mut arguments = Group::new(Delimiter::Parenthesis, TokenTree::Literal(message).into());
+    arguments.set_span(span);
+    output.extend([TokenTree::Group(arguments)]);
+    output
+}
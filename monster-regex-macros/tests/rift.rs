@@ -0,0 +1,34 @@
+use monster_regex::Regex;
+use monster_regex_macros::rift;
+
+fn version() -> &'static Regex {
+    rift!(r#"^v(\d+)"\.(\d+)/"#)
+}
+
+#[test]
+fn test_rift_compiles_once() {
+    assert!(std::ptr::eq(version(), version()));
+    assert!(version().is_match("v1\".2"));
+    assert_eq!(version().pattern(), r#"^v(\d+)"\.(\d+)"#);
+}
+
+#[test]
+fn test_rift_literals_and_flags() {
+    // Escapes in normal strings are resolved before the pattern is parsed
+    assert_eq!(rift!("\\d+\u{2e}\x41\t/").pattern(), "\\d+.A\t");
+    assert!(rift!("a\\/b/").is_match("a/b"));
+    assert_eq!(
+        rift!(
+            "a\
+        b/"
+        )
+        .pattern(),
+        "ab"
+    );
+
+    let re = rift!("HELLO/ig");
+    assert!(re.flags().global);
+    assert!(re.is_match("say hello"));
+    // Alternate delimiters work as in `parse_rift_format`
+    assert!(rift!("#a/b#").is_match("a/b"));
+}
@@ -0,0 +1,132 @@
+//! `msed`: runs a sed-style substitution command over files or standard input.
+//!
+//! ```text
+//! msed [-i[SUFFIX]] [ADDRESS]s/PATTERN/REPLACEMENT/FLAGS [FILE...]
+//! ```
+//!
+//! Each line is rewritten on its own, with its line ending set aside, so `^` and `$`
+//! match at the ends of every line. `ADDRESS` limits the command to some lines, as
+//! in `5,$s/a/b/`; `.` is the first line. The rewritten text goes to standard output,
+//! or back into each file with `-i`, which first copies the file to one named with
+//! `SUFFIX` appended if a suffix is given. With the `n` flag nothing is rewritten and
+//! the number of substitutions is printed instead.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use monster_regex::{Range, Regex, parse_range, parse_substitution};
+
+const USAGE: &str = "usage: msed [-i[SUFFIX]] [ADDRESS]s/PATTERN/REPLACEMENT/FLAGS [FILE...]";
+
+struct Command {
+    range: Option<Range>,
+    regex: Regex,
+    replacement: String,
+}
+
+impl Command {
+    fn parse(command: &str) -> Result<Self, String> {
+        let (range, rest) =
+            parse_range(command).map_err(|err| format!("invalid address: {:?}", err))?;
+        let (pattern, replacement, flags, _) =
+            parse_substitution(rest).map_err(|err| format!("invalid command: {:?}", err))?;
+        let regex =
+            Regex::new(&pattern, flags).map_err(|err| format!("invalid pattern: {:?}", err))?;
+        Ok(Command {
+            range,
+            regex,
+            replacement,
+        })
+    }
+
+    // Rewrites the text, returning it and how many substitutions were made
+    fn run(&self, text: &str) -> (String, usize) {
+        let last = text.lines().count();
+        let lines = self
+            .range
+            .map_or(Some(1..=last), |range| range.resolve(1, last));
+        let mut result = String::with_capacity(text.len());
+        let mut count = 0;
+
+        for (i, line) in text.split_inclusive('\n').enumerate() {
+            let content = line.trim_end_matches('\n');
+            let content = content.strip_suffix('\r').unwrap_or(content);
+            if !lines.as_ref().is_some_and(|lines| lines.contains(&(i + 1))) {
+                result.push_str(line);
+                continue;
+            }
+            let (rewritten, replaced) = self.regex.substitute(content, &self.replacement);
+            count += replaced;
+            result.push_str(&rewritten);
+            result.push_str(&line[content.len()..]);
+        }
+        (result, count)
+    }
+}
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("msed: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut args = args.into_iter().peekable();
+    let in_place = args
+        .next_if(|arg| arg.starts_with("-i"))
+        .map(|arg| arg[2..].to_string());
+    let command = args.next().ok_or(USAGE)?;
+    let command = Command::parse(&command)?;
+    let files: Vec<String> = args.collect();
+    let dry_run = command.regex.flags().dry_run;
+
+    if files.is_empty() {
+        if in_place.is_some() {
+            return Err("-i needs files to edit".to_string());
+        }
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|err| format!("reading standard input: {}", err))?;
+        return output(&command, &text, dry_run, None);
+    }
+
+    for file in &files {
+        let text = fs::read_to_string(file).map_err(|err| format!("reading {}: {}", file, err))?;
+        match &in_place {
+            Some(suffix) => {
+                if !suffix.is_empty() && !dry_run {
+                    let backup = format!("{}{}", file, suffix);
+                    fs::copy(file, &backup)
+                        .map_err(|err| format!("writing {}: {}", backup, err))?;
+                }
+                output(&command, &text, dry_run, Some(file))?;
+            }
+            None => output(&command, &text, dry_run, None)?,
+        }
+    }
+    Ok(())
+}
+
+// Writes the rewritten text to the file, or standard output if there is none. A dry
+// run only reports the count.
+fn output(command: &Command, text: &str, dry_run: bool, file: Option<&str>) -> Result<(), String> {
+    let (rewritten, count) = command.run(text);
+    if dry_run {
+        println!("{} substitutions", count);
+        return Ok(());
+    }
+    match file {
+        Some(file) => {
+            fs::write(file, rewritten).map_err(|err| format!("writing {}: {}", file, err))
+        }
+        None => io::stdout()
+            .write_all(rewritten.as_bytes())
+            .map_err(|err| format!("writing standard output: {}", err)),
+    }
+}
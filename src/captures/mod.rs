@@ -1,3 +1,8 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Range;
+
 /// Represents a single match within the text, defined by a start and end byte offset.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Match {
@@ -24,11 +29,39 @@ impl Match {
     ///
     /// Panics if the indices are out of bounds of the provided text or do not lie on UTF-8 boundaries.
     pub fn as_str<'a>(&self, text: &'a str) -> &'a str {
-        &text[self.start..self.end]
+        &text[self.range()]
+    }
+
+    /// Returns the substring of the text for this match, or `None` if the indices
+    /// are out of bounds of the text or don't lie on UTF-8 boundaries.
+    pub fn try_as_str<'a>(&self, text: &'a str) -> Option<&'a str> {
+        text.get(self.range())
+    }
+
+    /// Returns the byte range of the match, for slicing the text it was found in.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let text = "id: 42";
+    /// let m = Regex::new(r"\d+", Flags::default()).unwrap().find(text).unwrap();
+    /// assert_eq!(&text[m.range()], "42");
+    /// ```
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl From<Match> for Range<usize> {
+    fn from(m: Match) -> Self {
+        m.range()
     }
 }
 
 /// Represents the results of a regex match, including the full match and any captured groups.
+///
+/// Named groups are looked up through the pattern's name table, which every
+/// `Captures` from the same regex shares, so no per-match map is built.
 #[derive(Debug, Clone)]
 pub struct Captures {
     /// The match corresponding to the entire regex pattern (group 0).
@@ -36,11 +69,32 @@ pub struct Captures {
     /// Ordered list of captured groups (group 1, group 2, etc.).
     /// `None` indicates the group exists in the pattern but did not participate in the match.
     pub groups: Vec<Option<Match>>,
-    /// Map of named capture groups to their matches.
-    pub named: std::collections::HashMap<String, Match>,
+    names: Arc<[(String, usize)]>,
 }
 
 impl Captures {
+    /// Creates captures from the full match, the numbered groups, and the name
+    /// and index of each named group.
+    pub fn new(
+        full_match: Match,
+        groups: Vec<Option<Match>>,
+        names: impl IntoIterator<Item = (String, usize)>,
+    ) -> Self {
+        Self::with_names(full_match, groups, names.into_iter().collect())
+    }
+
+    pub(crate) fn with_names(
+        full_match: Match,
+        groups: Vec<Option<Match>>,
+        names: Arc<[(String, usize)]>,
+    ) -> Self {
+        Captures {
+            full_match,
+            groups,
+            names,
+        }
+    }
+
     /// Returns the match associated with the capture group at `index`.
     ///
     /// * `0` corresponds to the entire match.
@@ -56,8 +110,38 @@ impl Captures {
     }
 
     /// Returns the match associated with a named capture group.
+    ///
+    /// Returns `None` if there is no group with that name or if it did not participate in the match.
     pub fn get_named(&self, name: &str) -> Option<&Match> {
-        self.named.get(name)
+        let (_, index) = self.names.iter().find(|(n, _)| n == name)?;
+        self.get(*index)
+    }
+
+    /// Returns the named capture groups that participated in the match, in pattern order.
+    pub fn iter_named(&self) -> impl Iterator<Item = (&str, &Match)> {
+        self.names
+            .iter()
+            .filter_map(|(name, index)| Some((name.as_str(), self.get(*index)?)))
+    }
+
+    /// Returns the named capture groups that participated in the match, keyed by name.
+    ///
+    /// This replaces the `named` field `Captures` used to have, building the map on
+    /// each call. [`get_named`](Self::get_named) and
+    /// [`iter_named`](Self::iter_named) don't allocate.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"(?<key>\w+)=(?<value>\w+)?", Flags::default()).unwrap();
+    /// let caps = re.captures("id=").unwrap();
+    /// let named = caps.named();
+    /// assert_eq!(named["key"].as_str("id="), "id");
+    /// assert!(!named.contains_key("value"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn named(&self) -> std::collections::HashMap<&str, &Match> {
+        self.iter_named().collect()
     }
 
     /// Returns the substring of the original text for the capture group at `index`.
@@ -0,0 +1,137 @@
+use crate::parser::AstNode;
+use alloc::string::String;
+
+/// Returns the smallest number of characters the sequence must consume.
+pub fn min_len(nodes: &[AstNode]) -> usize {
+    nodes.iter().fold(0usize, |total, node| {
+        total.saturating_add(min_len_node(node))
+    })
+}
+
+fn min_len_node(node: &AstNode) -> usize {
+    match node {
+        AstNode::Literal(_) | AstNode::CharClass(_) | AstNode::Grapheme => 1,
+        AstNode::LiteralString(s) => s.chars().count(),
+        AstNode::Group { nodes, .. } => min_len(nodes),
+        AstNode::Alternation(alts) => alts.iter().map(|alt| min_len(alt)).min().unwrap_or(0),
+        AstNode::OneOrMore { node, .. } => min_len_node(node),
+        AstNode::Exact { node, count } => min_len_node(node).saturating_mul(*count),
+        AstNode::Range { node, min, .. } => min_len_node(node).saturating_mul(*min),
+        AstNode::ZeroOrMore { .. } | AstNode::Optional { .. } | AstNode::Backref(_) => 0,
+        AstNode::StartAnchor
+        | AstNode::EndAnchor
+        | AstNode::StartText
+        | AstNode::EndText
+        | AstNode::Line(..)
+        | AstNode::Column(..)
+        | AstNode::Cursor
+        | AstNode::Visual
+        | AstNode::Mark(..)
+        | AstNode::WordBoundary
+        | AstNode::StartWord
+        | AstNode::EndWord
+        | AstNode::SetMatchStart
+        | AstNode::SetMatchEnd
+        | AstNode::LookAhead { .. }
+        | AstNode::LookBehind { .. } => 0,
+    }
+}
+
+/// Returns the largest number of characters the sequence can consume, or `None` if unbounded.
+pub fn max_len(nodes: &[AstNode]) -> Option<usize> {
+    nodes
+        .iter()
+        .try_fold(0usize, |total, node| total.checked_add(max_len_node(node)?))
+}
+
+fn max_len_node(node: &AstNode) -> Option<usize> {
+    match node {
+        AstNode::Literal(_) | AstNode::CharClass(_) => Some(1),
+        AstNode::LiteralString(s) => Some(s.chars().count()),
+        AstNode::Group { nodes, .. } => max_len(nodes),
+        AstNode::Alternation(alts) => alts
+            .iter()
+            .try_fold(0usize, |widest, alt| Some(widest.max(max_len(alt)?))),
+        AstNode::Optional { node, .. } => max_len_node(node),
+        AstNode::Exact { node, count } => max_len_node(node)?.checked_mul(*count),
+        AstNode::Range {
+            node,
+            max: Some(max),
+            ..
+        } => max_len_node(node)?.checked_mul(*max),
+        AstNode::ZeroOrMore { node, .. }
+        | AstNode::OneOrMore { node, .. }
+        | AstNode::Range { node, .. } => match max_len_node(node)? {
+            0 => Some(0),
+            _ => None,
+        },
+        // A grapheme cluster can run on through any number of combining marks
+        AstNode::Backref(_) | AstNode::Grapheme => None,
+        AstNode::StartAnchor
+        | AstNode::EndAnchor
+        | AstNode::StartText
+        | AstNode::EndText
+        | AstNode::Line(..)
+        | AstNode::Column(..)
+        | AstNode::Cursor
+        | AstNode::Visual
+        | AstNode::Mark(..)
+        | AstNode::WordBoundary
+        | AstNode::StartWord
+        | AstNode::EndWord
+        | AstNode::SetMatchStart
+        | AstNode::SetMatchEnd
+        | AstNode::LookAhead { .. }
+        | AstNode::LookBehind { .. } => Some(0),
+    }
+}
+
+/// Returns true if every match must begin at a `^` anchor.
+pub(crate) fn starts_anchored(nodes: &[AstNode]) -> bool {
+    edge_anchored(
+        nodes.iter(),
+        &|node| matches!(node, AstNode::StartAnchor),
+        &|nodes| starts_anchored(nodes),
+    )
+}
+
+/// Returns true if every match must end at a `$` anchor.
+pub(crate) fn ends_anchored(nodes: &[AstNode]) -> bool {
+    edge_anchored(
+        nodes.iter().rev(),
+        &|node| matches!(node, AstNode::EndAnchor),
+        &|nodes| ends_anchored(nodes),
+    )
+}
+
+// Walks in from one edge of a sequence, skipping match-boundary markers
+fn edge_anchored<'a>(
+    mut nodes: impl Iterator<Item = &'a AstNode>,
+    is_anchor: &dyn Fn(&AstNode) -> bool,
+    recurse: &dyn Fn(&[AstNode]) -> bool,
+) -> bool {
+    nodes
+        .find(|node| !matches!(node, AstNode::SetMatchStart | AstNode::SetMatchEnd))
+        .is_some_and(|node| match node {
+            AstNode::Group { nodes, .. } => recurse(nodes),
+            AstNode::Alternation(alts) => alts.iter().all(|alt| recurse(alt)),
+            node => is_anchor(node),
+        })
+}
+
+/// Returns the text a pattern matches if it consists only of literal characters.
+pub(crate) fn literal_text(nodes: &[AstNode]) -> Option<String> {
+    nodes
+        .iter()
+        .try_fold(String::new(), |mut text, node| match node {
+            AstNode::Literal(c) => {
+                text.push(*c);
+                Some(text)
+            }
+            AstNode::LiteralString(s) => {
+                text.push_str(s);
+                Some(text)
+            }
+            _ => None,
+        })
+}
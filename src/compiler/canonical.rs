@@ -0,0 +1,61 @@
+use super::optimize;
+use crate::parser::{AstNode, CharClass, CharRange, map};
+use alloc::vec::Vec;
+
+/// Rewrites an AST into a canonical form, so patterns that differ only in how
+/// they're written print the same.
+///
+/// On top of [`optimize`], bounded quantifiers are written in their shortest form
+/// (`{0,}` becomes `*`, `{1,}` `+`, `{0,1}` `?`, `{n,n}` `{n}`, and `{1}` is
+/// dropped), and the items of each bracket expression are sorted with overlapping
+/// and adjacent ranges merged.
+pub fn canonicalize(nodes: Vec<AstNode>) -> Vec<AstNode> {
+    // Dropping `{1}` can leave a group to flatten, so optimize last
+    optimize(map(nodes, &mut canonical_node))
+}
+
+fn canonical_node(node: AstNode) -> AstNode {
+    match node {
+        AstNode::Range {
+            node,
+            min,
+            max,
+            greedy,
+        } => match (min, max) {
+            (0, None) => AstNode::ZeroOrMore { node, greedy },
+            (1, None) => AstNode::OneOrMore { node, greedy },
+            (0, Some(1)) => AstNode::Optional { node, greedy },
+            (1, Some(1)) => *node,
+            (min, Some(max)) if min == max => AstNode::Exact { node, count: min },
+            (min, max) => AstNode::Range {
+                node,
+                min,
+                max,
+                greedy,
+            },
+        },
+        AstNode::Exact { node, count: 1 } => *node,
+        AstNode::CharClass(CharClass::Set { chars, negated }) => {
+            AstNode::CharClass(CharClass::Set {
+                chars: merge_ranges(chars),
+                negated,
+            })
+        }
+        other => other,
+    }
+}
+
+pub(super) fn merge_ranges(mut ranges: Vec<CharRange>) -> Vec<CharRange> {
+    ranges.sort_by_key(|range| (range.start, range.end));
+    let mut merged: Vec<CharRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            // Overlapping, or adjacent like `a-c` and `d-f`
+            Some(last) if u32::from(range.start) <= u32::from(last.end) + 1 => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
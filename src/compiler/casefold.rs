@@ -0,0 +1,153 @@
+// Case-insensitive matching of bracket expressions. Characters match each other
+// when simple case mappings, ones to a single character, connect them, so `K`
+// matches the Kelvin sign and `ẞ` matches `ß`, but `ß` doesn't match `SS`. A set
+// is expanded to every such character once, at compile time, and is then matched
+// with plain range tests.
+
+use super::canonical::merge_ranges;
+use crate::parser::CharRange;
+use alloc::vec::Vec;
+
+/// Returns the ranges of every character that matches `ranges` case-insensitively:
+/// the members of the [`fold_orbit`] of each character in them. The result is
+/// sorted, with overlapping and adjacent ranges merged.
+pub(crate) fn case_closure(ranges: &[CharRange]) -> Vec<CharRange> {
+    let contains = |c: char| ranges.iter().any(|r| r.start <= c && c <= r.end);
+    let mut closure = ranges.to_vec();
+    for &(start, end) in CASED {
+        closure.extend(
+            (start..=end)
+                .filter(|&c| fold_orbit(c).any(contains))
+                .map(|c| CharRange { start: c, end: c }),
+        );
+    }
+    merge_ranges(closure)
+}
+
+/// Returns the characters connected to `c` by simple case mappings, `c` included:
+/// its lowercase and uppercase forms, theirs, and every character that maps to
+/// one of them. There are at most four.
+pub(crate) fn fold_orbit(c: char) -> impl Iterator<Item = char> {
+    // Lowercasing the uppercase form gives the same character across an orbit
+    let key = simple_lower(simple_upper(c));
+    let upper = simple_upper(key);
+    let start = EXTRAS.partition_point(|&(k, _)| k < key);
+    let extras = EXTRAS[start..]
+        .iter()
+        .take_while(move |&&(k, _)| k == key)
+        .map(|&(_, c)| c);
+    core::iter::once(key)
+        .chain((upper != key).then_some(upper))
+        .chain(extras)
+}
+
+fn simple_lower(c: char) -> char {
+    single(c.to_lowercase()).unwrap_or(c)
+}
+
+fn simple_upper(c: char) -> char {
+    single(c.to_uppercase()).unwrap_or(c)
+}
+
+fn single(mut chars: impl Iterator<Item = char>) -> Option<char> {
+    let c = chars.next()?;
+    chars.next().is_none().then_some(c)
+}
+
+// The members of orbits besides the key and its uppercase form, like the Kelvin
+// sign, the dotless `ı` and the final `ς`, by key, from the same mappings as `CASED`
+#[rustfmt::skip]
+const EXTRAS: &[(char, char)] = &[
+    ('\u{69}', '\u{131}'),
+    ('\u{6B}', '\u{212A}'),
+    ('\u{73}', '\u{17F}'),
+    ('\u{DF}', '\u{1E9E}'),
+    ('\u{E5}', '\u{212B}'),
+    ('\u{1C6}', '\u{1C5}'),
+    ('\u{1C9}', '\u{1C8}'),
+    ('\u{1CC}', '\u{1CB}'),
+    ('\u{1F3}', '\u{1F2}'),
+    ('\u{3B2}', '\u{3D0}'),
+    ('\u{3B5}', '\u{3F5}'),
+    ('\u{3B8}', '\u{3D1}'),
+    ('\u{3B8}', '\u{3F4}'),
+    ('\u{3B9}', '\u{345}'),
+    ('\u{3B9}', '\u{1FBE}'),
+    ('\u{3BA}', '\u{3F0}'),
+    ('\u{3BC}', '\u{B5}'),
+    ('\u{3C0}', '\u{3D6}'),
+    ('\u{3C1}', '\u{3F1}'),
+    ('\u{3C3}', '\u{3C2}'),
+    ('\u{3C6}', '\u{3D5}'),
+    ('\u{3C9}', '\u{2126}'),
+    ('\u{432}', '\u{1C80}'),
+    ('\u{434}', '\u{1C81}'),
+    ('\u{43E}', '\u{1C82}'),
+    ('\u{441}', '\u{1C83}'),
+    ('\u{442}', '\u{1C84}'),
+    ('\u{442}', '\u{1C85}'),
+    ('\u{44A}', '\u{1C86}'),
+    ('\u{463}', '\u{1C87}'),
+    ('\u{1E61}', '\u{1E9B}'),
+    ('\u{1F80}', '\u{1F88}'),
+    ('\u{1F81}', '\u{1F89}'),
+    ('\u{1F82}', '\u{1F8A}'),
+    ('\u{1F83}', '\u{1F8B}'),
+    ('\u{1F84}', '\u{1F8C}'),
+    ('\u{1F85}', '\u{1F8D}'),
+    ('\u{1F86}', '\u{1F8E}'),
+    ('\u{1F87}', '\u{1F8F}'),
+    ('\u{1F90}', '\u{1F98}'),
+    ('\u{1F91}', '\u{1F99}'),
+    ('\u{1F92}', '\u{1F9A}'),
+    ('\u{1F93}', '\u{1F9B}'),
+    ('\u{1F94}', '\u{1F9C}'),
+    ('\u{1F95}', '\u{1F9D}'),
+    ('\u{1F96}', '\u{1F9E}'),
+    ('\u{1F97}', '\u{1F9F}'),
+    ('\u{1FA0}', '\u{1FA8}'),
+    ('\u{1FA1}', '\u{1FA9}'),
+    ('\u{1FA2}', '\u{1FAA}'),
+    ('\u{1FA3}', '\u{1FAB}'),
+    ('\u{1FA4}', '\u{1FAC}'),
+    ('\u{1FA5}', '\u{1FAD}'),
+    ('\u{1FA6}', '\u{1FAE}'),
+    ('\u{1FA7}', '\u{1FAF}'),
+    ('\u{1FB3}', '\u{1FBC}'),
+    ('\u{1FC3}', '\u{1FCC}'),
+    ('\u{1FF3}', '\u{1FFC}'),
+    ('\u{A64B}', '\u{1C88}'),
+];
+
+// Covers every character with a case mapping, from the Unicode 17 mappings of
+// `char::to_lowercase` and `char::to_uppercase`. Gaps of up to 32 characters are
+// bridged to keep the table short
+#[rustfmt::skip]
+const CASED: &[(char, char)] = &[
+    ('\u{41}', '\u{7A}'),
+    ('\u{B5}', '\u{29E}'),
+    ('\u{345}', '\u{345}'),
+    ('\u{370}', '\u{587}'),
+    ('\u{10A0}', '\u{10FF}'),
+    ('\u{13A0}', '\u{13FD}'),
+    ('\u{1C80}', '\u{1CBF}'),
+    ('\u{1D79}', '\u{1D8E}'),
+    ('\u{1E00}', '\u{1FFC}'),
+    ('\u{2126}', '\u{2184}'),
+    ('\u{24B6}', '\u{24E9}'),
+    ('\u{2C00}', '\u{2D2D}'),
+    ('\u{A640}', '\u{A69B}'),
+    ('\u{A722}', '\u{A7F6}'),
+    ('\u{AB53}', '\u{ABBF}'),
+    ('\u{FB00}', '\u{FB17}'),
+    ('\u{FF21}', '\u{FF5A}'),
+    ('\u{10400}', '\u{1044F}'),
+    ('\u{104B0}', '\u{104FB}'),
+    ('\u{10570}', '\u{105BC}'),
+    ('\u{10C80}', '\u{10CF2}'),
+    ('\u{10D50}', '\u{10D85}'),
+    ('\u{118A0}', '\u{118DF}'),
+    ('\u{16E40}', '\u{16E7F}'),
+    ('\u{16EA0}', '\u{16ED3}'),
+    ('\u{1E900}', '\u{1E943}'),
+];
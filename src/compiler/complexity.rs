@@ -0,0 +1,276 @@
+use crate::engine::{class_matches, literal_matches};
+use crate::errors::CompileError;
+use crate::flags::Flags;
+use crate::parser::{AstNode, CharClass, Parser, Span, SpanMap};
+use crate::regex::Regex;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+// Characters compared when deciding whether two classes can match the same character
+const SAMPLE_END: char = '\u{2FF}';
+
+/// A shape in a pattern that can make backtracking take exponential time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplexityKind {
+    /// An unbounded quantifier inside another one, where an iteration of the
+    /// inner quantifier can also start the next step of the outer one, as in
+    /// `(a+)+` or `(\w+\s?)*`. Text that almost matches can be split between the
+    /// two quantifiers in exponentially many ways.
+    NestedQuantifier,
+    /// An alternation under an unbounded quantifier whose branches can start with
+    /// the same character, as in `(a|ab)*` or `(\w|\d)+`. Each iteration can take
+    /// either branch, so failing text is tried both ways at every step.
+    OverlappingAlternation,
+}
+
+/// A risky construct found by [`check_complexity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComplexityWarning {
+    pub kind: ComplexityKind,
+    /// The byte range of the inner quantifier, or of the repeated alternation.
+    pub span: Span,
+    pub message: String,
+}
+
+/// Looks for shapes in a pattern that can make matching take exponential time.
+///
+/// The check is structural and conservative: it may warn about patterns that are
+/// safe on every input, but flags the classic catastrophic shapes. Services that
+/// accept patterns from users can reject or sandbox the ones it reports.
+///
+/// ```
+/// use monster_regex::{ComplexityKind, Flags, check_complexity};
+///
+/// let warnings = check_complexity(r"^(\w+\s?)*$", Flags::default()).unwrap();
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].kind, ComplexityKind::NestedQuantifier);
+/// // The span covers the inner `\w+`
+/// assert_eq!((warnings[0].span.start, warnings[0].span.end), (2, 5));
+///
+/// assert!(check_complexity(r"^(\w+\s)*$", Flags::default()).unwrap().is_empty());
+/// ```
+///
+/// # Errors
+///
+/// Returns a `CompileError` if the pattern doesn't parse.
+pub fn check_complexity(
+    pattern: &str,
+    flags: Flags,
+) -> Result<Vec<ComplexityWarning>, CompileError> {
+    let (ast, spans) = Parser::new(pattern, flags)
+        .parse_with_spans()
+        .map_err(|e| CompileError::InvalidPattern(e.to_string()))?;
+    let flags = Regex::resolve_case(&ast, flags);
+    let mut checker = Checker {
+        flags,
+        spans,
+        next_index: 0,
+        warnings: vec![],
+    };
+    checker.sequence(&ast, &Follow::outside());
+    Ok(checker.warnings)
+}
+
+/// A single-character test that can start a match.
+#[derive(Debug, Clone)]
+enum Test<'a> {
+    Literal(char),
+    Class(&'a CharClass),
+}
+
+/// What may come right after a position in the pattern.
+#[derive(Debug, Clone)]
+struct Follow<'a> {
+    /// Tests for the next character, as far as it's decided inside the enclosing loop.
+    tests: Vec<Test<'a>>,
+    /// Whether we're inside an unbounded loop at all.
+    in_loop: bool,
+}
+
+impl Follow<'_> {
+    fn outside() -> Self {
+        Follow {
+            tests: vec![],
+            in_loop: false,
+        }
+    }
+}
+
+struct Checker {
+    flags: Flags,
+    spans: SpanMap,
+    next_index: usize,
+    warnings: Vec<ComplexityWarning>,
+}
+
+impl Checker {
+    fn sequence<'a>(&mut self, nodes: &'a [AstNode], follow: &Follow<'a>) {
+        for (i, node) in nodes.iter().enumerate() {
+            let (mut tests, nullable) = first(&nodes[i + 1..]);
+            if nullable {
+                tests.extend(follow.tests.iter().cloned());
+            }
+            let node_follow = Follow {
+                tests,
+                in_loop: follow.in_loop,
+            };
+            self.node(node, &node_follow);
+        }
+    }
+
+    // Visits nodes in `walk` order so `next_index` numbers them like the span map
+    fn node<'a>(&mut self, node: &'a AstNode, follow: &Follow<'a>) {
+        let span = self.spans.get(self.next_index);
+        self.next_index += 1;
+        match node {
+            AstNode::ZeroOrMore { node: inner, .. }
+            | AstNode::OneOrMore { node: inner, .. }
+            | AstNode::Range {
+                node: inner,
+                max: None,
+                ..
+            } => {
+                let (body_first, _) = first(core::slice::from_ref(inner.as_ref()));
+                if follow.in_loop && self.overlaps(&body_first, &follow.tests) {
+                    self.warn(
+                        ComplexityKind::NestedQuantifier,
+                        span,
+                        "nested quantifiers can split the same text many ways",
+                    );
+                }
+                // Inside the loop, the body may be followed by its own next iteration
+                let mut tests = body_first;
+                if follow.in_loop {
+                    tests.extend(follow.tests.iter().cloned());
+                }
+                let body_follow = Follow {
+                    tests,
+                    in_loop: true,
+                };
+                if let AstNode::Group { nodes, .. } = inner.as_ref()
+                    && let [AstNode::Alternation(alts)] = nodes.as_slice()
+                {
+                    self.check_branches(alts, span);
+                }
+                if let AstNode::Alternation(alts) = inner.as_ref() {
+                    self.check_branches(alts, span);
+                }
+                self.node(inner, &body_follow);
+            }
+            AstNode::Optional { node: inner, .. }
+            | AstNode::Exact { node: inner, .. }
+            | AstNode::Range { node: inner, .. } => self.node(inner, follow),
+            AstNode::Group { nodes, .. } => self.sequence(nodes, follow),
+            AstNode::Alternation(alts) => {
+                for alt in alts {
+                    self.sequence(alt, follow);
+                }
+            }
+            // Lookarounds run as separate searches
+            AstNode::LookAhead { nodes, .. } | AstNode::LookBehind { nodes, .. } => {
+                self.sequence(nodes, &Follow::outside());
+            }
+            _ => {}
+        }
+    }
+
+    fn check_branches(&mut self, alts: &[Vec<AstNode>], span: Option<Span>) {
+        let firsts: Vec<_> = alts.iter().map(|alt| first(alt).0).collect();
+        let overlapping = firsts
+            .iter()
+            .enumerate()
+            .any(|(i, a)| firsts[i + 1..].iter().any(|b| self.overlaps(a, b)));
+        if overlapping {
+            self.warn(
+                ComplexityKind::OverlappingAlternation,
+                span,
+                "repeated alternation has branches that start alike",
+            );
+        }
+    }
+
+    fn warn(&mut self, kind: ComplexityKind, span: Option<Span>, message: &str) {
+        if let Some(span) = span {
+            self.warnings.push(ComplexityWarning {
+                kind,
+                span,
+                message: message.to_string(),
+            });
+        }
+    }
+
+    fn overlaps(&self, a: &[Test], b: &[Test]) -> bool {
+        a.iter().any(|x| b.iter().any(|y| self.intersect(x, y)))
+    }
+
+    fn intersect(&self, a: &Test, b: &Test) -> bool {
+        match (a, b) {
+            (Test::Literal(x), Test::Literal(y)) => literal_matches(*x, *y, &self.flags),
+            (Test::Literal(c), Test::Class(class)) | (Test::Class(class), Test::Literal(c)) => {
+                class_matches(class, *c, &self.flags)
+            }
+            (Test::Class(x), Test::Class(y)) => ('\0'..=SAMPLE_END)
+                .any(|c| class_matches(x, c, &self.flags) && class_matches(y, c, &self.flags)),
+        }
+    }
+}
+
+// The tests that can consume the sequence's first character, and whether it can
+// match without consuming anything
+fn first(nodes: &[AstNode]) -> (Vec<Test<'_>>, bool) {
+    let mut tests = vec![];
+    for node in nodes {
+        let (node_tests, nullable) = first_node(node);
+        tests.extend(node_tests);
+        if !nullable {
+            return (tests, false);
+        }
+    }
+    (tests, true)
+}
+
+fn first_node(node: &AstNode) -> (Vec<Test<'_>>, bool) {
+    match node {
+        AstNode::Literal(c) => (vec![Test::Literal(*c)], false),
+        AstNode::LiteralString(s) => match s.chars().next() {
+            Some(c) => (vec![Test::Literal(c)], false),
+            None => (vec![], true),
+        },
+        AstNode::CharClass(class) => (vec![Test::Class(class)], false),
+        // A cluster starts with a character `.` would match alone
+        AstNode::Grapheme => (vec![Test::Class(&CharClass::Dot)], false),
+        AstNode::Group { nodes, .. } => first(nodes),
+        AstNode::Alternation(alts) => alts.iter().fold((vec![], false), |(mut tests, any), alt| {
+            let (alt_tests, nullable) = first(alt);
+            tests.extend(alt_tests);
+            (tests, any || nullable)
+        }),
+        AstNode::ZeroOrMore { node, .. } | AstNode::Optional { node, .. } => {
+            (first_node(node).0, true)
+        }
+        AstNode::OneOrMore { node, .. } => first_node(node),
+        AstNode::Exact { node, count: n } | AstNode::Range { node, min: n, .. } => {
+            let (tests, nullable) = first_node(node);
+            (tests, nullable || *n == 0)
+        }
+        // Backreferences repeat text already matched, which the caller can't see
+        AstNode::Backref(_)
+        | AstNode::StartAnchor
+        | AstNode::EndAnchor
+        | AstNode::StartText
+        | AstNode::EndText
+        | AstNode::Line(..)
+        | AstNode::Column(..)
+        | AstNode::Cursor
+        | AstNode::Visual
+        | AstNode::Mark(..)
+        | AstNode::WordBoundary
+        | AstNode::StartWord
+        | AstNode::EndWord
+        | AstNode::SetMatchStart
+        | AstNode::SetMatchEnd
+        | AstNode::LookAhead { .. }
+        | AstNode::LookBehind { .. } => (vec![], true),
+    }
+}
@@ -0,0 +1,58 @@
+use crate::errors::Diagnostic;
+use crate::flags::Flags;
+use crate::parser::Parser;
+use crate::regex::Regex;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{check_complexity, lint};
+
+/// Checks a pattern and reports every problem found as a [`Diagnostic`], for
+/// editors and language servers.
+///
+/// A pattern that doesn't parse or compile gives a single error; parse errors
+/// point at the character where parsing stopped, or at syntax from another
+/// dialect. A valid pattern gives the warnings from [`lint`] and
+/// [`check_complexity`], in the order their spans start.
+///
+/// ```
+/// use monster_regex::{Flags, Severity, diagnose};
+///
+/// // `\z` must be followed by `s` or `e`
+/// let diagnostics = diagnose(r"a\zqb", Flags::default());
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].code, "invalid-escape");
+/// let span = diagnostics[0].span.unwrap();
+/// assert_eq!((span.start, span.end), (3, 4));
+///
+/// let diagnostics = diagnose(r"(?:x)(a+)+", Flags::default());
+/// let codes: Vec<_> = diagnostics.iter().map(|d| d.code).collect();
+/// assert_eq!(codes, ["useless-group", "nested-quantifier"]);
+/// assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+///
+/// assert!(diagnose(r"\d+", Flags::default()).is_empty());
+/// ```
+pub fn diagnose(pattern: &str, flags: Flags) -> Vec<Diagnostic> {
+    let mut parser = Parser::new(pattern, flags);
+    if let Err(err) = parser.parse() {
+        return vec![err.to_diagnostic().with_span(parser.error_span(&err))];
+    }
+    if let Err(err) = Regex::new(pattern, flags) {
+        return vec![err.to_diagnostic()];
+    }
+
+    // The pattern parsed, so neither check can fail
+    let mut diagnostics: Vec<_> = lint(pattern, flags)
+        .into_iter()
+        .flatten()
+        .map(|warning| warning.to_diagnostic())
+        .chain(
+            check_complexity(pattern, flags)
+                .into_iter()
+                .flatten()
+                .map(|warning| warning.to_diagnostic()),
+        )
+        .collect();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+    diagnostics
+}
@@ -0,0 +1,73 @@
+use super::analysis::{ends_anchored, starts_anchored};
+use super::prefilter::{ScanPlan, literal_prefix};
+use crate::flags::Flags;
+use crate::parser::{AstNode, fold};
+use alloc::string::String;
+
+/// Structural facts about a compiled pattern.
+///
+/// Tools can use these to decide how to route or optimize searches, e.g. to
+/// skip an index lookup for anchored patterns or to avoid backreferences in
+/// untrusted input.
+///
+/// ```
+/// use monster_regex::{Flags, Regex};
+///
+/// let re = Regex::new(r"^(\w+)-\1", Flags::default()).unwrap();
+/// let info = re.info();
+/// assert!(info.anchored_start);
+/// assert!(info.has_backrefs);
+/// assert!(!info.has_lookaround);
+/// assert_eq!(info.min_len, 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternInfo {
+    /// Every match starts at `^`.
+    pub anchored_start: bool,
+    /// Every match ends at `$`.
+    pub anchored_end: bool,
+    /// The pattern uses backreferences such as `\1`.
+    pub has_backrefs: bool,
+    /// The pattern uses lookahead or lookbehind assertions.
+    pub has_lookaround: bool,
+    /// The pattern uses `\zs` or `\ze` to move the bounds of its matches.
+    pub has_match_bounds: bool,
+    /// Literal text every match consumes first. Empty if there is none, or if the
+    /// pattern is case-insensitive.
+    pub literal_prefix: String,
+    /// The fewest characters any match consumes.
+    pub min_len: usize,
+    /// The most characters any match consumes, or `None` if unbounded.
+    pub max_len: Option<usize>,
+}
+
+impl PatternInfo {
+    pub(crate) fn new(nodes: &[AstNode], flags: &Flags, plan: &ScanPlan) -> Self {
+        let (has_backrefs, has_lookaround, has_match_bounds) = fold(
+            nodes,
+            (false, false, false),
+            &mut |(backrefs, looks, bounds), node| {
+                (
+                    backrefs || matches!(node, AstNode::Backref(_)),
+                    looks || matches!(node, AstNode::LookAhead { .. } | AstNode::LookBehind { .. }),
+                    bounds || matches!(node, AstNode::SetMatchStart | AstNode::SetMatchEnd),
+                )
+            },
+        );
+        let literal_prefix = if flags.ignore_case.unwrap_or(false) {
+            String::new()
+        } else {
+            literal_prefix(nodes)
+        };
+        PatternInfo {
+            anchored_start: starts_anchored(nodes),
+            anchored_end: ends_anchored(nodes),
+            has_backrefs,
+            has_lookaround,
+            has_match_bounds,
+            literal_prefix,
+            min_len: plan.min_len,
+            max_len: plan.max_len,
+        }
+    }
+}
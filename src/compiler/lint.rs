@@ -0,0 +1,264 @@
+use crate::errors::CompileError;
+use crate::flags::Flags;
+use crate::parser::{AstNode, CharClass, CharRange, Parser, Span, SpanMap};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A kind of pattern that is valid but likely not what the author meant, or can be
+/// written more simply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    /// A quantifier that matches exactly once, such as `a{1}` or `a{1,1}`.
+    RedundantQuantifier,
+    /// An alternation branch identical to an earlier one, as in `cat|dog|cat`.
+    DuplicateBranch,
+    /// A character or range in a bracket expression that another one already covers,
+    /// as in `[a-zc-f]` or `[aa]`.
+    RedundantSetItem,
+    /// A literal `-` in the middle of a bracket expression, as in `[a-c-e]`. Escape
+    /// it or move it to the start or end to show it's intended.
+    AmbiguousHyphen,
+    /// A non-capturing group that doesn't change what the pattern matches, as in
+    /// `(?:abc)` or `(?:a)*`.
+    UselessGroup,
+}
+
+/// A finding reported by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    pub kind: LintKind,
+    /// The byte range in the pattern the warning is about.
+    pub span: Span,
+    pub message: String,
+}
+
+/// Checks a pattern for constructs that are valid but redundant or easy to misread.
+///
+/// Warnings are returned in the order their spans start in the pattern, so editors
+/// can show them inline.
+///
+/// ```
+/// use monster_regex::{Flags, LintKind, lint};
+///
+/// let warnings = lint("(?:ab){1}", Flags::default()).unwrap();
+/// let kinds: Vec<_> = warnings.iter().map(|w| w.kind).collect();
+/// assert_eq!(kinds, [LintKind::RedundantQuantifier, LintKind::UselessGroup]);
+/// assert_eq!((warnings[0].span.start, warnings[0].span.end), (0, 9));
+///
+/// assert!(lint("(ab)+", Flags::default()).unwrap().is_empty());
+/// ```
+///
+/// # Errors
+///
+/// Returns a `CompileError` if the pattern doesn't parse.
+pub fn lint(pattern: &str, flags: Flags) -> Result<Vec<LintWarning>, CompileError> {
+    let (ast, spans) = Parser::new(pattern, flags)
+        .parse_with_spans()
+        .map_err(|e| CompileError::InvalidPattern(e.to_string()))?;
+    let mut linter = Linter {
+        pattern,
+        spans,
+        next_index: 0,
+        warnings: vec![],
+    };
+    linter.sequence(&ast);
+    linter.warnings.sort_by_key(|w| w.span.start);
+    Ok(linter.warnings)
+}
+
+struct Linter<'p> {
+    pattern: &'p str,
+    spans: SpanMap,
+    next_index: usize,
+    warnings: Vec<LintWarning>,
+}
+
+impl Linter<'_> {
+    // Returns the span covering the whole sequence, if it has any nodes
+    fn sequence(&mut self, nodes: &[AstNode]) -> Option<Span> {
+        let mut covered: Option<Span> = None;
+        for node in nodes {
+            if let Some(span) = self.node(node, false) {
+                covered = Some(match covered {
+                    Some(c) => Span {
+                        start: c.start,
+                        end: span.end,
+                    },
+                    None => span,
+                });
+            }
+        }
+        covered
+    }
+
+    // Visits nodes in `walk` order so `next_index` numbers them like the span map
+    fn node(&mut self, node: &AstNode, quantified: bool) -> Option<Span> {
+        let span = self.spans.get(self.next_index);
+        self.next_index += 1;
+        match node {
+            AstNode::Exact {
+                node: inner,
+                count: 1,
+            }
+            | AstNode::Range {
+                node: inner,
+                min: 1,
+                max: Some(1),
+                ..
+            } => {
+                self.warn(LintKind::RedundantQuantifier, span, |text| {
+                    format!("`{text}` matches exactly once; the quantifier can be removed")
+                });
+                self.node(inner, false);
+            }
+            AstNode::ZeroOrMore { node: inner, .. }
+            | AstNode::OneOrMore { node: inner, .. }
+            | AstNode::Optional { node: inner, .. }
+            | AstNode::Exact { node: inner, .. }
+            | AstNode::Range { node: inner, .. } => {
+                self.node(inner, true);
+            }
+            AstNode::Group {
+                nodes,
+                capture: false,
+                ..
+            } => {
+                let needed = if quantified {
+                    !matches!(
+                        nodes.as_slice(),
+                        [AstNode::Literal(_)
+                            | AstNode::CharClass(_)
+                            | AstNode::Group { .. }
+                            | AstNode::Backref(_)]
+                    )
+                } else {
+                    matches!(nodes.as_slice(), [AstNode::Alternation(_)])
+                };
+                if !needed {
+                    self.warn(LintKind::UselessGroup, span, |text| {
+                        format!("the group in `{text}` doesn't change what it matches")
+                    });
+                }
+                self.sequence(nodes);
+            }
+            AstNode::Group { nodes, .. }
+            | AstNode::LookAhead { nodes, .. }
+            | AstNode::LookBehind { nodes, .. } => {
+                self.sequence(nodes);
+            }
+            AstNode::Alternation(alts) => {
+                for (i, alt) in alts.iter().enumerate() {
+                    let branch = self.sequence(alt).or(span);
+                    if alts[..i].contains(alt) {
+                        self.warn(LintKind::DuplicateBranch, branch, |text| {
+                            format!("the branch `{text}` repeats an earlier one")
+                        });
+                    }
+                }
+            }
+            AstNode::CharClass(CharClass::Set { chars, .. }) => {
+                if let Some(span) = span {
+                    self.set(chars, span);
+                }
+            }
+            _ => {}
+        }
+        span
+    }
+
+    fn set(&mut self, chars: &[CharRange], span: Span) {
+        let items = set_items(self.pattern, span);
+        // The scan mirrors the parser, but fall back to no spans rather than
+        // misplacing them if it ever disagrees
+        if items.len() != chars.len() {
+            return;
+        }
+        for (i, item) in items.iter().enumerate() {
+            let range = &chars[i];
+            let covered = chars.iter().enumerate().any(|(j, other)| {
+                j != i
+                    && other.start <= range.start
+                    && range.end <= other.end
+                    && (other != range || j < i)
+            });
+            if covered {
+                self.warn(LintKind::RedundantSetItem, Some(item.span), |text| {
+                    format!("`{text}` is already covered by the rest of the set")
+                });
+            }
+            if item.bare_hyphen {
+                self.warn(LintKind::AmbiguousHyphen, Some(item.span), |_| {
+                    "a `-` in the middle of a set is easy to misread as a range; escape it or move it to the end".to_string()
+                });
+            }
+        }
+    }
+
+    fn warn(&mut self, kind: LintKind, span: Option<Span>, message: impl FnOnce(&str) -> String) {
+        if let Some(span) = span {
+            self.warnings.push(LintWarning {
+                kind,
+                span,
+                message: message(&self.pattern[span.start..span.end]),
+            });
+        }
+    }
+}
+
+/// A character or range of a bracket expression, as written.
+struct SetItem {
+    span: Span,
+    /// Whether this is an unescaped `-` standing for itself between other items.
+    bare_hyphen: bool,
+}
+
+// Splits the text of a bracket expression into its items, the same way the parser
+// does
+fn set_items(pattern: &str, span: Span) -> Vec<SetItem> {
+    let text = &pattern[span.start..span.end];
+    let chars: Vec<(usize, char)> = text
+        .char_indices()
+        .map(|(i, c)| (span.start + i, c))
+        .collect();
+    let at = |pos: usize| chars.get(pos).map(|&(_, c)| c);
+    let offset = |pos: usize| chars.get(pos).map_or(span.end, |&(i, _)| i);
+
+    // Returns the position after the character starting at `pos`
+    let skip_char = |mut pos: usize| {
+        if at(pos) == Some('\\') {
+            pos += 1;
+            if at(pos) == Some('x') && at(pos + 1) == Some('{') {
+                while at(pos).is_some_and(|c| c != '}') {
+                    pos += 1;
+                }
+            }
+        }
+        pos + 1
+    };
+
+    let mut pos = 1;
+    if at(pos) == Some('^') {
+        pos += 1;
+    }
+    let first = pos;
+    let mut items = vec![];
+    while pos < chars.len() && at(pos) != Some(']') {
+        let start = pos;
+        pos = skip_char(pos);
+        if at(pos) == Some('-') && at(pos + 1).is_some_and(|c| c != ']') {
+            pos = skip_char(pos + 1);
+        }
+        let bare_hyphen =
+            pos == start + 1 && at(start) == Some('-') && start != first && at(pos) != Some(']');
+        items.push(SetItem {
+            span: Span {
+                start: offset(start),
+                end: offset(pos),
+            },
+            bare_hyphen,
+        });
+    }
+    items
+}
@@ -0,0 +1,99 @@
+mod optimize;
+pub use optimize::optimize;
+
+mod analysis;
+mod canonical;
+mod casefold;
+mod complexity;
+mod diagnose;
+mod info;
+mod lint;
+pub(crate) use analysis::literal_text;
+pub use analysis::{max_len, min_len};
+pub(crate) use canonical::canonicalize;
+pub(crate) use casefold::{case_closure, fold_orbit};
+pub use complexity::{ComplexityKind, ComplexityWarning, check_complexity};
+pub use diagnose::diagnose;
+pub use info::PatternInfo;
+pub use lint::{LintKind, LintWarning, lint};
+
+pub(crate) mod prefilter;
+pub(crate) use prefilter::{Anchor, ScanPlan};
+
+mod validate;
+pub(crate) use validate::*;
+
+use crate::parser::MAX_NESTING_DEPTH;
+use crate::regex::EmptyMatchPolicy;
+
+/// The default upper bound for counted repetitions such as `{n}` or `{n,m}`.
+pub const DEFAULT_REPETITION_LIMIT: usize = 1000;
+
+/// The default upper bound on a pattern's length in bytes.
+pub const DEFAULT_SIZE_LIMIT: usize = 1 << 20;
+
+/// The default upper bound on the number of instructions a pattern compiles to.
+pub const DEFAULT_PROGRAM_SIZE_LIMIT: usize = 1 << 20;
+
+/// How backreferences to groups that can't have matched yet are treated: the
+/// group the reference is inside of, like `\1` in `(a\1)`, a group that comes
+/// later, or one that doesn't exist.
+///
+/// ```
+/// use monster_regex::{BackrefPolicy, CompileError, RegexBuilder};
+///
+/// let strict = RegexBuilder::new(r"(a\1)").build();
+/// assert!(matches!(strict, Err(CompileError::InvalidBackref(_))));
+///
+/// let re = RegexBuilder::new(r"(a\1)")
+///     .backrefs(BackrefPolicy::MatchEmpty)
+///     .build()
+///     .unwrap();
+/// assert!(re.is_match("a"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackrefPolicy {
+    /// The pattern is rejected with `CompileError::InvalidBackref`. A reference
+    /// to an earlier group that didn't take part in the match, like `\1` in
+    /// `(a)?\1`, still fails to match, as in PCRE.
+    #[default]
+    Strict,
+    /// The pattern compiles, and a reference to a group that isn't set matches
+    /// the empty string, as in JavaScript. This goes for earlier groups that
+    /// didn't take part in the match too.
+    MatchEmpty,
+}
+
+/// Options that control how a pattern is compiled.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CompileOptions {
+    /// The largest count accepted in a bounded quantifier.
+    pub repetition_limit: usize,
+    /// How iteration treats empty matches.
+    pub empty_matches: EmptyMatchPolicy,
+    /// The longest pattern accepted, in bytes.
+    pub size_limit: usize,
+    /// How deeply groups and lookarounds may be nested, at most
+    /// [`MAX_NESTING_DEPTH`](crate::parser::MAX_NESTING_DEPTH).
+    pub nest_limit: usize,
+    /// The most instructions the compiled program may have.
+    pub program_size_limit: usize,
+    /// Whether groups captured inside a lookaround that matched stay set.
+    pub lookaround_captures: bool,
+    /// How backreferences to groups that can't have matched yet are treated.
+    pub backrefs: BackrefPolicy,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            repetition_limit: DEFAULT_REPETITION_LIMIT,
+            empty_matches: EmptyMatchPolicy::default(),
+            size_limit: DEFAULT_SIZE_LIMIT,
+            nest_limit: MAX_NESTING_DEPTH,
+            program_size_limit: DEFAULT_PROGRAM_SIZE_LIMIT,
+            lookaround_captures: true,
+            backrefs: BackrefPolicy::Strict,
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::parser::AstNode;
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Rewrites an AST into an equivalent one that is cheaper to match.
+///
+/// The passes preserve match results, including leftmost-first alternation priority
+/// and capture group positions:
+///
+/// * Non-capturing groups that aren't quantified are spliced into their parent,
+///   and quantified ones holding a single atom are unwrapped (`(?:a)*` becomes `a*`).
+/// * Alternation branches identical to an earlier branch are dropped, since they can
+///   never produce a match the earlier branch didn't.
+/// * Adjacent branches sharing leading nodes have the prefix hoisted out
+///   (`abc|abd` becomes `ab(?:c|d)`).
+/// * Runs of adjacent `Literal` nodes are merged into a single `LiteralString`.
+pub fn optimize(nodes: Vec<AstNode>) -> Vec<AstNode> {
+    merge_literals(simplify_sequence(nodes))
+}
+
+// Pass 1: flatten groups and simplify alternations, bottom-up
+fn simplify_sequence(nodes: Vec<AstNode>) -> Vec<AstNode> {
+    let mut out = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        match simplify_node(node) {
+            AstNode::Group {
+                nodes,
+                capture: false,
+                ..
+            } => out.extend(nodes),
+            AstNode::Alternation(mut alts) if alts.len() == 1 => out.append(&mut alts[0]),
+            AstNode::Alternation(alts) => out.extend(hoist_prefixes(alts)),
+            node => out.push(node),
+        }
+    }
+    out
+}
+
+fn simplify_node(node: AstNode) -> AstNode {
+    match node {
+        AstNode::ZeroOrMore { node, greedy } => AstNode::ZeroOrMore {
+            node: Box::new(simplify_node(*node)),
+            greedy,
+        },
+        AstNode::OneOrMore { node, greedy } => AstNode::OneOrMore {
+            node: Box::new(simplify_node(*node)),
+            greedy,
+        },
+        AstNode::Optional { node, greedy } => AstNode::Optional {
+            node: Box::new(simplify_node(*node)),
+            greedy,
+        },
+        AstNode::Exact { node, count } => AstNode::Exact {
+            node: Box::new(simplify_node(*node)),
+            count,
+        },
+        AstNode::Range {
+            node,
+            min,
+            max,
+            greedy,
+        } => AstNode::Range {
+            node: Box::new(simplify_node(*node)),
+            min,
+            max,
+            greedy,
+        },
+        AstNode::Group {
+            nodes,
+            name,
+            capture,
+            index,
+        } => AstNode::Group {
+            nodes: simplify_sequence(nodes),
+            name,
+            capture,
+            index,
+        },
+        AstNode::Alternation(alts) => {
+            let mut unique: Vec<Vec<AstNode>> = Vec::with_capacity(alts.len());
+            for alt in alts {
+                let alt = simplify_sequence(alt);
+                if !unique.contains(&alt) {
+                    unique.push(alt);
+                }
+            }
+            AstNode::Alternation(unique)
+        }
+        AstNode::LookAhead { nodes, positive } => AstNode::LookAhead {
+            nodes: simplify_sequence(nodes),
+            positive,
+        },
+        AstNode::LookBehind { nodes, positive } => AstNode::LookBehind {
+            nodes: simplify_sequence(nodes),
+            positive,
+        },
+        leaf => leaf,
+    }
+}
+
+// Hoists prefixes shared by runs of adjacent branches. Returns the nodes that replace
+// the alternation in its parent sequence.
+fn hoist_prefixes(alts: Vec<Vec<AstNode>>) -> Vec<AstNode> {
+    let mut runs: Vec<Vec<Vec<AstNode>>> = vec![];
+    for alt in alts {
+        match runs.last_mut() {
+            Some(run) if !alt.is_empty() && run[0].first() == alt.first() => run.push(alt),
+            _ => runs.push(vec![alt]),
+        }
+    }
+
+    let mut branches: Vec<Vec<AstNode>> = runs
+        .into_iter()
+        .map(|run| {
+            if run.len() == 1 {
+                return run.into_iter().next().unwrap();
+            }
+            let shared = common_prefix_len(&run);
+            let mut prefix = run[0][..shared].to_vec();
+            let suffixes = run
+                .into_iter()
+                .map(|mut alt| alt.split_off(shared))
+                .collect();
+            prefix.extend(simplify_sequence(vec![AstNode::Alternation(suffixes)]));
+            prefix
+        })
+        .collect();
+
+    if branches.len() == 1 {
+        branches.pop().unwrap()
+    } else {
+        vec![AstNode::Alternation(branches)]
+    }
+}
+
+fn common_prefix_len(run: &[Vec<AstNode>]) -> usize {
+    let shortest = run.iter().map(Vec::len).min().unwrap_or(0);
+    (0..shortest)
+        .take_while(|&i| run.iter().all(|alt| alt[i] == run[0][i]))
+        .count()
+}
+
+// Pass 2: merge adjacent literals, bottom-up
+fn merge_literals(nodes: Vec<AstNode>) -> Vec<AstNode> {
+    let mut out: Vec<AstNode> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let node = merge_node(node);
+        let merged = match (out.last_mut(), &node) {
+            (Some(AstNode::Literal(prev)), AstNode::Literal(c)) => {
+                let mut s = prev.to_string();
+                s.push(*c);
+                Some(AstNode::LiteralString(s))
+            }
+            (Some(AstNode::LiteralString(s)), AstNode::Literal(c)) => {
+                s.push(*c);
+                continue;
+            }
+            _ => None,
+        };
+        match merged {
+            Some(merged) => *out.last_mut().unwrap() = merged,
+            None => out.push(node),
+        }
+    }
+    out
+}
+
+fn merge_node(node: AstNode) -> AstNode {
+    match node {
+        AstNode::ZeroOrMore { node, greedy } => AstNode::ZeroOrMore {
+            node: Box::new(merge_operand(*node)),
+            greedy,
+        },
+        AstNode::OneOrMore { node, greedy } => AstNode::OneOrMore {
+            node: Box::new(merge_operand(*node)),
+            greedy,
+        },
+        AstNode::Optional { node, greedy } => AstNode::Optional {
+            node: Box::new(merge_operand(*node)),
+            greedy,
+        },
+        AstNode::Exact { node, count } => AstNode::Exact {
+            node: Box::new(merge_operand(*node)),
+            count,
+        },
+        AstNode::Range {
+            node,
+            min,
+            max,
+            greedy,
+        } => AstNode::Range {
+            node: Box::new(merge_operand(*node)),
+            min,
+            max,
+            greedy,
+        },
+        AstNode::Group {
+            nodes,
+            name,
+            capture,
+            index,
+        } => AstNode::Group {
+            nodes: merge_literals(nodes),
+            name,
+            capture,
+            index,
+        },
+        AstNode::Alternation(alts) => {
+            AstNode::Alternation(alts.into_iter().map(merge_literals).collect())
+        }
+        AstNode::LookAhead { nodes, positive } => AstNode::LookAhead {
+            nodes: merge_literals(nodes),
+            positive,
+        },
+        AstNode::LookBehind { nodes, positive } => AstNode::LookBehind {
+            nodes: merge_literals(nodes),
+            positive,
+        },
+        leaf => leaf,
+    }
+}
+
+// Unwraps a quantified non-capturing group holding a single atom
+fn merge_operand(node: AstNode) -> AstNode {
+    match merge_node(node) {
+        AstNode::Group {
+            mut nodes,
+            capture: false,
+            ..
+        } if nodes.len() == 1 && is_atom(&nodes[0]) => nodes.pop().unwrap(),
+        node => node,
+    }
+}
+
+fn is_atom(node: &AstNode) -> bool {
+    !matches!(
+        node,
+        AstNode::ZeroOrMore { .. }
+            | AstNode::OneOrMore { .. }
+            | AstNode::Optional { .. }
+            | AstNode::Exact { .. }
+            | AstNode::Range { .. }
+            | AstNode::Alternation(_)
+    )
+}
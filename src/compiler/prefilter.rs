@@ -0,0 +1,409 @@
+use super::analysis::{ends_anchored, max_len, min_len, starts_anchored};
+use super::casefold::case_closure;
+use crate::engine::scan;
+use crate::flags::Flags;
+use crate::parser::{AstNode, CharClass};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+// Sets larger than this aren't worth scanning for
+const MAX_FIRST_CHARS: usize = 32;
+
+const KELVIN_SIGN: char = '\u{212A}';
+
+/// The compile-time facts the matcher uses to choose start positions.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ScanPlan {
+    /// Skips to positions where a match may start.
+    pub prefilter: Option<Prefilter>,
+    /// A literal every match contains, used to rule out text without it.
+    pub required: Option<RequiredLiteral>,
+    /// Matches can only start at the start of the text, or of a line in multiline mode.
+    pub start_anchor: Option<Anchor>,
+    /// Matches can only end at the end of the text, or of a line in multiline mode,
+    /// and consume at most this many characters.
+    pub end_anchor: Option<(Anchor, usize)>,
+    /// The fewest characters any match consumes.
+    pub min_len: usize,
+    /// The most characters any match consumes, if bounded.
+    pub max_len: Option<usize>,
+}
+
+/// Where an anchored pattern may match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Anchor {
+    /// Only at the start or end of the whole text.
+    Text,
+    /// At any line boundary (multiline mode).
+    Line,
+}
+
+impl ScanPlan {
+    /// Analyzes an optimized AST.
+    pub(crate) fn new(nodes: &[AstNode], flags: &Flags) -> Self {
+        // Lines ending in `\r` aren't scanned for, so CRLF mode gives up on line anchors
+        let anchor = match (flags.multiline, flags.crlf) {
+            (false, _) => Some(Anchor::Text),
+            (true, false) => Some(Anchor::Line),
+            (true, true) => None,
+        };
+        let max = max_len(nodes);
+        Self {
+            prefilter: Prefilter::new(nodes, flags),
+            required: RequiredLiteral::new(nodes, flags),
+            start_anchor: anchor.filter(|_| starts_anchored(nodes)),
+            end_anchor: anchor.zip(max).filter(|_| ends_anchored(nodes)),
+            min_len: min_len(nodes),
+            max_len: max,
+        }
+    }
+}
+
+/// A fast scan that finds positions where a match may start.
+///
+/// Built at compile time from the pattern's structure, it lets the matcher skip
+/// start positions that cannot begin a match instead of trying every character.
+#[derive(Debug, Clone)]
+pub(crate) enum Prefilter {
+    /// Every match starts with this literal text (case-sensitive only).
+    Prefix(String),
+    /// Every match starts with one of these characters.
+    FirstChars(FirstChars),
+}
+
+/// A small set of characters, with a byte table for the ASCII-only case.
+#[derive(Debug, Clone)]
+pub(crate) struct FirstChars {
+    chars: Vec<char>,
+    ascii: Option<[bool; 128]>,
+    /// The characters as bytes, when there are few enough ASCII ones to scan for
+    /// together.
+    bytes: Option<Vec<u8>>,
+}
+
+impl Prefilter {
+    /// Builds a prefilter for the pattern, if it has a usable required start.
+    pub(crate) fn new(nodes: &[AstNode], flags: &Flags) -> Option<Self> {
+        let ignore_case = flags.ignore_case.unwrap_or(false);
+
+        if !ignore_case {
+            let prefix = literal_prefix(nodes);
+            if prefix.chars().count() > 1 {
+                return Some(Prefilter::Prefix(prefix));
+            }
+        }
+
+        match first_of_sequence(nodes, ignore_case) {
+            First::Chars(chars) => Some(Prefilter::FirstChars(FirstChars::new(chars))),
+            _ => None,
+        }
+    }
+
+    /// Returns the first position at or after `from` where a match may start.
+    pub(crate) fn next_candidate(&self, text: &str, from: usize) -> Option<usize> {
+        let haystack = text.get(from..)?;
+        let offset = match self {
+            Prefilter::Prefix(prefix) => haystack.find(prefix.as_str()),
+            Prefilter::FirstChars(set) => set.find_in(haystack),
+        };
+        offset.map(|i| from + i)
+    }
+}
+
+impl FirstChars {
+    fn new(mut chars: Vec<char>) -> Self {
+        chars.sort_unstable();
+        chars.dedup();
+        let ascii = chars.iter().all(char::is_ascii).then(|| {
+            let mut table = [false; 128];
+            for &c in &chars {
+                table[c as usize] = true;
+            }
+            table
+        });
+        let bytes = (ascii.is_some() && (1..=scan::MAX_NEEDLES).contains(&chars.len()))
+            .then(|| chars.iter().map(|&c| c as u8).collect());
+        Self {
+            chars,
+            ascii,
+            bytes,
+        }
+    }
+
+    fn find_in(&self, haystack: &str) -> Option<usize> {
+        if let Some(bytes) = &self.bytes {
+            return scan::find_any_byte(bytes, haystack.as_bytes());
+        }
+        match &self.ascii {
+            // ASCII bytes never occur inside multi-byte sequences
+            Some(table) => haystack.bytes().position(|b| b < 128 && table[b as usize]),
+            None => haystack
+                .char_indices()
+                .find(|(_, c)| self.chars.binary_search(c).is_ok())
+                .map(|(i, _)| i),
+        }
+    }
+}
+
+/// A literal that appears somewhere inside every match, e.g. the `@` in an email pattern.
+#[derive(Debug, Clone)]
+pub(crate) struct RequiredLiteral {
+    needle: String,
+    ignore_case: bool,
+    /// The most characters a match can consume before reaching the literal, if bounded.
+    max_before: Option<usize>,
+}
+
+impl RequiredLiteral {
+    /// Picks the longest top-level literal that isn't the start of the pattern.
+    ///
+    /// Literals at the start are already covered by `Prefilter`.
+    pub(crate) fn new(nodes: &[AstNode], flags: &Flags) -> Option<Self> {
+        let ignore_case = flags.ignore_case.unwrap_or(false);
+        let mut best: Option<Self> = None;
+        collect_required(nodes, Some(0), &mut |needle, max_before| {
+            // Folded search is only exact for ASCII text, and 'k' also folds to U+212A
+            if ignore_case
+                && !needle
+                    .chars()
+                    .all(|c| c.is_ascii() && !c.eq_ignore_ascii_case(&'k'))
+            {
+                return;
+            }
+            if max_before == Some(0) {
+                return;
+            }
+            if best.as_ref().is_none_or(|b| needle.len() > b.needle.len()) {
+                best = Some(Self {
+                    needle: needle.to_string(),
+                    ignore_case,
+                    max_before,
+                });
+            }
+        });
+        best
+    }
+
+    /// Returns the first occurrence of the literal at or after `from`.
+    pub(crate) fn find(&self, text: &str, from: usize) -> Option<usize> {
+        let haystack = text.get(from..)?;
+        let offset = if self.ignore_case {
+            scan::find_ignore_ascii_case(self.needle.as_bytes(), haystack.as_bytes())
+        } else {
+            haystack.find(self.needle.as_str())
+        };
+        offset.map(|i| from + i)
+    }
+
+    /// Returns the earliest byte position a match containing the literal at `found` may start.
+    pub(crate) fn earliest_start(&self, found: usize) -> usize {
+        match self.max_before {
+            // A character is at most 4 bytes in UTF-8
+            Some(chars) => found.saturating_sub(chars.saturating_mul(4)),
+            None => 0,
+        }
+    }
+}
+
+// Reports each top-level literal with the bound on characters consumed before it
+fn collect_required(
+    nodes: &[AstNode],
+    mut before: Option<usize>,
+    report: &mut dyn FnMut(&str, Option<usize>),
+) -> Option<usize> {
+    for node in nodes {
+        match node {
+            AstNode::Literal(c) => report(c.encode_utf8(&mut [0; 4]), before),
+            AstNode::LiteralString(s) => report(s, before),
+            AstNode::Group { nodes, .. } => {
+                before = collect_required(nodes, before, report);
+                continue;
+            }
+            _ => {}
+        }
+        before = before
+            .zip(max_len(core::slice::from_ref(node)))
+            .map(|(a, b)| a + b);
+    }
+    before
+}
+
+// Collects the literal text every match must begin with
+pub(super) fn literal_prefix(nodes: &[AstNode]) -> String {
+    let mut prefix = String::new();
+    collect_prefix(nodes, &mut prefix);
+    prefix
+}
+
+// Returns true if the whole sequence was literal, so the caller may continue past it
+fn collect_prefix(nodes: &[AstNode], prefix: &mut String) -> bool {
+    for node in nodes {
+        match node {
+            AstNode::Literal(c) => prefix.push(*c),
+            AstNode::LiteralString(s) => prefix.push_str(s),
+            AstNode::Group { nodes, .. } => {
+                if !collect_prefix(nodes, prefix) {
+                    return false;
+                }
+            }
+            node if is_zero_width(node) => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// What is known about the first character consumed by a sequence.
+enum First {
+    /// A character from the set is always consumed first.
+    Chars(Vec<char>),
+    /// Either a character from the set is consumed first, or nothing is consumed.
+    Maybe(Vec<char>),
+    /// Nothing is ever consumed.
+    Empty,
+    /// Anything could come first.
+    Unknown,
+}
+
+fn first_of_sequence(nodes: &[AstNode], ignore_case: bool) -> First {
+    let mut maybe: Vec<char> = vec![];
+    for node in nodes {
+        match first_of_node(node, ignore_case) {
+            First::Chars(chars) => {
+                maybe.extend(chars);
+                return First::Chars(maybe);
+            }
+            First::Maybe(chars) => maybe.extend(chars),
+            First::Empty => {}
+            First::Unknown => return First::Unknown,
+        }
+        if maybe.len() > MAX_FIRST_CHARS {
+            return First::Unknown;
+        }
+    }
+    if maybe.is_empty() {
+        First::Empty
+    } else {
+        First::Maybe(maybe)
+    }
+}
+
+fn first_of_node(node: &AstNode, ignore_case: bool) -> First {
+    match node {
+        AstNode::Literal(c) => literal_first(*c, ignore_case),
+        AstNode::LiteralString(s) => match s.chars().next() {
+            Some(c) => literal_first(c, ignore_case),
+            None => First::Empty,
+        },
+        AstNode::CharClass(class) => match class_chars(class, ignore_case) {
+            Some(chars) => First::Chars(chars),
+            None => First::Unknown,
+        },
+        AstNode::Group { nodes, .. } => first_of_sequence(nodes, ignore_case),
+        AstNode::Alternation(alts) => {
+            let mut chars = vec![];
+            let mut optional = false;
+            for alt in alts {
+                match first_of_sequence(alt, ignore_case) {
+                    First::Chars(c) => chars.extend(c),
+                    First::Maybe(c) => {
+                        chars.extend(c);
+                        optional = true;
+                    }
+                    First::Empty => optional = true,
+                    First::Unknown => return First::Unknown,
+                }
+            }
+            match (optional, chars.is_empty()) {
+                (true, true) => First::Empty,
+                (true, false) => First::Maybe(chars),
+                (false, _) => First::Chars(chars),
+            }
+        }
+        AstNode::OneOrMore { node, .. } => first_of_node(node, ignore_case),
+        AstNode::Exact { node, count } if *count > 0 => first_of_node(node, ignore_case),
+        AstNode::Range { node, min, .. } if *min > 0 => first_of_node(node, ignore_case),
+        AstNode::ZeroOrMore { node, .. }
+        | AstNode::Optional { node, .. }
+        | AstNode::Exact { node, .. }
+        | AstNode::Range { node, .. } => match first_of_node(node, ignore_case) {
+            First::Chars(chars) | First::Maybe(chars) => First::Maybe(chars),
+            other => other,
+        },
+        AstNode::Backref(_) => First::Unknown,
+        node if is_zero_width(node) => First::Empty,
+        _ => First::Unknown,
+    }
+}
+
+fn literal_first(c: char, ignore_case: bool) -> First {
+    if !ignore_case {
+        return First::Chars(vec![c]);
+    }
+    match case_variants(c) {
+        Some(chars) => First::Chars(chars),
+        None => First::Unknown,
+    }
+}
+
+// All characters that compare equal to `c` under case folding, for ASCII `c`
+fn case_variants(c: char) -> Option<Vec<char>> {
+    if !c.is_ascii() {
+        return None;
+    }
+    let mut chars = vec![c.to_ascii_lowercase(), c.to_ascii_uppercase()];
+    if c.eq_ignore_ascii_case(&'k') {
+        chars.push(KELVIN_SIGN);
+    }
+    Some(chars)
+}
+
+fn class_chars(class: &CharClass, ignore_case: bool) -> Option<Vec<char>> {
+    match class {
+        CharClass::Digit => Some(('0'..='9').collect()),
+        CharClass::Octal => Some(('0'..='7').collect()),
+        CharClass::Set {
+            chars,
+            negated: false,
+        } => {
+            // Folding can reach non-ASCII characters, like 'K' for 'k'
+            let chars = if ignore_case {
+                case_closure(chars)
+            } else {
+                chars.clone()
+            };
+            let total: usize = chars
+                .iter()
+                .map(|r| (r.end as usize).saturating_sub(r.start as usize) + 1)
+                .sum();
+            if total > MAX_FIRST_CHARS {
+                return None;
+            }
+            Some(chars.iter().flat_map(|r| r.start..=r.end).collect())
+        }
+        _ => None,
+    }
+}
+
+fn is_zero_width(node: &AstNode) -> bool {
+    matches!(
+        node,
+        AstNode::StartAnchor
+            | AstNode::EndAnchor
+            | AstNode::StartText
+            | AstNode::EndText
+            | AstNode::Line(..)
+            | AstNode::Column(..)
+            | AstNode::Cursor
+            | AstNode::Visual
+            | AstNode::Mark(..)
+            | AstNode::WordBoundary
+            | AstNode::StartWord
+            | AstNode::EndWord
+            | AstNode::SetMatchStart
+            | AstNode::SetMatchEnd
+            | AstNode::LookAhead { .. }
+            | AstNode::LookBehind { .. }
+    )
+}
@@ -0,0 +1,188 @@
+use super::{BackrefPolicy, CompileOptions};
+use crate::Set;
+use crate::errors::CompileError;
+use crate::parser::{AstNode, MAX_BACKREF, MAX_NESTING_DEPTH};
+use alloc::format;
+use alloc::string::{String, ToString};
+
+/// Checks an AST for constructs that are syntactically valid but cannot be compiled.
+///
+/// This runs on parsed patterns and on ASTs passed to `Regex::from_ast`, so it also
+/// checks invariants the parser guarantees but hand-built trees may violate.
+///
+/// # Errors
+///
+/// * `CompileError::InvalidQuantifier` if a bounded quantifier has a minimum greater
+///   than its maximum, or a count above `options.repetition_limit`.
+/// * `CompileError::DuplicateGroupName` if two groups share a name.
+/// * `CompileError::InvalidGroup` if capture group indices are missing, zero, or repeated,
+///   or a non-capturing group is named.
+/// * `CompileError::InvalidBackref` if a backreference comes before the group it
+///   refers to closes, or there's no such group, under [`BackrefPolicy::Strict`],
+///   or if it refers to a group after the ninth, which the syntax can't express.
+/// * `CompileError::ExceededLimits` if groups, lookarounds, quantifiers and
+///   alternations are nested more than `options.nest_limit` deep, or
+///   [`MAX_NESTING_DEPTH`] if that's lower.
+pub(crate) fn validate(nodes: &[AstNode], options: &CompileOptions) -> Result<(), CompileError> {
+    let mut validator = Validator {
+        options,
+        names: Set::new(),
+        indices: Set::new(),
+        closed: Set::new(),
+        depth: 0,
+    };
+    validator.validate(nodes)
+}
+
+struct Validator<'a> {
+    options: &'a CompileOptions,
+    names: Set<String>,
+    indices: Set<usize>,
+    // The groups that have closed so far, in pattern order
+    closed: Set<usize>,
+    depth: usize,
+}
+
+impl Validator<'_> {
+    fn validate(&mut self, nodes: &[AstNode]) -> Result<(), CompileError> {
+        for node in nodes {
+            self.validate_node(node)?;
+        }
+        Ok(())
+    }
+
+    fn validate_node(&mut self, node: &AstNode) -> Result<(), CompileError> {
+        match node {
+            AstNode::Exact { node, count } => {
+                self.check_count(*count)?;
+                self.nested(|v| v.validate_node(node))
+            }
+            AstNode::Range { node, min, max, .. } => {
+                self.check_count(*min)?;
+                if let Some(max) = max {
+                    self.check_count(*max)?;
+                    if min > max {
+                        return Err(CompileError::InvalidQuantifier(format!(
+                            "{{{},{}}}: minimum exceeds maximum",
+                            min, max
+                        )));
+                    }
+                }
+                self.nested(|v| v.validate_node(node))
+            }
+            AstNode::ZeroOrMore { node, .. }
+            | AstNode::OneOrMore { node, .. }
+            | AstNode::Optional { node, .. } => self.nested(|v| v.validate_node(node)),
+            AstNode::Group {
+                nodes,
+                name,
+                capture,
+                index,
+            } => {
+                self.check_group(name.as_deref(), *capture, *index)?;
+                self.nested(|v| v.validate(nodes))?;
+                if let Some(index) = index {
+                    self.closed.insert(*index);
+                }
+                Ok(())
+            }
+            // `\12` would read back as `\1` followed by `2`
+            AstNode::Backref(index) if *index > MAX_BACKREF => {
+                Err(CompileError::InvalidBackref(format!(
+                    "\\{} can't be written; only groups 1 to 9 can be referred to",
+                    index
+                )))
+            }
+            AstNode::Backref(index)
+                if self.options.backrefs == BackrefPolicy::Strict
+                    && !self.closed.contains(index) =>
+            {
+                let problem = if self.indices.contains(index) {
+                    "refers to a group it's inside of"
+                } else {
+                    "refers to a group that comes after it or doesn't exist"
+                };
+                Err(CompileError::InvalidBackref(format!(
+                    "\\{} {}",
+                    index, problem
+                )))
+            }
+            AstNode::LookAhead { nodes, .. } | AstNode::LookBehind { nodes, .. } => {
+                self.nested(|v| v.validate(nodes))
+            }
+            AstNode::Alternation(alts) => self.nested(|v| {
+                for alt in alts {
+                    v.validate(alt)?;
+                }
+                Ok(())
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Every node that contains others counts toward the depth, so hand-built trees
+    // can't nest deep enough to overflow the stack of a later pass
+    fn nested(
+        &mut self,
+        validate: impl FnOnce(&mut Self) -> Result<(), CompileError>,
+    ) -> Result<(), CompileError> {
+        let limit = self.options.nest_limit.min(MAX_NESTING_DEPTH);
+        if self.depth == limit {
+            return Err(nesting_exceeded(limit));
+        }
+        self.depth += 1;
+        let result = validate(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn check_count(&self, count: usize) -> Result<(), CompileError> {
+        if count > self.options.repetition_limit {
+            return Err(CompileError::InvalidQuantifier(format!(
+                "repetition count {} exceeds the limit of {}",
+                count, self.options.repetition_limit
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_group(
+        &mut self,
+        name: Option<&str>,
+        capture: bool,
+        index: Option<usize>,
+    ) -> Result<(), CompileError> {
+        match (capture, index) {
+            (true, None) | (true, Some(0)) => {
+                return Err(CompileError::InvalidGroup(
+                    "capture group needs an index of 1 or more".to_string(),
+                ));
+            }
+            (true, Some(i)) => {
+                if !self.indices.insert(i) {
+                    return Err(CompileError::InvalidGroup(format!(
+                        "capture group index {} is used twice",
+                        i
+                    )));
+                }
+            }
+            (false, _) if name.is_some() => {
+                return Err(CompileError::InvalidGroup(
+                    "named group must be capturing".to_string(),
+                ));
+            }
+            (false, _) => {}
+        }
+
+        if let Some(name) = name
+            && !self.names.insert(name.to_string())
+        {
+            return Err(CompileError::DuplicateGroupName(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn nesting_exceeded(limit: usize) -> CompileError {
+    CompileError::ExceededLimits(format!("pattern nested more than {} deep", limit))
+}
@@ -0,0 +1,161 @@
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use crate::captures::Match;
+use crate::regex::{FindAllIterator, Regex};
+
+/// A place in a [`Document`], as a line and a byte offset into that line, both
+/// counting from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// The line, counting from 0.
+    pub line: usize,
+    /// The byte offset from the start of the line.
+    pub column: usize,
+}
+
+/// A match in a [`Document`]. A match spanning lines starts and ends on different
+/// lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocumentMatch {
+    /// Where the match starts.
+    pub start: Position,
+    /// Where the match ends (exclusive).
+    pub end: Position,
+}
+
+/// Text split into lines, searched as a whole with matches reported as lines and
+/// columns.
+///
+/// Lines end at each `\n`, and a `\r` before it belongs to no line. The whole text
+/// is searched at once, so a match can span lines, and `^` and `$` match at line
+/// ends only under the [`multiline`](crate::Flags::multiline) flag, as for any text.
+///
+/// ```
+/// use monster_regex::{Document, Flags, Position, Regex};
+///
+/// let doc = Document::from_lines(&["let x = 1;", "let y =", "  2;"]);
+/// let re = Regex::new(r"=\s*\d", Flags::default()).unwrap();
+/// let spans: Vec<_> = doc
+///     .find_all(&re)
+///     .map(|m| (m.start.line, m.start.column, m.end.line, m.end.column))
+///     .collect();
+/// assert_eq!(spans, [(0, 6, 0, 9), (1, 6, 2, 3)]);
+/// assert_eq!(doc.offset(Position { line: 2, column: 2 }), Some(21));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Document<'a> {
+    text: Cow<'a, str>,
+    // The offset each line starts at
+    line_starts: Vec<usize>,
+}
+
+impl<'a> Document<'a> {
+    /// Creates a document over a whole text.
+    pub fn new(text: &'a str) -> Self {
+        Document::with_text(Cow::Borrowed(text))
+    }
+
+    /// Creates a document from lines without their line endings, joined with `\n`.
+    pub fn from_lines<S: AsRef<str>>(lines: &[S]) -> Document<'static> {
+        let text = lines
+            .iter()
+            .map(AsRef::as_ref)
+            .collect::<Vec<_>>()
+            .join("\n");
+        Document::with_text(Cow::Owned(text))
+    }
+
+    fn with_text(text: Cow<'a, str>) -> Self {
+        let line_starts = core::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Document { text, line_starts }
+    }
+
+    /// Returns the whole text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the number of lines. Text ending in `\n` has an empty last line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns a line without its line ending, or `None` past the last line.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line)?;
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.text.len(), |next| next - 1);
+        let content = &self.text[start..end];
+        Some(content.strip_suffix('\r').unwrap_or(content))
+    }
+
+    /// Returns the position of a byte offset into the text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset is past the end of the text.
+    pub fn position(&self, offset: usize) -> Position {
+        assert!(
+            offset <= self.text.len(),
+            "offset {} is past the end of the text",
+            offset
+        );
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        Position {
+            line,
+            column: offset - self.line_starts[line],
+        }
+    }
+
+    /// Returns the byte offset into the text of a position, or `None` if the line
+    /// doesn't exist or is shorter than the column.
+    pub fn offset(&self, position: Position) -> Option<usize> {
+        let start = *self.line_starts.get(position.line)?;
+        let end = self
+            .line_starts
+            .get(position.line + 1)
+            .map_or(self.text.len(), |next| next - 1);
+        (position.column <= end - start).then_some(start + position.column)
+    }
+
+    /// Converts a match in the text to positions.
+    pub fn locate(&self, m: &Match) -> DocumentMatch {
+        DocumentMatch {
+            start: self.position(m.start),
+            end: self.position(m.end),
+        }
+    }
+
+    /// Finds the first match in the document.
+    pub fn find(&self, regex: &Regex) -> Option<DocumentMatch> {
+        regex.find(&self.text).map(|m| self.locate(&m))
+    }
+
+    /// Returns an iterator over all non-overlapping matches in the document.
+    pub fn find_all<'d>(&'d self, regex: &'d Regex) -> DocumentMatches<'d> {
+        DocumentMatches {
+            document: self,
+            matches: regex.find_all(&self.text),
+        }
+    }
+}
+
+/// An iterator over the matches in a [`Document`].
+pub struct DocumentMatches<'d> {
+    document: &'d Document<'d>,
+    matches: FindAllIterator<'d>,
+}
+
+impl Iterator for DocumentMatches<'_> {
+    type Item = DocumentMatch;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = self.matches.next()?;
+        Some(self.document.locate(&m))
+    }
+}
@@ -0,0 +1,315 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, TryLockError};
+
+use super::nfa::{CharKind, Nfa, StateId};
+use crate::Map;
+use crate::flags::Flags;
+use crate::parser::AstNode;
+
+// Once the cache holds this many states it's thrown away and rebuilt on demand
+const MAX_CACHED_STATES: usize = 4096;
+const UNKNOWN: u32 = u32::MAX;
+
+/// A DFA built lazily from the pattern's NFA while searching.
+///
+/// Each DFA state is a set of NFA states together with the kind of the previous
+/// character, which is enough to decide every assertion once the next character is
+/// known. States and transitions are created the first time a search needs them and
+/// cached across searches. Without std there's no lock to share the cache through,
+/// so each search starts from an empty one.
+///
+/// A second DFA runs the reversed pattern backwards through the text, to find where
+/// matches start.
+#[derive(Debug)]
+pub(crate) struct LazyDfa {
+    nfa: Nfa,
+    reverse: Nfa,
+    one_pass: bool,
+    #[cfg(feature = "std")]
+    cache: Mutex<Cache>,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    anchored: Dfa,
+    unanchored: Dfa,
+    reverse: Dfa,
+}
+
+#[derive(Debug, Default)]
+struct Dfa {
+    states: Vec<DfaState>,
+    ids: Map<(Vec<StateId>, CharKind), usize>,
+}
+
+#[derive(Debug)]
+struct DfaState {
+    core: Vec<StateId>,
+    before: CharKind,
+    ascii: Box<[u32; 128]>,
+    other: Map<char, usize>,
+    accepts: [Option<bool>; CharKind::COUNT],
+}
+
+impl Dfa {
+    fn intern(&mut self, core: Vec<StateId>, before: CharKind) -> usize {
+        if let Some(&id) = self.ids.get(&(core.clone(), before)) {
+            return id;
+        }
+        self.states.push(DfaState {
+            core: core.clone(),
+            before,
+            ascii: Box::new([UNKNOWN; 128]),
+            other: Map::new(),
+            accepts: [None; CharKind::COUNT],
+        });
+        let id = self.states.len() - 1;
+        self.ids.insert((core, before), id);
+        id
+    }
+
+    fn cached_transition(&self, id: usize, c: char) -> Option<usize> {
+        let state = &self.states[id];
+        if c.is_ascii() {
+            let next = state.ascii[c as usize];
+            (next != UNKNOWN).then_some(next as usize)
+        } else {
+            state.other.get(&c).copied()
+        }
+    }
+
+    fn cache_transition(&mut self, id: usize, c: char, next: usize) {
+        let state = &mut self.states[id];
+        if c.is_ascii() {
+            state.ascii[c as usize] = next as u32;
+        } else {
+            state.other.insert(c, next);
+        }
+    }
+
+    // Switches to the same state in another DFA
+    fn carry(&self, id: usize, to: &mut Dfa) -> usize {
+        let state = &self.states[id];
+        to.intern(state.core.clone(), state.before)
+    }
+
+    fn accepts(&mut self, nfa: &Nfa, id: usize, after: CharKind) -> bool {
+        let state = &self.states[id];
+        if let Some(accepts) = state.accepts[after as usize] {
+            return accepts;
+        }
+        let (_, matched) = nfa.closure(&state.core, state.before, after);
+        self.states[id].accepts[after as usize] = Some(matched);
+        matched
+    }
+
+    fn transition(&mut self, nfa: &Nfa, id: usize, c: char, anchored: bool) -> usize {
+        if let Some(next) = self.cached_transition(id, c) {
+            return next;
+        }
+
+        let state = &self.states[id];
+        let after = CharKind::of(Some(c));
+        let (consuming, _) = nfa.closure(&state.core, state.before, after);
+        let mut core: Vec<StateId> = consuming
+            .into_iter()
+            .filter_map(|s| nfa.step(s, c))
+            .collect();
+        // An unanchored search may start a new match attempt after any character
+        if !anchored {
+            core.push(nfa.start());
+        }
+        core.sort_unstable();
+        core.dedup();
+
+        if self.states.len() >= MAX_CACHED_STATES {
+            *self = Dfa::default();
+            return self.intern(core, after);
+        }
+        let next = self.intern(core, after);
+        self.cache_transition(id, c, next);
+        next
+    }
+}
+
+impl LazyDfa {
+    /// Builds the DFA, or returns `None` if the pattern isn't regular.
+    pub(crate) fn new(nodes: &[AstNode], flags: &Flags) -> Option<Self> {
+        let nfa = Nfa::new(nodes, flags)?;
+        Some(LazyDfa {
+            one_pass: nfa.is_one_pass(),
+            reverse: Nfa::reversed(nodes, flags)?,
+            nfa,
+            #[cfg(feature = "std")]
+            cache: Mutex::new(Cache::default()),
+        })
+    }
+
+    pub(crate) fn nfa(&self) -> &Nfa {
+        &self.nfa
+    }
+
+    /// Whether the backtracking matcher decides any start position without
+    /// backtracking, making a DFA check of an anchored start redundant.
+    pub(crate) fn is_one_pass(&self) -> bool {
+        self.one_pass
+    }
+
+    /// Returns the end of the match that finishes earliest among matches starting
+    /// at or after `start`, or `None` if there is no match at all.
+    pub(crate) fn earliest_end(&self, text: &str, start: usize) -> Option<usize> {
+        self.with_cache(|cache| self.run(&mut cache.unanchored, text, start, false))
+    }
+
+    /// Returns where the leftmost match starting at or after `start` begins, counting
+    /// only matches that consume no text past `limit`.
+    ///
+    /// That match starts no later than the earliest end of any match, so the forward
+    /// pass stops starting attempts there and runs on to the last end of the ones
+    /// already started. The reversed pattern, run back from that end, then finds the
+    /// first position a match starts at. Both passes are linear in the text.
+    pub(crate) fn leftmost_start(&self, text: &str, start: usize, limit: usize) -> Option<usize> {
+        self.with_cache(|cache| {
+            let (first_end, last_end) = self.last_end(cache, text, start, limit)?;
+
+            let dfa = &mut cache.reverse;
+            let before = CharKind::of(text[last_end..].chars().next());
+            let mut current = dfa.intern(vec![self.reverse.start()], before);
+            let mut leftmost = None;
+            let mut pos = last_end;
+            loop {
+                let c = text[..pos].chars().next_back();
+                if pos <= first_end && dfa.accepts(&self.reverse, current, CharKind::of(c)) {
+                    leftmost = Some(pos);
+                }
+                match c {
+                    Some(c) if pos > start => {
+                        current = dfa.transition(&self.reverse, current, c, false);
+                        pos -= c.len_utf8();
+                    }
+                    _ => return leftmost,
+                }
+            }
+        })
+    }
+
+    // Returns the earliest end of any match starting at or after `start`, and the
+    // last end of a match starting no later than that
+    fn last_end(
+        &self,
+        cache: &mut Cache,
+        text: &str,
+        start: usize,
+        limit: usize,
+    ) -> Option<(usize, usize)> {
+        let before = CharKind::of(text[..start].chars().next_back());
+        let mut current = cache.unanchored.intern(vec![self.nfa.start()], before);
+        let mut ends: Option<(usize, usize)> = None;
+        let mut pos = start;
+        loop {
+            let c = text[pos..].chars().next();
+            let dfa = match ends {
+                Some(_) => &mut cache.anchored,
+                None => &mut cache.unanchored,
+            };
+            if dfa.accepts(&self.nfa, current, CharKind::of(c)) {
+                match &mut ends {
+                    Some((_, last)) => *last = pos,
+                    None => {
+                        ends = Some((pos, pos));
+                        // No attempt starting later can be the leftmost match
+                        current = cache.unanchored.carry(current, &mut cache.anchored);
+                    }
+                }
+            }
+            let dfa = match ends {
+                Some(_) => &mut cache.anchored,
+                None => &mut cache.unanchored,
+            };
+            match c {
+                Some(c) if pos < limit => {
+                    current = dfa.transition(&self.nfa, current, c, ends.is_some());
+                    if dfa.states[current].core.is_empty() {
+                        return ends;
+                    }
+                    pos += c.len_utf8();
+                }
+                _ => return ends,
+            }
+        }
+    }
+
+    /// Checks whether some match starts exactly at `start`.
+    pub(crate) fn matches_at(&self, text: &str, start: usize) -> bool {
+        self.with_cache(|cache| self.run(&mut cache.anchored, text, start, true))
+            .is_some()
+    }
+
+    /// Checks whether a match attempt starting anywhere from `start` to `last_start`
+    /// is still running at the end of `text`, so that more text could change what
+    /// matches there.
+    #[cfg(feature = "std")]
+    pub(crate) fn is_live_at_end(&self, text: &str, start: usize, last_start: usize) -> bool {
+        self.with_cache(|cache| {
+            let before = CharKind::of(text[..start].chars().next_back());
+            let mut current = cache.unanchored.intern(vec![self.nfa.start()], before);
+            let mut anchored = false;
+            for (offset, c) in text[start..].char_indices() {
+                let next = start + offset + c.len_utf8();
+                // No attempts start after `last_start`, so carry on without adding any
+                if !anchored && next > last_start {
+                    anchored = true;
+                    current = cache.unanchored.carry(current, &mut cache.anchored);
+                }
+                let dfa = if anchored {
+                    &mut cache.anchored
+                } else {
+                    &mut cache.unanchored
+                };
+                current = dfa.transition(&self.nfa, current, c, anchored);
+                if dfa.states[current].core.is_empty() {
+                    return false;
+                }
+            }
+            true
+        })
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn with_cache<R>(&self, f: impl FnOnce(&mut Cache) -> R) -> R {
+        f(&mut Cache::default())
+    }
+
+    #[cfg(feature = "std")]
+    fn with_cache<R>(&self, f: impl FnOnce(&mut Cache) -> R) -> R {
+        match self.cache.try_lock() {
+            Ok(mut cache) => f(&mut cache),
+            // Another thread is searching; use a scratch cache rather than wait
+            Err(TryLockError::WouldBlock) => f(&mut Cache::default()),
+            Err(TryLockError::Poisoned(poisoned)) => {
+                let mut cache = poisoned.into_inner();
+                *cache = Cache::default();
+                f(&mut cache)
+            }
+        }
+    }
+
+    fn run(&self, dfa: &mut Dfa, text: &str, start: usize, anchored: bool) -> Option<usize> {
+        let before = CharKind::of(text[..start].chars().next_back());
+        let mut current = dfa.intern(vec![self.nfa.start()], before);
+        for (offset, c) in text[start..].char_indices() {
+            if dfa.accepts(&self.nfa, current, CharKind::of(Some(c))) {
+                return Some(start + offset);
+            }
+            current = dfa.transition(&self.nfa, current, c, anchored);
+            if dfa.states[current].core.is_empty() {
+                return None;
+            }
+        }
+        dfa.accepts(&self.nfa, current, CharKind::Edge)
+            .then_some(text.len())
+    }
+}
@@ -0,0 +1,740 @@
+// Extended grapheme cluster boundaries, the user-perceived characters `.` matches
+// under `DotMode::Grapheme`, following the rules of UAX #29 with Unicode 14 data.
+
+use core::cmp::Ordering;
+
+/// A character's `Grapheme_Cluster_Break` property. Characters missing from the
+/// table are `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Break {
+    Cr,
+    Lf,
+    Control,
+    Extend,
+    Zwj,
+    RegionalIndicator,
+    Prepend,
+    SpacingMark,
+    L,
+    V,
+    T,
+    Lv,
+    Lvt,
+    Other,
+}
+
+/// Returns where the cluster starting at `start` ends, or `None` at the end of the
+/// text.
+pub(crate) fn cluster_end(text: &str, start: usize) -> Option<usize> {
+    let mut chars = text[start..].char_indices();
+    let (_, first) = chars.next()?;
+    let mut prev = break_of(first);
+    // Whether the cluster so far ends in a pictograph and any extenders after it,
+    // and whether a joiner followed those
+    let mut pictograph = is_pictographic(first);
+    let mut joined_pictograph = false;
+    // Whether the regional indicators just before pair up evenly
+    let mut unpaired_indicator = prev == Break::RegionalIndicator;
+    for (offset, c) in chars {
+        let next = break_of(c);
+        let joins = match (prev, next) {
+            (Break::Cr, Break::Lf) => true,
+            (Break::Cr | Break::Lf | Break::Control, _)
+            | (_, Break::Cr | Break::Lf | Break::Control) => false,
+            (Break::L, Break::L | Break::V | Break::Lv | Break::Lvt)
+            | (Break::Lv | Break::V, Break::V | Break::T)
+            | (Break::Lvt | Break::T, Break::T)
+            | (_, Break::Extend | Break::Zwj | Break::SpacingMark)
+            | (Break::Prepend, _) => true,
+            (Break::Zwj, _) => joined_pictograph && is_pictographic(c),
+            (Break::RegionalIndicator, Break::RegionalIndicator) => unpaired_indicator,
+            _ => false,
+        };
+        if !joins {
+            return Some(start + offset);
+        }
+        joined_pictograph = pictograph && next == Break::Zwj;
+        pictograph = is_pictographic(c) || (pictograph && next == Break::Extend);
+        unpaired_indicator = next == Break::RegionalIndicator && !unpaired_indicator;
+        prev = next;
+    }
+    Some(text.len())
+}
+
+fn break_of(c: char) -> Break {
+    // Hangul syllables alternate between LV and LVT, one LV every 28
+    if ('\u{AC00}'..='\u{D7A3}').contains(&c) {
+        return if (u32::from(c) - 0xAC00) % 28 == 0 {
+            Break::Lv
+        } else {
+            Break::Lvt
+        };
+    }
+    match BREAKS.binary_search_by(|&(start, end, _)| locate(start, end, c)) {
+        Ok(i) => BREAKS[i].2,
+        Err(_) => Break::Other,
+    }
+}
+
+fn is_pictographic(c: char) -> bool {
+    PICTOGRAPHIC
+        .binary_search_by(|&(start, end)| locate(start, end, c))
+        .is_ok()
+}
+
+// Orders the range `start..=end` against `c`, equal if it holds it
+fn locate(start: char, end: char, c: char) -> Ordering {
+    if end < c {
+        Ordering::Less
+    } else if start > c {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+// Generated from the Unicode 14 GraphemeBreakProperty.txt, without the Hangul
+// syllables
+#[rustfmt::skip]
+const BREAKS: &[(char, char, Break)] = &[
+    ('\u{0}', '\u{9}', Break::Control),
+    ('\u{A}', '\u{A}', Break::Lf),
+    ('\u{B}', '\u{C}', Break::Control),
+    ('\u{D}', '\u{D}', Break::Cr),
+    ('\u{E}', '\u{1F}', Break::Control),
+    ('\u{7F}', '\u{9F}', Break::Control),
+    ('\u{AD}', '\u{AD}', Break::Control),
+    ('\u{300}', '\u{36F}', Break::Extend),
+    ('\u{483}', '\u{489}', Break::Extend),
+    ('\u{591}', '\u{5BD}', Break::Extend),
+    ('\u{5BF}', '\u{5BF}', Break::Extend),
+    ('\u{5C1}', '\u{5C2}', Break::Extend),
+    ('\u{5C4}', '\u{5C5}', Break::Extend),
+    ('\u{5C7}', '\u{5C7}', Break::Extend),
+    ('\u{600}', '\u{605}', Break::Prepend),
+    ('\u{610}', '\u{61A}', Break::Extend),
+    ('\u{61C}', '\u{61C}', Break::Control),
+    ('\u{64B}', '\u{65F}', Break::Extend),
+    ('\u{670}', '\u{670}', Break::Extend),
+    ('\u{6D6}', '\u{6DC}', Break::Extend),
+    ('\u{6DD}', '\u{6DD}', Break::Prepend),
+    ('\u{6DF}', '\u{6E4}', Break::Extend),
+    ('\u{6E7}', '\u{6E8}', Break::Extend),
+    ('\u{6EA}', '\u{6ED}', Break::Extend),
+    ('\u{70F}', '\u{70F}', Break::Prepend),
+    ('\u{711}', '\u{711}', Break::Extend),
+    ('\u{730}', '\u{74A}', Break::Extend),
+    ('\u{7A6}', '\u{7B0}', Break::Extend),
+    ('\u{7EB}', '\u{7F3}', Break::Extend),
+    ('\u{7FD}', '\u{7FD}', Break::Extend),
+    ('\u{816}', '\u{819}', Break::Extend),
+    ('\u{81B}', '\u{823}', Break::Extend),
+    ('\u{825}', '\u{827}', Break::Extend),
+    ('\u{829}', '\u{82D}', Break::Extend),
+    ('\u{859}', '\u{85B}', Break::Extend),
+    ('\u{890}', '\u{891}', Break::Prepend),
+    ('\u{898}', '\u{89F}', Break::Extend),
+    ('\u{8CA}', '\u{8E1}', Break::Extend),
+    ('\u{8E2}', '\u{8E2}', Break::Prepend),
+    ('\u{8E3}', '\u{902}', Break::Extend),
+    ('\u{903}', '\u{903}', Break::SpacingMark),
+    ('\u{93A}', '\u{93A}', Break::Extend),
+    ('\u{93B}', '\u{93B}', Break::SpacingMark),
+    ('\u{93C}', '\u{93C}', Break::Extend),
+    ('\u{93E}', '\u{940}', Break::SpacingMark),
+    ('\u{941}', '\u{948}', Break::Extend),
+    ('\u{949}', '\u{94C}', Break::SpacingMark),
+    ('\u{94D}', '\u{94D}', Break::Extend),
+    ('\u{94E}', '\u{94F}', Break::SpacingMark),
+    ('\u{951}', '\u{957}', Break::Extend),
+    ('\u{962}', '\u{963}', Break::Extend),
+    ('\u{981}', '\u{981}', Break::Extend),
+    ('\u{982}', '\u{983}', Break::SpacingMark),
+    ('\u{9BC}', '\u{9BC}', Break::Extend),
+    ('\u{9BE}', '\u{9BE}', Break::Extend),
+    ('\u{9BF}', '\u{9C0}', Break::SpacingMark),
+    ('\u{9C1}', '\u{9C4}', Break::Extend),
+    ('\u{9C7}', '\u{9C8}', Break::SpacingMark),
+    ('\u{9CB}', '\u{9CC}', Break::SpacingMark),
+    ('\u{9CD}', '\u{9CD}', Break::Extend),
+    ('\u{9D7}', '\u{9D7}', Break::Extend),
+    ('\u{9E2}', '\u{9E3}', Break::Extend),
+    ('\u{9FE}', '\u{9FE}', Break::Extend),
+    ('\u{A01}', '\u{A02}', Break::Extend),
+    ('\u{A03}', '\u{A03}', Break::SpacingMark),
+    ('\u{A3C}', '\u{A3C}', Break::Extend),
+    ('\u{A3E}', '\u{A40}', Break::SpacingMark),
+    ('\u{A41}', '\u{A42}', Break::Extend),
+    ('\u{A47}', '\u{A48}', Break::Extend),
+    ('\u{A4B}', '\u{A4D}', Break::Extend),
+    ('\u{A51}', '\u{A51}', Break::Extend),
+    ('\u{A70}', '\u{A71}', Break::Extend),
+    ('\u{A75}', '\u{A75}', Break::Extend),
+    ('\u{A81}', '\u{A82}', Break::Extend),
+    ('\u{A83}', '\u{A83}', Break::SpacingMark),
+    ('\u{ABC}', '\u{ABC}', Break::Extend),
+    ('\u{ABE}', '\u{AC0}', Break::SpacingMark),
+    ('\u{AC1}', '\u{AC5}', Break::Extend),
+    ('\u{AC7}', '\u{AC8}', Break::Extend),
+    ('\u{AC9}', '\u{AC9}', Break::SpacingMark),
+    ('\u{ACB}', '\u{ACC}', Break::SpacingMark),
+    ('\u{ACD}', '\u{ACD}', Break::Extend),
+    ('\u{AE2}', '\u{AE3}', Break::Extend),
+    ('\u{AFA}', '\u{AFF}', Break::Extend),
+    ('\u{B01}', '\u{B01}', Break::Extend),
+    ('\u{B02}', '\u{B03}', Break::SpacingMark),
+    ('\u{B3C}', '\u{B3C}', Break::Extend),
+    ('\u{B3E}', '\u{B3F}', Break::Extend),
+    ('\u{B40}', '\u{B40}', Break::SpacingMark),
+    ('\u{B41}', '\u{B44}', Break::Extend),
+    ('\u{B47}', '\u{B48}', Break::SpacingMark),
+    ('\u{B4B}', '\u{B4C}', Break::SpacingMark),
+    ('\u{B4D}', '\u{B4D}', Break::Extend),
+    ('\u{B55}', '\u{B57}', Break::Extend),
+    ('\u{B62}', '\u{B63}', Break::Extend),
+    ('\u{B82}', '\u{B82}', Break::Extend),
+    ('\u{BBE}', '\u{BBE}', Break::Extend),
+    ('\u{BBF}', '\u{BBF}', Break::SpacingMark),
+    ('\u{BC0}', '\u{BC0}', Break::Extend),
+    ('\u{BC1}', '\u{BC2}', Break::SpacingMark),
+    ('\u{BC6}', '\u{BC8}', Break::SpacingMark),
+    ('\u{BCA}', '\u{BCC}', Break::SpacingMark),
+    ('\u{BCD}', '\u{BCD}', Break::Extend),
+    ('\u{BD7}', '\u{BD7}', Break::Extend),
+    ('\u{C00}', '\u{C00}', Break::Extend),
+    ('\u{C01}', '\u{C03}', Break::SpacingMark),
+    ('\u{C04}', '\u{C04}', Break::Extend),
+    ('\u{C3C}', '\u{C3C}', Break::Extend),
+    ('\u{C3E}', '\u{C40}', Break::Extend),
+    ('\u{C41}', '\u{C44}', Break::SpacingMark),
+    ('\u{C46}', '\u{C48}', Break::Extend),
+    ('\u{C4A}', '\u{C4D}', Break::Extend),
+    ('\u{C55}', '\u{C56}', Break::Extend),
+    ('\u{C62}', '\u{C63}', Break::Extend),
+    ('\u{C81}', '\u{C81}', Break::Extend),
+    ('\u{C82}', '\u{C83}', Break::SpacingMark),
+    ('\u{CBC}', '\u{CBC}', Break::Extend),
+    ('\u{CBE}', '\u{CBE}', Break::SpacingMark),
+    ('\u{CBF}', '\u{CBF}', Break::Extend),
+    ('\u{CC0}', '\u{CC1}', Break::SpacingMark),
+    ('\u{CC2}', '\u{CC2}', Break::Extend),
+    ('\u{CC3}', '\u{CC4}', Break::SpacingMark),
+    ('\u{CC6}', '\u{CC6}', Break::Extend),
+    ('\u{CC7}', '\u{CC8}', Break::SpacingMark),
+    ('\u{CCA}', '\u{CCB}', Break::SpacingMark),
+    ('\u{CCC}', '\u{CCD}', Break::Extend),
+    ('\u{CD5}', '\u{CD6}', Break::Extend),
+    ('\u{CE2}', '\u{CE3}', Break::Extend),
+    ('\u{D00}', '\u{D01}', Break::Extend),
+    ('\u{D02}', '\u{D03}', Break::SpacingMark),
+    ('\u{D3B}', '\u{D3C}', Break::Extend),
+    ('\u{D3E}', '\u{D3E}', Break::Extend),
+    ('\u{D3F}', '\u{D40}', Break::SpacingMark),
+    ('\u{D41}', '\u{D44}', Break::Extend),
+    ('\u{D46}', '\u{D48}', Break::SpacingMark),
+    ('\u{D4A}', '\u{D4C}', Break::SpacingMark),
+    ('\u{D4D}', '\u{D4D}', Break::Extend),
+    ('\u{D4E}', '\u{D4E}', Break::Prepend),
+    ('\u{D57}', '\u{D57}', Break::Extend),
+    ('\u{D62}', '\u{D63}', Break::Extend),
+    ('\u{D81}', '\u{D81}', Break::Extend),
+    ('\u{D82}', '\u{D83}', Break::SpacingMark),
+    ('\u{DCA}', '\u{DCA}', Break::Extend),
+    ('\u{DCF}', '\u{DCF}', Break::Extend),
+    ('\u{DD0}', '\u{DD1}', Break::SpacingMark),
+    ('\u{DD2}', '\u{DD4}', Break::Extend),
+    ('\u{DD6}', '\u{DD6}', Break::Extend),
+    ('\u{DD8}', '\u{DDE}', Break::SpacingMark),
+    ('\u{DDF}', '\u{DDF}', Break::Extend),
+    ('\u{DF2}', '\u{DF3}', Break::SpacingMark),
+    ('\u{E31}', '\u{E31}', Break::Extend),
+    ('\u{E33}', '\u{E33}', Break::SpacingMark),
+    ('\u{E34}', '\u{E3A}', Break::Extend),
+    ('\u{E47}', '\u{E4E}', Break::Extend),
+    ('\u{EB1}', '\u{EB1}', Break::Extend),
+    ('\u{EB3}', '\u{EB3}', Break::SpacingMark),
+    ('\u{EB4}', '\u{EBC}', Break::Extend),
+    ('\u{EC8}', '\u{ECD}', Break::Extend),
+    ('\u{F18}', '\u{F19}', Break::Extend),
+    ('\u{F35}', '\u{F35}', Break::Extend),
+    ('\u{F37}', '\u{F37}', Break::Extend),
+    ('\u{F39}', '\u{F39}', Break::Extend),
+    ('\u{F3E}', '\u{F3F}', Break::SpacingMark),
+    ('\u{F71}', '\u{F7E}', Break::Extend),
+    ('\u{F7F}', '\u{F7F}', Break::SpacingMark),
+    ('\u{F80}', '\u{F84}', Break::Extend),
+    ('\u{F86}', '\u{F87}', Break::Extend),
+    ('\u{F8D}', '\u{F97}', Break::Extend),
+    ('\u{F99}', '\u{FBC}', Break::Extend),
+    ('\u{FC6}', '\u{FC6}', Break::Extend),
+    ('\u{102D}', '\u{1030}', Break::Extend),
+    ('\u{1031}', '\u{1031}', Break::SpacingMark),
+    ('\u{1032}', '\u{1037}', Break::Extend),
+    ('\u{1039}', '\u{103A}', Break::Extend),
+    ('\u{103B}', '\u{103C}', Break::SpacingMark),
+    ('\u{103D}', '\u{103E}', Break::Extend),
+    ('\u{1056}', '\u{1057}', Break::SpacingMark),
+    ('\u{1058}', '\u{1059}', Break::Extend),
+    ('\u{105E}', '\u{1060}', Break::Extend),
+    ('\u{1071}', '\u{1074}', Break::Extend),
+    ('\u{1082}', '\u{1082}', Break::Extend),
+    ('\u{1084}', '\u{1084}', Break::SpacingMark),
+    ('\u{1085}', '\u{1086}', Break::Extend),
+    ('\u{108D}', '\u{108D}', Break::Extend),
+    ('\u{109D}', '\u{109D}', Break::Extend),
+    ('\u{1100}', '\u{115F}', Break::L),
+    ('\u{1160}', '\u{11A7}', Break::V),
+    ('\u{11A8}', '\u{11FF}', Break::T),
+    ('\u{135D}', '\u{135F}', Break::Extend),
+    ('\u{1712}', '\u{1714}', Break::Extend),
+    ('\u{1715}', '\u{1715}', Break::SpacingMark),
+    ('\u{1732}', '\u{1733}', Break::Extend),
+    ('\u{1734}', '\u{1734}', Break::SpacingMark),
+    ('\u{1752}', '\u{1753}', Break::Extend),
+    ('\u{1772}', '\u{1773}', Break::Extend),
+    ('\u{17B4}', '\u{17B5}', Break::Extend),
+    ('\u{17B6}', '\u{17B6}', Break::SpacingMark),
+    ('\u{17B7}', '\u{17BD}', Break::Extend),
+    ('\u{17BE}', '\u{17C5}', Break::SpacingMark),
+    ('\u{17C6}', '\u{17C6}', Break::Extend),
+    ('\u{17C7}', '\u{17C8}', Break::SpacingMark),
+    ('\u{17C9}', '\u{17D3}', Break::Extend),
+    ('\u{17DD}', '\u{17DD}', Break::Extend),
+    ('\u{180B}', '\u{180D}', Break::Extend),
+    ('\u{180E}', '\u{180E}', Break::Control),
+    ('\u{180F}', '\u{180F}', Break::Extend),
+    ('\u{1885}', '\u{1886}', Break::Extend),
+    ('\u{18A9}', '\u{18A9}', Break::Extend),
+    ('\u{1920}', '\u{1922}', Break::Extend),
+    ('\u{1923}', '\u{1926}', Break::SpacingMark),
+    ('\u{1927}', '\u{1928}', Break::Extend),
+    ('\u{1929}', '\u{192B}', Break::SpacingMark),
+    ('\u{1930}', '\u{1931}', Break::SpacingMark),
+    ('\u{1932}', '\u{1932}', Break::Extend),
+    ('\u{1933}', '\u{1938}', Break::SpacingMark),
+    ('\u{1939}', '\u{193B}', Break::Extend),
+    ('\u{1A17}', '\u{1A18}', Break::Extend),
+    ('\u{1A19}', '\u{1A1A}', Break::SpacingMark),
+    ('\u{1A1B}', '\u{1A1B}', Break::Extend),
+    ('\u{1A55}', '\u{1A55}', Break::SpacingMark),
+    ('\u{1A56}', '\u{1A56}', Break::Extend),
+    ('\u{1A57}', '\u{1A57}', Break::SpacingMark),
+    ('\u{1A58}', '\u{1A5E}', Break::Extend),
+    ('\u{1A60}', '\u{1A60}', Break::Extend),
+    ('\u{1A62}', '\u{1A62}', Break::Extend),
+    ('\u{1A65}', '\u{1A6C}', Break::Extend),
+    ('\u{1A6D}', '\u{1A72}', Break::SpacingMark),
+    ('\u{1A73}', '\u{1A7C}', Break::Extend),
+    ('\u{1A7F}', '\u{1A7F}', Break::Extend),
+    ('\u{1AB0}', '\u{1ACE}', Break::Extend),
+    ('\u{1B00}', '\u{1B03}', Break::Extend),
+    ('\u{1B04}', '\u{1B04}', Break::SpacingMark),
+    ('\u{1B34}', '\u{1B3A}', Break::Extend),
+    ('\u{1B3B}', '\u{1B3B}', Break::SpacingMark),
+    ('\u{1B3C}', '\u{1B3C}', Break::Extend),
+    ('\u{1B3D}', '\u{1B41}', Break::SpacingMark),
+    ('\u{1B42}', '\u{1B42}', Break::Extend),
+    ('\u{1B43}', '\u{1B44}', Break::SpacingMark),
+    ('\u{1B6B}', '\u{1B73}', Break::Extend),
+    ('\u{1B80}', '\u{1B81}', Break::Extend),
+    ('\u{1B82}', '\u{1B82}', Break::SpacingMark),
+    ('\u{1BA1}', '\u{1BA1}', Break::SpacingMark),
+    ('\u{1BA2}', '\u{1BA5}', Break::Extend),
+    ('\u{1BA6}', '\u{1BA7}', Break::SpacingMark),
+    ('\u{1BA8}', '\u{1BA9}', Break::Extend),
+    ('\u{1BAA}', '\u{1BAA}', Break::SpacingMark),
+    ('\u{1BAB}', '\u{1BAD}', Break::Extend),
+    ('\u{1BE6}', '\u{1BE6}', Break::Extend),
+    ('\u{1BE7}', '\u{1BE7}', Break::SpacingMark),
+    ('\u{1BE8}', '\u{1BE9}', Break::Extend),
+    ('\u{1BEA}', '\u{1BEC}', Break::SpacingMark),
+    ('\u{1BED}', '\u{1BED}', Break::Extend),
+    ('\u{1BEE}', '\u{1BEE}', Break::SpacingMark),
+    ('\u{1BEF}', '\u{1BF1}', Break::Extend),
+    ('\u{1BF2}', '\u{1BF3}', Break::SpacingMark),
+    ('\u{1C24}', '\u{1C2B}', Break::SpacingMark),
+    ('\u{1C2C}', '\u{1C33}', Break::Extend),
+    ('\u{1C34}', '\u{1C35}', Break::SpacingMark),
+    ('\u{1C36}', '\u{1C37}', Break::Extend),
+    ('\u{1CD0}', '\u{1CD2}', Break::Extend),
+    ('\u{1CD4}', '\u{1CE0}', Break::Extend),
+    ('\u{1CE1}', '\u{1CE1}', Break::SpacingMark),
+    ('\u{1CE2}', '\u{1CE8}', Break::Extend),
+    ('\u{1CED}', '\u{1CED}', Break::Extend),
+    ('\u{1CF4}', '\u{1CF4}', Break::Extend),
+    ('\u{1CF7}', '\u{1CF7}', Break::SpacingMark),
+    ('\u{1CF8}', '\u{1CF9}', Break::Extend),
+    ('\u{1DC0}', '\u{1DFF}', Break::Extend),
+    ('\u{200B}', '\u{200B}', Break::Control),
+    ('\u{200C}', '\u{200C}', Break::Extend),
+    ('\u{200D}', '\u{200D}', Break::Zwj),
+    ('\u{200E}', '\u{200F}', Break::Control),
+    ('\u{2028}', '\u{202E}', Break::Control),
+    ('\u{2060}', '\u{206F}', Break::Control),
+    ('\u{20D0}', '\u{20F0}', Break::Extend),
+    ('\u{2CEF}', '\u{2CF1}', Break::Extend),
+    ('\u{2D7F}', '\u{2D7F}', Break::Extend),
+    ('\u{2DE0}', '\u{2DFF}', Break::Extend),
+    ('\u{302A}', '\u{302F}', Break::Extend),
+    ('\u{3099}', '\u{309A}', Break::Extend),
+    ('\u{A66F}', '\u{A672}', Break::Extend),
+    ('\u{A674}', '\u{A67D}', Break::Extend),
+    ('\u{A69E}', '\u{A69F}', Break::Extend),
+    ('\u{A6F0}', '\u{A6F1}', Break::Extend),
+    ('\u{A802}', '\u{A802}', Break::Extend),
+    ('\u{A806}', '\u{A806}', Break::Extend),
+    ('\u{A80B}', '\u{A80B}', Break::Extend),
+    ('\u{A823}', '\u{A824}', Break::SpacingMark),
+    ('\u{A825}', '\u{A826}', Break::Extend),
+    ('\u{A827}', '\u{A827}', Break::SpacingMark),
+    ('\u{A82C}', '\u{A82C}', Break::Extend),
+    ('\u{A880}', '\u{A881}', Break::SpacingMark),
+    ('\u{A8B4}', '\u{A8C3}', Break::SpacingMark),
+    ('\u{A8C4}', '\u{A8C5}', Break::Extend),
+    ('\u{A8E0}', '\u{A8F1}', Break::Extend),
+    ('\u{A8FF}', '\u{A8FF}', Break::Extend),
+    ('\u{A926}', '\u{A92D}', Break::Extend),
+    ('\u{A947}', '\u{A951}', Break::Extend),
+    ('\u{A952}', '\u{A953}', Break::SpacingMark),
+    ('\u{A960}', '\u{A97C}', Break::L),
+    ('\u{A980}', '\u{A982}', Break::Extend),
+    ('\u{A983}', '\u{A983}', Break::SpacingMark),
+    ('\u{A9B3}', '\u{A9B3}', Break::Extend),
+    ('\u{A9B4}', '\u{A9B5}', Break::SpacingMark),
+    ('\u{A9B6}', '\u{A9B9}', Break::Extend),
+    ('\u{A9BA}', '\u{A9BB}', Break::SpacingMark),
+    ('\u{A9BC}', '\u{A9BD}', Break::Extend),
+    ('\u{A9BE}', '\u{A9C0}', Break::SpacingMark),
+    ('\u{A9E5}', '\u{A9E5}', Break::Extend),
+    ('\u{AA29}', '\u{AA2E}', Break::Extend),
+    ('\u{AA2F}', '\u{AA30}', Break::SpacingMark),
+    ('\u{AA31}', '\u{AA32}', Break::Extend),
+    ('\u{AA33}', '\u{AA34}', Break::SpacingMark),
+    ('\u{AA35}', '\u{AA36}', Break::Extend),
+    ('\u{AA43}', '\u{AA43}', Break::Extend),
+    ('\u{AA4C}', '\u{AA4C}', Break::Extend),
+    ('\u{AA4D}', '\u{AA4D}', Break::SpacingMark),
+    ('\u{AA7C}', '\u{AA7C}', Break::Extend),
+    ('\u{AAB0}', '\u{AAB0}', Break::Extend),
+    ('\u{AAB2}', '\u{AAB4}', Break::Extend),
+    ('\u{AAB7}', '\u{AAB8}', Break::Extend),
+    ('\u{AABE}', '\u{AABF}', Break::Extend),
+    ('\u{AAC1}', '\u{AAC1}', Break::Extend),
+    ('\u{AAEB}', '\u{AAEB}', Break::SpacingMark),
+    ('\u{AAEC}', '\u{AAED}', Break::Extend),
+    ('\u{AAEE}', '\u{AAEF}', Break::SpacingMark),
+    ('\u{AAF5}', '\u{AAF5}', Break::SpacingMark),
+    ('\u{AAF6}', '\u{AAF6}', Break::Extend),
+    ('\u{ABE3}', '\u{ABE4}', Break::SpacingMark),
+    ('\u{ABE5}', '\u{ABE5}', Break::Extend),
+    ('\u{ABE6}', '\u{ABE7}', Break::SpacingMark),
+    ('\u{ABE8}', '\u{ABE8}', Break::Extend),
+    ('\u{ABE9}', '\u{ABEA}', Break::SpacingMark),
+    ('\u{ABEC}', '\u{ABEC}', Break::SpacingMark),
+    ('\u{ABED}', '\u{ABED}', Break::Extend),
+    ('\u{D7B0}', '\u{D7C6}', Break::V),
+    ('\u{D7CB}', '\u{D7FB}', Break::T),
+    ('\u{FB1E}', '\u{FB1E}', Break::Extend),
+    ('\u{FE00}', '\u{FE0F}', Break::Extend),
+    ('\u{FE20}', '\u{FE2F}', Break::Extend),
+    ('\u{FEFF}', '\u{FEFF}', Break::Control),
+    ('\u{FF9E}', '\u{FF9F}', Break::Extend),
+    ('\u{FFF0}', '\u{FFFB}', Break::Control),
+    ('\u{101FD}', '\u{101FD}', Break::Extend),
+    ('\u{102E0}', '\u{102E0}', Break::Extend),
+    ('\u{10376}', '\u{1037A}', Break::Extend),
+    ('\u{10A01}', '\u{10A03}', Break::Extend),
+    ('\u{10A05}', '\u{10A06}', Break::Extend),
+    ('\u{10A0C}', '\u{10A0F}', Break::Extend),
+    ('\u{10A38}', '\u{10A3A}', Break::Extend),
+    ('\u{10A3F}', '\u{10A3F}', Break::Extend),
+    ('\u{10AE5}', '\u{10AE6}', Break::Extend),
+    ('\u{10D24}', '\u{10D27}', Break::Extend),
+    ('\u{10EAB}', '\u{10EAC}', Break::Extend),
+    ('\u{10F46}', '\u{10F50}', Break::Extend),
+    ('\u{10F82}', '\u{10F85}', Break::Extend),
+    ('\u{11000}', '\u{11000}', Break::SpacingMark),
+    ('\u{11001}', '\u{11001}', Break::Extend),
+    ('\u{11002}', '\u{11002}', Break::SpacingMark),
+    ('\u{11038}', '\u{11046}', Break::Extend),
+    ('\u{11070}', '\u{11070}', Break::Extend),
+    ('\u{11073}', '\u{11074}', Break::Extend),
+    ('\u{1107F}', '\u{11081}', Break::Extend),
+    ('\u{11082}', '\u{11082}', Break::SpacingMark),
+    ('\u{110B0}', '\u{110B2}', Break::SpacingMark),
+    ('\u{110B3}', '\u{110B6}', Break::Extend),
+    ('\u{110B7}', '\u{110B8}', Break::SpacingMark),
+    ('\u{110B9}', '\u{110BA}', Break::Extend),
+    ('\u{110BD}', '\u{110BD}', Break::Prepend),
+    ('\u{110C2}', '\u{110C2}', Break::Extend),
+    ('\u{110CD}', '\u{110CD}', Break::Prepend),
+    ('\u{11100}', '\u{11102}', Break::Extend),
+    ('\u{11127}', '\u{1112B}', Break::Extend),
+    ('\u{1112C}', '\u{1112C}', Break::SpacingMark),
+    ('\u{1112D}', '\u{11134}', Break::Extend),
+    ('\u{11145}', '\u{11146}', Break::SpacingMark),
+    ('\u{11173}', '\u{11173}', Break::Extend),
+    ('\u{11180}', '\u{11181}', Break::Extend),
+    ('\u{11182}', '\u{11182}', Break::SpacingMark),
+    ('\u{111B3}', '\u{111B5}', Break::SpacingMark),
+    ('\u{111B6}', '\u{111BE}', Break::Extend),
+    ('\u{111BF}', '\u{111C0}', Break::SpacingMark),
+    ('\u{111C2}', '\u{111C3}', Break::Prepend),
+    ('\u{111C9}', '\u{111CC}', Break::Extend),
+    ('\u{111CE}', '\u{111CE}', Break::SpacingMark),
+    ('\u{111CF}', '\u{111CF}', Break::Extend),
+    ('\u{1122C}', '\u{1122E}', Break::SpacingMark),
+    ('\u{1122F}', '\u{11231}', Break::Extend),
+    ('\u{11232}', '\u{11233}', Break::SpacingMark),
+    ('\u{11234}', '\u{11234}', Break::Extend),
+    ('\u{11235}', '\u{11235}', Break::SpacingMark),
+    ('\u{11236}', '\u{11237}', Break::Extend),
+    ('\u{1123E}', '\u{1123E}', Break::Extend),
+    ('\u{112DF}', '\u{112DF}', Break::Extend),
+    ('\u{112E0}', '\u{112E2}', Break::SpacingMark),
+    ('\u{112E3}', '\u{112EA}', Break::Extend),
+    ('\u{11300}', '\u{11301}', Break::Extend),
+    ('\u{11302}', '\u{11303}', Break::SpacingMark),
+    ('\u{1133B}', '\u{1133C}', Break::Extend),
+    ('\u{1133E}', '\u{1133E}', Break::Extend),
+    ('\u{1133F}', '\u{1133F}', Break::SpacingMark),
+    ('\u{11340}', '\u{11340}', Break::Extend),
+    ('\u{11341}', '\u{11344}', Break::SpacingMark),
+    ('\u{11347}', '\u{11348}', Break::SpacingMark),
+    ('\u{1134B}', '\u{1134D}', Break::SpacingMark),
+    ('\u{11357}', '\u{11357}', Break::Extend),
+    ('\u{11362}', '\u{11363}', Break::SpacingMark),
+    ('\u{11366}', '\u{1136C}', Break::Extend),
+    ('\u{11370}', '\u{11374}', Break::Extend),
+    ('\u{11435}', '\u{11437}', Break::SpacingMark),
+    ('\u{11438}', '\u{1143F}', Break::Extend),
+    ('\u{11440}', '\u{11441}', Break::SpacingMark),
+    ('\u{11442}', '\u{11444}', Break::Extend),
+    ('\u{11445}', '\u{11445}', Break::SpacingMark),
+    ('\u{11446}', '\u{11446}', Break::Extend),
+    ('\u{1145E}', '\u{1145E}', Break::Extend),
+    ('\u{114B0}', '\u{114B0}', Break::Extend),
+    ('\u{114B1}', '\u{114B2}', Break::SpacingMark),
+    ('\u{114B3}', '\u{114B8}', Break::Extend),
+    ('\u{114B9}', '\u{114B9}', Break::SpacingMark),
+    ('\u{114BA}', '\u{114BA}', Break::Extend),
+    ('\u{114BB}', '\u{114BC}', Break::SpacingMark),
+    ('\u{114BD}', '\u{114BD}', Break::Extend),
+    ('\u{114BE}', '\u{114BE}', Break::SpacingMark),
+    ('\u{114BF}', '\u{114C0}', Break::Extend),
+    ('\u{114C1}', '\u{114C1}', Break::SpacingMark),
+    ('\u{114C2}', '\u{114C3}', Break::Extend),
+    ('\u{115AF}', '\u{115AF}', Break::Extend),
+    ('\u{115B0}', '\u{115B1}', Break::SpacingMark),
+    ('\u{115B2}', '\u{115B5}', Break::Extend),
+    ('\u{115B8}', '\u{115BB}', Break::SpacingMark),
+    ('\u{115BC}', '\u{115BD}', Break::Extend),
+    ('\u{115BE}', '\u{115BE}', Break::SpacingMark),
+    ('\u{115BF}', '\u{115C0}', Break::Extend),
+    ('\u{115DC}', '\u{115DD}', Break::Extend),
+    ('\u{11630}', '\u{11632}', Break::SpacingMark),
+    ('\u{11633}', '\u{1163A}', Break::Extend),
+    ('\u{1163B}', '\u{1163C}', Break::SpacingMark),
+    ('\u{1163D}', '\u{1163D}', Break::Extend),
+    ('\u{1163E}', '\u{1163E}', Break::SpacingMark),
+    ('\u{1163F}', '\u{11640}', Break::Extend),
+    ('\u{116AB}', '\u{116AB}', Break::Extend),
+    ('\u{116AC}', '\u{116AC}', Break::SpacingMark),
+    ('\u{116AD}', '\u{116AD}', Break::Extend),
+    ('\u{116AE}', '\u{116AF}', Break::SpacingMark),
+    ('\u{116B0}', '\u{116B5}', Break::Extend),
+    ('\u{116B6}', '\u{116B6}', Break::SpacingMark),
+    ('\u{116B7}', '\u{116B7}', Break::Extend),
+    ('\u{1171D}', '\u{1171F}', Break::Extend),
+    ('\u{11722}', '\u{11725}', Break::Extend),
+    ('\u{11726}', '\u{11726}', Break::SpacingMark),
+    ('\u{11727}', '\u{1172B}', Break::Extend),
+    ('\u{1182C}', '\u{1182E}', Break::SpacingMark),
+    ('\u{1182F}', '\u{11837}', Break::Extend),
+    ('\u{11838}', '\u{11838}', Break::SpacingMark),
+    ('\u{11839}', '\u{1183A}', Break::Extend),
+    ('\u{11930}', '\u{11930}', Break::Extend),
+    ('\u{11931}', '\u{11935}', Break::SpacingMark),
+    ('\u{11937}', '\u{11938}', Break::SpacingMark),
+    ('\u{1193B}', '\u{1193C}', Break::Extend),
+    ('\u{1193D}', '\u{1193D}', Break::SpacingMark),
+    ('\u{1193E}', '\u{1193E}', Break::Extend),
+    ('\u{1193F}', '\u{1193F}', Break::Prepend),
+    ('\u{11940}', '\u{11940}', Break::SpacingMark),
+    ('\u{11941}', '\u{11941}', Break::Prepend),
+    ('\u{11942}', '\u{11942}', Break::SpacingMark),
+    ('\u{11943}', '\u{11943}', Break::Extend),
+    ('\u{119D1}', '\u{119D3}', Break::SpacingMark),
+    ('\u{119D4}', '\u{119D7}', Break::Extend),
+    ('\u{119DA}', '\u{119DB}', Break::Extend),
+    ('\u{119DC}', '\u{119DF}', Break::SpacingMark),
+    ('\u{119E0}', '\u{119E0}', Break::Extend),
+    ('\u{119E4}', '\u{119E4}', Break::SpacingMark),
+    ('\u{11A01}', '\u{11A0A}', Break::Extend),
+    ('\u{11A33}', '\u{11A38}', Break::Extend),
+    ('\u{11A39}', '\u{11A39}', Break::SpacingMark),
+    ('\u{11A3A}', '\u{11A3A}', Break::Prepend),
+    ('\u{11A3B}', '\u{11A3E}', Break::Extend),
+    ('\u{11A47}', '\u{11A47}', Break::Extend),
+    ('\u{11A51}', '\u{11A56}', Break::Extend),
+    ('\u{11A57}', '\u{11A58}', Break::SpacingMark),
+    ('\u{11A59}', '\u{11A5B}', Break::Extend),
+    ('\u{11A84}', '\u{11A89}', Break::Prepend),
+    ('\u{11A8A}', '\u{11A96}', Break::Extend),
+    ('\u{11A97}', '\u{11A97}', Break::SpacingMark),
+    ('\u{11A98}', '\u{11A99}', Break::Extend),
+    ('\u{11C2F}', '\u{11C2F}', Break::SpacingMark),
+    ('\u{11C30}', '\u{11C36}', Break::Extend),
+    ('\u{11C38}', '\u{11C3D}', Break::Extend),
+    ('\u{11C3E}', '\u{11C3E}', Break::SpacingMark),
+    ('\u{11C3F}', '\u{11C3F}', Break::Extend),
+    ('\u{11C92}', '\u{11CA7}', Break::Extend),
+    ('\u{11CA9}', '\u{11CA9}', Break::SpacingMark),
+    ('\u{11CAA}', '\u{11CB0}', Break::Extend),
+    ('\u{11CB1}', '\u{11CB1}', Break::SpacingMark),
+    ('\u{11CB2}', '\u{11CB3}', Break::Extend),
+    ('\u{11CB4}', '\u{11CB4}', Break::SpacingMark),
+    ('\u{11CB5}', '\u{11CB6}', Break::Extend),
+    ('\u{11D31}', '\u{11D36}', Break::Extend),
+    ('\u{11D3A}', '\u{11D3A}', Break::Extend),
+    ('\u{11D3C}', '\u{11D3D}', Break::Extend),
+    ('\u{11D3F}', '\u{11D45}', Break::Extend),
+    ('\u{11D46}', '\u{11D46}', Break::Prepend),
+    ('\u{11D47}', '\u{11D47}', Break::Extend),
+    ('\u{11D8A}', '\u{11D8E}', Break::SpacingMark),
+    ('\u{11D90}', '\u{11D91}', Break::Extend),
+    ('\u{11D93}', '\u{11D94}', Break::SpacingMark),
+    ('\u{11D95}', '\u{11D95}', Break::Extend),
+    ('\u{11D96}', '\u{11D96}', Break::SpacingMark),
+    ('\u{11D97}', '\u{11D97}', Break::Extend),
+    ('\u{11EF3}', '\u{11EF4}', Break::Extend),
+    ('\u{11EF5}', '\u{11EF6}', Break::SpacingMark),
+    ('\u{13430}', '\u{13438}', Break::Control),
+    ('\u{16AF0}', '\u{16AF4}', Break::Extend),
+    ('\u{16B30}', '\u{16B36}', Break::Extend),
+    ('\u{16F4F}', '\u{16F4F}', Break::Extend),
+    ('\u{16F51}', '\u{16F87}', Break::SpacingMark),
+    ('\u{16F8F}', '\u{16F92}', Break::Extend),
+    ('\u{16FE4}', '\u{16FE4}', Break::Extend),
+    ('\u{16FF0}', '\u{16FF1}', Break::SpacingMark),
+    ('\u{1BC9D}', '\u{1BC9E}', Break::Extend),
+    ('\u{1BCA0}', '\u{1BCA3}', Break::Control),
+    ('\u{1CF00}', '\u{1CF2D}', Break::Extend),
+    ('\u{1CF30}', '\u{1CF46}', Break::Extend),
+    ('\u{1D165}', '\u{1D165}', Break::Extend),
+    ('\u{1D166}', '\u{1D166}', Break::SpacingMark),
+    ('\u{1D167}', '\u{1D169}', Break::Extend),
+    ('\u{1D16D}', '\u{1D16D}', Break::SpacingMark),
+    ('\u{1D16E}', '\u{1D172}', Break::Extend),
+    ('\u{1D173}', '\u{1D17A}', Break::Control),
+    ('\u{1D17B}', '\u{1D182}', Break::Extend),
+    ('\u{1D185}', '\u{1D18B}', Break::Extend),
+    ('\u{1D1AA}', '\u{1D1AD}', Break::Extend),
+    ('\u{1D242}', '\u{1D244}', Break::Extend),
+    ('\u{1DA00}', '\u{1DA36}', Break::Extend),
+    ('\u{1DA3B}', '\u{1DA6C}', Break::Extend),
+    ('\u{1DA75}', '\u{1DA75}', Break::Extend),
+    ('\u{1DA84}', '\u{1DA84}', Break::Extend),
+    ('\u{1DA9B}', '\u{1DA9F}', Break::Extend),
+    ('\u{1DAA1}', '\u{1DAAF}', Break::Extend),
+    ('\u{1E000}', '\u{1E006}', Break::Extend),
+    ('\u{1E008}', '\u{1E018}', Break::Extend),
+    ('\u{1E01B}', '\u{1E021}', Break::Extend),
+    ('\u{1E023}', '\u{1E024}', Break::Extend),
+    ('\u{1E026}', '\u{1E02A}', Break::Extend),
+    ('\u{1E130}', '\u{1E136}', Break::Extend),
+    ('\u{1E2AE}', '\u{1E2AE}', Break::Extend),
+    ('\u{1E2EC}', '\u{1E2EF}', Break::Extend),
+    ('\u{1E8D0}', '\u{1E8D6}', Break::Extend),
+    ('\u{1E944}', '\u{1E94A}', Break::Extend),
+    ('\u{1F1E6}', '\u{1F1FF}', Break::RegionalIndicator),
+    ('\u{1F3FB}', '\u{1F3FF}', Break::Extend),
+    ('\u{E0000}', '\u{E001F}', Break::Control),
+    ('\u{E0020}', '\u{E007F}', Break::Extend),
+    ('\u{E0080}', '\u{E00FF}', Break::Control),
+    ('\u{E0100}', '\u{E01EF}', Break::Extend),
+    ('\u{E01F0}', '\u{E0FFF}', Break::Control),
+];
+
+// Generated from the Unicode 14 emoji-data.txt, `Extended_Pictographic`
+#[rustfmt::skip]
+const PICTOGRAPHIC: &[(char, char)] = &[
+    ('\u{A9}', '\u{A9}'),
+    ('\u{AE}', '\u{AE}'),
+    ('\u{203C}', '\u{203C}'),
+    ('\u{2049}', '\u{2049}'),
+    ('\u{2122}', '\u{2122}'),
+    ('\u{2139}', '\u{2139}'),
+    ('\u{2194}', '\u{2199}'),
+    ('\u{21A9}', '\u{21AA}'),
+    ('\u{231A}', '\u{231B}'),
+    ('\u{2328}', '\u{2328}'),
+    ('\u{2388}', '\u{2388}'),
+    ('\u{23CF}', '\u{23CF}'),
+    ('\u{23E9}', '\u{23F3}'),
+    ('\u{23F8}', '\u{23FA}'),
+    ('\u{24C2}', '\u{24C2}'),
+    ('\u{25AA}', '\u{25AB}'),
+    ('\u{25B6}', '\u{25B6}'),
+    ('\u{25C0}', '\u{25C0}'),
+    ('\u{25FB}', '\u{25FE}'),
+    ('\u{2600}', '\u{2605}'),
+    ('\u{2607}', '\u{2612}'),
+    ('\u{2614}', '\u{2685}'),
+    ('\u{2690}', '\u{2705}'),
+    ('\u{2708}', '\u{2712}'),
+    ('\u{2714}', '\u{2714}'),
+    ('\u{2716}', '\u{2716}'),
+    ('\u{271D}', '\u{271D}'),
+    ('\u{2721}', '\u{2721}'),
+    ('\u{2728}', '\u{2728}'),
+    ('\u{2733}', '\u{2734}'),
+    ('\u{2744}', '\u{2744}'),
+    ('\u{2747}', '\u{2747}'),
+    ('\u{274C}', '\u{274C}'),
+    ('\u{274E}', '\u{274E}'),
+    ('\u{2753}', '\u{2755}'),
+    ('\u{2757}', '\u{2757}'),
+    ('\u{2763}', '\u{2767}'),
+    ('\u{2795}', '\u{2797}'),
+    ('\u{27A1}', '\u{27A1}'),
+    ('\u{27B0}', '\u{27B0}'),
+    ('\u{27BF}', '\u{27BF}'),
+    ('\u{2934}', '\u{2935}'),
+    ('\u{2B05}', '\u{2B07}'),
+    ('\u{2B1B}', '\u{2B1C}'),
+    ('\u{2B50}', '\u{2B50}'),
+    ('\u{2B55}', '\u{2B55}'),
+    ('\u{3030}', '\u{3030}'),
+    ('\u{303D}', '\u{303D}'),
+    ('\u{3297}', '\u{3297}'),
+    ('\u{3299}', '\u{3299}'),
+    ('\u{1F000}', '\u{1F0FF}'),
+    ('\u{1F10D}', '\u{1F10F}'),
+    ('\u{1F12F}', '\u{1F12F}'),
+    ('\u{1F16C}', '\u{1F171}'),
+    ('\u{1F17E}', '\u{1F17F}'),
+    ('\u{1F18E}', '\u{1F18E}'),
+    ('\u{1F191}', '\u{1F19A}'),
+    ('\u{1F1AD}', '\u{1F1E5}'),
+    ('\u{1F201}', '\u{1F20F}'),
+    ('\u{1F21A}', '\u{1F21A}'),
+    ('\u{1F22F}', '\u{1F22F}'),
+    ('\u{1F232}', '\u{1F23A}'),
+    ('\u{1F23C}', '\u{1F23F}'),
+    ('\u{1F249}', '\u{1F3FA}'),
+    ('\u{1F400}', '\u{1F53D}'),
+    ('\u{1F546}', '\u{1F64F}'),
+    ('\u{1F680}', '\u{1F6FF}'),
+    ('\u{1F774}', '\u{1F77F}'),
+    ('\u{1F7D5}', '\u{1F7FF}'),
+    ('\u{1F80C}', '\u{1F80F}'),
+    ('\u{1F848}', '\u{1F84F}'),
+    ('\u{1F85A}', '\u{1F85F}'),
+    ('\u{1F888}', '\u{1F88F}'),
+    ('\u{1F8AE}', '\u{1F8FF}'),
+    ('\u{1F90C}', '\u{1F93A}'),
+    ('\u{1F93C}', '\u{1F945}'),
+    ('\u{1F947}', '\u{1FAFF}'),
+    ('\u{1FC00}', '\u{1FFFD}'),
+];
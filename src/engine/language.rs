@@ -0,0 +1,273 @@
+use crate::{Map, Set};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::nfa::{CharKind, CharTest, Nfa, StateId};
+
+// Determinizing can take exponentially many states; give up past this many
+const MAX_STATES: usize = 10_000;
+
+// Set ranges up to this size contribute all their members to an enumeration alphabet
+const MAX_LISTED_RANGE: u32 = 256;
+
+// Characters beyond Latin that classes tend to treat differently: letters in both
+// cases, non-ASCII digits and spaces, symbols, an emoji and the last code point
+const UNICODE_SAMPLES: &str = "ΩωЯя中٣\u{3000}\u{2028}€\u{212A}\u{2126}😀\u{10FFFF}";
+
+/// A DFA for the strings an NFA matches in full, over a fixed list of characters.
+///
+/// States are sets of NFA states together with the kind of the previous character,
+/// like the lazy DFA's, but every state and transition is built up front. Characters
+/// outside the alphabet have no transitions at all.
+#[derive(Debug, Clone)]
+pub(crate) struct Automaton {
+    pub(crate) alphabet: Vec<char>,
+    /// The next state for each state and alphabet index.
+    pub(crate) transitions: Vec<Vec<usize>>,
+    /// Whether the text can end in each state with a full match.
+    pub(crate) accepting: Vec<bool>,
+}
+
+impl Automaton {
+    /// The state before any character has been read.
+    pub(crate) const START: usize = 0;
+
+    /// Builds the automaton, or returns `None` if it would have too many states.
+    pub(crate) fn new(nfa: &Nfa, alphabet: Vec<char>) -> Option<Self> {
+        let mut ids: Map<(Vec<StateId>, CharKind), usize> = Map::new();
+        let mut states = vec![(vec![nfa.start()], CharKind::Edge)];
+        ids.insert(states[0].clone(), 0);
+        let mut transitions = vec![];
+        let mut accepting = vec![];
+
+        // States are numbered in the order they're found, so this visits each once
+        while transitions.len() < states.len() {
+            let (core, before) = states[transitions.len()].clone();
+            accepting.push(nfa.closure(&core, before, CharKind::Edge).1);
+            let mut row = Vec::with_capacity(alphabet.len());
+            for &c in &alphabet {
+                let after = CharKind::of(Some(c));
+                let (consuming, _) = nfa.closure(&core, before, after);
+                let mut next: Vec<StateId> = consuming
+                    .into_iter()
+                    .filter_map(|s| nfa.step(s, c))
+                    .collect();
+                next.sort_unstable();
+                next.dedup();
+                let key = (next, after);
+                let id = match ids.get(&key) {
+                    Some(&id) => id,
+                    None => {
+                        if states.len() == MAX_STATES {
+                            return None;
+                        }
+                        ids.insert(key.clone(), states.len());
+                        states.push(key);
+                        states.len() - 1
+                    }
+                };
+                row.push(id);
+            }
+            transitions.push(row);
+        }
+        Some(Automaton {
+            alphabet,
+            transitions,
+            accepting,
+        })
+    }
+}
+
+/// The characters an enumeration tries: printable ASCII, tab and newline, and every
+/// character the pattern spells out, in order.
+pub(crate) fn enumeration_alphabet(nfa: &Nfa) -> Vec<char> {
+    let mut chars: Vec<char> = (' '..='~').chain(['\t', '\n']).collect();
+    for test in nfa.tests() {
+        match test {
+            CharTest::Literal(c) => {
+                chars.push(*c);
+                chars.extend(c.to_lowercase().chain(c.to_uppercase()));
+            }
+            CharTest::Set { chars: ranges, .. } => {
+                for range in ranges {
+                    if u32::from(range.end) - u32::from(range.start) < MAX_LISTED_RANGE {
+                        chars.extend(range.start..=range.end);
+                    } else {
+                        chars.extend([range.start, range.end]);
+                    }
+                }
+            }
+            CharTest::Class(_) => {}
+        }
+    }
+    chars.sort_unstable();
+    chars.dedup();
+    chars
+}
+
+/// One character for each way the automata's tests and assertions can tell
+/// characters apart, so automata built over it decide the same things as over all
+/// characters.
+///
+/// The candidates are Latin-1 and Latin Extended, a few characters from other
+/// scripts, and every character the patterns name, with its case variants and
+/// neighbours at the edges of set ranges. Characters outside that sample are assumed
+/// to behave like one in it.
+pub(crate) fn representative_alphabet(nfas: &[&Nfa]) -> Vec<char> {
+    let mut candidates: Vec<char> = ('\0'..='\u{2FF}').chain(UNICODE_SAMPLES.chars()).collect();
+    let mut named = vec![];
+    for test in nfas.iter().flat_map(|nfa| nfa.tests()) {
+        match test {
+            CharTest::Literal(c) => named.push(*c),
+            CharTest::Set { chars: ranges, .. } => {
+                for range in ranges {
+                    let (start, end) = (u32::from(range.start), u32::from(range.end));
+                    named.extend(
+                        [
+                            start.checked_sub(1),
+                            Some(start),
+                            Some(end),
+                            end.checked_add(1),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .filter_map(char::from_u32),
+                    );
+                }
+            }
+            CharTest::Class(_) => {}
+        }
+    }
+    for c in named {
+        candidates.push(c);
+        candidates.extend(c.to_lowercase().chain(c.to_uppercase()));
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let tests: Vec<(&Nfa, &CharTest)> = nfas
+        .iter()
+        .flat_map(|&nfa| nfa.tests().map(move |test| (nfa, test)))
+        .collect();
+    let mut seen = Set::new();
+    candidates.retain(|&c| {
+        let signature: Vec<bool> = tests
+            .iter()
+            .map(|(nfa, test)| nfa.passes(test, c))
+            .collect();
+        seen.insert((CharKind::of(Some(c)), signature))
+    });
+    candidates
+}
+
+/// Searches the product of two automata over the same alphabet for a state pair
+/// where `goal` holds of their accepting flags.
+pub(crate) fn product_reaches(
+    a: &Automaton,
+    b: &Automaton,
+    goal: impl Fn(bool, bool) -> bool,
+) -> bool {
+    let mut seen = Set::new();
+    let mut stack = vec![(Automaton::START, Automaton::START)];
+    while let Some((x, y)) = stack.pop() {
+        if !seen.insert((x, y)) {
+            continue;
+        }
+        if goal(a.accepting[x], b.accepting[y]) {
+            return true;
+        }
+        stack.extend(
+            a.transitions[x]
+                .iter()
+                .zip(&b.transitions[y])
+                .map(|(&x, &y)| (x, y)),
+        );
+    }
+    false
+}
+
+/// Every string a pattern matches in full, up to a length limit, shortest first and
+/// then in character order. Created by
+/// [`Regex::matching_strings`](crate::Regex::matching_strings).
+///
+/// Strings are built from printable ASCII, tab, newline and the characters the
+/// pattern itself names, so classes such as `.` or `\w` stand for the members of that
+/// set rather than all of Unicode.
+#[derive(Debug, Clone)]
+pub struct MatchingStrings {
+    automaton: Automaton,
+    /// For each length `k`, whether a full match can be completed from each state
+    /// with exactly `k` more characters.
+    completes: Vec<Vec<bool>>,
+    /// The length currently being enumerated, plus one.
+    next_len: usize,
+    /// Each level of the search: the state reached and the next alphabet index to try.
+    stack: Vec<(usize, usize)>,
+    text: Vec<char>,
+}
+
+impl MatchingStrings {
+    pub(crate) fn new(automaton: Automaton, max_len: usize) -> Self {
+        let mut completes = vec![automaton.accepting.clone()];
+        for k in 1..=max_len {
+            let shorter = &completes[k - 1];
+            let row = automaton
+                .transitions
+                .iter()
+                .map(|row| row.iter().any(|&next| shorter[next]))
+                .collect();
+            completes.push(row);
+        }
+        MatchingStrings {
+            automaton,
+            completes,
+            next_len: 0,
+            stack: vec![],
+            text: vec![],
+        }
+    }
+}
+
+impl Iterator for MatchingStrings {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let Some(&mut (state, ref mut symbol)) = self.stack.last_mut() else {
+                // Start on the next length, if any string of it matches
+                let len = self.next_len;
+                if len >= self.completes.len() {
+                    return None;
+                }
+                self.next_len += 1;
+                if self.completes[len][Automaton::START] {
+                    if len == 0 {
+                        return Some(String::new());
+                    }
+                    self.stack.push((Automaton::START, 0));
+                }
+                continue;
+            };
+            if *symbol == self.automaton.alphabet.len() {
+                self.stack.pop();
+                self.text.pop();
+                continue;
+            }
+            let c = self.automaton.alphabet[*symbol];
+            let next = self.automaton.transitions[state][*symbol];
+            *symbol += 1;
+            let remaining = self.next_len - 1 - self.text.len() - 1;
+            if !self.completes[remaining][next] {
+                continue;
+            }
+            self.text.push(c);
+            if remaining == 0 {
+                let found = self.text.iter().collect();
+                self.text.pop();
+                return Some(found);
+            }
+            self.stack.push((next, 0));
+        }
+    }
+}
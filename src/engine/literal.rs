@@ -0,0 +1,89 @@
+use super::program::FoldedChar;
+use super::scan;
+use crate::captures::Match;
+use crate::flags::Flags;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Finds a pattern that is a plain string with a substring search instead of the
+/// matcher.
+///
+/// Case-insensitive needles are compared a character at a time with the matcher's
+/// precomputed folds, so the match can differ in length from the needle (e.g. `k` against
+/// the Kelvin sign). ASCII needles that can't fold to anything outside ASCII take a
+/// byte-wise fast path.
+#[derive(Debug, Clone)]
+pub(crate) struct LiteralSearcher {
+    needle: String,
+    flags: Flags,
+    folded: Vec<FoldedChar>,
+    ascii_fold: bool,
+}
+
+impl LiteralSearcher {
+    pub(crate) fn new(needle: String, flags: &Flags) -> Self {
+        // 'k' also folds to U+212A, the only non-ASCII character folding into ASCII
+        let ascii_fold = needle
+            .chars()
+            .all(|c| c.is_ascii() && !c.eq_ignore_ascii_case(&'k'));
+        LiteralSearcher {
+            folded: needle.chars().map(FoldedChar::new).collect(),
+            needle,
+            flags: *flags,
+            ascii_fold,
+        }
+    }
+
+    pub(crate) fn ignore_case(&self) -> bool {
+        self.flags.ignore_case.unwrap_or(false)
+    }
+
+    pub(crate) fn needle(&self) -> &str {
+        &self.needle
+    }
+
+    /// Returns the first occurrence of the needle starting at or after `from`.
+    pub(crate) fn find_at(&self, text: &str, from: usize) -> Option<Match> {
+        let haystack = text.get(from..)?;
+        if !self.ignore_case() {
+            return haystack.find(self.needle.as_str()).map(|i| Match {
+                start: from + i,
+                end: from + i + self.needle.len(),
+            });
+        }
+        if self.ascii_fold {
+            let needle = self.needle.as_bytes();
+            if needle.is_empty() {
+                return Some(Match {
+                    start: from,
+                    end: from,
+                });
+            }
+            // An ASCII byte never sits inside a multi-byte character, so any hit
+            // starts on a character boundary
+            return scan::find_ignore_ascii_case(needle, haystack.as_bytes()).map(|i| Match {
+                start: from + i,
+                end: from + i + needle.len(),
+            });
+        }
+        haystack
+            .char_indices()
+            .map(|(i, _)| from + i)
+            .chain(core::iter::once(text.len()))
+            .find_map(|start| self.match_at(text, start).map(|end| Match { start, end }))
+    }
+
+    // Compares the needle a character at a time against the text at `start`
+    fn match_at(&self, text: &str, start: usize) -> Option<usize> {
+        let mut rest = text[start..].chars();
+        let mut end = start;
+        for folded in &self.folded {
+            let c = rest.next()?;
+            if !folded.matches(c, &self.flags) {
+                return None;
+            }
+            end += c.len_utf8();
+        }
+        Some(end)
+    }
+}
@@ -0,0 +1,65 @@
+use alloc::collections::BTreeMap;
+use core::ops::Range;
+
+/// Positions in the text that a pattern's `\%#`, `\%V` and `\%'m` atoms refer to,
+/// like an editor's cursor, visual selection and marks. Given to a search with
+/// [`Finder::marks`](crate::Finder::marks).
+///
+/// All positions are byte offsets into the searched text. An atom whose position
+/// isn't set never matches, which is also what happens in searches run without
+/// marks.
+///
+/// ```
+/// use monster_regex::{Flags, Marks, Regex};
+///
+/// let re = Regex::new(r"\%V\w+", Flags::default()).unwrap();
+/// let marks = Marks::new().visual(4..9);
+/// let m = re.finder().marks(&marks).run("one two three").unwrap();
+/// assert_eq!(m.full_match.start, 4);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Marks {
+    cursor: Option<usize>,
+    visual: Option<Range<usize>>,
+    named: BTreeMap<char, usize>,
+}
+
+impl Marks {
+    /// Creates a set with no positions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the cursor, where `\%#` matches.
+    pub fn cursor(mut self, offset: usize) -> Self {
+        self.cursor = Some(offset);
+        self
+    }
+
+    /// Sets the visual area. `\%V` matches at the positions of the characters
+    /// inside it, so `\%V` before the first and last character of a pattern
+    /// keeps the whole match inside the area, as in Vim.
+    pub fn visual(mut self, area: Range<usize>) -> Self {
+        self.visual = Some(area);
+        self
+    }
+
+    /// Sets the mark `name`, where `\%'name` matches. `\%<'name` matches before it
+    /// and `\%>'name` after it.
+    pub fn mark(mut self, name: char, offset: usize) -> Self {
+        self.named.insert(name, offset);
+        self
+    }
+
+    pub(crate) fn cursor_at(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    pub(crate) fn in_visual(&self, pos: usize) -> bool {
+        self.visual.as_ref().is_some_and(|area| area.contains(&pos))
+    }
+
+    pub(crate) fn get(&self, name: char) -> Option<usize> {
+        self.named.get(&name).copied()
+    }
+}
@@ -1,487 +1,837 @@
-use crate::captures::Match;
-use crate::flags::Flags;
-use crate::parser::{AstNode, CharClass};
+mod dfa;
+mod grapheme;
+mod language;
+mod literal;
+mod marks;
+mod nfa;
+pub(crate) mod program;
+pub(crate) mod scan;
+mod trace;
+pub(crate) use dfa::LazyDfa;
+pub use language::MatchingStrings;
+pub(crate) use language::{
+    Automaton, enumeration_alphabet, product_reaches, representative_alphabet,
+};
+pub(crate) use literal::LiteralSearcher;
+pub use marks::Marks;
+pub(crate) use program::Program;
+pub use trace::{MatchStats, Trace, TraceEvent};
 
-/// The matching engine that walks the AST to find matches in text.
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::{Cell, OnceCell, RefCell};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::captures::{Captures, Match};
+use crate::compiler::{Anchor, ScanPlan, fold_orbit};
+use crate::flags::{DotMode, Flags};
+use crate::parser::{AstNode, CharClass, CharRange};
+use nfa::{Assertion, CharKind};
+use program::{FoldedChar, Inst, Pc, Position};
+
+/// The search strategy a compiled regex uses, from cheapest to most general.
+///
+/// Chosen automatically at compile time; see [`Regex::engine_kind`](crate::Regex::engine_kind).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineKind {
+    /// The pattern is a plain string, found with a substring search.
+    Literal,
+    /// The pattern is regular. A forward DFA finds where matches end and a reverse
+    /// one where the leftmost match starts, in time linear in the text, and the
+    /// backtracking matcher only resolves the match found there.
+    Dfa,
+    /// The pattern uses backreferences, lookarounds, `\zs`/`\ze` or line, column
+    /// or mark atoms, so every candidate start is tried by the backtracking matcher.
+    Backtracking,
+}
+
+/// The matching engine that runs a compiled pattern over text.
+///
+/// Backtracking uses an explicit stack rather than recursion, so neither long
+/// inputs nor deeply nested patterns can overflow the call stack. Only lookarounds
+/// recurse, as deep as they are nested in the pattern.
 pub struct Matcher<'a> {
-    nodes: &'a [AstNode],
+    program: Cow<'a, Program>,
     flags: &'a Flags,
     text: &'a str,
+    // Matches can't consume text past this offset, though assertions can look past it
+    limit: usize,
+    plan: Option<&'a ScanPlan>,
+    dfa: Option<&'a LazyDfa>,
+    tracer: Option<RefCell<&'a mut dyn FnMut(TraceEvent)>>,
+    stats: Option<RefCell<MatchStats>>,
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
+    // Steps taken towards the next look at the clock
+    #[cfg(feature = "std")]
+    clock_steps: Cell<usize>,
+    timed_out: Cell<bool>,
+    // Where each line starts, for `\%l` and `\%c`
+    line_starts: OnceCell<Vec<usize>>,
+    marks: Option<&'a Marks>,
+    #[cfg(feature = "async")]
+    step_budget: Option<usize>,
+    #[cfg(feature = "async")]
+    steps_taken: Cell<usize>,
+    #[cfg(feature = "async")]
+    paused_at: Cell<Option<usize>>,
 }
 
-struct QuantifierParams {
-    min: usize,
-    max: Option<usize>,
-    greedy: bool,
-}
+// Reading the clock costs more than a step, so it's only read this often
+#[cfg(feature = "std")]
+const CLOCK_INTERVAL: usize = 1024;
 
-#[derive(Clone, Debug)]
-struct MatchContext {
+/// The state of a match attempt, reusable across searches of the same program.
+#[derive(Debug)]
+pub(crate) struct MatchContext {
     captures: Vec<Option<Match>>,
+    registers: Vec<usize>,
     match_start_override: Option<usize>,
     match_end_override: Option<usize>,
+    /// The text the last match actually consumed, before `\zs`/`\ze` moved its bounds.
+    pub(crate) consumed: Match,
+    undo_log: Vec<Undo>,
+}
+
+/// A state change made during a match attempt, with the value it replaced.
+#[derive(Debug)]
+enum Undo {
+    Capture(usize, Option<Match>),
+    Register(usize, usize),
+    MatchStart(Option<usize>),
+    MatchEnd(Option<usize>),
 }
 
 impl MatchContext {
-    fn new(group_count: usize) -> Self {
+    pub(crate) fn new(program: &Program) -> Self {
         Self {
-            captures: vec![None; group_count + 1], // +1 for 1-based indexing
+            captures: vec![None; program.group_count + 1], // +1 for 1-based indexing
+            registers: vec![0; program.registers],
             match_start_override: None,
             match_end_override: None,
+            consumed: Match { start: 0, end: 0 },
+            undo_log: vec![],
+        }
+    }
+
+    // Remembers the current state, to be restored with `undo`
+    fn mark(&self) -> usize {
+        self.undo_log.len()
+    }
+
+    // Reverts every change made since `mark`
+    fn undo(&mut self, mark: usize) {
+        while self.undo_log.len() > mark {
+            match self.undo_log.pop() {
+                Some(Undo::Capture(idx, old)) => self.captures[idx] = old,
+                Some(Undo::Register(reg, old)) => self.registers[reg] = old,
+                Some(Undo::MatchStart(old)) => self.match_start_override = old,
+                Some(Undo::MatchEnd(old)) => self.match_end_override = old,
+                None => {}
+            }
+        }
+    }
+
+    // Reverts every change made since `mark` except the captures, which are set
+    // again on top of the older state so that they're undone in turn
+    fn undo_keeping_captures(&mut self, mark: usize) {
+        let kept: Vec<(usize, Match)> = self.undo_log[mark..]
+            .iter()
+            .filter_map(|undo| match undo {
+                Undo::Capture(idx, _) => Some((*idx, self.captures[*idx].clone()?)),
+                _ => None,
+            })
+            .collect();
+        self.undo(mark);
+        for (idx, m) in kept {
+            self.set_capture(idx, m);
+        }
+    }
+
+    fn set_capture(&mut self, idx: usize, m: Match) {
+        if let Some(slot) = self.captures.get_mut(idx) {
+            let old = slot.replace(m);
+            self.undo_log.push(Undo::Capture(idx, old));
         }
     }
+
+    fn set_register(&mut self, reg: usize, value: usize) {
+        let old = core::mem::replace(&mut self.registers[reg], value);
+        self.undo_log.push(Undo::Register(reg, old));
+    }
+
+    fn set_match_start(&mut self, pos: usize) {
+        let old = self.match_start_override.replace(pos);
+        self.undo_log.push(Undo::MatchStart(old));
+    }
+
+    fn set_match_end(&mut self, pos: usize) {
+        let old = self.match_end_override.replace(pos);
+        self.undo_log.push(Undo::MatchEnd(old));
+    }
 }
 
 impl<'a> Matcher<'a> {
     /// Creates a new Matcher instance.
     pub fn new(nodes: &'a [AstNode], flags: &'a Flags, text: &'a str) -> Self {
-        Self { nodes, flags, text }
+        Self {
+            program: Cow::Owned(Program::new(nodes, flags)),
+            flags,
+            text,
+            limit: text.len(),
+            plan: None,
+            dfa: None,
+            tracer: None,
+            stats: None,
+            #[cfg(feature = "std")]
+            deadline: None,
+            #[cfg(feature = "std")]
+            clock_steps: Cell::new(0),
+            timed_out: Cell::new(false),
+            line_starts: OnceCell::new(),
+            marks: None,
+            #[cfg(feature = "async")]
+            step_budget: None,
+            #[cfg(feature = "async")]
+            steps_taken: Cell::new(0),
+            #[cfg(feature = "async")]
+            paused_at: Cell::new(None),
+        }
+    }
+
+    /// Creates a Matcher for an already compiled program.
+    pub(crate) fn from_program(program: &'a Program, flags: &'a Flags, text: &'a str) -> Self {
+        Self {
+            program: Cow::Borrowed(program),
+            flags,
+            text,
+            limit: text.len(),
+            plan: None,
+            dfa: None,
+            tracer: None,
+            stats: None,
+            #[cfg(feature = "std")]
+            deadline: None,
+            #[cfg(feature = "std")]
+            clock_steps: Cell::new(0),
+            timed_out: Cell::new(false),
+            line_starts: OnceCell::new(),
+            marks: None,
+            #[cfg(feature = "async")]
+            step_budget: None,
+            #[cfg(feature = "async")]
+            steps_taken: Cell::new(0),
+            #[cfg(feature = "async")]
+            paused_at: Cell::new(None),
+        }
+    }
+
+    /// Uses the compile-time scan plan to skip start positions that cannot begin a match.
+    pub(crate) fn with_plan(mut self, plan: &'a ScanPlan) -> Self {
+        self.plan = Some(plan);
+        self
+    }
+
+    /// Uses a DFA for the pattern to rule out start positions before backtracking.
+    pub(crate) fn with_dfa(mut self, dfa: &'a LazyDfa) -> Self {
+        self.dfa = Some(dfa);
+        self
+    }
+
+    /// Confines matches to the text before the byte offset `limit`. Assertions and
+    /// lookarounds still see the text after it.
+    pub(crate) fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Gives `\%#`, `\%V` and `\%'m` the positions they match at.
+    pub(crate) fn with_marks(mut self, marks: &'a Marks) -> Self {
+        self.marks = Some(marks);
+        self
+    }
+
+    /// Reports what the matcher does to `tracer` as it runs.
+    pub(crate) fn with_tracer(mut self, tracer: &'a mut dyn FnMut(TraceEvent)) -> Self {
+        self.tracer = Some(RefCell::new(tracer));
+        self
+    }
+
+    /// Counts the work searches do, to be read back with [`stats`](Self::stats).
+    pub(crate) fn with_stats(mut self) -> Self {
+        self.stats = Some(RefCell::default());
+        self
+    }
+
+    /// Returns the counters collected since [`with_stats`](Self::with_stats).
+    pub(crate) fn stats(&self) -> MatchStats {
+        self.stats
+            .as_ref()
+            .map(|stats| stats.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    /// Gives up on searches still running at `deadline`; see
+    /// [`timed_out`](Self::timed_out).
+    #[cfg(feature = "std")]
+    pub(crate) fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Returns `true` if a search gave up because it ran past the deadline, in which
+    /// case its result means nothing.
+    pub(crate) fn timed_out(&self) -> bool {
+        self.timed_out.get()
+    }
+
+    /// Stops searches from trying further start positions once `steps` steps have
+    /// been taken; see [`paused_at`](Self::paused_at). The attempt at each start
+    /// position runs to the end, and a search never pauses where it started.
+    #[cfg(feature = "async")]
+    pub(crate) fn with_step_budget(mut self, steps: usize) -> Self {
+        self.step_budget = Some(steps);
+        self
+    }
+
+    /// Returns the start position a search stopped at because it ran out of steps,
+    /// in which case its result means nothing and searching on from there finds
+    /// the match it would have.
+    #[cfg(feature = "async")]
+    pub(crate) fn paused_at(&self) -> Option<usize> {
+        self.paused_at.get()
+    }
+
+    // Checks the deadline every `CLOCK_INTERVAL` steps
+    fn out_of_time(&self) -> bool {
+        #[cfg(feature = "std")]
+        if let Some(deadline) = self.deadline
+            && !self.timed_out.get()
+        {
+            let steps = self.clock_steps.get() + 1;
+            self.clock_steps.set(steps % CLOCK_INTERVAL);
+            if steps == CLOCK_INTERVAL && Instant::now() >= deadline {
+                self.timed_out.set(true);
+            }
+        }
+        self.timed_out.get()
+    }
+
+    fn count(&self, update: impl FnOnce(&mut MatchStats)) {
+        if let Some(stats) = &self.stats {
+            update(&mut stats.borrow_mut());
+        }
+    }
+
+    fn emit(&self, event: TraceEvent) {
+        if let Some(tracer) = &self.tracer {
+            (tracer.borrow_mut())(event);
+        }
     }
 
     /// Finds the first match in the text.
     pub fn find(&self) -> Option<Match> {
-        // Determine max group index for context sizing
-        let max_group = self.count_groups(self.nodes);
+        self.find_at(0, &mut MatchContext::new(&self.program))
+    }
+
+    /// Finds the first match starting at or after the byte offset `from`, which must
+    /// lie on a character boundary.
+    ///
+    /// Assertions still see the whole text, so `^` doesn't match at `from` unless a
+    /// line starts there. The context is reset first and holds the match's captures
+    /// afterwards.
+    pub(crate) fn find_at(&self, from: usize, context: &mut MatchContext) -> Option<Match> {
+        context.undo(0);
+
+        // Try to match starting at every character boundary, including the very end
+        // of the string (for empty matches or anchors)
+        let mut start_pos = from;
+        let mut last_start = if self.flags.anchored {
+            from
+        } else {
+            self.limit
+        };
+        let mut next_required = None;
+        let mut plan = self.plan;
+
+        match self.dfa {
+            // The DFAs find where the leftmost match starts, so the backtracker only
+            // runs there, to pick the match and its captures
+            Some(dfa) if !self.flags.anchored => {
+                start_pos = dfa.leftmost_start(self.text, from, self.limit)?;
+                last_start = start_pos;
+                plan = None;
+            }
+            // One-pass patterns decide the start position as cheaply as the DFA would
+            Some(dfa) if !dfa.is_one_pass() && !dfa.matches_at(self.text, from) => return None,
+            _ => {}
+        }
+
+        loop {
+            if self.timed_out() {
+                return None;
+            }
+            if let Some(plan) = plan {
+                start_pos = self.next_candidate(plan, start_pos, &mut next_required)?;
+            }
+            if start_pos > last_start {
+                return None;
+            }
+            // Pausing only past `from` means every search gets somewhere
+            #[cfg(feature = "async")]
+            if start_pos > from
+                && self
+                    .step_budget
+                    .is_some_and(|budget| self.steps_taken.get() >= budget)
+            {
+                self.paused_at.set(Some(start_pos));
+                return None;
+            }
 
-        // Try to match starting at every character boundary
-        for (start_pos, _) in self.text.char_indices() {
-            let mut context = MatchContext::new(max_group);
-            if let Some(end_pos) = self.match_nodes(self.nodes, start_pos, &mut context) {
+            self.emit(TraceEvent::Attempt { start: start_pos });
+            self.count(|stats| stats.positions_attempted += 1);
+            if let Some(end_pos) = self.run(0, start_pos, context, None, self.limit) {
+                // The last `\zs`/`\ze` executed wins. If they cross, the match is
+                // empty at the `\zs` position.
                 let start = context.match_start_override.unwrap_or(start_pos);
-                let end = context.match_end_override.unwrap_or(end_pos);
+                let end = context.match_end_override.unwrap_or(end_pos).max(start);
+                context.consumed = Match {
+                    start: start_pos,
+                    end: end_pos,
+                };
                 return Some(Match { start, end });
             }
-        }
 
-        // Also try matching at the very end of the string (for empty matches or anchors)
-        let mut context = MatchContext::new(max_group);
-        if let Some(end_pos) = self.match_nodes(self.nodes, self.text.len(), &mut context) {
-            let start = context.match_start_override.unwrap_or(self.text.len());
-            let end = context.match_end_override.unwrap_or(end_pos);
-            return Some(Match { start, end });
+            match self.text[start_pos..].chars().next() {
+                Some(c) => start_pos += c.len_utf8(),
+                None => return None,
+            }
         }
+    }
 
-        None
+    /// Finds a match that starts at the start of the text and consumes all of it,
+    /// trying every way through the pattern rather than only the one a search would
+    /// pick. The context is reset first and holds the match's captures afterwards.
+    pub(crate) fn find_whole(&self, context: &mut MatchContext) -> Option<Match> {
+        context.undo(0);
+        let end = self.text.len();
+        self.run(0, 0, context, Some(end), end)?;
+        let start = context.match_start_override.unwrap_or(0);
+        context.consumed = Match { start: 0, end };
+        Some(Match {
+            start,
+            end: context.match_end_override.unwrap_or(end).max(start),
+        })
     }
 
-    // Helper to count groups to size the capture vector
-    fn count_groups(&self, nodes: &[AstNode]) -> usize {
-        let mut max = 0;
-        for node in nodes {
-            match node {
-                AstNode::Group { index, nodes, .. } => {
-                    if let Some(i) = index {
-                        max = max.max(*i);
-                    }
-                    max = max.max(self.count_groups(nodes));
-                }
-                AstNode::Alternation(alts) => {
-                    for alt in alts {
-                        max = max.max(self.count_groups(alt));
-                    }
-                }
-                AstNode::ZeroOrMore { node, .. }
-                | AstNode::OneOrMore { node, .. }
-                | AstNode::Optional { node, .. }
-                | AstNode::Exact { node, .. }
-                | AstNode::Range { node, .. } => {
-                    max = max.max(self.count_groups(std::slice::from_ref(node)));
+    /// Finds the match that ends first among those starting at or after `from`, the
+    /// leftmost of them if several end at the same place. The context is reset first
+    /// and holds the match's captures afterwards.
+    ///
+    /// The DFA tells where the first match ends; without it every end is tried in
+    /// turn, each against every start before it.
+    pub(crate) fn find_earliest(&self, from: usize, context: &mut MatchContext) -> Option<Match> {
+        let first_end = match self.dfa {
+            Some(dfa) if !self.flags.anchored => dfa.earliest_end(self.text, from)?,
+            _ => from,
+        };
+        if first_end > self.limit {
+            return None;
+        }
+        for end in self.boundaries(first_end, self.limit) {
+            let last_start = if self.flags.anchored { from } else { end };
+            for start_pos in self.boundaries(from, last_start) {
+                if self.timed_out() {
+                    return None;
                 }
-                AstNode::LookAhead { nodes, .. } | AstNode::LookBehind { nodes, .. } => {
-                    max = max.max(self.count_groups(nodes));
+                context.undo(0);
+                self.count(|stats| stats.positions_attempted += 1);
+                if self
+                    .run(0, start_pos, context, Some(end), self.limit)
+                    .is_some()
+                {
+                    let start = context.match_start_override.unwrap_or(start_pos);
+                    context.consumed = Match {
+                        start: start_pos,
+                        end,
+                    };
+                    return Some(Match {
+                        start,
+                        end: context.match_end_override.unwrap_or(end).max(start),
+                    });
                 }
-                _ => {}
             }
         }
-        max
+        None
     }
 
-    fn match_nodes(&self, nodes: &[AstNode], pos: usize, ctx: &mut MatchContext) -> Option<usize> {
-        if nodes.is_empty() {
-            return Some(pos);
-        }
+    // The character boundaries from `from` up to and including `to`
+    fn boundaries(&self, from: usize, to: usize) -> impl Iterator<Item = usize> + '_ {
+        self.text[from..to]
+            .char_indices()
+            .map(move |(i, _)| from + i)
+            .chain(core::iter::once(to))
+    }
 
-        let node = &nodes[0];
-        let remaining = &nodes[1..];
+    /// Collects the capture groups of a match [`find_at`](Self::find_at) just found.
+    pub(crate) fn captures(&self, full_match: Match, context: &MatchContext) -> Captures {
+        Captures::with_names(
+            full_match,
+            context.captures[1..].to_vec(),
+            self.program.names.clone(),
+        )
+    }
 
-        match node {
-            AstNode::Literal(c) => {
-                let char_len = c.len_utf8();
-                if pos + char_len > self.text.len() {
-                    return None;
+    // Skips ahead to the next position the scan plan allows a match to start at
+    fn next_candidate(
+        &self,
+        plan: &ScanPlan,
+        mut pos: usize,
+        next_required: &mut Option<usize>,
+    ) -> Option<usize> {
+        // Each step only moves forward, so repeat until all of them accept the position
+        loop {
+            let before = pos;
+
+            // Every character is at least one byte
+            if self.text.len() - pos < plan.min_len {
+                return None;
+            }
+
+            match plan.start_anchor {
+                Some(Anchor::Text) if pos > 0 => return None,
+                Some(Anchor::Line) if pos > 0 && self.text.as_bytes()[pos - 1] != b'\n' => {
+                    pos += self.text[pos..].find('\n')? + 1;
                 }
+                _ => {}
+            }
 
-                let matches = if self.flags.ignore_case.unwrap_or(false) {
-                    let current_char = self.text[pos..].chars().next()?;
-                    c.to_lowercase().eq(current_char.to_lowercase())
-                } else {
-                    self.text[pos..].starts_with(*c)
+            if let Some((anchor, max_len)) = plan.end_anchor {
+                let end = match anchor {
+                    Anchor::Text => self.text.len(),
+                    Anchor::Line => self.text[pos..]
+                        .find('\n')
+                        .map_or(self.text.len(), |i| pos + i),
                 };
+                // A character is at most 4 bytes in UTF-8
+                pos = pos.max(
+                    self.char_boundary_at_or_after(end.saturating_sub(max_len.saturating_mul(4))),
+                );
+            }
 
-                if matches {
-                    let next_pos = pos + self.text[pos..].chars().next()?.len_utf8();
-                    self.match_nodes(remaining, next_pos, ctx)
-                } else {
-                    None
-                }
+            if let Some(required) = &plan.required {
+                // The required literal must occur at or after the match start
+                let found = match *next_required {
+                    Some(found) if found >= pos => found,
+                    _ => required.find(self.text, pos)?,
+                };
+                *next_required = Some(found);
+                pos = pos.max(self.char_boundary_at_or_after(required.earliest_start(found)));
             }
-            AstNode::CharClass(class) => {
-                let current_char = self.text[pos..].chars().next()?;
-                if self.match_char_class(class, current_char) {
-                    self.match_nodes(remaining, pos + current_char.len_utf8(), ctx)
-                } else {
-                    None
-                }
+
+            if let Some(prefilter) = &plan.prefilter {
+                pos = prefilter.next_candidate(self.text, pos)?;
             }
-            AstNode::StartAnchor => {
-                let is_start = pos == 0;
-                let is_line_start =
-                    self.flags.multiline && pos > 0 && self.text.as_bytes()[pos - 1] == b'\n';
-                if is_start || is_line_start {
-                    self.match_nodes(remaining, pos, ctx)
-                } else {
-                    None
-                }
+
+            if pos == before {
+                return Some(pos);
             }
-            AstNode::EndAnchor => {
-                let is_end = pos == self.text.len();
-                let is_line_end = self.flags.multiline
-                    && pos < self.text.len()
-                    && self.text.as_bytes()[pos] == b'\n';
-                if is_end || is_line_end {
-                    self.match_nodes(remaining, pos, ctx)
-                } else {
-                    None
-                }
+        }
+    }
+
+    fn char_boundary_at_or_after(&self, mut pos: usize) -> usize {
+        while !self.text.is_char_boundary(pos) {
+            pos += 1;
+        }
+        pos
+    }
+
+    // Runs the program from `pc` as a backtracking search. If `end` is given, the
+    // match has to finish exactly there, and it can't consume text past `limit`.
+    fn run(
+        &self,
+        pc: Pc,
+        pos: usize,
+        ctx: &mut MatchContext,
+        end: Option<usize>,
+        limit: usize,
+    ) -> Option<usize> {
+        let insts = &self.program.insts;
+        let entry = ctx.mark();
+        // Alternatives still to try, with the undo mark to roll back to first
+        let mut backtrack: Vec<(Pc, usize, usize)> = vec![];
+        let (mut pc, mut pos) = (pc, pos);
+
+        loop {
+            if self.out_of_time() {
+                return None;
             }
-            AstNode::WordBoundary => {
-                if self.is_word_boundary(pos) {
-                    self.match_nodes(remaining, pos, ctx)
-                } else {
+            #[cfg(feature = "async")]
+            self.steps_taken.set(self.steps_taken.get() + 1);
+            self.emit(TraceEvent::Step { pc, pos });
+            self.count(|stats| stats.steps += 1);
+            let step = match &insts[pc] {
+                Inst::Match => {
+                    if end.is_none_or(|end| end == pos) {
+                        return Some(pos);
+                    }
                     None
                 }
-            }
-            AstNode::StartWord => {
-                if self.is_word_boundary(pos) && self.is_word_char_at(pos) {
-                    self.match_nodes(remaining, pos, ctx)
-                } else {
-                    None
+                Inst::Literal(c) => self.text[pos..]
+                    .starts_with(*c)
+                    .then(|| (pc + 1, pos + c.len_utf8())),
+                Inst::LiteralString(s) => self.text[pos..]
+                    .starts_with(s.as_str())
+                    .then(|| (pc + 1, pos + s.len())),
+                Inst::FoldedLiteral(chars) => chars
+                    .iter()
+                    .try_fold(pos, |at, folded| self.match_folded(folded, at))
+                    .map(|next| (pc + 1, next)),
+                Inst::Class(class) => self.text[pos..]
+                    .chars()
+                    .next()
+                    .filter(|c| class.matches(*c, self.flags))
+                    .map(|c| (pc + 1, pos + c.len_utf8())),
+                Inst::Grapheme => grapheme::cluster_end(self.text, pos)
+                    .filter(|&end| cluster_matches(&self.text[pos..end], self.flags))
+                    .map(|end| (pc + 1, end)),
+                Inst::Assert(position) => {
+                    self.check_position(*position, pos).then_some((pc + 1, pos))
                 }
-            }
-            AstNode::EndWord => {
-                if self.is_word_boundary(pos) && !self.is_word_char_at(pos) {
-                    self.match_nodes(remaining, pos, ctx)
-                } else {
-                    None
+                Inst::SetMatchStart => {
+                    ctx.set_match_start(pos);
+                    Some((pc + 1, pos))
                 }
-            }
-            AstNode::SetMatchStart => {
-                ctx.match_start_override = Some(pos);
-                self.match_nodes(remaining, pos, ctx)
-            }
-            AstNode::SetMatchEnd => {
-                ctx.match_end_override = Some(pos);
-                self.match_nodes(remaining, pos, ctx)
-            }
-            AstNode::Alternation(alts) => {
-                for alt in alts {
-                    // Snapshot context
-                    let mut fork_ctx = ctx.clone();
-                    if let Some(next_pos) = self.match_nodes(alt, pos, &mut fork_ctx)
-                        && let Some(final_pos) =
-                            self.match_nodes(remaining, next_pos, &mut fork_ctx)
-                    {
-                        *ctx = fork_ctx;
-                        return Some(final_pos);
-                    }
+                Inst::SetMatchEnd => {
+                    ctx.set_match_end(pos);
+                    Some((pc + 1, pos))
                 }
-
-                None
-            }
-            AstNode::Group {
-                nodes: group_nodes,
-                capture,
-                index,
-                ..
-            } => {
-                let start_capture = pos;
-                if let Some(next_pos) = self.match_nodes(group_nodes, pos, ctx) {
-                    if *capture && index.is_some() {
-                        let idx = index.unwrap();
-                        if idx < ctx.captures.len() {
-                            ctx.captures[idx] = Some(Match {
-                                start: start_capture,
-                                end: next_pos,
-                            });
-                        }
-                    }
-                    self.match_nodes(remaining, next_pos, ctx)
-                } else {
-                    None
+                Inst::Split { first, second } => {
+                    backtrack.push((*second, pos, ctx.mark()));
+                    Some((*first, pos))
                 }
-            }
-            AstNode::Backref(idx) => {
-                if let Some(Some(m)) = ctx.captures.get(*idx) {
-                    let captured_text = &self.text[m.start..m.end];
-                    if self.text[pos..].starts_with(captured_text) {
-                        self.match_nodes(remaining, pos + captured_text.len(), ctx)
+                Inst::Jump(target) => Some((*target, pos)),
+                Inst::GroupStart { reg } => {
+                    ctx.set_register(*reg, pos);
+                    Some((pc + 1, pos))
+                }
+                Inst::GroupEnd { index, reg } => {
+                    let span = Match {
+                        start: ctx.registers[*reg],
+                        end: pos,
+                    };
+                    ctx.set_capture(*index, span.clone());
+                    self.emit(TraceEvent::CaptureSaved {
+                        group: *index,
+                        span,
+                    });
+                    Some((pc + 1, pos))
+                }
+                Inst::Backref(idx) => self
+                    .match_backref(*idx, pos, ctx)
+                    .map(|next| (pc + 1, next)),
+                Inst::Look {
+                    body,
+                    next,
+                    behind,
+                    positive,
+                    min_len,
+                    max_len,
+                } => {
+                    let matched = if *behind {
+                        self.match_lookbehind(*body, (*min_len, *max_len), pos, ctx)
                     } else {
-                        None
+                        self.match_lookahead(*body, pos, ctx)
+                    };
+                    (matched == *positive).then_some((*next, pos))
+                }
+                Inst::RepeatStart { counter } => {
+                    ctx.set_register(*counter, 0);
+                    Some((pc + 1, pos))
+                }
+                Inst::RepeatLoop {
+                    counter,
+                    min,
+                    max,
+                    greedy,
+                    exit,
+                } => {
+                    let count = ctx.registers[*counter];
+                    let can_stop = count >= *min;
+                    let can_repeat = max.is_none_or(|max| count < max);
+                    match (can_stop, can_repeat) {
+                        (false, _) => Some((pc + 1, pos)),
+                        (true, false) => Some((*exit, pos)),
+                        (true, true) if *greedy => {
+                            backtrack.push((*exit, pos, ctx.mark()));
+                            Some((pc + 1, pos))
+                        }
+                        (true, true) => {
+                            backtrack.push((pc + 1, pos, ctx.mark()));
+                            Some((*exit, pos))
+                        }
                     }
-                } else {
-                    // Backref to non-existent group fails
-                    None
                 }
-            }
-            AstNode::LookAhead {
-                nodes: look_nodes,
-                positive,
-            } => {
-                let mut look_ctx = ctx.clone();
-                let matched = self.match_nodes(look_nodes, pos, &mut look_ctx).is_some();
-                if matched == *positive {
-                    self.match_nodes(remaining, pos, ctx)
-                } else {
-                    None
+                Inst::IterStart { counter } => {
+                    ctx.set_register(counter + 1, pos);
+                    Some((pc + 1, pos))
                 }
-            }
-            AstNode::LookBehind {
-                nodes: look_nodes,
-                positive,
-            } => {
-                // Lookbehind implementation: try matching ending at pos
-                let mut matched = false;
-                for start in 0..=pos {
-                    let mut look_ctx = ctx.clone();
-                    if let Some(end) = self.match_nodes(look_nodes, start, &mut look_ctx)
-                        && end == pos
-                    {
-                        matched = true;
-                        break;
+                Inst::RepeatNext { counter, min, head } => {
+                    let count = ctx.registers[*counter];
+                    // An empty iteration ends the loop, as in PCRE, keeping what it
+                    // captured. The loop's exit is the next instruction.
+                    if pos == ctx.registers[counter + 1] && count >= *min {
+                        Some((pc + 1, pos))
+                    } else {
+                        ctx.set_register(*counter, count + 1);
+                        Some((*head, pos))
                     }
                 }
+            };
 
-                if matched == *positive {
-                    self.match_nodes(remaining, pos, ctx)
-                } else {
-                    None
+            self.count(|stats| {
+                stats.peak_backtrack_depth = stats.peak_backtrack_depth.max(backtrack.len())
+            });
+            match step.filter(|&(_, next_pos)| next_pos <= limit) {
+                Some((next_pc, next_pos)) => {
+                    pc = next_pc;
+                    pos = next_pos;
                 }
-            }
-            AstNode::ZeroOrMore {
-                node: inner,
-                greedy,
-            } => self.match_quantifier(
-                inner,
-                QuantifierParams {
-                    min: 0,
-                    max: None,
-                    greedy: *greedy,
-                },
-                remaining,
-                pos,
-                ctx,
-            ),
-            AstNode::OneOrMore {
-                node: inner,
-                greedy,
-            } => self.match_quantifier(
-                inner,
-                QuantifierParams {
-                    min: 1,
-                    max: None,
-                    greedy: *greedy,
-                },
-                remaining,
-                pos,
-                ctx,
-            ),
-            AstNode::Optional {
-                node: inner,
-                greedy,
-            } => self.match_quantifier(
-                inner,
-                QuantifierParams {
-                    min: 0,
-                    max: Some(1),
-                    greedy: *greedy,
-                },
-                remaining,
-                pos,
-                ctx,
-            ),
-            AstNode::Exact { node: inner, count } => self.match_quantifier(
-                inner,
-                QuantifierParams {
-                    min: *count,
-                    max: Some(*count),
-                    greedy: true,
-                },
-                remaining,
-                pos,
-                ctx,
-            ),
-            AstNode::Range {
-                node: inner,
-                min,
-                max,
-                greedy,
-            } => self.match_quantifier(
-                inner,
-                QuantifierParams {
-                    min: *min,
-                    max: *max,
-                    greedy: *greedy,
+                None => match backtrack.pop() {
+                    Some((next_pc, next_pos, mark)) => {
+                        ctx.undo(mark);
+                        self.emit(TraceEvent::Backtrack {
+                            pc: next_pc,
+                            pos: next_pos,
+                        });
+                        self.count(|stats| stats.backtracks += 1);
+                        pc = next_pc;
+                        pos = next_pos;
+                    }
+                    None => {
+                        ctx.undo(entry);
+                        return None;
+                    }
                 },
-                remaining,
-                pos,
-                ctx,
-            ),
+            }
         }
     }
 
-    fn match_quantifier(
-        &self,
-        node: &AstNode,
-        params: QuantifierParams,
-        remaining: &[AstNode],
-        pos: usize,
-        ctx: &mut MatchContext,
-    ) -> Option<usize> {
-        // 1. Match minimum required times
-        let mut curr_pos = pos;
-        for _ in 0..params.min {
-            if let Some(next_pos) = self.match_nodes(std::slice::from_ref(node), curr_pos, ctx) {
-                curr_pos = next_pos;
-            } else {
-                return None;
+    fn check_position(&self, position: Position, pos: usize) -> bool {
+        match position {
+            Position::Start | Position::End => {
+                let before = CharKind::of(self.text[..pos].chars().next_back());
+                let after = CharKind::of(self.text[pos..].chars().next());
+                let assertion = match position {
+                    Position::Start => Assertion::LineStart,
+                    _ => Assertion::LineEnd,
+                };
+                assertion.holds(before, after, *self.flags)
+            }
+            Position::TextStart => pos == 0,
+            Position::TextEnd => pos == self.text.len(),
+            Position::Line(line, ordering) => (self.line_of(pos) + 1).cmp(&line) == ordering,
+            Position::Column(column, ordering) => {
+                let start = self.line_starts()[self.line_of(pos)];
+                (pos - start + 1).cmp(&column) == ordering
             }
+            Position::Cursor => self.marks.and_then(Marks::cursor_at) == Some(pos),
+            Position::Visual => self.marks.is_some_and(|marks| marks.in_visual(pos)),
+            Position::Mark(name, ordering) => self
+                .marks
+                .and_then(|marks| marks.get(name))
+                .is_some_and(|mark| pos.cmp(&mark) == ordering),
+            Position::WordBoundary => self.is_word_boundary(pos),
+            Position::StartWord => self.is_word_boundary(pos) && self.is_word_char_at(pos),
+            Position::EndWord => self.is_word_boundary(pos) && !self.is_word_char_at(pos),
         }
-
-        // 2. Match optional times
-        self.match_quantifier_optional(
-            node,
-            params.max.map(|m| m - params.min),
-            params.greedy,
-            remaining,
-            curr_pos,
-            ctx,
-        )
     }
 
-    fn match_quantifier_optional(
-        &self,
-        node: &AstNode,
-        max_remaining: Option<usize>,
-        greedy: bool,
-        remaining: &[AstNode],
-        pos: usize,
-        ctx: &mut MatchContext,
-    ) -> Option<usize> {
-        if let Some(0) = max_remaining {
-            return self.match_nodes(remaining, pos, ctx);
-        }
+    // The offset each line of the text starts at, found the first time a line or
+    // column atom needs them
+    fn line_starts(&self) -> &[usize] {
+        self.line_starts.get_or_init(|| {
+            core::iter::once(0)
+                .chain(self.text.match_indices('\n').map(|(i, _)| i + 1))
+                .collect()
+        })
+    }
 
-        if greedy {
-            // Try to match one more
-            let mut fork_ctx = ctx.clone();
-            if let Some(next_pos) = self.match_nodes(std::slice::from_ref(node), pos, &mut fork_ctx)
-            {
-                // Prevent infinite loops on zero-width matches
-                if next_pos > pos
-                    && let Some(final_pos) = self.match_quantifier_optional(
-                        node,
-                        max_remaining.map(|m| m - 1),
-                        greedy,
-                        remaining,
-                        next_pos,
-                        &mut fork_ctx,
-                    )
-                {
-                    *ctx = fork_ctx;
-                    return Some(final_pos);
-                }
-            }
+    // The line pos is on, counting from 0
+    fn line_of(&self, pos: usize) -> usize {
+        self.line_starts().partition_point(|&start| start <= pos) - 1
+    }
 
-            // If we couldn't match more, or the recursive call failed, try matching the rest
-            self.match_nodes(remaining, pos, ctx)
-        } else {
-            // Lazy: Try matching the rest first
-            let mut fork_ctx = ctx.clone();
-            if let Some(final_pos) = self.match_nodes(remaining, pos, &mut fork_ctx) {
-                *ctx = fork_ctx;
-                return Some(final_pos);
-            }
+    // Matches one character case-insensitively at pos, returning the position after it
+    fn match_folded(&self, folded: &FoldedChar, pos: usize) -> Option<usize> {
+        let current_char = self.text[pos..].chars().next()?;
+        folded
+            .matches(current_char, self.flags)
+            .then(|| pos + current_char.len_utf8())
+    }
 
-            // If that fails, try matching one more
-            if let Some(next_pos) = self.match_nodes(std::slice::from_ref(node), pos, ctx)
-                && next_pos > pos
-            {
-                return self.match_quantifier_optional(
-                    node,
-                    max_remaining.map(|m| m - 1),
-                    greedy,
-                    remaining,
-                    next_pos,
-                    ctx,
-                );
+    fn match_backref(&self, idx: usize, pos: usize, ctx: &MatchContext) -> Option<usize> {
+        // A backreference to a group that isn't set fails, or matches nothing
+        let Some(m) = ctx.captures.get(idx).and_then(Option::as_ref) else {
+            return self.program.unset_backrefs_match_empty.then_some(pos);
+        };
+        let captured_text = &self.text[m.start..m.end];
+        if !self.flags.ignore_case.unwrap_or(false) {
+            return self.text[pos..]
+                .starts_with(captured_text)
+                .then(|| pos + captured_text.len());
+        }
+        // Compare folded characters; the repeat may differ in byte length, e.g. `k`
+        // against the Kelvin sign
+        let mut rest = self.text[pos..].chars();
+        let mut end = pos;
+        for captured in captured_text.chars() {
+            let c = rest.next()?;
+            if !literal_matches(captured, c, self.flags) {
+                return None;
             }
-            None
+            end += c.len_utf8();
         }
+        Some(end)
     }
 
-    fn match_char_class(&self, class: &CharClass, c: char) -> bool {
-        match class {
-            CharClass::Digit => c.is_ascii_digit(),
-            CharClass::NonDigit => !c.is_ascii_digit(),
-            CharClass::Word => c.is_alphanumeric() || c == '_',
-            CharClass::NonWord => !(c.is_alphanumeric() || c == '_'),
-            CharClass::Whitespace => c.is_whitespace(),
-            CharClass::NonWhitespace => !c.is_whitespace(),
-            CharClass::Dot => self.flags.dotall || c != '\n',
-            CharClass::Lowercase => {
-                c.is_lowercase() || (self.flags.ignore_case.unwrap_or(false) && c.is_uppercase())
-            }
-            CharClass::NonLowercase => {
-                !c.is_lowercase() && (!self.flags.ignore_case.unwrap_or(false) || !c.is_uppercase())
-            }
-            CharClass::Uppercase => {
-                c.is_uppercase() || (self.flags.ignore_case.unwrap_or(false) && c.is_lowercase())
-            }
-            CharClass::NonUppercase => {
-                !c.is_uppercase() && (!self.flags.ignore_case.unwrap_or(false) || !c.is_lowercase())
-            }
-            CharClass::Hex => c.is_ascii_hexdigit(),
-            CharClass::NonHex => !c.is_ascii_hexdigit(),
-            CharClass::Octal => c.is_digit(8),
-            CharClass::NonOctal => !c.is_digit(8),
-            CharClass::Alphanumeric => c.is_alphanumeric(),
-            CharClass::NonAlphanumeric => !c.is_alphanumeric(),
-            CharClass::Punctuation => c.is_ascii_punctuation(),
-            CharClass::NonPunctuation => !c.is_ascii_punctuation(),
-            CharClass::WordStart => c.is_alphabetic() || c == '_',
-            CharClass::NonWordStart => !(c.is_alphabetic() || c == '_'),
-            CharClass::Set { chars, negated } => {
-                let ignore_case = self.flags.ignore_case.unwrap_or(false);
-                let found = chars.iter().any(|range| {
-                    if c >= range.start && c <= range.end {
-                        return true;
-                    }
-                    if ignore_case {
-                        if c.to_lowercase()
-                            .any(|lc| lc >= range.start && lc <= range.end)
-                        {
-                            return true;
-                        }
-                        if c.to_uppercase()
-                            .any(|uc| uc >= range.start && uc <= range.end)
-                        {
-                            return true;
-                        }
-                    }
-                    false
-                });
-                if *negated { !found } else { found }
-            }
+    // Lookarounds are atomic and don't keep the captures they make
+    fn match_lookahead(&self, body: Pc, pos: usize, ctx: &mut MatchContext) -> bool {
+        let mark = ctx.mark();
+        let matched = self.run(body, pos, ctx, None, self.text.len()).is_some();
+        self.leave_look(mark, matched, ctx);
+        matched
+    }
+
+    fn match_lookbehind(
+        &self,
+        body: Pc,
+        (min_len, max_len): (usize, Option<usize>),
+        pos: usize,
+        ctx: &mut MatchContext,
+    ) -> bool {
+        // Try matching ending at pos, starting only between `min_len` and `max_len`
+        // characters back, nearest first
+        let mut starts = core::iter::once(pos)
+            .chain(self.text[..pos].char_indices().rev().map(|(i, _)| i))
+            .skip(min_len)
+            .take(max_len.map_or(usize::MAX, |max| (max + 1).saturating_sub(min_len)));
+        let mark = ctx.mark();
+        let matched = starts.any(|start| self.run(body, start, ctx, Some(pos), pos).is_some());
+        self.leave_look(mark, matched, ctx);
+        matched
+    }
+
+    // Lookarounds are atomic, so nothing their body did survives except, as in
+    // PCRE, the groups captured by the match it found. A negative lookaround whose
+    // body matched fails, which undoes those too.
+    fn leave_look(&self, mark: usize, matched: bool, ctx: &mut MatchContext) {
+        if matched && self.program.lookaround_captures {
+            ctx.undo_keeping_captures(mark);
+        } else {
+            ctx.undo(mark);
         }
     }
 
@@ -519,6 +869,79 @@ impl<'a> Matcher<'a> {
     }
 
     fn is_word_char(&self, c: char) -> bool {
-        c.is_alphanumeric() || c == '_'
+        is_word_char(c)
     }
 }
+
+/// Tests a single character against a character class under the given flags.
+pub(crate) fn class_matches(class: &CharClass, c: char, flags: &Flags) -> bool {
+    match class {
+        CharClass::Digit => c.is_ascii_digit(),
+        CharClass::NonDigit => !c.is_ascii_digit(),
+        CharClass::Word => c.is_alphanumeric() || c == '_',
+        CharClass::NonWord => !(c.is_alphanumeric() || c == '_'),
+        CharClass::Whitespace => c.is_whitespace(),
+        CharClass::NonWhitespace => !c.is_whitespace(),
+        CharClass::Dot => {
+            (flags.dotall || !ends_line(c, flags)) && (flags.dot != DotMode::Ascii || c.is_ascii())
+        }
+        CharClass::Lowercase => {
+            c.is_lowercase() || (flags.ignore_case.unwrap_or(false) && c.is_uppercase())
+        }
+        CharClass::NonLowercase => {
+            !c.is_lowercase() && (!flags.ignore_case.unwrap_or(false) || !c.is_uppercase())
+        }
+        CharClass::Uppercase => {
+            c.is_uppercase() || (flags.ignore_case.unwrap_or(false) && c.is_lowercase())
+        }
+        CharClass::NonUppercase => {
+            !c.is_uppercase() && (!flags.ignore_case.unwrap_or(false) || !c.is_lowercase())
+        }
+        CharClass::Hex => c.is_ascii_hexdigit(),
+        CharClass::NonHex => !c.is_ascii_hexdigit(),
+        CharClass::Octal => c.is_digit(8),
+        CharClass::NonOctal => !c.is_digit(8),
+        CharClass::Alphanumeric => c.is_alphanumeric(),
+        CharClass::NonAlphanumeric => !c.is_alphanumeric(),
+        CharClass::Punctuation => c.is_ascii_punctuation(),
+        CharClass::NonPunctuation => !c.is_ascii_punctuation(),
+        CharClass::WordStart => c.is_alphabetic() || c == '_',
+        CharClass::NonWordStart => !(c.is_alphabetic() || c == '_'),
+        CharClass::Set { chars, negated } => {
+            let ignore_case = flags.ignore_case.unwrap_or(false);
+            let found = set_contains(chars, c)
+                || ignore_case && fold_orbit(c).any(|variant| set_contains(chars, variant));
+            if *negated { !found } else { found }
+        }
+    }
+}
+
+/// Checks whether `c` is in one of a set's ranges, without folding case.
+pub(crate) fn set_contains(chars: &[CharRange], c: char) -> bool {
+    chars.iter().any(|range| range.start <= c && c <= range.end)
+}
+
+/// Tests a whole grapheme cluster against `.` under the given flags. A cluster
+/// holds a line ending only at its start, or as the `\n` of `\r\n`.
+pub(crate) fn cluster_matches(cluster: &str, flags: &Flags) -> bool {
+    flags.dotall
+        || !(cluster.contains('\n') || cluster.chars().next().is_some_and(|c| ends_line(c, flags)))
+}
+
+// Whether `.` stops at `c` without `dotall`
+fn ends_line(c: char, flags: &Flags) -> bool {
+    c == '\n' || (c == '\r' && (flags.crlf || flags.dot == DotMode::NotCr))
+}
+
+/// Compares a pattern literal against a text character, folding case if requested.
+pub(crate) fn literal_matches(literal: char, c: char, flags: &Flags) -> bool {
+    if flags.ignore_case.unwrap_or(false) {
+        literal.to_lowercase().eq(c.to_lowercase())
+    } else {
+        literal == c
+    }
+}
+
+pub(crate) fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
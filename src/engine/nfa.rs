@@ -0,0 +1,452 @@
+use super::{class_matches, is_word_char, literal_matches, set_contains};
+use crate::compiler::{case_closure, min_len};
+use crate::flags::Flags;
+use crate::parser::{AstNode, CharClass, CharRange};
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub(crate) type StateId = usize;
+
+// Counted repetitions copy their operand, so large counts can blow up the automaton
+const MAX_STATES: usize = 10_000;
+
+// Character tests with more members than this aren't enumerated for the one-pass check
+const MAX_TEST_CHARS: usize = 256;
+
+// Positions with more possible next tests than this aren't considered one-pass
+const MAX_ONE_PASS_BRANCHES: usize = 16;
+
+const KELVIN_SIGN: char = '\u{212A}';
+
+/// What the character on one side of a position looks like, as far as assertions care.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum CharKind {
+    /// The start or end of the text.
+    Edge,
+    Newline,
+    /// A `\r`, which ends lines in CRLF mode.
+    Return,
+    Word,
+    Other,
+}
+
+impl CharKind {
+    pub(crate) const COUNT: usize = 5;
+
+    pub(crate) fn of(c: Option<char>) -> Self {
+        match c {
+            None => CharKind::Edge,
+            Some('\n') => CharKind::Newline,
+            Some('\r') => CharKind::Return,
+            Some(c) if is_word_char(c) => CharKind::Word,
+            Some(_) => CharKind::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Assertion {
+    LineStart,
+    LineEnd,
+    TextStart,
+    TextEnd,
+    WordBoundary,
+    WordStart,
+    WordEnd,
+}
+
+impl Assertion {
+    pub(crate) fn holds(self, before: CharKind, after: CharKind, flags: Flags) -> bool {
+        // In CRLF mode a `\r` ends a line too, but `\r\n` is a single line end
+        let crlf = flags.crlf;
+        match self {
+            Assertion::LineStart => {
+                before == CharKind::Edge
+                    || (flags.multiline
+                        && (before == CharKind::Newline
+                            || (crlf && before == CharKind::Return && after != CharKind::Newline)))
+            }
+            Assertion::LineEnd => {
+                after == CharKind::Edge
+                    || (flags.multiline
+                        && ((after == CharKind::Newline && !(crlf && before == CharKind::Return))
+                            || (crlf && after == CharKind::Return)))
+            }
+            Assertion::TextStart => before == CharKind::Edge,
+            Assertion::TextEnd => after == CharKind::Edge,
+            Assertion::WordBoundary => (before == CharKind::Word) != (after == CharKind::Word),
+            Assertion::WordStart => before != CharKind::Word && after == CharKind::Word,
+            Assertion::WordEnd => before == CharKind::Word && after != CharKind::Word,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum CharTest {
+    Literal(char),
+    /// Any class but a set.
+    Class(CharClass),
+    /// A bracket expression, tested without folding case: under `ignore_case` its
+    /// ranges are already the case-folded closure.
+    Set {
+        chars: Vec<CharRange>,
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum State {
+    /// Consumes one character that passes the test.
+    Char {
+        test: CharTest,
+        next: StateId,
+    },
+    /// Continues with every target at once, without consuming anything.
+    Split(Vec<StateId>),
+    /// Continues with `next` only if the assertion holds at the current position.
+    Assert {
+        assertion: Assertion,
+        next: StateId,
+    },
+    Match,
+}
+
+/// A Thompson NFA for the regular subset of the AST.
+///
+/// Priorities between alternatives and greedy/lazy quantifiers are dropped, so the
+/// automaton only answers *whether* (and where) a match ends, not which match the
+/// backtracking engine would pick.
+#[derive(Debug)]
+pub(crate) struct Nfa {
+    states: Vec<State>,
+    start: StateId,
+    flags: Flags,
+    nullable_loop: bool,
+    reversed: bool,
+}
+
+impl Nfa {
+    /// Builds the NFA, or returns `None` if the pattern uses backreferences,
+    /// lookarounds, `\zs`/`\ze` or line, column or mark atoms, or is too large.
+    pub(crate) fn new(nodes: &[AstNode], flags: &Flags) -> Option<Self> {
+        Self::build(nodes, flags, false)
+    }
+
+    /// Builds an NFA for the pattern read back to front, which matches the reversed
+    /// text of every match. Its `before` side is the character after a position.
+    pub(crate) fn reversed(nodes: &[AstNode], flags: &Flags) -> Option<Self> {
+        Self::build(nodes, flags, true)
+    }
+
+    fn build(nodes: &[AstNode], flags: &Flags, reversed: bool) -> Option<Self> {
+        let mut builder = Builder {
+            states: vec![State::Match],
+            nullable_loop: false,
+            ignore_case: flags.ignore_case.unwrap_or(false),
+            reversed,
+        };
+        let start = builder.sequence(nodes, 0)?;
+        Some(Nfa {
+            states: builder.states,
+            start,
+            flags: *flags,
+            nullable_loop: builder.nullable_loop,
+            reversed,
+        })
+    }
+
+    pub(crate) fn start(&self) -> StateId {
+        self.start
+    }
+
+    /// Returns the test of every character-consuming state.
+    pub(crate) fn tests(&self) -> impl Iterator<Item = &CharTest> {
+        self.states.iter().filter_map(|state| match state {
+            State::Char { test, .. } => Some(test),
+            _ => None,
+        })
+    }
+
+    /// Follows every non-consuming transition reachable from `core` at a position
+    /// between `before` and `after`. Returns the character-consuming states reached
+    /// and whether the match state was reached.
+    pub(crate) fn closure(
+        &self,
+        core: &[StateId],
+        before: CharKind,
+        after: CharKind,
+    ) -> (Vec<StateId>, bool) {
+        let mut seen = vec![false; self.states.len()];
+        let mut stack = core.to_vec();
+        let mut consuming = vec![];
+        let mut matched = false;
+        while let Some(id) = stack.pop() {
+            if core::mem::replace(&mut seen[id], true) {
+                continue;
+            }
+            match &self.states[id] {
+                State::Char { .. } => consuming.push(id),
+                State::Split(targets) => stack.extend(targets.iter().rev()),
+                State::Assert { assertion, next } => {
+                    let (before, after) = if self.reversed {
+                        (after, before)
+                    } else {
+                        (before, after)
+                    };
+                    if assertion.holds(before, after, self.flags) {
+                        stack.push(*next);
+                    }
+                }
+                State::Match => matched = true,
+            }
+        }
+        (consuming, matched)
+    }
+
+    /// Checks whether a match attempt can always tell which way to go from the next
+    /// character alone, so the backtracking matcher never revisits a choice.
+    ///
+    /// Wherever several consuming states are reachable at once, their tests have to be
+    /// provably disjoint. Unbounded loops whose body can match empty are rejected too,
+    /// since the backtracker can split the same input between iterations many ways.
+    pub(crate) fn is_one_pass(&self) -> bool {
+        if self.nullable_loop {
+            return false;
+        }
+        let positions = core::iter::once(self.start).chain(self.states.iter().filter_map(
+            |state| match state {
+                State::Char { next, .. } => Some(*next),
+                _ => None,
+            },
+        ));
+        for position in positions {
+            let Some(consuming) = self.reachable_tests(position) else {
+                return false;
+            };
+            for (i, a) in consuming.iter().enumerate() {
+                for b in &consuming[i + 1..] {
+                    if !self.disjoint(a, b) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    // Like `closure`, but assumes every assertion may hold. Gives up on positions
+    // with too many branches to compare pairwise.
+    fn reachable_tests(&self, from: StateId) -> Option<Vec<&CharTest>> {
+        let mut seen = vec![false; self.states.len()];
+        let mut stack = vec![from];
+        let mut tests = vec![];
+        while let Some(id) = stack.pop() {
+            if core::mem::replace(&mut seen[id], true) {
+                continue;
+            }
+            match &self.states[id] {
+                State::Char { test, .. } => {
+                    if tests.len() == MAX_ONE_PASS_BRANCHES {
+                        return None;
+                    }
+                    tests.push(test);
+                }
+                State::Split(targets) => stack.extend(targets),
+                State::Assert { next, .. } => stack.push(*next),
+                State::Match => {}
+            }
+        }
+        Some(tests)
+    }
+
+    fn disjoint(&self, a: &CharTest, b: &CharTest) -> bool {
+        let (small, other) = match (self.members(a), self.members(b)) {
+            (Some(chars), _) => (chars, b),
+            (None, Some(chars)) => (chars, a),
+            (None, None) => return false,
+        };
+        !small.into_iter().any(|c| self.passes(other, c))
+    }
+
+    // Every character a test accepts, if there are few enough to list
+    fn members(&self, test: &CharTest) -> Option<Vec<char>> {
+        let ignore_case = self.flags.ignore_case.unwrap_or(false);
+        match test {
+            CharTest::Literal(c) if !ignore_case => Some(vec![*c]),
+            CharTest::Literal(c) if c.is_ascii() => {
+                let mut chars = vec![c.to_ascii_lowercase(), c.to_ascii_uppercase()];
+                if c.eq_ignore_ascii_case(&'k') {
+                    chars.push(KELVIN_SIGN);
+                }
+                Some(chars)
+            }
+            CharTest::Class(CharClass::Digit) => Some(('0'..='9').collect()),
+            CharTest::Class(CharClass::Octal) => Some(('0'..='7').collect()),
+            CharTest::Class(CharClass::Hex) => {
+                Some(('0'..='9').chain('a'..='f').chain('A'..='F').collect())
+            }
+            CharTest::Set {
+                chars,
+                negated: false,
+            } => {
+                let total: usize = chars
+                    .iter()
+                    .map(|r| (r.end as usize).saturating_sub(r.start as usize) + 1)
+                    .sum();
+                (total <= MAX_TEST_CHARS)
+                    .then(|| chars.iter().flat_map(|r| r.start..=r.end).collect())
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn passes(&self, test: &CharTest, c: char) -> bool {
+        match test {
+            CharTest::Literal(literal) => literal_matches(*literal, c, &self.flags),
+            CharTest::Class(class) => class_matches(class, c, &self.flags),
+            CharTest::Set { chars, negated } => set_contains(chars, c) != *negated,
+        }
+    }
+
+    /// Returns the state a consuming state moves to on `c`, if its test passes.
+    pub(crate) fn step(&self, id: StateId, c: char) -> Option<StateId> {
+        match &self.states[id] {
+            State::Char { test, next } => self.passes(test, c).then_some(*next),
+            _ => None,
+        }
+    }
+}
+
+struct Builder {
+    states: Vec<State>,
+    nullable_loop: bool,
+    ignore_case: bool,
+    reversed: bool,
+}
+
+impl Builder {
+    fn push(&mut self, state: State) -> Option<StateId> {
+        if self.states.len() >= MAX_STATES {
+            return None;
+        }
+        self.states.push(state);
+        Some(self.states.len() - 1)
+    }
+
+    // Compiles back to front, so every node already knows where it continues. A
+    // reversed NFA is compiled front to back instead.
+    fn sequence(&mut self, nodes: &[AstNode], next: StateId) -> Option<StateId> {
+        if self.reversed {
+            nodes
+                .iter()
+                .try_fold(next, |next, node| self.node(node, next))
+        } else {
+            nodes
+                .iter()
+                .rev()
+                .try_fold(next, |next, node| self.node(node, next))
+        }
+    }
+
+    fn literal(&mut self, c: char, next: StateId) -> Option<StateId> {
+        self.push(State::Char {
+            test: CharTest::Literal(c),
+            next,
+        })
+    }
+
+    fn node(&mut self, node: &AstNode, next: StateId) -> Option<StateId> {
+        match node {
+            AstNode::Literal(c) => self.literal(*c, next),
+            AstNode::LiteralString(s) if self.reversed => {
+                s.chars().try_fold(next, |next, c| self.literal(c, next))
+            }
+            AstNode::LiteralString(s) => s
+                .chars()
+                .rev()
+                .try_fold(next, |next, c| self.literal(c, next)),
+            AstNode::CharClass(CharClass::Set { chars, negated }) => self.push(State::Char {
+                test: CharTest::Set {
+                    chars: if self.ignore_case {
+                        case_closure(chars)
+                    } else {
+                        chars.clone()
+                    },
+                    negated: *negated,
+                },
+                next,
+            }),
+            AstNode::CharClass(class) => self.push(State::Char {
+                test: CharTest::Class(class.clone()),
+                next,
+            }),
+            AstNode::StartAnchor => self.assert(Assertion::LineStart, next),
+            AstNode::EndAnchor => self.assert(Assertion::LineEnd, next),
+            AstNode::StartText => self.assert(Assertion::TextStart, next),
+            AstNode::EndText => self.assert(Assertion::TextEnd, next),
+            AstNode::WordBoundary => self.assert(Assertion::WordBoundary, next),
+            AstNode::StartWord => self.assert(Assertion::WordStart, next),
+            AstNode::EndWord => self.assert(Assertion::WordEnd, next),
+            AstNode::ZeroOrMore { node, .. } => self.repeat(node, 0, None, next),
+            AstNode::OneOrMore { node, .. } => self.repeat(node, 1, None, next),
+            AstNode::Optional { node, .. } => self.repeat(node, 0, Some(1), next),
+            AstNode::Exact { node, count } => self.repeat(node, *count, Some(*count), next),
+            AstNode::Range { node, min, max, .. } => self.repeat(node, *min, *max, next),
+            AstNode::Group { nodes, .. } => self.sequence(nodes, next),
+            AstNode::Alternation(alts) => {
+                let branches = alts
+                    .iter()
+                    .map(|alt| self.sequence(alt, next))
+                    .collect::<Option<Vec<_>>>()?;
+                self.push(State::Split(branches))
+            }
+            AstNode::SetMatchStart
+            | AstNode::SetMatchEnd
+            | AstNode::Line(..)
+            | AstNode::Column(..)
+            | AstNode::Cursor
+            | AstNode::Visual
+            | AstNode::Mark(..)
+            | AstNode::Grapheme
+            | AstNode::Backref(_)
+            | AstNode::LookAhead { .. }
+            | AstNode::LookBehind { .. } => None,
+        }
+    }
+
+    fn assert(&mut self, assertion: Assertion, next: StateId) -> Option<StateId> {
+        self.push(State::Assert { assertion, next })
+    }
+
+    fn repeat(
+        &mut self,
+        node: &AstNode,
+        min: usize,
+        max: Option<usize>,
+        next: StateId,
+    ) -> Option<StateId> {
+        let mut current = match max {
+            None => {
+                if min_len(core::slice::from_ref(node)) == 0 {
+                    self.nullable_loop = true;
+                }
+                let split = self.push(State::Split(vec![]))?;
+                let body = self.node(node, split)?;
+                self.states[split] = State::Split(vec![body, next]);
+                split
+            }
+            Some(max) => {
+                let mut current = next;
+                for _ in min..max {
+                    let body = self.node(node, current)?;
+                    current = self.push(State::Split(vec![body, next]))?;
+                }
+                current
+            }
+        };
+        for _ in 0..min {
+            current = self.node(node, current)?;
+        }
+        Some(current)
+    }
+}
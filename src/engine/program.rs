@@ -0,0 +1,512 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use super::{class_matches, literal_matches, set_contains};
+use crate::compiler::{case_closure, max_len, min_len};
+use crate::flags::Flags;
+use crate::parser::{AstNode, CharClass, DotGraph};
+
+pub(crate) type Pc = usize;
+
+/// A zero-width check on the characters around a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Position {
+    Start,
+    End,
+    TextStart,
+    TextEnd,
+    // On the line, or at the byte column, counting from 1, or before or after it
+    Line(usize, Ordering),
+    Column(usize, Ordering),
+    // Where the search's marks put the cursor, the visual area or a named mark
+    Cursor,
+    Visual,
+    Mark(char, Ordering),
+    WordBoundary,
+    StartWord,
+    EndWord,
+}
+
+/// A character class with its answers for the first 256 code points precomputed.
+///
+/// Those cover ASCII and Latin-1, where most text and most classes live, so the
+/// common case is a single bit test. Other characters take the general path.
+#[derive(Debug, Clone)]
+pub(crate) struct ClassTable {
+    bits: [u64; 4],
+    class: CharClass,
+}
+
+impl ClassTable {
+    /// Builds the table for `class`. Under `ignore_case` a set is replaced by its
+    /// case-folded closure here, so matching it never folds case.
+    pub(crate) fn new(class: &CharClass, flags: &Flags) -> Self {
+        let class = match class {
+            CharClass::Set { chars, negated } if flags.ignore_case.unwrap_or(false) => {
+                CharClass::Set {
+                    chars: case_closure(chars),
+                    negated: *negated,
+                }
+            }
+            class => class.clone(),
+        };
+        let mut table = ClassTable {
+            bits: [0; 4],
+            class,
+        };
+        for (byte, c) in (0..=255u8).map(|b| (b as usize, b as char)) {
+            if table.matches_general(c, flags) {
+                table.bits[byte / 64] |= 1 << (byte % 64);
+            }
+        }
+        table
+    }
+
+    pub(crate) fn matches(&self, c: char, flags: &Flags) -> bool {
+        match usize::try_from(u32::from(c)) {
+            Ok(code) if code < 256 => self.bits[code / 64] & (1 << (code % 64)) != 0,
+            _ => self.matches_general(c, flags),
+        }
+    }
+
+    /// The class as matched, with any set already folded.
+    pub(crate) fn class(&self) -> &CharClass {
+        &self.class
+    }
+
+    fn matches_general(&self, c: char, flags: &Flags) -> bool {
+        match &self.class {
+            CharClass::Set { chars, negated } => set_contains(chars, c) != *negated,
+            class => class_matches(class, c, flags),
+        }
+    }
+}
+
+/// A pattern character with its lowercase form precomputed, for case-insensitive
+/// matching.
+///
+/// Folds to a single character, which covers nearly everything, are compared
+/// directly; ASCII text needs no Unicode tables at all.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FoldedChar {
+    literal: char,
+    lower: char,
+    simple: bool,
+}
+
+impl FoldedChar {
+    pub(crate) fn new(literal: char) -> Self {
+        let mut lower = literal.to_lowercase();
+        let first = lower.next().unwrap_or(literal);
+        FoldedChar {
+            literal,
+            lower: first,
+            simple: lower.next().is_none(),
+        }
+    }
+
+    pub(crate) fn matches(&self, c: char, flags: &Flags) -> bool {
+        if !self.simple {
+            return literal_matches(self.literal, c, flags);
+        }
+        if c.is_ascii() {
+            return c.to_ascii_lowercase() == self.lower;
+        }
+        let mut lower = c.to_lowercase();
+        lower.next() == Some(self.lower) && lower.next().is_none()
+    }
+}
+
+/// One instruction of a compiled [`Program`].
+///
+/// Unless noted otherwise, an instruction continues at the next one when it succeeds.
+#[derive(Debug, Clone)]
+pub(crate) enum Inst {
+    /// The match is complete.
+    Match,
+    Literal(char),
+    LiteralString(String),
+    /// Literal characters matched case-insensitively.
+    FoldedLiteral(Box<[FoldedChar]>),
+    Class(ClassTable),
+    /// An extended grapheme cluster that `.` may match.
+    Grapheme,
+    Assert(Position),
+    SetMatchStart,
+    SetMatchEnd,
+    /// Continues at `first`, and at `second` if that fails.
+    Split {
+        first: Pc,
+        second: Pc,
+    },
+    Jump(Pc),
+    /// Records where a capture group starts, in a register.
+    GroupStart {
+        reg: usize,
+    },
+    /// Captures the text from the register's position up to here.
+    GroupEnd {
+        index: usize,
+        reg: usize,
+    },
+    Backref(usize),
+    /// Runs the assertion body at `body` (ending in `Match`) as its own atomic search.
+    Look {
+        body: Pc,
+        next: Pc,
+        behind: bool,
+        positive: bool,
+        min_len: usize,
+        max_len: Option<usize>,
+    },
+    /// Resets the iteration counter of a repetition.
+    RepeatStart {
+        counter: usize,
+    },
+    /// Decides whether to run another iteration, starting at the next instruction,
+    /// or to leave the loop for `exit`.
+    RepeatLoop {
+        counter: usize,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+        exit: Pc,
+    },
+    /// Records the position an iteration starts at.
+    IterStart {
+        counter: usize,
+    },
+    /// Ends an iteration and jumps back to the `RepeatLoop` at `head`, or leaves the
+    /// loop if the iteration was empty and `min` iterations are done.
+    RepeatNext {
+        counter: usize,
+        min: usize,
+        head: Pc,
+    },
+}
+
+/// A pattern compiled for the backtracking matcher.
+///
+/// Repetitions loop over their body with a counter instead of copying it, so the
+/// program stays proportional to the pattern's size.
+#[derive(Debug, Clone)]
+pub(crate) struct Program {
+    pub insts: Vec<Inst>,
+    /// The highest capture group index.
+    pub group_count: usize,
+    /// The index of each named capture group.
+    pub names: Arc<[(String, usize)]>,
+    /// Registers hold group starts, and for each repetition a counter and the
+    /// position its current iteration started at.
+    pub registers: usize,
+    /// Whether groups captured inside a lookaround that matched stay set.
+    pub lookaround_captures: bool,
+    /// Whether a backreference to a group that isn't set matches the empty string
+    /// rather than failing.
+    pub unset_backrefs_match_empty: bool,
+}
+
+impl Program {
+    pub(crate) fn new(nodes: &[AstNode], flags: &Flags) -> Self {
+        let mut compiler = Compiler {
+            flags,
+            insts: vec![],
+            group_count: 0,
+            names: vec![],
+            registers: 0,
+        };
+        compiler.sequence(nodes);
+        compiler.insts.push(Inst::Match);
+        Program {
+            insts: compiler.insts,
+            group_count: compiler.group_count,
+            names: compiler.names.into(),
+            registers: compiler.registers,
+            lookaround_captures: true,
+            unset_backrefs_match_empty: false,
+        }
+    }
+
+    /// Returns the class each class instruction tests, in program order.
+    pub(crate) fn classes(&self) -> impl Iterator<Item = &CharClass> {
+        self.insts.iter().filter_map(|inst| match inst {
+            Inst::Class(table) => Some(table.class()),
+            _ => None,
+        })
+    }
+
+    /// Renders the program as a Graphviz DOT graph, one node per instruction.
+    pub(crate) fn to_dot(&self) -> String {
+        let mut graph = DotGraph::new("program");
+        for (pc, inst) in self.insts.iter().enumerate() {
+            let label = match inst {
+                Inst::Match => "match".to_string(),
+                Inst::Literal(c) => format!("{c:?}"),
+                Inst::LiteralString(s) => format!("{s:?}"),
+                Inst::FoldedLiteral(chars) => {
+                    let text: String = chars.iter().map(|folded| folded.literal).collect();
+                    format!("{text:?} (any case)")
+                }
+                Inst::Class(table) => format!("class {}", table.class()),
+                Inst::Grapheme => "grapheme".to_string(),
+                Inst::Assert(position) => format!("assert {position:?}"),
+                Inst::SetMatchStart => "set match start".to_string(),
+                Inst::SetMatchEnd => "set match end".to_string(),
+                Inst::Split { .. } => "split".to_string(),
+                Inst::Jump(_) => "jump".to_string(),
+                Inst::GroupStart { reg } => format!("group start (r{reg})"),
+                Inst::GroupEnd { index, reg } => format!("group {index} end (r{reg})"),
+                Inst::Backref(index) => format!("backref {index}"),
+                Inst::Look {
+                    behind, positive, ..
+                } => {
+                    let direction = if *behind { "lookbehind" } else { "lookahead" };
+                    let sense = if *positive { "" } else { "negative " };
+                    format!("{sense}{direction}")
+                }
+                Inst::RepeatStart { counter } => format!("repeat start (r{counter})"),
+                Inst::RepeatLoop {
+                    min, max, greedy, ..
+                } => {
+                    let max = max.map_or("inf".to_string(), |max| max.to_string());
+                    let lazy = if *greedy { "" } else { ", lazy" };
+                    format!("repeat {min}..{max}{lazy}")
+                }
+                Inst::IterStart { .. } => "iteration start".to_string(),
+                Inst::RepeatNext { .. } => "repeat next".to_string(),
+            };
+            graph.node_with_id(pc, &format!("{pc}: {label}"));
+        }
+        for (pc, inst) in self.insts.iter().enumerate() {
+            match inst {
+                Inst::Match => {}
+                Inst::Split { first, second } => {
+                    graph.edge(pc, *first, Some("1"));
+                    graph.edge(pc, *second, Some("2"));
+                }
+                Inst::Jump(target) => graph.edge(pc, *target, None),
+                Inst::Look { body, next, .. } => {
+                    graph.edge(pc, *body, Some("body"));
+                    graph.edge(pc, *next, None);
+                }
+                Inst::RepeatLoop { exit, .. } => {
+                    graph.edge(pc, pc + 1, Some("again"));
+                    graph.edge(pc, *exit, Some("exit"));
+                }
+                Inst::RepeatNext { head, .. } => {
+                    graph.edge(pc, *head, Some("loop"));
+                    graph.edge(pc, pc + 1, Some("exit"));
+                }
+                _ => graph.edge(pc, pc + 1, None),
+            }
+        }
+        graph.finish()
+    }
+}
+
+struct Compiler<'f> {
+    flags: &'f Flags,
+    insts: Vec<Inst>,
+    group_count: usize,
+    names: Vec<(String, usize)>,
+    registers: usize,
+}
+
+impl Compiler<'_> {
+    fn emit(&mut self, inst: Inst) -> Pc {
+        self.insts.push(inst);
+        self.insts.len() - 1
+    }
+
+    fn ignore_case(&self) -> bool {
+        self.flags.ignore_case.unwrap_or(false)
+    }
+
+    fn next_pc(&self) -> Pc {
+        self.insts.len()
+    }
+
+    fn register(&mut self) -> usize {
+        self.registers += 1;
+        self.registers - 1
+    }
+
+    fn sequence(&mut self, nodes: &[AstNode]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Literal(c) if self.ignore_case() => {
+                self.emit(Inst::FoldedLiteral(Box::new([FoldedChar::new(*c)])));
+            }
+            AstNode::LiteralString(s) if self.ignore_case() => {
+                self.emit(Inst::FoldedLiteral(
+                    s.chars().map(FoldedChar::new).collect(),
+                ));
+            }
+            AstNode::Literal(c) => {
+                self.emit(Inst::Literal(*c));
+            }
+            AstNode::LiteralString(s) => {
+                self.emit(Inst::LiteralString(s.clone()));
+            }
+            AstNode::CharClass(class) => {
+                self.emit(Inst::Class(ClassTable::new(class, self.flags)));
+            }
+            AstNode::Grapheme => {
+                self.emit(Inst::Grapheme);
+            }
+            AstNode::StartAnchor => {
+                self.emit(Inst::Assert(Position::Start));
+            }
+            AstNode::EndAnchor => {
+                self.emit(Inst::Assert(Position::End));
+            }
+            AstNode::StartText => {
+                self.emit(Inst::Assert(Position::TextStart));
+            }
+            AstNode::EndText => {
+                self.emit(Inst::Assert(Position::TextEnd));
+            }
+            AstNode::Line(line, ordering) => {
+                self.emit(Inst::Assert(Position::Line(*line, *ordering)));
+            }
+            AstNode::Column(column, ordering) => {
+                self.emit(Inst::Assert(Position::Column(*column, *ordering)));
+            }
+            AstNode::Cursor => {
+                self.emit(Inst::Assert(Position::Cursor));
+            }
+            AstNode::Visual => {
+                self.emit(Inst::Assert(Position::Visual));
+            }
+            AstNode::Mark(name, ordering) => {
+                self.emit(Inst::Assert(Position::Mark(*name, *ordering)));
+            }
+            AstNode::WordBoundary => {
+                self.emit(Inst::Assert(Position::WordBoundary));
+            }
+            AstNode::StartWord => {
+                self.emit(Inst::Assert(Position::StartWord));
+            }
+            AstNode::EndWord => {
+                self.emit(Inst::Assert(Position::EndWord));
+            }
+            AstNode::SetMatchStart => {
+                self.emit(Inst::SetMatchStart);
+            }
+            AstNode::SetMatchEnd => {
+                self.emit(Inst::SetMatchEnd);
+            }
+            AstNode::Backref(idx) => {
+                self.emit(Inst::Backref(*idx));
+            }
+            AstNode::Group {
+                nodes,
+                name,
+                capture,
+                index,
+            } => match index.filter(|_| *capture) {
+                Some(index) => {
+                    self.group_count = self.group_count.max(index);
+                    if let Some(name) = name {
+                        self.names.push((name.clone(), index));
+                    }
+                    let reg = self.register();
+                    self.emit(Inst::GroupStart { reg });
+                    self.sequence(nodes);
+                    self.emit(Inst::GroupEnd { index, reg });
+                }
+                None => self.sequence(nodes),
+            },
+            AstNode::Alternation(alts) => self.alternation(alts),
+            AstNode::LookAhead { nodes, positive } => self.look(nodes, false, *positive),
+            AstNode::LookBehind { nodes, positive } => self.look(nodes, true, *positive),
+            AstNode::ZeroOrMore { node, greedy } => self.repeat(node, 0, None, *greedy),
+            AstNode::OneOrMore { node, greedy } => self.repeat(node, 1, None, *greedy),
+            AstNode::Optional { node, greedy } => self.repeat(node, 0, Some(1), *greedy),
+            AstNode::Exact { node, count } => self.repeat(node, *count, Some(*count), true),
+            AstNode::Range {
+                node,
+                min,
+                max,
+                greedy,
+            } => self.repeat(node, *min, *max, *greedy),
+        }
+    }
+
+    // Each branch but the last is guarded by a split falling through to the next one
+    fn alternation(&mut self, alts: &[Vec<AstNode>]) {
+        let mut jumps = vec![];
+        for (i, alt) in alts.iter().enumerate() {
+            if i + 1 == alts.len() {
+                self.sequence(alt);
+                break;
+            }
+            let split = self.emit(Inst::Split {
+                first: 0,
+                second: 0,
+            });
+            self.sequence(alt);
+            jumps.push(self.emit(Inst::Jump(0)));
+            let second = self.next_pc();
+            self.insts[split] = Inst::Split {
+                first: split + 1,
+                second,
+            };
+        }
+        if alts.is_empty() {
+            // No branch can match
+            let empty = CharClass::Set {
+                chars: vec![],
+                negated: false,
+            };
+            self.emit(Inst::Class(ClassTable::new(&empty, self.flags)));
+        }
+        let end = self.next_pc();
+        for jump in jumps {
+            self.insts[jump] = Inst::Jump(end);
+        }
+    }
+
+    fn look(&mut self, nodes: &[AstNode], behind: bool, positive: bool) {
+        let look = self.emit(Inst::Match);
+        let body = self.next_pc();
+        self.sequence(nodes);
+        self.emit(Inst::Match);
+        self.insts[look] = Inst::Look {
+            body,
+            next: self.next_pc(),
+            behind,
+            positive,
+            min_len: min_len(nodes),
+            max_len: max_len(nodes),
+        };
+    }
+
+    fn repeat(&mut self, node: &AstNode, min: usize, max: Option<usize>, greedy: bool) {
+        // The counter and the iteration's start position live in adjacent registers
+        let counter = self.register();
+        self.register();
+        self.emit(Inst::RepeatStart { counter });
+        let head = self.emit(Inst::Match);
+        self.emit(Inst::IterStart { counter });
+        self.node(node);
+        self.emit(Inst::RepeatNext { counter, min, head });
+        self.insts[head] = Inst::RepeatLoop {
+            counter,
+            min,
+            max,
+            greedy,
+            exit: self.next_pc(),
+        };
+    }
+}
@@ -0,0 +1,86 @@
+// Byte scans for candidate match positions. With the `simd` feature these compare
+// 16 bytes at a time on x86_64, where SSE2 is always available; elsewhere, and for
+// the tail of the text, they compare a byte at a time.
+
+/// The most bytes [`find_any_byte`] looks for at once.
+pub(crate) const MAX_NEEDLES: usize = 3;
+
+/// Returns the position of the first byte in `haystack` that is one of `needles`.
+///
+/// `needles` holds between one and [`MAX_NEEDLES`] bytes.
+pub(crate) fn find_any_byte(needles: &[u8], haystack: &[u8]) -> Option<usize> {
+    debug_assert!((1..=MAX_NEEDLES).contains(&needles.len()));
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    // SAFETY: SSE2 is part of every x86_64 target
+    let found = unsafe { sse2::find_any_byte(needles, haystack) };
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    let found = scalar_find_any_byte(needles, haystack);
+    found
+}
+
+/// Returns the position of the first occurrence of `needle` in `haystack`,
+/// ignoring ASCII case.
+pub(crate) fn find_ignore_ascii_case(needle: &[u8], haystack: &[u8]) -> Option<usize> {
+    let Some(&first) = needle.first() else {
+        return Some(0);
+    };
+    let lower = first.to_ascii_lowercase();
+    let upper = first.to_ascii_uppercase();
+    let firsts = [lower, upper];
+    let firsts = if lower == upper {
+        &firsts[..1]
+    } else {
+        &firsts
+    };
+    let mut from = 0;
+    // Jump between occurrences of the first byte, checking the rest at each
+    while let Some(i) = find_any_byte(firsts, haystack.get(from..)?) {
+        let start = from + i;
+        let window = haystack.get(start..start + needle.len())?;
+        if window.eq_ignore_ascii_case(needle) {
+            return Some(start);
+        }
+        from = start + 1;
+    }
+    None
+}
+
+fn scalar_find_any_byte(needles: &[u8], haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|b| needles.contains(b))
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use core::arch::x86_64::{
+        _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_or_si128, _mm_set1_epi8,
+        _mm_setzero_si128,
+    };
+
+    const LANES: usize = 16;
+
+    #[target_feature(enable = "sse2")]
+    pub(super) fn find_any_byte(needles: &[u8], haystack: &[u8]) -> Option<usize> {
+        let mut splats = [_mm_setzero_si128(); super::MAX_NEEDLES];
+        for (splat, &b) in splats.iter_mut().zip(needles) {
+            *splat = _mm_set1_epi8(b as i8);
+        }
+        let splats = &splats[..needles.len()];
+
+        let mut chunks = haystack.chunks_exact(LANES);
+        let mut offset = 0;
+        for chunk in &mut chunks {
+            // SAFETY: the chunk is exactly 16 bytes, which is what an unaligned load
+            // reads
+            let bytes = unsafe { _mm_loadu_si128(chunk.as_ptr().cast()) };
+            let hits = splats.iter().fold(_mm_setzero_si128(), |hits, &splat| {
+                _mm_or_si128(hits, _mm_cmpeq_epi8(bytes, splat))
+            });
+            let mask = _mm_movemask_epi8(hits);
+            if mask != 0 {
+                return Some(offset + mask.trailing_zeros() as usize);
+            }
+            offset += LANES;
+        }
+        super::scalar_find_any_byte(needles, chunks.remainder()).map(|i| offset + i)
+    }
+}
@@ -0,0 +1,55 @@
+use crate::captures::Match;
+use alloc::vec::Vec;
+
+/// Something the backtracking matcher did during a traced search.
+///
+/// Instructions are identified by their index in the compiled program, the same
+/// numbers [`Regex::to_dot`](crate::Regex::to_dot) labels its nodes with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// A match attempt begins at this byte offset.
+    Attempt { start: usize },
+    /// The instruction at `pc` runs at byte offset `pos`.
+    Step { pc: usize, pos: usize },
+    /// An instruction failed, and the matcher resumes an alternative it saved earlier.
+    Backtrack { pc: usize, pos: usize },
+    /// A capture group finished, recording the text it matched so far. A later
+    /// backtrack may still undo it.
+    CaptureSaved { group: usize, span: Match },
+}
+
+/// The outcome of [`Regex::trace`](crate::Regex::trace): every event of a search,
+/// in order, and the match it found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub events: Vec<TraceEvent>,
+    pub result: Option<Match>,
+}
+
+impl Trace {
+    /// Returns the number of times the matcher backtracked.
+    pub fn backtracks(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| matches!(event, TraceEvent::Backtrack { .. }))
+            .count()
+    }
+}
+
+/// Counters describing the work a search did, from
+/// [`Regex::find_with_stats`](crate::Regex::find_with_stats).
+///
+/// Only the backtracking matcher is counted. Start positions ruled out by the
+/// literal search, DFA or scan plan cost nothing here, so a rewrite that lets those
+/// filters do more of the work shows up as fewer attempts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MatchStats {
+    /// Start positions the backtracking matcher tried.
+    pub positions_attempted: usize,
+    /// Instructions executed, across all attempts.
+    pub steps: usize,
+    /// Times a failed path was abandoned for a saved alternative.
+    pub backtracks: usize,
+    /// The most alternatives saved at once, i.e. how deep the backtracking stack got.
+    pub peak_backtrack_depth: usize,
+}
@@ -1,3 +1,5 @@
+use alloc::string::String;
+
 /// Errors that can occur during the compilation of a regular expression.
 #[derive(Debug)]
 pub enum CompileError {
@@ -13,4 +15,10 @@ pub enum CompileError {
     InvalidEscape(String),
     /// A named capture group uses a name that has already been used.
     DuplicateGroupName(String),
+    /// A backreference refers to a group that hasn't closed by then, which
+    /// [`BackrefPolicy::Strict`](crate::BackrefPolicy::Strict) rejects.
+    InvalidBackref(String),
+    /// The pattern is longer, nests groups deeper or compiles to a larger program
+    /// than the limits it was compiled with allow.
+    ExceededLimits(String),
 }
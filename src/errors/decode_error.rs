@@ -0,0 +1,17 @@
+use super::CompileError;
+
+/// Errors that can occur when loading a regex saved with
+/// [`Regex::to_bytes`](crate::Regex::to_bytes).
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The bytes don't start with the header `to_bytes` writes.
+    InvalidHeader,
+    /// The bytes were written in a format version this build can't read.
+    UnsupportedVersion(u8),
+    /// The bytes end in the middle of a value.
+    UnexpectedEnd,
+    /// The value at this byte offset is malformed or out of range.
+    InvalidData(usize),
+    /// The saved program is well-formed but fails the checks every compiled regex passes.
+    Invalid(CompileError),
+}
@@ -0,0 +1,258 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use super::{CompileError, ParseError};
+use crate::compiler::{ComplexityKind, ComplexityWarning, LintKind, LintWarning};
+use crate::parser::{self, MAX_NESTING_DEPTH, Span};
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The pattern can't be used.
+    Error,
+    /// The pattern works, but likely not as intended, or is risky.
+    Warning,
+}
+
+/// An error or warning about a pattern in a structured form, for editors, language
+/// servers and web UIs to show without parsing message strings.
+///
+/// ```
+/// use monster_regex::{Flags, Severity, diagnose};
+///
+/// let diagnostics = diagnose("ab(c", Flags::default());
+/// assert_eq!(diagnostics[0].code, "unmatched-paren");
+/// assert_eq!(diagnostics[0].severity, Severity::Error);
+/// assert_eq!(
+///     diagnostics[0].to_json(),
+///     r#"{"code":"unmatched-paren","message":"Unmatched parenthesis","severity":"error","span":{"start":4,"end":4},"suggestion":"close the group with `)`"}"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// A stable kebab-case identifier for the kind of problem, like `unmatched-paren`.
+    pub code: &'static str,
+    /// A description of the problem for people.
+    pub message: String,
+    pub severity: Severity,
+    /// The byte range in the pattern the diagnostic is about, when it's known.
+    pub span: Option<Span>,
+    /// How the pattern could be fixed, when there's an obvious way.
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    fn error(code: &'static str, message: impl ToString) -> Self {
+        Diagnostic {
+            code,
+            message: message.to_string(),
+            severity: Severity::Error,
+            span: None,
+            suggestion: None,
+        }
+    }
+
+    fn warning(code: &'static str, message: &str, span: Span) -> Self {
+        Diagnostic {
+            code,
+            message: message.to_string(),
+            severity: Severity::Warning,
+            span: Some(span),
+            suggestion: None,
+        }
+    }
+
+    /// Sets the span the diagnostic is about.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    fn suggest(mut self, suggestion: impl ToString) -> Self {
+        self.suggestion = Some(suggestion.to_string());
+        self
+    }
+
+    /// Writes the diagnostic as a JSON object with the keys `code`, `message`,
+    /// `severity` (`"error"` or `"warning"`), `span` (`{"start":_,"end":_}` or
+    /// `null`) and `suggestion` (a string or `null`).
+    pub fn to_json(&self) -> String {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let span = match self.span {
+            Some(span) => format!(r#"{{"start":{},"end":{}}}"#, span.start, span.end),
+            None => "null".to_string(),
+        };
+        let suggestion = match &self.suggestion {
+            Some(suggestion) => json_string(suggestion),
+            None => "null".to_string(),
+        };
+        format!(
+            r#"{{"code":{},"message":{},"severity":"{}","span":{},"suggestion":{}}}"#,
+            json_string(self.code),
+            json_string(&self.message),
+            severity,
+            span,
+            suggestion
+        )
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if u32::from(c) < 0x20 => json.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+impl parser::ParseError {
+    /// Describes the error as a [`Diagnostic`]. The error doesn't know where in
+    /// the pattern it happened, so the span is left out; [`diagnose`](crate::diagnose)
+    /// fills it in.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        use parser::ParseError::*;
+
+        let code = match self {
+            UnexpectedChar(..) => "unexpected-char",
+            UnexpectedEof => "unexpected-end",
+            InvalidQuantifier(_) => "invalid-quantifier",
+            UnmatchedParen => "unmatched-paren",
+            InvalidGroupName(_) => "invalid-group-name",
+            InvalidEscape(_) => "invalid-escape",
+            InvalidControlEscape(_) => "invalid-control-escape",
+            UnknownCharName(_) => "unknown-char-name",
+            InvalidCharClass { .. } => "invalid-char-class",
+            DuplicateGroupName(_) => "duplicate-group-name",
+            InvalidBackref(_) => "invalid-backref",
+            InvalidLineNumber(_) => "invalid-line-number",
+            InvalidGroup(_) => "invalid-group",
+            NestingTooDeep => "nesting-too-deep",
+            ForeignSyntax { .. } => "foreign-syntax",
+        };
+        let diagnostic = Diagnostic::error(code, self);
+        match self {
+            UnmatchedParen => diagnostic.suggest("close the group with `)`"),
+            InvalidEscape(c) => diagnostic.suggest(format!(
+                "drop the `\\` to match `{}`, or write `\\\\` to match a backslash",
+                c
+            )),
+            InvalidControlEscape(_) => {
+                diagnostic.suggest("follow `\\c` with a letter, like `\\cC` for Ctrl-C")
+            }
+            #[cfg(feature = "unicode-names")]
+            UnknownCharName(_) => {
+                diagnostic.suggest("check the name against the Unicode chart, or write `\\x{HEX}`")
+            }
+            #[cfg(not(feature = "unicode-names"))]
+            UnknownCharName(_) => diagnostic.suggest(
+                "enable the `unicode-names` feature to look up names, or write `\\x{HEX}`",
+            ),
+            InvalidCharClass { .. } => diagnostic.suggest(
+                "write the lower end of the range first, or escape a literal `-` as `\\-`",
+            ),
+            DuplicateGroupName(name) => {
+                diagnostic.suggest(format!("rename one of the groups named `{}`", name))
+            }
+            NestingTooDeep => {
+                diagnostic.suggest(format!("nest groups at most {} deep", MAX_NESTING_DEPTH))
+            }
+            ForeignSyntax { rift, .. } => diagnostic.suggest(format!("write `{}` instead", rift)),
+            _ => diagnostic,
+        }
+    }
+
+    /// Writes the error as a JSON [`Diagnostic`].
+    pub fn to_json(&self) -> String {
+        self.to_diagnostic().to_json()
+    }
+}
+
+impl ParseError {
+    /// Describes the error as a [`Diagnostic`] about the rift-format input.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            ParseError::NoDelimiter => Diagnostic::error(
+                "no-delimiter",
+                "Missing `/` between the pattern and its flags",
+            )
+            .suggest("end the pattern with `/`, followed by any flags"),
+            ParseError::InvalidFlags(c) => {
+                Diagnostic::error("invalid-flag", format!("Invalid flag: {}", c))
+            }
+            ParseError::NotSubstitution => {
+                Diagnostic::error("not-substitution", "Not a substitution command")
+                    .suggest("write it as `s/pattern/replacement/flags`")
+            }
+            ParseError::InvalidRange => Diagnostic::error("invalid-range", "Invalid line range"),
+        }
+    }
+
+    /// Writes the error as a JSON [`Diagnostic`].
+    pub fn to_json(&self) -> String {
+        self.to_diagnostic().to_json()
+    }
+}
+
+impl CompileError {
+    /// Describes the error as a [`Diagnostic`], without a span.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            CompileError::InvalidPattern(message) => Diagnostic::error("invalid-pattern", message),
+            CompileError::InvalidQuantifier(message) => {
+                Diagnostic::error("invalid-quantifier", message)
+            }
+            CompileError::InvalidGroup(message) => Diagnostic::error("invalid-group", message),
+            CompileError::UnmatchedParen => {
+                Diagnostic::error("unmatched-paren", "Unmatched parenthesis")
+                    .suggest("close the group with `)`")
+            }
+            CompileError::InvalidEscape(message) => Diagnostic::error("invalid-escape", message),
+            CompileError::DuplicateGroupName(name) => Diagnostic::error(
+                "duplicate-group-name",
+                format!("Duplicate group name: {}", name),
+            )
+            .suggest(format!("rename one of the groups named `{}`", name)),
+            CompileError::InvalidBackref(message) => Diagnostic::error("invalid-backref", message)
+                .suggest("refer only to groups that close before the reference"),
+            CompileError::ExceededLimits(message) => Diagnostic::error("exceeded-limits", message),
+        }
+    }
+}
+
+impl LintWarning {
+    /// Describes the warning as a [`Diagnostic`].
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (code, suggestion) = match self.kind {
+            LintKind::RedundantQuantifier => ("redundant-quantifier", "remove the quantifier"),
+            LintKind::DuplicateBranch => ("duplicate-branch", "remove the branch"),
+            LintKind::RedundantSetItem => ("redundant-set-item", "remove the item"),
+            LintKind::AmbiguousHyphen => ("ambiguous-hyphen", "escape it as `\\-`"),
+            LintKind::UselessGroup => ("useless-group", "remove the `(?:` and `)`"),
+        };
+        Diagnostic::warning(code, &self.message, self.span).suggest(suggestion)
+    }
+}
+
+impl ComplexityWarning {
+    /// Describes the warning as a [`Diagnostic`].
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let code = match self.kind {
+            ComplexityKind::NestedQuantifier => "nested-quantifier",
+            ComplexityKind::OverlappingAlternation => "overlapping-alternation",
+        };
+        Diagnostic::warning(code, &self.message, self.span)
+    }
+}
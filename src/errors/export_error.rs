@@ -0,0 +1,8 @@
+use alloc::string::String;
+
+/// Errors that can occur when translating a pattern into another regex dialect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportError {
+    /// The pattern uses a construct the target dialect can't express.
+    Unsupported(String),
+}
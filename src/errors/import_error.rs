@@ -0,0 +1,10 @@
+use alloc::string::String;
+
+/// Errors that can occur when reading a pattern written in another regex dialect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// The pattern isn't valid in its own dialect.
+    InvalidPattern(String),
+    /// The pattern uses a construct this crate can't express.
+    Unsupported(String),
+}
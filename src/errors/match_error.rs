@@ -0,0 +1,6 @@
+/// Errors that can stop a search before it finds out whether the text matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchError {
+    /// The search ran past its deadline.
+    Timeout,
+}
@@ -3,3 +3,18 @@ pub use compile_error::*;
 
 mod parse_error;
 pub use parse_error::*;
+
+mod export_error;
+pub use export_error::*;
+
+mod import_error;
+pub use import_error::*;
+
+mod decode_error;
+pub use decode_error::*;
+
+mod match_error;
+pub use match_error::*;
+
+mod diagnostic;
+pub use diagnostic::*;
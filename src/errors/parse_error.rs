@@ -5,4 +5,8 @@ pub enum ParseError {
     NoDelimiter,
     /// An invalid flag character was encountered.
     InvalidFlags(char),
+    /// The input isn't a substitution command: it doesn't start with `s` and a delimiter.
+    NotSubstitution,
+    /// A line-range address is malformed, e.g. `5,` or a number that's too large.
+    InvalidRange,
 }
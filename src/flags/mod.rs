@@ -1,8 +1,14 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::errors::ParseError;
+
 /// Configuration flags that modify the behavior of the regular expression engine.
-#[derive(Default, Clone, Copy, Debug)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Flags {
     /// Controls case sensitivity.
-    /// - `None`: Smartcase (case-insensitive if pattern is all lowercase, sensitive otherwise).
+    /// - `None`: Smartcase (case-insensitive if the characters the pattern matches are
+    ///   all lowercase, sensitive otherwise).
     /// - `Some(true)`: Case-insensitive (`i` flag).
     /// - `Some(false)`: Case-sensitive (`c` flag).
     pub ignore_case: Option<bool>,
@@ -10,6 +16,9 @@ pub struct Flags {
     pub multiline: bool,
     /// If true, `.` matches newlines (`s` flag).
     pub dotall: bool,
+    /// What `.` matches apart from line endings, which [`dotall`](Flags::dotall)
+    /// decides. Has no letter.
+    pub dot: DotMode,
     /// If true, whitespace and comments in the pattern are ignored (`x` flag).
     pub verbose: bool,
     /// If true, enables Unicode support for character classes (`u` flag).
@@ -17,4 +26,255 @@ pub struct Flags {
     /// If true, indicates that the regex should match all occurrences (`g` flag).
     /// Note: This flag is often handled by the caller (e.g., `find_all` vs `find`), but is preserved here for parsing.
     pub global: bool,
+    /// If true, matches must start where the search starts: at the start of the text,
+    /// or for successive matches, where the previous one ended (`A` flag).
+    pub anchored: bool,
+    /// If true, quantifiers are lazy by default and a trailing `?` makes them greedy
+    /// (`U` flag).
+    pub ungreedy: bool,
+    /// If true, `\r\n` and a lone `\r` also end lines: `^` and `$` in multiline mode
+    /// match at them, but never between `\r` and `\n`, and `.` doesn't match `\r`
+    /// (`R` flag).
+    pub crlf: bool,
+    /// If true, replacing leaves the text unchanged and only counts the replacements
+    /// that would be made (`n` flag). See [`Regex::substitute`](crate::Regex::substitute).
+    pub dry_run: bool,
+    /// If true, each replacement should be confirmed first (`C` flag, Vim's `c`).
+    /// Note: Like `g`, this is left to the caller, e.g. by replacing with
+    /// [`Regex::replace_all_with_decision`](crate::Regex::replace_all_with_decision).
+    pub confirm: bool,
+    /// Which match replacing starts from, counting from 1, as in `s/a/b/2`: only that
+    /// match, or with `g` that match and every one after it. `None` starts from the
+    /// first.
+    pub occurrence: Option<usize>,
+}
+
+impl Flags {
+    /// Creates flags with every option off and smartcase on, the same as
+    /// [`Flags::default`], for chaining the setters below.
+    ///
+    /// ```
+    /// use monster_regex::Flags;
+    ///
+    /// let flags = Flags::new().ignore_case(true).multiline(true);
+    /// assert_eq!(flags.to_string(), "im");
+    /// ```
+    pub const fn new() -> Self {
+        Flags {
+            ignore_case: None,
+            multiline: false,
+            dotall: false,
+            dot: DotMode::Char,
+            verbose: false,
+            unicode: false,
+            global: false,
+            anchored: false,
+            ungreedy: false,
+            crlf: false,
+            dry_run: false,
+            confirm: false,
+            occurrence: None,
+        }
+    }
+
+    /// Makes matching case-insensitive (`true`) or case-sensitive (`false`),
+    /// overriding smartcase.
+    pub const fn ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = Some(ignore_case);
+        self
+    }
+
+    /// Infers case sensitivity from the pattern, which is the default.
+    pub const fn smartcase(mut self) -> Self {
+        self.ignore_case = None;
+        self
+    }
+
+    /// Sets the `m` flag.
+    pub const fn multiline(mut self, multiline: bool) -> Self {
+        self.multiline = multiline;
+        self
+    }
+
+    /// Sets the `s` flag.
+    pub const fn dotall(mut self, dotall: bool) -> Self {
+        self.dotall = dotall;
+        self
+    }
+
+    /// Selects what `.` matches.
+    ///
+    /// ```
+    /// use monster_regex::{DotMode, Flags, Regex};
+    ///
+    /// let re = Regex::new("^.$", Flags::new().dot(DotMode::Grapheme)).unwrap();
+    /// assert!(re.is_match("e\u{301}"));
+    /// ```
+    pub const fn dot(mut self, dot: DotMode) -> Self {
+        self.dot = dot;
+        self
+    }
+
+    /// Sets the `x` flag.
+    pub const fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Sets the `u` flag.
+    pub const fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    /// Sets the `g` flag.
+    pub const fn global(mut self, global: bool) -> Self {
+        self.global = global;
+        self
+    }
+
+    /// Sets the `A` flag.
+    pub const fn anchored(mut self, anchored: bool) -> Self {
+        self.anchored = anchored;
+        self
+    }
+
+    /// Sets the `U` flag.
+    pub const fn ungreedy(mut self, ungreedy: bool) -> Self {
+        self.ungreedy = ungreedy;
+        self
+    }
+
+    /// Sets the `R` flag.
+    pub const fn crlf(mut self, crlf: bool) -> Self {
+        self.crlf = crlf;
+        self
+    }
+
+    /// Sets the `n` flag.
+    pub const fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets the `C` flag.
+    pub const fn confirm(mut self, confirm: bool) -> Self {
+        self.confirm = confirm;
+        self
+    }
+
+    /// Selects the match replacing starts from, counting from 1. `0` selects none,
+    /// so replacing starts from the first match.
+    pub const fn occurrence(mut self, occurrence: usize) -> Self {
+        self.occurrence = if occurrence == 0 {
+            None
+        } else {
+            Some(occurrence)
+        };
+        self
+    }
+
+    // Turns on the flag a letter stands for. Returns false if it isn't a flag.
+    pub(crate) fn set_letter(&mut self, letter: char) -> bool {
+        match letter {
+            'i' => self.ignore_case = Some(true),
+            'c' => self.ignore_case = Some(false),
+            'm' => self.multiline = true,
+            's' => self.dotall = true,
+            'x' => self.verbose = true,
+            'u' => self.unicode = true,
+            'g' => self.global = true,
+            'A' => self.anchored = true,
+            'U' => self.ungreedy = true,
+            'R' => self.crlf = true,
+            'n' => self.dry_run = true,
+            'C' => self.confirm = true,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// What `.` matches, set with [`Flags::dot`].
+///
+/// Unless [`dotall`](Flags::dotall) is on, `.` never matches `\n`, nor `\r` with the
+/// `R` flag, in any mode.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DotMode {
+    /// Any single character.
+    #[default]
+    Char,
+    /// Any single character, but not `\r` either unless `dotall` is on, without the
+    /// `R` flag's other effects on `^` and `$`.
+    NotCr,
+    /// Any single ASCII character.
+    Ascii,
+    /// A whole extended grapheme cluster, like a letter followed by combining
+    /// accents or an emoji sequence, as [Unicode segments text][uax29] into
+    /// user-perceived characters. `\r\n` is one cluster, a line ending.
+    ///
+    /// [uax29]: https://www.unicode.org/reports/tr29/
+    Grapheme,
+}
+
+/// Parses flag letters as written after a rift pattern, e.g. `"gim"`. Later case
+/// letters override earlier ones. A number among the letters selects the
+/// [`occurrence`](Flags::occurrence), as in `"g2"`.
+impl FromStr for Flags {
+    type Err = ParseError;
+
+    fn from_str(letters: &str) -> Result<Self, Self::Err> {
+        let mut flags = Flags::new();
+        let mut digits: Option<(usize, usize)> = None;
+        for (i, letter) in letters.char_indices() {
+            if letter.is_ascii_digit() {
+                // The number has to be written in one piece
+                match &mut digits {
+                    None => digits = Some((i, i + 1)),
+                    Some((_, end)) if *end == i => *end = i + 1,
+                    Some(_) => return Err(ParseError::InvalidFlags(letter)),
+                }
+            } else if !flags.set_letter(letter) {
+                return Err(ParseError::InvalidFlags(letter));
+            }
+        }
+        if let Some((start, end)) = digits {
+            let number = &letters[start..end];
+            let occurrence = number.parse().ok().filter(|&n| n > 0);
+            let first_digit = number.chars().next().unwrap_or('0');
+            flags.occurrence = Some(occurrence.ok_or(ParseError::InvalidFlags(first_digit))?);
+        }
+        Ok(flags)
+    }
+}
+
+/// Writes the flag letters that are on, in the order `i`/`c`, `m`, `s`, `x`, `u`,
+/// `g`, `A`, `U`, `R`, `n`, `C`, followed by the occurrence. Parsing the result gives the same flags back,
+/// except for the [`dot`](Flags::dot) mode, which has no letter.
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let letters = [
+            (self.ignore_case == Some(true), 'i'),
+            (self.ignore_case == Some(false), 'c'),
+            (self.multiline, 'm'),
+            (self.dotall, 's'),
+            (self.verbose, 'x'),
+            (self.unicode, 'u'),
+            (self.global, 'g'),
+            (self.anchored, 'A'),
+            (self.ungreedy, 'U'),
+            (self.crlf, 'R'),
+            (self.dry_run, 'n'),
+            (self.confirm, 'C'),
+        ];
+        letters
+            .into_iter()
+            .filter(|&(on, _)| on)
+            .try_for_each(|(_, letter)| write!(f, "{}", letter))?;
+        // `0` can't be parsed back, and starts from the first match like `None`
+        match self.occurrence {
+            Some(occurrence) if occurrence > 0 => write!(f, "{}", occurrence),
+            _ => Ok(()),
+        }
+    }
 }
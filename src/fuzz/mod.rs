@@ -0,0 +1,259 @@
+//! Helpers for fuzzing the parser and engine, built with the `fuzzing` feature.
+//!
+//! A fuzz target feeds its input to [`check_round_trip`] or [`check_consistency`],
+//! which panic when the crate disagrees with itself. [`shrink`] and
+//! [`shrink_pattern`] then cut a failing pattern down to a small one that still
+//! fails. The AST doesn't implement `arbitrary::Arbitrary`, since the crate has no
+//! dependencies, so targets work on pattern strings.
+//!
+//! ```
+//! use monster_regex::Flags;
+//! use monster_regex::fuzz::{check_consistency, check_round_trip};
+//!
+//! let pattern = "(fuzz|er)+ input";
+//! check_round_trip(pattern);
+//! check_consistency(pattern, Flags::default(), "some text");
+//! ```
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::flags::Flags;
+use crate::parser::{AstNode, CharClass, Parser, to_pattern};
+use crate::regex::Regex;
+
+/// Checks that printing a pattern's AST and parsing the result gives the same AST.
+///
+/// Patterns that don't parse are ignored.
+///
+/// # Panics
+///
+/// Panics if the printed pattern doesn't parse, or parses to a different AST.
+pub fn check_round_trip(pattern: &str) {
+    let Ok(ast) = Parser::new(pattern, Flags::default()).parse() else {
+        return;
+    };
+    let printed = to_pattern(&ast);
+    match Parser::new(&printed, Flags::default()).parse() {
+        Ok(reparsed) => assert_eq!(
+            reparsed, ast,
+            "'{}' printed as '{}' parses differently",
+            pattern, printed
+        ),
+        Err(err) => panic!(
+            "'{}' printed as '{}', which doesn't parse: {:?}",
+            pattern, printed, err
+        ),
+    }
+}
+
+/// Checks that the ways of searching the text with a pattern agree.
+///
+/// The first match is found with the full engine, with the plain backtracking
+/// matcher that skips every prefilter, and with a regex loaded back from
+/// [`Regex::to_bytes`], and all three must agree with each other and with
+/// [`Regex::is_match`]. Patterns that don't compile are ignored.
+///
+/// # Panics
+///
+/// Panics if any two searches disagree.
+pub fn check_consistency(pattern: &str, flags: Flags, text: &str) {
+    let Ok(regex) = Regex::new(pattern, flags) else {
+        return;
+    };
+    let found = regex.find(text);
+    let context = || format!("'{}' with flags '{}' on {:?}", pattern, flags, text);
+    assert_eq!(
+        regex.find_traced(text, |_| {}),
+        found,
+        "backtracking alone finds a different match for {}",
+        context()
+    );
+    assert_eq!(
+        regex.is_match(text),
+        found.is_some(),
+        "is_match disagrees with find for {}",
+        context()
+    );
+    let loaded = Regex::from_bytes(&regex.to_bytes()).expect("saved regexes load");
+    assert_eq!(
+        loaded.find(text),
+        found,
+        "a regex loaded from bytes finds a different match for {}",
+        context()
+    );
+}
+
+/// Cuts an AST down while `fails` keeps returning true for it, returning the
+/// smallest AST found.
+///
+/// Each step tries removing a node, replacing a node with one of its parts (a
+/// quantifier with what it repeats, a group with its contents, an alternation with
+/// one branch) and shortening literal strings and sets, keeping the first change
+/// that still fails. It stops when no change does.
+pub fn shrink(mut nodes: Vec<AstNode>, mut fails: impl FnMut(&[AstNode]) -> bool) -> Vec<AstNode> {
+    while let Some(smaller) = smaller_lists(&nodes)
+        .into_iter()
+        .find(|candidate| fails(candidate))
+    {
+        nodes = smaller;
+    }
+    nodes
+}
+
+/// Cuts a pattern down while `fails` keeps returning true for it, like [`shrink`].
+///
+/// Patterns that don't parse are returned as they are.
+pub fn shrink_pattern(pattern: &str, flags: Flags, mut fails: impl FnMut(&str) -> bool) -> String {
+    match Parser::new(pattern, flags).parse() {
+        Ok(nodes) => to_pattern(&shrink(nodes, |nodes| fails(&to_pattern(nodes)))),
+        Err(_) => pattern.into(),
+    }
+}
+
+// Every list one step smaller than `nodes`
+fn smaller_lists(nodes: &[AstNode]) -> Vec<Vec<AstNode>> {
+    let mut lists = vec![];
+    for i in 0..nodes.len() {
+        let mut removed = nodes.to_vec();
+        removed.remove(i);
+        lists.push(removed);
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        for replacement in smaller_nodes(node) {
+            let mut replaced = nodes[..i].to_vec();
+            replaced.extend(replacement);
+            replaced.extend_from_slice(&nodes[i + 1..]);
+            lists.push(replaced);
+        }
+    }
+    lists
+}
+
+// Everything one step smaller that a node could be replaced with
+fn smaller_nodes(node: &AstNode) -> Vec<Vec<AstNode>> {
+    // Shrinks the node inside a quantifier, keeping the quantifier
+    let inner = |node: &AstNode, wrap: &dyn Fn(Box<AstNode>) -> AstNode| {
+        let mut smaller = vec![vec![node.clone()]];
+        smaller.extend(
+            smaller_nodes(node)
+                .into_iter()
+                .filter_map(|mut nodes| (nodes.len() == 1).then(|| nodes.remove(0)))
+                .map(|node| vec![wrap(Box::new(node))]),
+        );
+        smaller
+    };
+
+    match node {
+        AstNode::ZeroOrMore { node, greedy } => inner(node, &|node| AstNode::ZeroOrMore {
+            node,
+            greedy: *greedy,
+        }),
+        AstNode::OneOrMore { node, greedy } => inner(node, &|node| AstNode::OneOrMore {
+            node,
+            greedy: *greedy,
+        }),
+        AstNode::Optional { node, greedy } => inner(node, &|node| AstNode::Optional {
+            node,
+            greedy: *greedy,
+        }),
+        AstNode::Exact { node, count } => inner(node, &|node| AstNode::Exact {
+            node,
+            count: *count,
+        }),
+        AstNode::Range {
+            node,
+            min,
+            max,
+            greedy,
+        } => inner(node, &|node| AstNode::Range {
+            node,
+            min: *min,
+            max: *max,
+            greedy: *greedy,
+        }),
+        AstNode::Group {
+            nodes,
+            name,
+            capture,
+            index,
+        } => {
+            let mut smaller = vec![nodes.clone()];
+            smaller.extend(smaller_lists(nodes).into_iter().map(|nodes| {
+                vec![AstNode::Group {
+                    nodes,
+                    name: name.clone(),
+                    capture: *capture,
+                    index: *index,
+                }]
+            }));
+            smaller
+        }
+        AstNode::LookAhead { nodes, positive } => {
+            let mut smaller = vec![nodes.clone()];
+            smaller.extend(smaller_lists(nodes).into_iter().map(|nodes| {
+                vec![AstNode::LookAhead {
+                    nodes,
+                    positive: *positive,
+                }]
+            }));
+            smaller
+        }
+        AstNode::LookBehind { nodes, positive } => {
+            let mut smaller = vec![nodes.clone()];
+            smaller.extend(smaller_lists(nodes).into_iter().map(|nodes| {
+                vec![AstNode::LookBehind {
+                    nodes,
+                    positive: *positive,
+                }]
+            }));
+            smaller
+        }
+        AstNode::Alternation(branches) => {
+            let mut smaller: Vec<_> = branches.clone();
+            if branches.len() > 2 {
+                for i in 0..branches.len() {
+                    let mut fewer = branches.clone();
+                    fewer.remove(i);
+                    smaller.push(vec![AstNode::Alternation(fewer)]);
+                }
+            }
+            for (i, branch) in branches.iter().enumerate() {
+                for shrunk in smaller_lists(branch) {
+                    let mut changed = branches.clone();
+                    changed[i] = shrunk;
+                    smaller.push(vec![AstNode::Alternation(changed)]);
+                }
+            }
+            smaller
+        }
+        AstNode::LiteralString(text) => {
+            let mut chars = text.chars();
+            chars.next();
+            let without_first = chars.as_str();
+            let mut chars = text.chars();
+            chars.next_back();
+            let without_last = chars.as_str();
+            [without_first, without_last]
+                .into_iter()
+                .filter(|shorter| !shorter.is_empty())
+                .map(|shorter| vec![AstNode::LiteralString(shorter.into())])
+                .collect()
+        }
+        AstNode::CharClass(CharClass::Set { chars, negated }) if chars.len() > 1 => (0..chars
+            .len())
+            .map(|i| {
+                let mut fewer = chars.clone();
+                fewer.remove(i);
+                vec![AstNode::CharClass(CharClass::Set {
+                    chars: fewer,
+                    negated: *negated,
+                })]
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
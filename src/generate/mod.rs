@@ -0,0 +1,240 @@
+use crate::engine::class_matches;
+use crate::flags::Flags;
+use crate::parser::{AstNode, CharClass, Parser};
+use crate::regex::Regex;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The default number of extra iterations an unbounded quantifier may add beyond its minimum.
+pub const DEFAULT_MAX_REPEAT: usize = 8;
+
+/// How many candidates [`Generator::generate`] builds before giving up.
+const ATTEMPTS: usize = 100;
+
+// Characters drawn from for classes that aren't a custom set: printable ASCII plus
+// whitespace and a few non-ASCII letters, symbols and an emoji
+const SAMPLE: &str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~\t\n\r\u{B}\u{C}\u{A0}éßÀΩλЯ中😀";
+
+/// Produces random strings that a regex matches, for fuzzing code downstream of it or
+/// building test fixtures.
+///
+/// Strings are built by walking the pattern, picking a random branch at each
+/// alternation, a random count for each quantifier and a random character for each
+/// class. Every candidate is then checked with the regex, so lookarounds, anchors and
+/// word boundaries are honoured by rejecting candidates that break them.
+///
+/// The same seed and pattern always produce the same sequence of strings.
+///
+/// ```
+/// use monster_regex::{Flags, Generator, Regex};
+///
+/// let re = Regex::new(r"^[a-f]{2}-\d+(?:x|yz)?$", Flags::default()).unwrap();
+/// let mut generator = Generator::new(&re, 7);
+/// for _ in 0..20 {
+///     let text = generator.generate().unwrap();
+///     assert!(re.is_match(&text));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Generator<'r> {
+    regex: &'r Regex,
+    ast: Vec<AstNode>,
+    rng: SplitMix64,
+    max_repeat: usize,
+}
+
+impl<'r> Generator<'r> {
+    /// Creates a generator for `regex`, seeded with `seed`.
+    pub fn new(regex: &'r Regex, seed: u64) -> Self {
+        // Compiling already parsed this pattern, so parsing it again succeeds
+        let ast = Parser::new(regex.pattern(), *regex.flags())
+            .parse()
+            .expect("a compiled pattern parses");
+        Generator {
+            regex,
+            ast,
+            rng: SplitMix64(seed),
+            max_repeat: DEFAULT_MAX_REPEAT,
+        }
+    }
+
+    /// Sets how many iterations beyond its minimum a quantifier may add, which
+    /// bounds `*`, `+` and `{n,}`. Bounded quantifiers never exceed their maximum.
+    /// Defaults to [`DEFAULT_MAX_REPEAT`].
+    pub fn max_repeat(mut self, max_repeat: usize) -> Self {
+        self.max_repeat = max_repeat;
+        self
+    }
+
+    /// Returns a random string the regex matches.
+    ///
+    /// Returns `None` if no candidate matched after many tries, which happens when
+    /// the pattern can't match anything (e.g. `a(?<=b)`) or its assertions rarely
+    /// hold for random text.
+    pub fn generate(&mut self) -> Option<String> {
+        let flags = *self.regex.flags();
+        let ast = core::mem::take(&mut self.ast);
+        let found = (0..ATTEMPTS).find_map(|_| {
+            let mut state = State {
+                out: String::new(),
+                groups: vec![],
+            };
+            self.sequence(&ast, &flags, &mut state)?;
+            self.regex.is_match(&state.out).then_some(state.out)
+        });
+        self.ast = ast;
+        found
+    }
+
+    fn sequence(&mut self, nodes: &[AstNode], flags: &Flags, state: &mut State) -> Option<()> {
+        nodes
+            .iter()
+            .try_for_each(|node| self.node(node, flags, state))
+    }
+
+    fn node(&mut self, node: &AstNode, flags: &Flags, state: &mut State) -> Option<()> {
+        match node {
+            AstNode::Literal(c) => state.out.push(*c),
+            AstNode::LiteralString(s) => state.out.push_str(s),
+            AstNode::CharClass(class) => {
+                let c = self.pick_char(class, flags)?;
+                state.out.push(c);
+            }
+            // A single character is a cluster of its own
+            AstNode::Grapheme => {
+                let c = self.pick_char(&CharClass::Dot, flags)?;
+                state.out.push(c);
+            }
+            AstNode::ZeroOrMore { node, .. } => self.repeat(node, 0, None, flags, state)?,
+            AstNode::OneOrMore { node, .. } => self.repeat(node, 1, None, flags, state)?,
+            AstNode::Optional { node, .. } => self.repeat(node, 0, Some(1), flags, state)?,
+            AstNode::Exact { node, count } => {
+                self.repeat(node, *count, Some(*count), flags, state)?
+            }
+            AstNode::Range { node, min, max, .. } => self.repeat(node, *min, *max, flags, state)?,
+            AstNode::Group { nodes, index, .. } => {
+                let start = state.out.len();
+                self.sequence(nodes, flags, state)?;
+                if let Some(index) = *index {
+                    if state.groups.len() <= index {
+                        state.groups.resize(index + 1, None);
+                    }
+                    state.groups[index] = Some(state.out[start..].to_string());
+                }
+            }
+            AstNode::Alternation(alts) => {
+                let alt = &alts[self.rng.below(alts.len())];
+                self.sequence(alt, flags, state)?;
+            }
+            AstNode::Backref(index) => {
+                if let Some(Some(text)) = state.groups.get(*index) {
+                    let text = text.clone();
+                    state.out.push_str(&text);
+                }
+            }
+            // Writing out what a lookaround looks for often satisfies it, as in
+            // `(?<=\d)x` or `foo(?>=bar)\w+`; the other half of the time it's left to
+            // the text around it
+            AstNode::LookAhead {
+                nodes,
+                positive: true,
+            }
+            | AstNode::LookBehind {
+                nodes,
+                positive: true,
+            } => {
+                if self.rng.below(2) == 0 {
+                    self.sequence(nodes, flags, state)?;
+                }
+            }
+            // Zero-width; candidates that break them are rejected afterwards
+            AstNode::StartAnchor
+            | AstNode::EndAnchor
+            | AstNode::StartText
+            | AstNode::EndText
+            | AstNode::Line(..)
+            | AstNode::Column(..)
+            | AstNode::Cursor
+            | AstNode::Visual
+            | AstNode::Mark(..)
+            | AstNode::WordBoundary
+            | AstNode::StartWord
+            | AstNode::EndWord
+            | AstNode::SetMatchStart
+            | AstNode::SetMatchEnd
+            | AstNode::LookAhead { .. }
+            | AstNode::LookBehind { .. } => {}
+        }
+        Some(())
+    }
+
+    fn repeat(
+        &mut self,
+        node: &AstNode,
+        min: usize,
+        max: Option<usize>,
+        flags: &Flags,
+        state: &mut State,
+    ) -> Option<()> {
+        let max = max
+            .unwrap_or(usize::MAX)
+            .min(min.saturating_add(self.max_repeat));
+        let count = min + self.rng.below((max - min).saturating_add(1));
+        (0..count).try_for_each(|_| self.node(node, flags, state))
+    }
+
+    fn pick_char(&mut self, class: &CharClass, flags: &Flags) -> Option<char> {
+        if let CharClass::Set {
+            chars,
+            negated: false,
+        } = class
+        {
+            // Pick within a listed range, so sets of characters outside the sample work
+            let range = chars.get(self.rng.below(chars.len().max(1)))?;
+            let (start, end) = (u32::from(range.start), u32::from(range.end));
+            for _ in 0..ATTEMPTS {
+                let code = start + self.rng.below((end - start) as usize + 1) as u32;
+                if let Some(c) = char::from_u32(code) {
+                    return Some(c);
+                }
+            }
+            return Some(range.start);
+        }
+        let candidates: Vec<char> = SAMPLE
+            .chars()
+            .filter(|&c| class_matches(class, c, flags))
+            .collect();
+        candidates
+            .get(self.rng.below(candidates.len().max(1)))
+            .copied()
+    }
+}
+
+// The text built so far, and what each capture group matched in it
+struct State {
+    out: String,
+    groups: Vec<Option<String>>,
+}
+
+/// A small, fast pseudo-random generator. Not suitable for cryptography.
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // A number in `0..n`, or 0 if `n` is 0
+    fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        (self.next() % n as u64) as usize
+    }
+}
@@ -0,0 +1,208 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, BufRead};
+
+use crate::captures::Match;
+use crate::regex::Regex;
+
+/// Which lines [`grep`] and [`grep_reader`] report besides the selected ones.
+///
+/// ```
+/// use monster_regex::GrepOptions;
+///
+/// let options = GrepOptions::new().invert(true).context(2);
+/// assert_eq!((options.before_context, options.after_context), (2, 2));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GrepOptions {
+    /// If true, lines without a match are selected instead of lines with one.
+    pub invert: bool,
+    /// How many lines before each selected line to report as context.
+    pub before_context: usize,
+    /// How many lines after each selected line to report as context.
+    pub after_context: usize,
+}
+
+impl GrepOptions {
+    /// Creates options selecting matching lines, without context.
+    pub const fn new() -> Self {
+        GrepOptions {
+            invert: false,
+            before_context: 0,
+            after_context: 0,
+        }
+    }
+
+    /// Selects lines without a match instead.
+    pub const fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Sets how many lines before each selected line to report.
+    pub const fn before_context(mut self, lines: usize) -> Self {
+        self.before_context = lines;
+        self
+    }
+
+    /// Sets how many lines after each selected line to report.
+    pub const fn after_context(mut self, lines: usize) -> Self {
+        self.after_context = lines;
+        self
+    }
+
+    /// Sets how many lines on both sides of each selected line to report.
+    pub const fn context(self, lines: usize) -> Self {
+        self.before_context(lines).after_context(lines)
+    }
+}
+
+/// A line reported by [`grep`] or [`grep_reader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepLine<L> {
+    /// The line's number, counting from 1.
+    pub line_number: usize,
+    /// The line, without its `\n` or `\r\n`.
+    pub line: L,
+    /// The matches in the line, with offsets into `line`.
+    pub matches: Vec<Match>,
+    /// Whether the line is only reported as context around a selected line.
+    pub context: bool,
+}
+
+/// Searches each line of the text, reporting the lines with a match.
+///
+/// Each line is searched on its own with its line ending removed, so `^` and `$`
+/// match at the start and end of every line whatever the regex's flags, and no
+/// match spans lines. Lines are reported in order, each at most once, with context
+/// lines around the selected ones as `options` asks.
+///
+/// ```
+/// use monster_regex::{Flags, GrepOptions, Regex, grep};
+///
+/// let re = Regex::new(r"^\d+$", Flags::default()).unwrap();
+/// let text = "one\n22\r\nthree\n4x\n";
+/// let lines: Vec<_> = grep(&re, text, GrepOptions::new())
+///     .map(|line| (line.line_number, line.line))
+///     .collect();
+/// assert_eq!(lines, [(2, "22")]);
+///
+/// let with_context: Vec<_> = grep(&re, text, GrepOptions::new().after_context(1))
+///     .map(|line| (line.line, line.context))
+///     .collect();
+/// assert_eq!(with_context, [("22", false), ("three", true)]);
+/// ```
+pub fn grep<'a>(
+    regex: &'a Regex,
+    text: &'a str,
+    options: GrepOptions,
+) -> Grep<'a, core::str::Lines<'a>, &'a str> {
+    Grep::new(regex, text.lines(), options)
+}
+
+/// Searches each line read from `reader` like [`grep`], reading one line at a time.
+///
+/// # Errors
+///
+/// The iterator yields an error for a line that can't be read or isn't valid UTF-8.
+///
+/// ```
+/// use monster_regex::{Flags, GrepOptions, Regex, grep_reader};
+///
+/// let re = Regex::new("b", Flags::default()).unwrap();
+/// let reader = std::io::Cursor::new("ab\ncd\nbb\n");
+/// let lines: Vec<_> = grep_reader(&re, reader, GrepOptions::new().invert(true))
+///     .map(|line| line.unwrap().line)
+///     .collect();
+/// assert_eq!(lines, ["cd"]);
+/// ```
+#[cfg(feature = "std")]
+pub fn grep_reader<R: BufRead>(
+    regex: &Regex,
+    reader: R,
+    options: GrepOptions,
+) -> Grep<'_, io::Lines<R>, String> {
+    Grep::new(regex, reader.lines(), options)
+}
+
+/// An iterator over the lines [`grep`] or [`grep_reader`] report.
+pub struct Grep<'r, I, L> {
+    regex: &'r Regex,
+    lines: I,
+    options: GrepOptions,
+    line_number: usize,
+    // Unselected lines kept in case a later line needs them as context
+    before: VecDeque<GrepLine<L>>,
+    // Lines ready to be reported
+    ready: VecDeque<GrepLine<L>>,
+    after_remaining: usize,
+}
+
+impl<'r, I, L: AsRef<str>> Grep<'r, I, L> {
+    fn new(regex: &'r Regex, lines: I, options: GrepOptions) -> Self {
+        Grep {
+            regex,
+            lines,
+            options,
+            line_number: 0,
+            before: VecDeque::new(),
+            ready: VecDeque::new(),
+            after_remaining: 0,
+        }
+    }
+
+    // Searches the next line and queues whatever it makes reportable
+    fn push(&mut self, line: L) {
+        self.line_number += 1;
+        let matches: Vec<_> = self.regex.find_all(line.as_ref()).collect();
+        let selected = matches.is_empty() == self.options.invert;
+        let line = GrepLine {
+            line_number: self.line_number,
+            line,
+            matches,
+            context: !selected,
+        };
+
+        if selected {
+            self.ready.extend(self.before.drain(..));
+            self.ready.push_back(line);
+            self.after_remaining = self.options.after_context;
+        } else if self.after_remaining > 0 {
+            self.after_remaining -= 1;
+            self.ready.push_back(line);
+        } else if self.options.before_context > 0 {
+            if self.before.len() == self.options.before_context {
+                self.before.pop_front();
+            }
+            self.before.push_back(line);
+        }
+    }
+}
+
+impl<'r, 'a> Iterator for Grep<'r, core::str::Lines<'a>, &'a str> {
+    type Item = GrepLine<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.ready.is_empty() {
+            let line = self.lines.next()?;
+            self.push(line);
+        }
+        self.ready.pop_front()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'r, R: BufRead> Iterator for Grep<'r, io::Lines<R>, String> {
+    type Item = io::Result<GrepLine<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.ready.is_empty() {
+            match self.lines.next()? {
+                Ok(line) => self.push(line),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.ready.pop_front().map(Ok)
+    }
+}
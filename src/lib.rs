@@ -1,17 +1,60 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub mod captures;
+pub mod compiler;
+pub mod document;
 pub mod engine;
 pub mod errors;
 pub mod flags;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzz;
+pub mod generate;
+pub mod grep;
 pub mod parser;
 pub mod parsing;
 pub mod regex;
 
 pub use captures::{Captures, Match};
-pub use errors::{CompileError, ParseError};
-pub use flags::Flags;
-pub use parser::{AstNode, CharClass, CharRange, Parser};
-pub use parsing::parse_rift_format;
-pub use regex::Regex;
+pub use compiler::{
+    BackrefPolicy, ComplexityKind, ComplexityWarning, LintKind, LintWarning, PatternInfo,
+    check_complexity, diagnose, lint,
+};
+pub use document::{Document, DocumentMatch, DocumentMatches, Position};
+pub use engine::{EngineKind, Marks, MatchStats, MatchingStrings, Trace, TraceEvent};
+pub use errors::{
+    CompileError, DecodeError, Diagnostic, ExportError, ImportError, MatchError, ParseError,
+    Severity,
+};
+pub use flags::{DotMode, Flags};
+pub use generate::Generator;
+#[cfg(feature = "std")]
+pub use grep::grep_reader;
+pub use grep::{Grep, GrepLine, GrepOptions, grep};
+/// Derives a `parse` constructor for a struct from a pattern with named groups.
+#[cfg(feature = "derive")]
+pub use monster_regex_derive::FromRegex;
+pub use parser::{
+    AstNode, CharClass, CharRange, Highlight, HighlightKind, Parser, Span, SpanMap, Visitor,
+    explain, fold, from_pcre, highlight, highlight_rift, map, map_node, to_dot, to_pattern,
+    to_pcre, to_rust_regex, walk, walk_node,
+};
+pub use parsing::{Address, Line, Range, parse_range, parse_rift_format, parse_substitution};
+#[cfg(feature = "async")]
+pub use regex::{DEFAULT_STEP_BUDGET, FindFuture};
+pub use regex::{
+    Decision, Edit, EmptyMatchPolicy, Finder, Lexer, MatchStyle, OverlapPolicy, Regex,
+    RegexBuilder, RegexSet, Rewriter, Segment, Token, Tokens,
+};
+#[cfg(feature = "std")]
+pub use regex::{ReaderMatches, RiftStrExt};
+
+// Hash maps need std, so without it the engine keeps its state in ordered maps
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::collections::{BTreeMap as Map, BTreeSet as Set};
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap as Map, HashSet as Set};
 
 #[cfg(test)]
 #[path = "tests/mod.rs"]
@@ -0,0 +1,315 @@
+// Collating elements and equivalence classes of POSIX bracket expressions, for
+// `[[.ch.]]` and `[[=e=]]`. Without a locale's collation order, a character is
+// equivalent to the others that are the same base letter with accents or other
+// marks added, as their canonical decompositions in Unicode 14.0 show.
+
+// Characters by their POSIX names, for `[[.hyphen.]]` and the like
+const NAMES: &[(&str, char)] = &[
+    ("NUL", '\0'),
+    ("alert", '\x07'),
+    ("backspace", '\x08'),
+    ("tab", '\t'),
+    ("newline", '\n'),
+    ("vertical-tab", '\x0B'),
+    ("form-feed", '\x0C'),
+    ("carriage-return", '\r'),
+    ("space", ' '),
+    ("exclamation-mark", '!'),
+    ("quotation-mark", '"'),
+    ("number-sign", '#'),
+    ("dollar-sign", '$'),
+    ("percent-sign", '%'),
+    ("ampersand", '&'),
+    ("apostrophe", '\''),
+    ("left-parenthesis", '('),
+    ("right-parenthesis", ')'),
+    ("asterisk", '*'),
+    ("plus-sign", '+'),
+    ("comma", ','),
+    ("hyphen", '-'),
+    ("hyphen-minus", '-'),
+    ("period", '.'),
+    ("full-stop", '.'),
+    ("slash", '/'),
+    ("solidus", '/'),
+    ("zero", '0'),
+    ("one", '1'),
+    ("two", '2'),
+    ("three", '3'),
+    ("four", '4'),
+    ("five", '5'),
+    ("six", '6'),
+    ("seven", '7'),
+    ("eight", '8'),
+    ("nine", '9'),
+    ("colon", ':'),
+    ("semicolon", ';'),
+    ("less-than-sign", '<'),
+    ("equals-sign", '='),
+    ("greater-than-sign", '>'),
+    ("question-mark", '?'),
+    ("commercial-at", '@'),
+    ("left-square-bracket", '['),
+    ("backslash", '\\'),
+    ("reverse-solidus", '\\'),
+    ("right-square-bracket", ']'),
+    ("circumflex", '^'),
+    ("circumflex-accent", '^'),
+    ("underscore", '_'),
+    ("low-line", '_'),
+    ("grave-accent", '`'),
+    ("left-brace", '{'),
+    ("left-curly-bracket", '{'),
+    ("vertical-line", '|'),
+    ("right-brace", '}'),
+    ("right-curly-bracket", '}'),
+    ("tilde", '~'),
+    ("DEL", '\x7F'),
+];
+
+/// Returns the character a POSIX collating symbol names, like `-` for `hyphen`.
+pub(super) fn named(name: &str) -> Option<char> {
+    NAMES
+        .iter()
+        .find(|&&(known, _)| known == name)
+        .map(|&(_, c)| c)
+}
+
+/// Returns the characters in the equivalence class of `c`: its base letter and
+/// every character made of that letter and marks. A character with no such
+/// relatives is alone in its class.
+pub(super) fn equivalents(c: char) -> impl Iterator<Item = char> {
+    let family = FAMILIES
+        .iter()
+        .find(|&&(base, marked)| base == c || marked.contains(c));
+    let (base, marked) = family.copied().unwrap_or((c, ""));
+    core::iter::once(base).chain(marked.chars())
+}
+
+// Each base letter with the precomposed characters that decompose to it followed
+// by marks, generated from the Unicode 14.0 decomposition mappings
+#[rustfmt::skip]
+const FAMILIES: &[(char, &str)] = &[
+    ('A', "ÀÁÂÃÄÅĀĂĄǍǞǠǺȀȂȦḀẠẢẤẦẨẪẬẮẰẲẴẶÅ"),
+    ('B', "ḂḄḆ"),
+    ('C', "ÇĆĈĊČḈ"),
+    ('D', "ĎḊḌḎḐḒ"),
+    ('E', "ÈÉÊËĒĔĖĘĚȄȆȨḔḖḘḚḜẸẺẼẾỀỂỄỆ"),
+    ('F', "Ḟ"),
+    ('G', "ĜĞĠĢǦǴḠ"),
+    ('H', "ĤȞḢḤḦḨḪ"),
+    ('I', "ÌÍÎÏĨĪĬĮİǏȈȊḬḮỈỊ"),
+    ('J', "Ĵ"),
+    ('K', "ĶǨḰḲḴ"),
+    ('L', "ĹĻĽḶḸḺḼ"),
+    ('M', "ḾṀṂ"),
+    ('N', "ÑŃŅŇǸṄṆṈṊ"),
+    ('O', "ÒÓÔÕÖŌŎŐƠǑǪǬȌȎȪȬȮȰṌṎṐṒỌỎỐỒỔỖỘỚỜỞỠỢ"),
+    ('P', "ṔṖ"),
+    ('R', "ŔŖŘȐȒṘṚṜṞ"),
+    ('S', "ŚŜŞŠȘṠṢṤṦṨ"),
+    ('T', "ŢŤȚṪṬṮṰ"),
+    ('U', "ÙÚÛÜŨŪŬŮŰŲƯǓǕǗǙǛȔȖṲṴṶṸṺỤỦỨỪỬỮỰ"),
+    ('V', "ṼṾ"),
+    ('W', "ŴẀẂẄẆẈ"),
+    ('X', "ẊẌ"),
+    ('Y', "ÝŶŸȲẎỲỴỶỸ"),
+    ('Z', "ŹŻŽẐẒẔ"),
+    ('a', "àáâãäåāăąǎǟǡǻȁȃȧḁạảấầẩẫậắằẳẵặ"),
+    ('b', "ḃḅḇ"),
+    ('c', "çćĉċčḉ"),
+    ('d', "ďḋḍḏḑḓ"),
+    ('e', "èéêëēĕėęěȅȇȩḕḗḙḛḝẹẻẽếềểễệ"),
+    ('f', "ḟ"),
+    ('g', "ĝğġģǧǵḡ"),
+    ('h', "ĥȟḣḥḧḩḫẖ"),
+    ('i', "ìíîïĩīĭįǐȉȋḭḯỉị"),
+    ('j', "ĵǰ"),
+    ('k', "ķǩḱḳḵ"),
+    ('l', "ĺļľḷḹḻḽ"),
+    ('m', "ḿṁṃ"),
+    ('n', "ñńņňǹṅṇṉṋ"),
+    ('o', "òóôõöōŏőơǒǫǭȍȏȫȭȯȱṍṏṑṓọỏốồổỗộớờởỡợ"),
+    ('p', "ṕṗ"),
+    ('r', "ŕŗřȑȓṙṛṝṟ"),
+    ('s', "śŝşšșṡṣṥṧṩ"),
+    ('t', "ţťțṫṭṯṱẗ"),
+    ('u', "ùúûüũūŭůűųưǔǖǘǚǜȕȗṳṵṷṹṻụủứừửữự"),
+    ('v', "ṽṿ"),
+    ('w', "ŵẁẃẅẇẉẘ"),
+    ('x', "ẋẍ"),
+    ('y', "ýÿŷȳẏẙỳỵỷỹ"),
+    ('z', "źżžẑẓẕ"),
+    ('Æ', "ǢǼ"),
+    ('Ø', "Ǿ"),
+    ('æ', "ǣǽ"),
+    ('ø', "ǿ"),
+    ('ſ', "ẛ"),
+    ('Ʒ', "Ǯ"),
+    ('ʒ', "ǯ"),
+    ('Α', "ΆἈἉἊἋἌἍἎἏᾈᾉᾊᾋᾌᾍᾎᾏᾸᾹᾺΆᾼ"),
+    ('Ε', "ΈἘἙἚἛἜἝῈΈ"),
+    ('Η', "ΉἨἩἪἫἬἭἮἯᾘᾙᾚᾛᾜᾝᾞᾟῊΉῌ"),
+    ('Ι', "ΊΪἸἹἺἻἼἽἾἿῘῙῚΊ"),
+    ('Ο', "ΌὈὉὊὋὌὍῸΌ"),
+    ('Ρ', "Ῥ"),
+    ('Υ', "ΎΫὙὛὝὟῨῩῪΎ"),
+    ('Ω', "ΏὨὩὪὫὬὭὮὯᾨᾩᾪᾫᾬᾭᾮᾯῺΏῼ"),
+    ('α', "άἀἁἂἃἄἅἆἇὰάᾀᾁᾂᾃᾄᾅᾆᾇᾰᾱᾲᾳᾴᾶᾷ"),
+    ('ε', "έἐἑἒἓἔἕὲέ"),
+    ('η', "ήἠἡἢἣἤἥἦἧὴήᾐᾑᾒᾓᾔᾕᾖᾗῂῃῄῆῇ"),
+    ('ι', "ΐίϊἰἱἲἳἴἵἶἷὶίῐῑῒΐῖῗ"),
+    ('ο', "όὀὁὂὃὄὅὸό"),
+    ('ρ', "ῤῥ"),
+    ('υ', "ΰϋύὐὑὒὓὔὕὖὗὺύῠῡῢΰῦῧ"),
+    ('ω', "ώὠὡὢὣὤὥὦὧὼώᾠᾡᾢᾣᾤᾥᾦᾧῲῳῴῶῷ"),
+    ('ϒ', "ϓϔ"),
+    ('І', "Ї"),
+    ('А', "ӐӒ"),
+    ('Г', "Ѓ"),
+    ('Е', "ЀЁӖ"),
+    ('Ж', "ӁӜ"),
+    ('З', "Ӟ"),
+    ('И', "ЍЙӢӤ"),
+    ('К', "Ќ"),
+    ('О', "Ӧ"),
+    ('У', "ЎӮӰӲ"),
+    ('Ч', "Ӵ"),
+    ('Ы', "Ӹ"),
+    ('Э', "Ӭ"),
+    ('а', "ӑӓ"),
+    ('г', "ѓ"),
+    ('е', "ѐёӗ"),
+    ('ж', "ӂӝ"),
+    ('з', "ӟ"),
+    ('и', "йѝӣӥ"),
+    ('к', "ќ"),
+    ('о', "ӧ"),
+    ('у', "ўӯӱӳ"),
+    ('ч', "ӵ"),
+    ('ы', "ӹ"),
+    ('э', "ӭ"),
+    ('і', "ї"),
+    ('Ѵ', "Ѷ"),
+    ('ѵ', "ѷ"),
+    ('Ә', "Ӛ"),
+    ('ә', "ӛ"),
+    ('Ө', "Ӫ"),
+    ('ө', "ӫ"),
+    ('א', "אַאָאּ"),
+    ('ב', "בּבֿ"),
+    ('ג', "גּ"),
+    ('ד', "דּ"),
+    ('ה', "הּ"),
+    ('ו', "וּוֹ"),
+    ('ז', "זּ"),
+    ('ט', "טּ"),
+    ('י', "יִיּ"),
+    ('ך', "ךּ"),
+    ('כ', "כּכֿ"),
+    ('ל', "לּ"),
+    ('מ', "מּ"),
+    ('נ', "נּ"),
+    ('ס', "סּ"),
+    ('ף', "ףּ"),
+    ('פ', "פּפֿ"),
+    ('צ', "צּ"),
+    ('ק', "קּ"),
+    ('ר', "רּ"),
+    ('ש', "שׁשׂשּׁשּׂשּ"),
+    ('ת', "תּ"),
+    ('ײ', "ײַ"),
+    ('ا', "آأإ"),
+    ('و', "ؤ"),
+    ('ي', "ئ"),
+    ('ہ', "ۂ"),
+    ('ے', "ۓ"),
+    ('ە', "ۀ"),
+    ('क', "क़"),
+    ('ख', "ख़"),
+    ('ग', "ग़"),
+    ('ज', "ज़"),
+    ('ड', "ड़"),
+    ('ढ', "ढ़"),
+    ('न', "ऩ"),
+    ('फ', "फ़"),
+    ('य', "य़"),
+    ('र', "ऱ"),
+    ('ळ', "ऴ"),
+    ('ড', "ড়"),
+    ('ঢ', "ঢ়"),
+    ('য', "য়"),
+    ('ਖ', "ਖ਼"),
+    ('ਗ', "ਗ਼"),
+    ('ਜ', "ਜ਼"),
+    ('ਫ', "ਫ਼"),
+    ('ਲ', "ਲ਼"),
+    ('ਸ', "ਸ਼"),
+    ('ଡ', "ଡ଼"),
+    ('ଢ', "ଢ଼"),
+    ('ஒ', "ஔ"),
+    ('ཀ', "ཀྵ"),
+    ('ག', "གྷ"),
+    ('ཌ', "ཌྷ"),
+    ('ད', "དྷ"),
+    ('བ', "བྷ"),
+    ('ཛ', "ཛྷ"),
+    ('ဥ', "ဦ"),
+    ('ᬅ', "ᬆ"),
+    ('ᬇ', "ᬈ"),
+    ('ᬉ', "ᬊ"),
+    ('ᬋ', "ᬌ"),
+    ('ᬍ', "ᬎ"),
+    ('ᬑ', "ᬒ"),
+    ('う', "ゔ"),
+    ('か', "が"),
+    ('き', "ぎ"),
+    ('く', "ぐ"),
+    ('け', "げ"),
+    ('こ', "ご"),
+    ('さ', "ざ"),
+    ('し', "じ"),
+    ('す', "ず"),
+    ('せ', "ぜ"),
+    ('そ', "ぞ"),
+    ('た', "だ"),
+    ('ち', "ぢ"),
+    ('つ', "づ"),
+    ('て', "で"),
+    ('と', "ど"),
+    ('は', "ばぱ"),
+    ('ひ', "びぴ"),
+    ('ふ', "ぶぷ"),
+    ('へ', "べぺ"),
+    ('ほ', "ぼぽ"),
+    ('ゝ', "ゞ"),
+    ('ウ', "ヴ"),
+    ('カ', "ガ"),
+    ('キ', "ギ"),
+    ('ク', "グ"),
+    ('ケ', "ゲ"),
+    ('コ', "ゴ"),
+    ('サ', "ザ"),
+    ('シ', "ジ"),
+    ('ス', "ズ"),
+    ('セ', "ゼ"),
+    ('ソ', "ゾ"),
+    ('タ', "ダ"),
+    ('チ', "ヂ"),
+    ('ツ', "ヅ"),
+    ('テ', "デ"),
+    ('ト', "ド"),
+    ('ハ', "バパ"),
+    ('ヒ', "ビピ"),
+    ('フ', "ブプ"),
+    ('ヘ', "ベペ"),
+    ('ホ', "ボポ"),
+    ('ワ', "ヷ"),
+    ('ヰ', "ヸ"),
+    ('ヱ', "ヹ"),
+    ('ヲ', "ヺ"),
+    ('ヽ', "ヾ"),
+    ('𑂙', "𑂚"),
+    ('𑂛', "𑂜"),
+    ('𑂥', "𑂫"),
+];
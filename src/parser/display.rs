@@ -0,0 +1,233 @@
+use super::{AstNode, CharClass, CharRange};
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::cmp::Ordering;
+use core::fmt;
+
+/// Serializes a sequence of AST nodes back into pattern text.
+///
+/// The output is a non-verbose pattern that parses back into an equivalent AST.
+/// Programmatically built trees are parenthesized with `(?:...)` where needed,
+/// e.g. an `Alternation` that is followed by other nodes in the same sequence.
+pub fn to_pattern(nodes: &[AstNode]) -> String {
+    let mut out = String::new();
+    write_sequence(&mut out, nodes).expect("writing to a String cannot fail");
+    out
+}
+
+impl AstNode {
+    /// Serializes this node back into pattern text.
+    pub fn to_pattern(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for AstNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AstNode::Literal(c) => write_literal(f, *c),
+            AstNode::LiteralString(s) => s.chars().try_for_each(|c| write_literal(f, c)),
+            AstNode::CharClass(class) => write!(f, "{}", class),
+            AstNode::Grapheme => f.write_str("."),
+            AstNode::StartAnchor => f.write_str("^"),
+            AstNode::EndAnchor => f.write_str("$"),
+            AstNode::StartText => f.write_str(r"\%^"),
+            AstNode::EndText => f.write_str(r"\%$"),
+            AstNode::Line(line, ordering) => write!(f, r"\%{}{}l", ordering_sign(*ordering), line),
+            AstNode::Column(column, ordering) => {
+                write!(f, r"\%{}{}c", ordering_sign(*ordering), column)
+            }
+            AstNode::Cursor => f.write_str(r"\%#"),
+            AstNode::Visual => f.write_str(r"\%V"),
+            AstNode::Mark(name, ordering) => write!(f, r"\%{}'{}", ordering_sign(*ordering), name),
+            AstNode::WordBoundary => f.write_str(r"\b"),
+            AstNode::StartWord => f.write_str(r"\<"),
+            AstNode::EndWord => f.write_str(r"\>"),
+            AstNode::SetMatchStart => f.write_str(r"\zs"),
+            AstNode::SetMatchEnd => f.write_str(r"\ze"),
+            AstNode::ZeroOrMore { node, greedy } => {
+                write_repeated(f, node)?;
+                write_suffix(f, "*", *greedy)
+            }
+            AstNode::OneOrMore { node, greedy } => {
+                write_repeated(f, node)?;
+                write_suffix(f, "+", *greedy)
+            }
+            AstNode::Optional { node, greedy } => {
+                write_repeated(f, node)?;
+                write_suffix(f, "?", *greedy)
+            }
+            AstNode::Exact { node, count } => {
+                write_repeated(f, node)?;
+                write!(f, "{{{}}}", count)
+            }
+            AstNode::Range {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                write_repeated(f, node)?;
+                let bounds = match max {
+                    Some(max) => format!("{{{},{}}}", min, max),
+                    None => format!("{{{},}}", min),
+                };
+                write_suffix(f, &bounds, *greedy)
+            }
+            AstNode::Group {
+                nodes,
+                name,
+                capture,
+                ..
+            } => {
+                match (name, capture) {
+                    (Some(name), _) => write!(f, "(?<{}>", name)?,
+                    (None, true) => f.write_str("(")?,
+                    (None, false) => f.write_str("(?:")?,
+                }
+                write_sequence(f, nodes)?;
+                f.write_str(")")
+            }
+            AstNode::Alternation(alts) => {
+                for (i, alt) in alts.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("|")?;
+                    }
+                    write_sequence(f, alt)?;
+                }
+                Ok(())
+            }
+            AstNode::Backref(index) => write!(f, "\\{}", index),
+            AstNode::LookAhead { nodes, positive } => {
+                f.write_str(if *positive { "(?>=" } else { "(?>!" })?;
+                write_sequence(f, nodes)?;
+                f.write_str(")")
+            }
+            AstNode::LookBehind { nodes, positive } => {
+                f.write_str(if *positive { "(?<=" } else { "(?<!" })?;
+                write_sequence(f, nodes)?;
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let escape = match self {
+            CharClass::Digit => r"\d",
+            CharClass::NonDigit => r"\D",
+            CharClass::Word => r"\w",
+            CharClass::NonWord => r"\W",
+            CharClass::Whitespace => r"\s",
+            CharClass::NonWhitespace => r"\S",
+            CharClass::Lowercase => r"\l",
+            CharClass::NonLowercase => r"\L",
+            CharClass::Uppercase => r"\u",
+            CharClass::NonUppercase => r"\U",
+            CharClass::Hex => r"\x",
+            CharClass::NonHex => r"\X",
+            CharClass::Octal => r"\o",
+            CharClass::NonOctal => r"\O",
+            CharClass::WordStart => r"\h",
+            CharClass::NonWordStart => r"\H",
+            CharClass::Punctuation => r"\p",
+            CharClass::NonPunctuation => r"\P",
+            CharClass::Alphanumeric => r"\a",
+            CharClass::NonAlphanumeric => r"\A",
+            CharClass::Dot => ".",
+            CharClass::Set { chars, negated } => {
+                f.write_str(if *negated { "[^" } else { "[" })?;
+                for range in chars {
+                    write!(f, "{}", range)?;
+                }
+                return f.write_str("]");
+            }
+        };
+        f.write_str(escape)
+    }
+}
+
+impl fmt::Display for CharRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_set_char(f, self.start)?;
+        if self.end != self.start {
+            f.write_str("-")?;
+            write_set_char(f, self.end)?;
+        }
+        Ok(())
+    }
+}
+
+// Writes a sequence, wrapping alternations that share it with other nodes
+fn write_sequence<W: fmt::Write>(out: &mut W, nodes: &[AstNode]) -> fmt::Result {
+    for node in nodes {
+        if matches!(node, AstNode::Alternation(_)) && nodes.len() > 1 {
+            write!(out, "(?:{})", node)?;
+        } else {
+            write!(out, "{}", node)?;
+        }
+    }
+    Ok(())
+}
+
+// Writes the operand of a quantifier, wrapping it if it is not a single atom
+fn write_repeated(f: &mut fmt::Formatter, node: &AstNode) -> fmt::Result {
+    match node {
+        AstNode::ZeroOrMore { .. }
+        | AstNode::OneOrMore { .. }
+        | AstNode::Optional { .. }
+        | AstNode::Exact { .. }
+        | AstNode::Range { .. }
+        | AstNode::LiteralString(_)
+        | AstNode::Alternation(_) => write!(f, "(?:{})", node),
+        _ => write!(f, "{}", node),
+    }
+}
+
+fn write_suffix(f: &mut fmt::Formatter, quantifier: &str, greedy: bool) -> fmt::Result {
+    f.write_str(quantifier)?;
+    if !greedy {
+        f.write_str("?")?;
+    }
+    Ok(())
+}
+
+// The `<` or `>` of `\%<23l`, `\%>23l` and the like
+fn ordering_sign(ordering: Ordering) -> &'static str {
+    match ordering {
+        Ordering::Less => "<",
+        Ordering::Equal => "",
+        Ordering::Greater => ">",
+    }
+}
+
+fn write_literal(f: &mut fmt::Formatter, c: char) -> fmt::Result {
+    match c {
+        '\n' => f.write_str(r"\n"),
+        '\t' => f.write_str(r"\t"),
+        '\r' => f.write_str(r"\r"),
+        '\x0C' => f.write_str(r"\f"),
+        '\x0B' => f.write_str(r"\v"),
+        '\0' => f.write_str(r"\0"),
+        '\x01'..='\x1A' => write!(f, "\\c{}", char::from(c as u8 + b'@')),
+        '.' | '*' | '+' | '?' | '^' | '$' | '|' | '(' | ')' | '[' | ']' | '{' | '}' | '\\' => {
+            write!(f, "\\{}", c)
+        }
+        _ => write!(f, "{}", c),
+    }
+}
+
+fn write_set_char(f: &mut fmt::Formatter, c: char) -> fmt::Result {
+    match c {
+        '\n' => f.write_str(r"\n"),
+        '\t' => f.write_str(r"\t"),
+        '\r' => f.write_str(r"\r"),
+        '\x0C' => f.write_str(r"\f"),
+        '\x0B' => f.write_str(r"\v"),
+        // `[` too, so a set holding `[=` and `=` can't read back as a POSIX bracket
+        '[' | ']' | '\\' | '^' | '-' => write!(f, "\\{}", c),
+        c if c.is_control() => write!(f, "\\x{{{:X}}}", u32::from(c)),
+        _ => write!(f, "{}", c),
+    }
+}
@@ -0,0 +1,157 @@
+use super::AstNode;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt::Write;
+
+/// Renders a sequence of AST nodes as a Graphviz DOT graph.
+///
+/// Each node becomes a box labelled with its pattern text, or with its operator
+/// for nodes that have children. Children are drawn left to right in pattern order,
+/// and each branch of an alternation hangs off its own `branch` node. Render the
+/// output with e.g. `dot -Tsvg`.
+///
+/// ```
+/// use monster_regex::{Flags, Parser, to_dot};
+///
+/// let ast = Parser::new("a+", Flags::default()).parse().unwrap();
+/// assert_eq!(
+///     to_dot(&ast),
+///     "digraph ast {\n  node [shape=box];\n  n0 [label=\"pattern\"];\n  \
+///      n1 [label=\"+\"];\n  n0 -> n1;\n  n2 [label=\"a\"];\n  n1 -> n2;\n}\n"
+/// );
+/// ```
+pub fn to_dot(nodes: &[AstNode]) -> String {
+    let mut graph = DotGraph::new("ast");
+    let root = graph.node("pattern");
+    add_sequence(&mut graph, root, nodes);
+    graph.finish()
+}
+
+impl AstNode {
+    /// Renders this node and its descendants as a Graphviz DOT graph. See [`to_dot`].
+    pub fn to_dot(&self) -> String {
+        to_dot(core::slice::from_ref(self))
+    }
+}
+
+fn add_sequence(graph: &mut DotGraph, parent: usize, nodes: &[AstNode]) {
+    for node in nodes {
+        add_node(graph, parent, node);
+    }
+}
+
+fn add_node(graph: &mut DotGraph, parent: usize, node: &AstNode) {
+    let suffix = |greedy: bool| if greedy { "" } else { "?" };
+    let label = match node {
+        AstNode::ZeroOrMore { greedy, .. } => format!("*{}", suffix(*greedy)),
+        AstNode::OneOrMore { greedy, .. } => format!("+{}", suffix(*greedy)),
+        AstNode::Optional { greedy, .. } => format!("?{}", suffix(*greedy)),
+        AstNode::Exact { count, .. } => format!("{{{count}}}"),
+        AstNode::Range {
+            min, max, greedy, ..
+        } => match max {
+            Some(max) => format!("{{{min},{max}}}{}", suffix(*greedy)),
+            None => format!("{{{min},}}{}", suffix(*greedy)),
+        },
+        AstNode::Group {
+            name,
+            index,
+            capture,
+            ..
+        } => match (name, index) {
+            (Some(name), Some(index)) => format!("group {index} <{name}>"),
+            (None, Some(index)) if *capture => format!("group {index}"),
+            _ => "(?:)".to_string(),
+        },
+        AstNode::Alternation(_) => "|".to_string(),
+        AstNode::LookAhead { positive, .. } => {
+            if *positive { "(?>=)" } else { "(?>!)" }.to_string()
+        }
+        AstNode::LookBehind { positive, .. } => {
+            if *positive { "(?<=)" } else { "(?<!)" }.to_string()
+        }
+        _ => node.to_pattern(),
+    };
+    let id = graph.node(&label);
+    graph.edge(parent, id, None);
+    match node {
+        AstNode::ZeroOrMore { node: inner, .. }
+        | AstNode::OneOrMore { node: inner, .. }
+        | AstNode::Optional { node: inner, .. }
+        | AstNode::Exact { node: inner, .. }
+        | AstNode::Range { node: inner, .. } => add_node(graph, id, inner),
+        AstNode::Group { nodes, .. }
+        | AstNode::LookAhead { nodes, .. }
+        | AstNode::LookBehind { nodes, .. } => add_sequence(graph, id, nodes),
+        AstNode::Alternation(alts) => {
+            for alt in alts {
+                let branch = graph.node("branch");
+                graph.edge(id, branch, None);
+                add_sequence(graph, branch, alt);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Builds the text of a DOT digraph with numbered box nodes.
+pub(crate) struct DotGraph {
+    out: String,
+    nodes: usize,
+}
+
+impl DotGraph {
+    pub(crate) fn new(name: &str) -> Self {
+        DotGraph {
+            out: format!("digraph {name} {{\n  node [shape=box];\n"),
+            nodes: 0,
+        }
+    }
+
+    /// Adds a node and returns its id.
+    pub(crate) fn node(&mut self, label: &str) -> usize {
+        let id = self.nodes;
+        self.nodes += 1;
+        self.node_with_id(id, label);
+        id
+    }
+
+    /// Adds a node with a caller-chosen id, such as an instruction's index.
+    pub(crate) fn node_with_id(&mut self, id: usize, label: &str) {
+        writeln!(self.out, "  n{id} [label=\"{}\"];", escape(label))
+            .expect("writing to a String cannot fail");
+    }
+
+    pub(crate) fn edge(&mut self, from: usize, to: usize, label: Option<&str>) {
+        match label {
+            Some(label) => writeln!(
+                self.out,
+                "  n{from} -> n{to} [label=\"{}\"];",
+                escape(label)
+            ),
+            None => writeln!(self.out, "  n{from} -> n{to};"),
+        }
+        .expect("writing to a String cannot fail");
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+// Escapes text for a double-quoted DOT string
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,277 @@
+use super::{AstNode, CharClass, CharRange};
+use crate::flags::{DotMode, Flags};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// Describes a sequence of AST nodes in plain English, one step per line.
+///
+/// Steps inside groups, quantifiers, alternatives and lookarounds are indented two
+/// spaces under the line that introduces them. Adjacent literal characters are
+/// described together. `flags` decide the meaning of `.`, `^` and `$`.
+///
+/// ```
+/// use monster_regex::{Flags, Parser, explain};
+///
+/// let flags = Flags::default();
+/// let ast = Parser::new(r"(\d+)-x", flags).parse().unwrap();
+/// assert_eq!(
+///     explain(&ast, &flags),
+///     "group 1:\n  one or more of: a digit\nliteral \"-x\""
+/// );
+/// ```
+pub fn explain(nodes: &[AstNode], flags: &Flags) -> String {
+    let mut lines = vec![];
+    explain_sequence(&mut lines, nodes, flags, 0);
+    lines.join("\n")
+}
+
+fn explain_sequence(lines: &mut Vec<String>, nodes: &[AstNode], flags: &Flags, depth: usize) {
+    if nodes.is_empty() {
+        push(lines, depth, "nothing".to_string());
+    }
+    let mut rest = nodes;
+    while let Some(node) = rest.first() {
+        let run = literal_run(rest);
+        if run.len() > 1 {
+            push(
+                lines,
+                depth,
+                format!("literal {:?}", String::from_iter(&run)),
+            );
+            rest = &rest[run.len()..];
+        } else {
+            explain_node(lines, node, flags, depth);
+            rest = &rest[1..];
+        }
+    }
+}
+
+fn explain_node(lines: &mut Vec<String>, node: &AstNode, flags: &Flags, depth: usize) {
+    if let Some(atom) = describe_atom(node, flags) {
+        push(lines, depth, atom);
+        return;
+    }
+    match node {
+        AstNode::ZeroOrMore { node, greedy } => {
+            explain_repeat(lines, "zero or more", node, *greedy, flags, depth)
+        }
+        AstNode::OneOrMore { node, greedy } => {
+            explain_repeat(lines, "one or more", node, *greedy, flags, depth)
+        }
+        AstNode::Optional { node, greedy } => {
+            explain_repeat(lines, "optionally", node, *greedy, flags, depth)
+        }
+        AstNode::Exact { node, count } => {
+            explain_repeat(lines, &format!("exactly {count}"), node, true, flags, depth)
+        }
+        AstNode::Range {
+            node,
+            min,
+            max,
+            greedy,
+        } => {
+            let times = match max {
+                Some(max) => format!("between {min} and {max}"),
+                None => format!("at least {min}"),
+            };
+            explain_repeat(lines, &times, node, *greedy, flags, depth)
+        }
+        AstNode::Group {
+            nodes, name, index, ..
+        } => {
+            let header = match (index, name) {
+                (Some(index), Some(name)) => format!("group {index} ({name}):"),
+                (Some(index), None) => format!("group {index}:"),
+                (None, _) => "group:".to_string(),
+            };
+            push(lines, depth, header);
+            explain_sequence(lines, nodes, flags, depth + 1);
+        }
+        AstNode::Alternation(alts) => {
+            for (i, alt) in alts.iter().enumerate() {
+                push(
+                    lines,
+                    depth,
+                    if i == 0 { "either:" } else { "or:" }.to_string(),
+                );
+                explain_sequence(lines, alt, flags, depth + 1);
+            }
+        }
+        AstNode::LookAhead { nodes, positive } => {
+            let header = if *positive {
+                "followed by:"
+            } else {
+                "not followed by:"
+            };
+            push(lines, depth, header.to_string());
+            explain_sequence(lines, nodes, flags, depth + 1);
+        }
+        AstNode::LookBehind { nodes, positive } => {
+            let header = if *positive {
+                "preceded by:"
+            } else {
+                "not preceded by:"
+            };
+            push(lines, depth, header.to_string());
+            explain_sequence(lines, nodes, flags, depth + 1);
+        }
+        _ => unreachable!("atoms are described above"),
+    }
+}
+
+// A quantified atom fits on one line; anything bigger gets its own block
+fn explain_repeat(
+    lines: &mut Vec<String>,
+    times: &str,
+    node: &AstNode,
+    greedy: bool,
+    flags: &Flags,
+    depth: usize,
+) {
+    let lazy = if greedy { "" } else { " (as few as possible)" };
+    let atom = match node {
+        AstNode::Group {
+            capture: false,
+            nodes,
+            ..
+        } => match nodes.as_slice() {
+            [inner] => describe_atom(inner, flags),
+            _ => None,
+        },
+        _ => describe_atom(node, flags),
+    };
+    match atom {
+        Some(atom) if times == "optionally" => push(lines, depth, format!("{times}{lazy}: {atom}")),
+        Some(atom) => push(lines, depth, format!("{times}{lazy} of: {atom}")),
+        None => {
+            let times = if times == "optionally" {
+                times.to_string()
+            } else {
+                format!("{times} times")
+            };
+            push(lines, depth, format!("{times}{lazy}:"));
+            match node {
+                AstNode::Group {
+                    capture: false,
+                    nodes,
+                    ..
+                } => explain_sequence(lines, nodes, flags, depth + 1),
+                _ => explain_node(lines, node, flags, depth + 1),
+            }
+        }
+    }
+}
+
+// Describes nodes that match without any nested structure
+fn describe_atom(node: &AstNode, flags: &Flags) -> Option<String> {
+    Some(match node {
+        AstNode::Literal(c) => format!("literal {c:?}"),
+        AstNode::LiteralString(s) => format!("literal {s:?}"),
+        AstNode::CharClass(class) => describe_class(class, flags),
+        AstNode::Grapheme if flags.dotall => "any grapheme cluster".to_string(),
+        AstNode::Grapheme => "any grapheme cluster except a line ending".to_string(),
+        AstNode::StartAnchor if flags.multiline => "start of a line".to_string(),
+        AstNode::StartAnchor => "start of the text".to_string(),
+        AstNode::EndAnchor if flags.multiline => "end of a line".to_string(),
+        AstNode::EndAnchor => "end of the text".to_string(),
+        AstNode::StartText => "start of the text".to_string(),
+        AstNode::EndText => "end of the text".to_string(),
+        AstNode::Line(line, ordering) => format!("{} line {line}", relation(*ordering, "on")),
+        AstNode::Column(column, ordering) => {
+            format!("{} column {column}", relation(*ordering, "at"))
+        }
+        AstNode::Cursor => "the cursor".to_string(),
+        AstNode::Visual => "inside the visual area".to_string(),
+        AstNode::Mark(name, ordering) => format!("{} mark {name:?}", relation(*ordering, "at")),
+        AstNode::WordBoundary => "a word boundary".to_string(),
+        AstNode::StartWord => "the start of a word".to_string(),
+        AstNode::EndWord => "the end of a word".to_string(),
+        AstNode::SetMatchStart => "the match starts here".to_string(),
+        AstNode::SetMatchEnd => "the match ends here".to_string(),
+        AstNode::Backref(index) => format!("the text matched by group {index}"),
+        _ => return None,
+    })
+}
+
+// How a position relates to the line, column or mark of `\%l`, `\%c` and `\%'m`
+fn relation(ordering: Ordering, equal: &'static str) -> &'static str {
+    match ordering {
+        Ordering::Less => "before",
+        Ordering::Equal => equal,
+        Ordering::Greater => "after",
+    }
+}
+
+fn describe_class(class: &CharClass, flags: &Flags) -> String {
+    let text = match class {
+        CharClass::Digit => "a digit",
+        CharClass::NonDigit => "a non-digit",
+        CharClass::Word => "a word character",
+        CharClass::NonWord => "a non-word character",
+        CharClass::Whitespace => "a whitespace character",
+        CharClass::NonWhitespace => "a non-whitespace character",
+        CharClass::Lowercase => "a lowercase letter",
+        CharClass::NonLowercase => "a character that isn't a lowercase letter",
+        CharClass::Uppercase => "an uppercase letter",
+        CharClass::NonUppercase => "a character that isn't an uppercase letter",
+        CharClass::Hex => "a hex digit",
+        CharClass::NonHex => "a character that isn't a hex digit",
+        CharClass::Octal => "an octal digit",
+        CharClass::NonOctal => "a character that isn't an octal digit",
+        CharClass::WordStart => "a letter or underscore",
+        CharClass::NonWordStart => "a character that isn't a letter or underscore",
+        CharClass::Punctuation => "a punctuation character",
+        CharClass::NonPunctuation => "a character that isn't punctuation",
+        CharClass::Alphanumeric => "a letter or digit",
+        CharClass::NonAlphanumeric => "a character that isn't a letter or digit",
+        CharClass::Dot => {
+            let any = match flags.dot {
+                DotMode::Ascii => "any ASCII character",
+                _ => "any character",
+            };
+            return if flags.dotall {
+                any.to_string()
+            } else if flags.crlf || flags.dot == DotMode::NotCr {
+                format!("{any} except a line ending")
+            } else {
+                format!("{any} except a newline")
+            };
+        }
+        CharClass::Set { chars, negated } => {
+            let items: Vec<_> = chars.iter().map(describe_range).collect();
+            return match (negated, items.is_empty()) {
+                (false, true) => "nothing (empty set)".to_string(),
+                (true, true) => "any character".to_string(),
+                (false, false) => format!("one of {}", items.join(", ")),
+                (true, false) => format!("any character except {}", items.join(", ")),
+            };
+        }
+    };
+    text.to_string()
+}
+
+fn describe_range(range: &CharRange) -> String {
+    if range.start == range.end {
+        format!("{:?}", range.start)
+    } else {
+        format!("{:?} to {:?}", range.start, range.end)
+    }
+}
+
+// The literal characters at the start of `nodes`
+fn literal_run(nodes: &[AstNode]) -> Vec<char> {
+    nodes
+        .iter()
+        .map_while(|node| match node {
+            AstNode::Literal(c) => Some(*c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn push(lines: &mut Vec<String>, depth: usize, line: String) {
+    lines.push(format!("{}{line}", "  ".repeat(depth)));
+}
@@ -0,0 +1,397 @@
+use super::{AstNode, CharClass, CharRange};
+use crate::compiler::max_len;
+use crate::errors::ExportError;
+use crate::flags::{DotMode, Flags};
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+
+/// Translates a pattern into the syntax of the Rust `regex` crate (1.10 or later).
+///
+/// Flags are folded into the output: case folding becomes a leading `(?i)`, while
+/// anchors and `.` are written out in the form `flags` selects, e.g. `^` becomes `\A`
+/// or `(?m:^)`. Classes are spelled with Unicode properties so they accept the same
+/// characters as here; `\b`, `\<` and `\>` use the other engine's word characters,
+/// which also count combining marks.
+///
+/// Fails on backreferences, lookarounds, `\zs`/`\ze` and line, column and mark
+/// atoms, which that crate doesn't support.
+///
+/// ```
+/// use monster_regex::{Flags, Parser, to_rust_regex};
+///
+/// let flags = Flags { ignore_case: Some(false), ..Flags::default() };
+/// let ast = Parser::new(r"^\d+\.\x*$", flags).parse().unwrap();
+/// assert_eq!(to_rust_regex(&ast, &flags).unwrap(), r"\A[0-9]+\.[0-9A-Fa-f]*\z");
+/// ```
+pub fn to_rust_regex(nodes: &[AstNode], flags: &Flags) -> Result<String, ExportError> {
+    Exporter::new(Dialect::Rust, flags).export(nodes)
+}
+
+/// Translates a pattern into PCRE2 syntax (10.40 or later, for its binary Unicode
+/// properties).
+///
+/// The output starts with `(*UTF)(*UCP)` so classes and word boundaries work on
+/// Unicode text. `\zs` becomes `\K`, lookaheads become `(?=...)`/`(?!...)`, and
+/// whatever follows `\ze` becomes a lookahead. Flags are folded in as for
+/// [`to_rust_regex`].
+///
+/// Fails on `\ze` where the rest of the pattern can't be turned into a lookahead
+/// (inside a capture group or quantifier), `\zs` inside a lookaround, and lookbehinds
+/// without a maximum length.
+///
+/// ```
+/// use monster_regex::{Flags, Parser, to_pcre};
+///
+/// let flags = Flags { ignore_case: Some(false), ..Flags::default() };
+/// let ast = Parser::new(r"foo\zsbar(?>=!)\ze\d", flags).parse().unwrap();
+/// assert_eq!(
+///     to_pcre(&ast, &flags).unwrap(),
+///     r"(*UTF)(*UCP)foo\Kbar(?=!)(?=[0-9])"
+/// );
+/// ```
+pub fn to_pcre(nodes: &[AstNode], flags: &Flags) -> Result<String, ExportError> {
+    Exporter::new(Dialect::Pcre, flags).export(nodes)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    Rust,
+    Pcre,
+}
+
+impl Dialect {
+    fn name(self) -> &'static str {
+        match self {
+            Dialect::Rust => "the Rust regex crate",
+            Dialect::Pcre => "PCRE",
+        }
+    }
+}
+
+struct Exporter<'f> {
+    dialect: Dialect,
+    flags: &'f Flags,
+    out: String,
+    /// Whether the node being written is inside a lookaround.
+    in_look: bool,
+}
+
+impl<'f> Exporter<'f> {
+    fn new(dialect: Dialect, flags: &'f Flags) -> Self {
+        Exporter {
+            dialect,
+            flags,
+            out: String::new(),
+            in_look: false,
+        }
+    }
+
+    fn export(mut self, nodes: &[AstNode]) -> Result<String, ExportError> {
+        if self.dialect == Dialect::Pcre {
+            self.out.push_str("(*UTF)(*UCP)");
+        }
+        if self.ignore_case() {
+            self.out.push_str("(?i)");
+        }
+        self.sequence(nodes, true)?;
+        Ok(self.out)
+    }
+
+    fn ignore_case(&self) -> bool {
+        self.flags.ignore_case.unwrap_or(false)
+    }
+
+    // Whether `^` and `$` match at lines, and if so whether `\r` ends lines too
+    fn line_mode(&self) -> Option<bool> {
+        self.flags.multiline.then_some(self.flags.crlf)
+    }
+
+    fn unsupported(&self, construct: &str) -> ExportError {
+        ExportError::Unsupported(format!(
+            "{} has no equivalent in {}",
+            construct,
+            self.dialect.name()
+        ))
+    }
+
+    // `tail` is set when nothing follows the sequence before the end of the match,
+    // so a `\ze` in it can turn the rest of the sequence into a lookahead
+    fn sequence(&mut self, nodes: &[AstNode], tail: bool) -> Result<(), ExportError> {
+        for (i, node) in nodes.iter().enumerate() {
+            let last = i + 1 == nodes.len();
+            match node {
+                AstNode::SetMatchEnd if tail && self.dialect == Dialect::Pcre => {
+                    self.out.push_str("(?=");
+                    self.look(&nodes[i + 1..])?;
+                    self.out.push(')');
+                    return Ok(());
+                }
+                AstNode::Alternation(_) if nodes.len() > 1 => {
+                    self.out.push_str("(?:");
+                    self.node(node, tail && last)?;
+                    self.out.push(')');
+                }
+                _ => self.node(node, tail && last)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn look(&mut self, nodes: &[AstNode]) -> Result<(), ExportError> {
+        let in_look = core::mem::replace(&mut self.in_look, true);
+        let result = self.sequence(nodes, false);
+        self.in_look = in_look;
+        result
+    }
+
+    fn node(&mut self, node: &AstNode, tail: bool) -> Result<(), ExportError> {
+        let pcre = self.dialect == Dialect::Pcre;
+        match node {
+            AstNode::Literal(c) => self.literal(*c),
+            AstNode::LiteralString(s) => s.chars().for_each(|c| self.literal(c)),
+            AstNode::CharClass(class) => self.class(class),
+            AstNode::Grapheme if !pcre => return Err(self.unsupported("a grapheme cluster")),
+            AstNode::Grapheme => self
+                .out
+                .push_str(match (self.flags.dotall, self.flags.crlf) {
+                    (true, _) => r"\X",
+                    (false, true) => r"(?![\n\r])\X",
+                    // `\r\n` is a single cluster, and holds a newline
+                    (false, false) => r"(?!\r?\n)\X",
+                }),
+            AstNode::StartAnchor => self.out.push_str(match (self.line_mode(), pcre) {
+                (None, _) => r"\A",
+                (Some(false), false) => "(?m:^)",
+                (Some(true), false) => "(?mR:^)",
+                // PCRE's multiline `^` doesn't match after a newline that ends the text
+                (Some(false), true) => r"(?<![^\n])",
+                (Some(true), true) => r"(?<![^\n\r])(?!(?<=\r)\n)",
+            }),
+            AstNode::EndAnchor => self.out.push_str(match (self.line_mode(), pcre) {
+                (None, _) => r"\z",
+                (Some(false), false) => "(?m:$)",
+                (Some(true), false) => "(?mR:$)",
+                (Some(false), true) => r"(?![^\n])",
+                (Some(true), true) => r"(?![^\n\r])(?!(?<=\r)\n)",
+            }),
+            AstNode::StartText => self.out.push_str(r"\A"),
+            AstNode::EndText => self.out.push_str(r"\z"),
+            AstNode::Line(..) => return Err(self.unsupported(r"a line atom `\%l`")),
+            AstNode::Column(..) => return Err(self.unsupported(r"a column atom `\%c`")),
+            AstNode::Cursor => return Err(self.unsupported(r"the cursor `\%#`")),
+            AstNode::Visual => return Err(self.unsupported(r"the visual area `\%V`")),
+            AstNode::Mark(..) => return Err(self.unsupported(r"a mark `\%'m`")),
+            AstNode::WordBoundary => self.out.push_str(r"\b"),
+            AstNode::StartWord => self
+                .out
+                .push_str(if pcre { r"\b(?=\w)" } else { r"\b{start}" }),
+            AstNode::EndWord => self
+                .out
+                .push_str(if pcre { r"\b(?<=\w)" } else { r"\b{end}" }),
+            AstNode::SetMatchStart if !pcre => return Err(self.unsupported(r"`\zs`")),
+            AstNode::SetMatchStart if self.in_look => {
+                return Err(self.unsupported(r"`\zs` inside a lookaround"));
+            }
+            AstNode::SetMatchStart => self.out.push_str(r"\K"),
+            AstNode::SetMatchEnd if !pcre => return Err(self.unsupported(r"`\ze`")),
+            AstNode::SetMatchEnd => {
+                return Err(self.unsupported(r"`\ze` inside a capture group or quantifier"));
+            }
+            AstNode::ZeroOrMore { node, greedy } => {
+                self.repeated(node)?;
+                self.suffix("*", *greedy);
+            }
+            AstNode::OneOrMore { node, greedy } => {
+                self.repeated(node)?;
+                self.suffix("+", *greedy);
+            }
+            AstNode::Optional { node, greedy } => {
+                self.repeated(node)?;
+                self.suffix("?", *greedy);
+            }
+            AstNode::Exact { node, count } => {
+                self.repeated(node)?;
+                self.suffix(&format!("{{{}}}", count), true);
+            }
+            AstNode::Range {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                self.repeated(node)?;
+                let bounds = match max {
+                    Some(max) => format!("{{{},{}}}", min, max),
+                    None => format!("{{{},}}", min),
+                };
+                self.suffix(&bounds, *greedy);
+            }
+            AstNode::Group {
+                nodes,
+                name,
+                capture,
+                ..
+            } => {
+                match (name, capture) {
+                    (Some(name), _) if pcre => write!(self.out, "(?<{}>", name).unwrap(),
+                    (Some(name), _) => write!(self.out, "(?P<{}>", name).unwrap(),
+                    (None, true) => self.out.push('('),
+                    (None, false) => self.out.push_str("(?:"),
+                }
+                // A capture group would lose the text after a `\ze` inside it
+                self.sequence(nodes, tail && !capture)?;
+                self.out.push(')');
+            }
+            AstNode::Alternation(alts) => {
+                for (i, alt) in alts.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push('|');
+                    }
+                    self.sequence(alt, tail)?;
+                }
+            }
+            AstNode::Backref(_) if !pcre => return Err(self.unsupported("a backreference")),
+            AstNode::Backref(index) => write!(self.out, r"\g{{{}}}", index).unwrap(),
+            AstNode::LookAhead { .. } if !pcre => return Err(self.unsupported("a lookahead")),
+            AstNode::LookAhead { nodes, positive } => {
+                self.out.push_str(if *positive { "(?=" } else { "(?!" });
+                self.look(nodes)?;
+                self.out.push(')');
+            }
+            AstNode::LookBehind { .. } if !pcre => return Err(self.unsupported("a lookbehind")),
+            AstNode::LookBehind { nodes, .. } if max_len(nodes).is_none() => {
+                return Err(self.unsupported("a lookbehind without a maximum length"));
+            }
+            AstNode::LookBehind { nodes, positive } => {
+                self.out.push_str(if *positive { "(?<=" } else { "(?<!" });
+                self.look(nodes)?;
+                self.out.push(')');
+            }
+        }
+        Ok(())
+    }
+
+    // Writes the operand of a quantifier, wrapping it if it is not a single atom
+    fn repeated(&mut self, node: &AstNode) -> Result<(), ExportError> {
+        match node {
+            AstNode::Literal(_)
+            | AstNode::CharClass(_)
+            | AstNode::Group { .. }
+            | AstNode::Backref(_) => self.node(node, false),
+            _ => {
+                self.out.push_str("(?:");
+                self.node(node, false)?;
+                self.out.push(')');
+                Ok(())
+            }
+        }
+    }
+
+    fn suffix(&mut self, quantifier: &str, greedy: bool) {
+        self.out.push_str(quantifier);
+        if !greedy {
+            self.out.push('?');
+        }
+    }
+
+    fn literal(&mut self, c: char) {
+        match c {
+            '.' | '*' | '+' | '?' | '^' | '$' | '|' | '(' | ')' | '[' | ']' | '{' | '}' | '\\' => {
+                self.out.push('\\');
+                self.out.push(c);
+            }
+            _ => self.escaped_char(c),
+        }
+    }
+
+    fn class(&mut self, class: &CharClass) {
+        let ignore_case = self.ignore_case();
+        let text = match class {
+            CharClass::Digit => "[0-9]",
+            CharClass::NonDigit => "[^0-9]",
+            CharClass::Word => r"[\p{Alphabetic}\p{N}_]",
+            CharClass::NonWord => r"[^\p{Alphabetic}\p{N}_]",
+            CharClass::Whitespace => r"\s",
+            CharClass::NonWhitespace => r"\S",
+            // Folding case makes the case classes accept both cases
+            CharClass::Lowercase | CharClass::Uppercase if ignore_case => {
+                r"[\p{Lowercase}\p{Uppercase}]"
+            }
+            CharClass::NonLowercase | CharClass::NonUppercase if ignore_case => {
+                r"[^\p{Lowercase}\p{Uppercase}]"
+            }
+            CharClass::Lowercase => r"\p{Lowercase}",
+            CharClass::NonLowercase => r"\P{Lowercase}",
+            CharClass::Uppercase => r"\p{Uppercase}",
+            CharClass::NonUppercase => r"\P{Uppercase}",
+            CharClass::Hex => "[0-9A-Fa-f]",
+            CharClass::NonHex => "[^0-9A-Fa-f]",
+            CharClass::Octal => "[0-7]",
+            CharClass::NonOctal => "[^0-7]",
+            CharClass::WordStart => r"[\p{Alphabetic}_]",
+            CharClass::NonWordStart => r"[^\p{Alphabetic}_]",
+            CharClass::Punctuation => r"[!-/:-@\[-`{-~]",
+            CharClass::NonPunctuation => r"[^!-/:-@\[-`{-~]",
+            CharClass::Alphanumeric => r"[\p{Alphabetic}\p{N}]",
+            CharClass::NonAlphanumeric => r"[^\p{Alphabetic}\p{N}]",
+            CharClass::Dot if self.flags.dot == DotMode::Ascii => {
+                match (self.flags.dotall, self.flags.crlf) {
+                    (true, _) => r"[\x{0}-\x{7F}]",
+                    (false, true) => r"[^\n\r\x{80}-\x{10FFFF}]",
+                    (false, false) => r"[^\n\x{80}-\x{10FFFF}]",
+                }
+            }
+            CharClass::Dot if self.flags.dotall => "(?s:.)",
+            CharClass::Dot if self.flags.crlf || self.flags.dot == DotMode::NotCr => r"[^\n\r]",
+            CharClass::Dot => r"[^\n]",
+            // Neither dialect accepts an empty set
+            CharClass::Set { chars, negated } if chars.is_empty() => {
+                if *negated {
+                    r"[\x{0}-\x{10FFFF}]"
+                } else {
+                    r"[^\x{0}-\x{10FFFF}]"
+                }
+            }
+            CharClass::Set { chars, negated } => {
+                self.out.push_str(if *negated { "[^" } else { "[" });
+                for range in chars {
+                    self.set_range(range);
+                }
+                self.out.push(']');
+                return;
+            }
+        };
+        self.out.push_str(text);
+    }
+
+    fn set_range(&mut self, range: &CharRange) {
+        self.set_char(range.start);
+        if range.end != range.start {
+            self.out.push('-');
+            self.set_char(range.end);
+        }
+    }
+
+    // Escapes set syntax for both dialects, including Rust's `&&`, `--` and `~~`
+    // set operations
+    fn set_char(&mut self, c: char) {
+        match c {
+            '\\' | ']' | '[' | '^' | '-' | '&' | '~' => {
+                self.out.push('\\');
+                self.out.push(c);
+            }
+            _ => self.escaped_char(c),
+        }
+    }
+
+    fn escaped_char(&mut self, c: char) {
+        match c {
+            '\n' => self.out.push_str(r"\n"),
+            '\t' => self.out.push_str(r"\t"),
+            '\r' => self.out.push_str(r"\r"),
+            c if c.is_control() => write!(self.out, r"\x{{{:X}}}", u32::from(c)).unwrap(),
+            _ => self.out.push(c),
+        }
+    }
+}
@@ -0,0 +1,438 @@
+use super::Span;
+use crate::flags::Flags;
+use crate::parsing::{is_alternate_delimiter, last_unescaped};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// What a piece of pattern text is, for syntax highlighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HighlightKind {
+    /// Characters that match themselves. Adjacent ones share a span.
+    Literal,
+    /// An escaped character that matches itself or a control character, like `\.`
+    /// or `\n`.
+    Escape,
+    /// A character class: `.`, an escape like `\d`, or a whole bracket expression.
+    Class,
+    /// A quantifier with its laziness marker, like `*`, `{2,3}` or `+?`.
+    Quantifier,
+    /// The opening of a group or lookaround, like `(`, `(?:` or `(?<name>`.
+    GroupOpen,
+    /// The `)` closing a group or lookaround.
+    GroupClose,
+    /// The `|` between alternation branches.
+    Alternation,
+    /// A zero-width assertion: `^`, `$`, `\b`, `\<`, `\>`, `\zs` or `\ze`.
+    Anchor,
+    /// A backreference like `\1`.
+    Backref,
+    /// A comment: `(?#...)`, or from `#` to the end of the line in verbose mode.
+    Comment,
+    /// A delimiter around the pattern in rift format.
+    Delimiter,
+    /// A flag letter or occurrence digit in rift format, or a `(?x)` or `(?-x)`
+    /// switching verbose mode.
+    Flag,
+    /// Text that won't parse, like an unknown group extension or flag.
+    Invalid,
+}
+
+/// A classified piece of pattern text returned by [`highlight`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Highlight {
+    pub kind: HighlightKind,
+    /// The byte range of the text.
+    pub span: Span,
+}
+
+/// Breaks a pattern into classified spans for syntax highlighting, without
+/// parsing it into an AST.
+///
+/// Every character is covered by a span except whitespace skipped in verbose mode.
+/// Text that won't parse is marked [`HighlightKind::Invalid`] and lexing carries
+/// on after it, so patterns can be highlighted as they're typed.
+///
+/// ```
+/// use monster_regex::{Flags, HighlightKind, highlight};
+///
+/// let pattern = r"(?<year>\d{4})-ab*";
+/// let spans: Vec<_> = highlight(pattern, Flags::default())
+///     .into_iter()
+///     .map(|h| (h.kind, &pattern[h.span.start..h.span.end]))
+///     .collect();
+/// assert_eq!(
+///     spans,
+///     [
+///         (HighlightKind::GroupOpen, "(?<year>"),
+///         (HighlightKind::Class, r"\d"),
+///         (HighlightKind::Quantifier, "{4}"),
+///         (HighlightKind::GroupClose, ")"),
+///         (HighlightKind::Literal, "-ab"),
+///         (HighlightKind::Quantifier, "*"),
+///     ]
+/// );
+/// ```
+pub fn highlight(pattern: &str, flags: Flags) -> Vec<Highlight> {
+    let mut lexer = HighlightLexer {
+        text: pattern,
+        pos: 0,
+        verbose: flags.verbose,
+        scopes: vec![],
+        out: vec![],
+    };
+    lexer.run();
+    lexer.out
+}
+
+/// Highlights a pattern in rift format, `pattern/flags` or with another delimiter
+/// as in `#a/b#i`, marking the delimiters and each flag as well as the pattern.
+///
+/// Input without a delimiter is highlighted as a pattern alone.
+///
+/// ```
+/// use monster_regex::{HighlightKind, highlight_rift};
+///
+/// let kinds: Vec<_> = highlight_rift(r"a\d/gq").into_iter().map(|h| h.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     [
+///         HighlightKind::Literal,
+///         HighlightKind::Class,
+///         HighlightKind::Delimiter,
+///         HighlightKind::Flag,
+///         HighlightKind::Invalid,
+///     ]
+/// );
+/// ```
+pub fn highlight_rift(input: &str) -> Vec<Highlight> {
+    let (pattern_start, pattern_end, flags_start) = match delimited(input) {
+        Some(bounds) => bounds,
+        None => match last_unescaped(input, '/') {
+            Some(slash) => (0, slash, slash + 1),
+            None => return highlight(input, Flags::default()),
+        },
+    };
+    let flags = &input[flags_start..];
+
+    let mut lexer = HighlightLexer {
+        text: &input[..pattern_end],
+        pos: 0,
+        verbose: flags.contains('x'),
+        scopes: vec![],
+        out: vec![],
+    };
+    if pattern_start > 0 {
+        lexer.pos = pattern_start;
+        lexer.push(HighlightKind::Delimiter, 0);
+    }
+    lexer.run();
+
+    // The rest is past the pattern's end, so push spans directly
+    let mut out = lexer.out;
+    let mut push = |kind, start, end| {
+        out.push(Highlight {
+            kind,
+            span: Span { start, end },
+        })
+    };
+    push(HighlightKind::Delimiter, pattern_end, flags_start);
+    for (i, c) in flags.char_indices() {
+        let kind = match c.is_ascii_digit() || Flags::new().set_letter(c) {
+            true => HighlightKind::Flag,
+            false => HighlightKind::Invalid,
+        };
+        let start = flags_start + i;
+        push(kind, start, start + c.len_utf8());
+    }
+    out
+}
+
+// The pattern's start and end and where the flags start, for input wrapped in an
+// alternate delimiter like `#pattern#flags`
+fn delimited(input: &str) -> Option<(usize, usize, usize)> {
+    let delimiter = input
+        .chars()
+        .next()
+        .filter(|&c| is_alternate_delimiter(c))?;
+    let start = delimiter.len_utf8();
+    let mut escaped = false;
+    for (i, c) in input[start..].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            let end = start + i;
+            return Some((start, end, end + c.len_utf8()));
+        }
+    }
+    None
+}
+
+struct HighlightLexer<'t> {
+    text: &'t str,
+    pos: usize,
+    verbose: bool,
+    // Whether verbose mode was on outside each open group, to restore at its `)`
+    scopes: Vec<bool>,
+    out: Vec<Highlight>,
+}
+
+impl HighlightLexer<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    // Whether the `\\` at the current position ends its line
+    fn continues_line(&self) -> bool {
+        let rest = &self.text[self.pos + 1..];
+        rest.starts_with('\n') || rest.starts_with("\r\n")
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn push(&mut self, kind: HighlightKind, start: usize) {
+        let span = Span {
+            start,
+            end: self.pos,
+        };
+        // Runs of literals read better as one span
+        if let Some(last) = self.out.last_mut()
+            && kind == HighlightKind::Literal
+            && last.kind == kind
+            && last.span.end == span.start
+        {
+            last.span.end = span.end;
+            return;
+        }
+        self.out.push(Highlight { kind, span });
+    }
+
+    fn run(&mut self) {
+        // Whether the last token can take a quantifier
+        let mut after_atom = false;
+        while let Some(c) = self.peek() {
+            let start = self.pos;
+            if self.verbose && c.is_whitespace() {
+                self.bump();
+                continue;
+            }
+            // A `\` ending the line continues the pattern on the next one
+            if self.verbose && c == '\\' && self.continues_line() {
+                self.bump();
+                continue;
+            }
+            if self.verbose && c == '#' {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+                self.push(HighlightKind::Comment, start);
+                continue;
+            }
+            self.bump();
+            let kind = match c {
+                '\\' => self.escape(),
+                '[' => self.bracket(),
+                '(' => self.group_open(),
+                ')' => {
+                    if let Some(verbose) = self.scopes.pop() {
+                        self.verbose = verbose;
+                    }
+                    HighlightKind::GroupClose
+                }
+                '|' => HighlightKind::Alternation,
+                '^' | '$' => HighlightKind::Anchor,
+                '.' => HighlightKind::Class,
+                '*' | '+' | '?' if after_atom => {
+                    if self.peek() == Some('?') {
+                        self.bump();
+                    }
+                    HighlightKind::Quantifier
+                }
+                '{' if after_atom => self.bounds(),
+                _ => HighlightKind::Literal,
+            };
+            self.push(kind, start);
+            // Comments and `(?x)` leave a quantifier to apply to what came before
+            if !matches!(kind, HighlightKind::Comment | HighlightKind::Flag) {
+                after_atom = !matches!(
+                    kind,
+                    HighlightKind::GroupOpen
+                        | HighlightKind::Alternation
+                        | HighlightKind::Quantifier
+                        | HighlightKind::Invalid
+                );
+            }
+        }
+    }
+
+    // After the backslash
+    fn escape(&mut self) -> HighlightKind {
+        let Some(c) = self.bump() else {
+            return HighlightKind::Invalid;
+        };
+        match c {
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' | 'l' | 'L' | 'u' | 'U' | 'x' | 'X' | 'o' | 'O'
+            | 'h' | 'H' | 'p' | 'P' | 'a' | 'A' => HighlightKind::Class,
+            'b' | '<' | '>' => HighlightKind::Anchor,
+            '%' if matches!(self.peek(), Some('^' | '$' | '#' | 'V')) => {
+                self.bump();
+                HighlightKind::Anchor
+            }
+            '%' if matches!(self.peek(), Some('<' | '>' | '\'' | '0'..='9')) => {
+                if matches!(self.peek(), Some('<' | '>')) {
+                    self.bump();
+                }
+                if self.peek() == Some('\'') {
+                    self.bump();
+                    return match self.bump() {
+                        Some(_) => HighlightKind::Anchor,
+                        None => HighlightKind::Invalid,
+                    };
+                }
+                while matches!(self.peek(), Some('0'..='9')) {
+                    self.bump();
+                }
+                match self.bump() {
+                    Some('l' | 'c') => HighlightKind::Anchor,
+                    _ => HighlightKind::Invalid,
+                }
+            }
+            'z' if matches!(self.peek(), Some('s' | 'e')) => {
+                self.bump();
+                HighlightKind::Anchor
+            }
+            'c' if self.peek().is_some_and(|c| c.is_ascii_alphabetic()) => {
+                self.bump();
+                HighlightKind::Escape
+            }
+            'N' if self.peek() == Some('{') => {
+                while let Some(c) = self.bump() {
+                    if c == '}' {
+                        return HighlightKind::Escape;
+                    }
+                }
+                HighlightKind::Invalid
+            }
+            'z' | 'K' | 'c' => HighlightKind::Invalid,
+            '1'..='9' => HighlightKind::Backref,
+            _ => HighlightKind::Escape,
+        }
+    }
+
+    // After the `[`, up to and including the `]`
+    fn bracket(&mut self) -> HighlightKind {
+        while let Some(c) = self.bump() {
+            match c {
+                ']' => return HighlightKind::Class,
+                '\\' => {
+                    self.bump();
+                }
+                _ => {}
+            }
+        }
+        HighlightKind::Invalid
+    }
+
+    // After the `(`
+    fn group_open(&mut self) -> HighlightKind {
+        let rest = &self.text[self.pos..];
+        if rest.starts_with("?#") {
+            // Parentheses inside the comment balance, as the parser reads them
+            let mut depth = 0;
+            while let Some(c) = self.bump() {
+                match c {
+                    '(' => depth += 1,
+                    ')' if depth == 0 => break,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+            }
+            return HighlightKind::Comment;
+        }
+        for (switch, verbose) in [("?x", true), ("?-x", false)] {
+            if let Some(after) = rest.strip_prefix(switch) {
+                if after.starts_with(')') {
+                    self.pos += switch.len() + 1;
+                    self.verbose = verbose;
+                    return HighlightKind::Flag;
+                }
+                if after.starts_with(':') {
+                    self.pos += switch.len() + 1;
+                    self.scopes.push(self.verbose);
+                    self.verbose = verbose;
+                    return HighlightKind::GroupOpen;
+                }
+            }
+        }
+        let kind = self.group_kind();
+        if kind == HighlightKind::GroupOpen {
+            self.scopes.push(self.verbose);
+        }
+        kind
+    }
+
+    fn group_kind(&mut self) -> HighlightKind {
+        if self.peek() != Some('?') {
+            return HighlightKind::GroupOpen;
+        }
+        self.bump();
+        match (self.bump(), self.peek()) {
+            (Some(':'), _) => HighlightKind::GroupOpen,
+            (Some('<' | '>'), Some('=' | '!')) => {
+                self.bump();
+                HighlightKind::GroupOpen
+            }
+            (Some('<'), _) => {
+                while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    self.bump();
+                }
+                match self.peek() {
+                    Some('>') => {
+                        self.bump();
+                        HighlightKind::GroupOpen
+                    }
+                    _ => HighlightKind::Invalid,
+                }
+            }
+            _ => HighlightKind::Invalid,
+        }
+    }
+
+    // After a `{` following an atom: `{n}`, `{n,}`, `{n,m}` or `{,m}`
+    fn bounds(&mut self) -> HighlightKind {
+        let start = self.pos;
+        let lazy = self.peek() == Some('-');
+        if lazy {
+            self.bump();
+        }
+        let digits = |lexer: &mut Self| {
+            let from = lexer.pos;
+            while lexer.peek().is_some_and(|c| c.is_ascii_digit()) {
+                lexer.bump();
+            }
+            lexer.pos > from
+        };
+        let min = digits(self);
+        let range = self.peek() == Some(',');
+        if range {
+            self.bump();
+            digits(self);
+        }
+        if (min || range || lazy) && self.peek() == Some('}') {
+            self.bump();
+            // Only ranges without a `-` take a laziness marker
+            if range && !lazy && self.peek() == Some('?') {
+                self.bump();
+            }
+            HighlightKind::Quantifier
+        } else {
+            self.pos = start;
+            HighlightKind::Invalid
+        }
+    }
+}
@@ -0,0 +1,1085 @@
+use super::collate::{equivalents, named};
+use super::{AstNode, CharClass, CharRange, MAX_BACKREF, MAX_NESTING_DEPTH};
+use crate::errors::ImportError;
+use crate::flags::Flags;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+// Horizontal whitespace, PCRE's `\h`
+const HORIZONTAL_SPACE: &[(char, char)] = &[
+    ('\t', '\t'),
+    (' ', ' '),
+    ('\u{A0}', '\u{A0}'),
+    ('\u{1680}', '\u{1680}'),
+    ('\u{180E}', '\u{180E}'),
+    ('\u{2000}', '\u{200A}'),
+    ('\u{202F}', '\u{202F}'),
+    ('\u{205F}', '\u{205F}'),
+    ('\u{3000}', '\u{3000}'),
+];
+
+// How `to_rust_regex` and `to_pcre` spell the classes no single property matches,
+// after the opening bracket
+const EXPORTED_CLASSES: &[(&str, CharClass)] = &[
+    (r"\p{Alphabetic}\p{N}_]", CharClass::Word),
+    (r"^\p{Alphabetic}\p{N}_]", CharClass::NonWord),
+    (r"\p{Alphabetic}\p{N}]", CharClass::Alphanumeric),
+    (r"^\p{Alphabetic}\p{N}]", CharClass::NonAlphanumeric),
+    (r"\p{Alphabetic}_]", CharClass::WordStart),
+    (r"^\p{Alphabetic}_]", CharClass::NonWordStart),
+    (r"\p{Lowercase}\p{Uppercase}]", CharClass::Lowercase),
+    (r"^\p{Lowercase}\p{Uppercase}]", CharClass::NonLowercase),
+];
+
+// Sets wider than this aren't expanded with their case variants
+const MAX_FOLDED_RANGE: u32 = 256;
+
+/// Parses a pattern written for the Rust `regex` crate or PCRE into this crate's AST,
+/// along with the flags it needs.
+///
+/// The syntax both dialects share is accepted, plus the common extensions of either:
+/// `(?P<name>...)`, `\K`, `\Q...\E`, `\g{N}`, `\k<name>`, POSIX classes and `\b{start}`.
+/// POSIX equivalence classes and collating symbols are read too. `[[=e=]]` matches
+/// the letter with or without accents, like `é` or `ê`. A collating symbol of
+/// several characters, like `[[.ch.]]`, matches that text as a unit, unless it's a
+/// POSIX name like `[.hyphen.]`.
+///
+/// Constructs are mapped onto their closest equivalents here. `\A` and `\z` become `^`
+/// and `$` unless the pattern is multiline, `(?=...)` becomes `(?>=...)`, and
+/// `\p{..}` properties become the extended classes, e.g. `\p{Lu}` becomes `\u`.
+/// Outside multiline mode `$` also matches before a newline that ends the text, as
+/// in PCRE, so it becomes `(?>=\n?\%$)` like `\Z`. The
+/// way [`to_rust_regex`](super::to_rust_regex) and [`to_pcre`](super::to_pcre) spell
+/// classes is read back as those classes.
+/// Flag groups such as `(?i)` at the very start set the returned flags; later
+/// ones are written out where possible, e.g. `(?s:.)` in a pattern without `s`
+/// becomes a set of any character.
+///
+/// The returned flags are case-sensitive unless the pattern asks otherwise, since
+/// smartcase doesn't apply to those dialects.
+///
+/// Fails on constructs with no equivalent here, such as atomic groups, possessive
+/// quantifiers, recursion and conditionals.
+///
+/// ```
+/// use monster_regex::{Regex, from_pcre};
+///
+/// let (ast, flags) = from_pcre(r"(?i)\A(?P<word>\p{Lu}\w*)(?=!)").unwrap();
+/// let re = Regex::from_ast(ast, flags).unwrap();
+/// assert_eq!(re.pattern(), r"^(?<word>\u\w*)(?>=!)");
+/// assert!(re.is_match("hello!"));
+/// ```
+pub fn from_pcre(pattern: &str) -> Result<(Vec<AstNode>, Flags), ImportError> {
+    let mut importer = Importer {
+        chars: pattern.char_indices().collect(),
+        len: pattern.len(),
+        pos: 0,
+        mode: Mode::default(),
+        global: Mode::default(),
+        groups: 0,
+        names: vec![],
+        depth: 0,
+        construct: 0,
+    };
+    importer.leading_flags()?;
+    importer.global = importer.mode;
+    let nodes = importer.alternation()?;
+    if importer.pos < importer.chars.len() {
+        return Err(importer.invalid("unmatched `)`"));
+    }
+    let flags = Flags {
+        ignore_case: Some(importer.global.ignore_case),
+        multiline: importer.global.multiline,
+        dotall: importer.global.dotall,
+        ..Flags::default()
+    };
+    Ok((nodes, flags))
+}
+
+/// The inline flags in effect at some point of the pattern.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Mode {
+    ignore_case: bool,
+    multiline: bool,
+    dotall: bool,
+    verbose: bool,
+}
+
+struct Importer {
+    chars: Vec<(usize, char)>,
+    len: usize,
+    pos: usize,
+    mode: Mode,
+    /// The flags the whole pattern is compiled with.
+    global: Mode,
+    /// How many capture groups have been opened so far.
+    groups: usize,
+    names: Vec<(String, usize)>,
+    depth: usize,
+    /// Where the atom or quantifier being read starts, for error messages.
+    construct: usize,
+}
+
+impl Importer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|&(_, c)| c)
+    }
+
+    fn peek_at(&self, ahead: usize) -> Option<char> {
+        self.chars.get(self.pos + ahead).map(|&(_, c)| c)
+    }
+
+    fn looking_at(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn next(&mut self) -> Result<char, ImportError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.invalid("unexpected end of pattern"))?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_str(&mut self, text: &str) -> bool {
+        let found = self.looking_at(text);
+        if found {
+            self.pos += text.chars().count();
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ImportError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.invalid(&format!("expected `{}`", c)))
+        }
+    }
+
+    fn offset(&self, pos: usize) -> usize {
+        self.chars.get(pos).map_or(self.len, |&(offset, _)| offset)
+    }
+
+    fn invalid(&self, message: &str) -> ImportError {
+        ImportError::InvalidPattern(format!("{} at byte {}", message, self.offset(self.pos)))
+    }
+
+    fn unsupported(&self, construct: &str) -> ImportError {
+        ImportError::Unsupported(format!(
+            "{} at byte {} has no equivalent here",
+            construct,
+            self.offset(self.construct)
+        ))
+    }
+
+    // Start-of-pattern verbs like `(*UCP)`, which only switch on what's always on
+    // here, and flag groups like `(?i)`, which become the pattern's flags
+    fn leading_flags(&mut self) -> Result<(), ImportError> {
+        loop {
+            if self.eat_str("(*UTF)") || self.eat_str("(*UTF8)") || self.eat_str("(*UCP)") {
+                continue;
+            }
+            let (start, mode) = (self.pos, self.mode);
+            if self.eat_str("(?") && self.flag_letters()? && self.eat(')') {
+                continue;
+            }
+            // A scoped group like `(?i:...)` is read as part of the pattern
+            self.pos = start;
+            self.mode = mode;
+            return Ok(());
+        }
+    }
+
+    // Reads flag letters such as `im-sx` into the current mode. Returns whether any
+    // were read, leaving the position after them.
+    fn flag_letters(&mut self) -> Result<bool, ImportError> {
+        let start = self.pos;
+        let mut on = true;
+        while let Some(c) = self.peek() {
+            let flag = match c {
+                'i' => &mut self.mode.ignore_case,
+                'm' => &mut self.mode.multiline,
+                's' => &mut self.mode.dotall,
+                'x' => &mut self.mode.verbose,
+                // Unicode is always on here
+                'u' => {
+                    self.pos += 1;
+                    continue;
+                }
+                '-' if on => {
+                    on = false;
+                    self.pos += 1;
+                    continue;
+                }
+                'U' | 'R' | 'n' | 'J' => {
+                    return Err(self.unsupported(&format!("the `{}` flag", c)));
+                }
+                _ => break,
+            };
+            *flag = on;
+            self.pos += 1;
+        }
+        Ok(self.pos > start)
+    }
+
+    fn skip_verbose(&mut self) {
+        if !self.mode.verbose {
+            return;
+        }
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '#' {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Vec<AstNode>, ImportError> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.sequence()?);
+        }
+        if alternatives.len() == 1 {
+            Ok(alternatives.pop().unwrap())
+        } else {
+            Ok(vec![AstNode::Alternation(alternatives)])
+        }
+    }
+
+    fn sequence(&mut self) -> Result<Vec<AstNode>, ImportError> {
+        let mut nodes = vec![];
+        loop {
+            self.skip_verbose();
+            match self.peek() {
+                None | Some('|') | Some(')') => return Ok(nodes),
+                Some('*' | '+' | '?') => return Err(self.invalid("nothing to repeat")),
+                _ => {}
+            }
+            self.construct = self.pos;
+            let atoms = self.atom()?;
+            self.skip_verbose();
+            self.construct = self.pos;
+            match self.quantifier(atoms.last())? {
+                Some(quantified) => {
+                    nodes.extend(atoms.into_iter().rev().skip(1).rev());
+                    nodes.push(quantified);
+                }
+                None => nodes.extend(atoms),
+            }
+        }
+    }
+
+    // Reads a quantifier for `operand`, if one follows
+    fn quantifier(&mut self, operand: Option<&AstNode>) -> Result<Option<AstNode>, ImportError> {
+        let braces = self.peek() == Some('{');
+        let (min, max, len) = match self.peek() {
+            Some('*') => (0, None, 1),
+            Some('+') => (1, None, 1),
+            Some('?') => (0, Some(1), 1),
+            Some('{') => match self.bounds() {
+                Some(bounds) => bounds,
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let Some(operand) = operand else {
+            return Err(self.invalid("nothing to repeat"));
+        };
+        self.pos += len;
+        if self.peek() == Some('+') {
+            return Err(self.unsupported("a possessive quantifier"));
+        }
+        let greedy = !self.eat('?');
+        let node = Box::new(operand.clone());
+        Ok(Some(match (braces, min, max) {
+            (false, 0, None) => AstNode::ZeroOrMore { node, greedy },
+            (false, 1, None) => AstNode::OneOrMore { node, greedy },
+            (false, _, _) => AstNode::Optional { node, greedy },
+            (true, min, Some(max)) if min == max => AstNode::Exact { node, count: min },
+            (true, min, max) => AstNode::Range {
+                node,
+                min,
+                max,
+                greedy,
+            },
+        }))
+    }
+
+    // Reads `{n}`, `{n,}`, `{,m}` or `{n,m}` without moving past it, returning the
+    // bounds and its length. Anything else isn't a quantifier, and the brace is a
+    // literal.
+    fn bounds(&self) -> Option<(usize, Option<usize>, usize)> {
+        let number = |i: &mut usize| {
+            let start = *i;
+            while self.peek_at(*i).is_some_and(|c| c.is_ascii_digit()) {
+                *i += 1;
+            }
+            let digits: String = (start..*i).filter_map(|j| self.peek_at(j)).collect();
+            digits.parse::<usize>().ok()
+        };
+        let mut i = 1;
+        let min = number(&mut i);
+        let max = if self.peek_at(i) == Some(',') {
+            i += 1;
+            number(&mut i)
+        } else {
+            Some(min?)
+        };
+        if self.peek_at(i) != Some('}') || (min.is_none() && max.is_none()) {
+            return None;
+        }
+        Some((min.unwrap_or(0), max, i + 1))
+    }
+
+    // Reads one atom. Flag groups and comments produce nothing, and `\Q...\E`
+    // produces a run of literals.
+    fn atom(&mut self) -> Result<Vec<AstNode>, ImportError> {
+        let c = self.next()?;
+        let node = match c {
+            '(' => return self.group(),
+            '[' => self.class()?,
+            '.' => self.dot(),
+            '^' => self.line_start(),
+            '$' => self.line_end(),
+            '\\' => return self.escape(),
+            c => self.literal(c)?,
+        };
+        Ok(vec![node])
+    }
+
+    fn group(&mut self) -> Result<Vec<AstNode>, ImportError> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return Err(self.invalid("groups nested too deeply"));
+        }
+        if self.peek() == Some('*') {
+            return Err(self.unsupported("a backtracking verb"));
+        }
+        if !self.eat('?') {
+            self.groups += 1;
+            let index = self.groups;
+            let nodes = self.group_body()?;
+            return Ok(vec![AstNode::Group {
+                nodes,
+                name: None,
+                capture: true,
+                index: Some(index),
+            }]);
+        }
+
+        if self.eat('#') {
+            while self.next()? != ')' {}
+            return Ok(vec![]);
+        }
+        if self.eat(':') {
+            return Ok(vec![self.non_capturing()?]);
+        }
+        for (opener, ahead, positive) in [
+            ("=", true, true),
+            ("!", true, false),
+            ("<=", false, true),
+            ("<!", false, false),
+        ] {
+            if self.eat_str(opener) {
+                let nodes = self.group_body()?;
+                return Ok(vec![if ahead {
+                    AstNode::LookAhead { nodes, positive }
+                } else {
+                    AstNode::LookBehind { nodes, positive }
+                }]);
+            }
+        }
+        if self.eat_str("P=") {
+            let name = self.name(')')?;
+            return Ok(vec![self.named_backref(&name)?]);
+        }
+        let close = if self.eat_str("P<") || self.eat('<') {
+            Some('>')
+        } else if self.eat('\'') {
+            Some('\'')
+        } else {
+            None
+        };
+        if let Some(close) = close {
+            let name = self.name(close)?;
+            self.groups += 1;
+            let index = self.groups;
+            self.names.push((name.clone(), index));
+            let nodes = self.group_body()?;
+            return Ok(vec![AstNode::Group {
+                nodes,
+                name: Some(name),
+                capture: true,
+                index: Some(index),
+            }]);
+        }
+
+        let saved = self.mode;
+        if self.flag_letters()? {
+            if self.eat(')') {
+                // Applies until the end of the enclosing group
+                return Ok(vec![]);
+            }
+            if self.eat(':') {
+                let group = self.non_capturing();
+                self.mode = saved;
+                return Ok(vec![group?]);
+            }
+        }
+        Err(match self.peek() {
+            Some('>') => self.unsupported("an atomic group"),
+            Some('|') => self.unsupported("a branch reset group"),
+            Some('(') => self.unsupported("a conditional"),
+            Some(c) if c == 'R' || c == '&' || c.is_ascii_digit() || c == '+' || c == '-' => {
+                self.unsupported("a subroutine call")
+            }
+            _ => self.invalid("unknown group syntax"),
+        })
+    }
+
+    fn non_capturing(&mut self) -> Result<AstNode, ImportError> {
+        Ok(AstNode::Group {
+            nodes: self.group_body()?,
+            name: None,
+            capture: false,
+            index: None,
+        })
+    }
+
+    // Reads the rest of a group up to its closing parenthesis. Flags set inside
+    // end with it.
+    fn group_body(&mut self) -> Result<Vec<AstNode>, ImportError> {
+        let saved = self.mode;
+        self.depth += 1;
+        let nodes = self.alternation();
+        self.depth -= 1;
+        self.mode = saved;
+        let nodes = nodes?;
+        self.expect(')')?;
+        Ok(nodes)
+    }
+
+    fn name(&mut self, close: char) -> Result<String, ImportError> {
+        let mut name = String::new();
+        loop {
+            match self.next()? {
+                c if c == close => break,
+                c if c.is_alphanumeric() || c == '_' => name.push(c),
+                _ => return Err(self.invalid("invalid group name")),
+            }
+        }
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(self.invalid("invalid group name"));
+        }
+        Ok(name)
+    }
+
+    fn named_backref(&self, name: &str) -> Result<AstNode, ImportError> {
+        let &(_, index) = self
+            .names
+            .iter()
+            .find(|(known, _)| known == name)
+            .ok_or_else(|| self.invalid(&format!("unknown group name `{}`", name)))?;
+        self.backref(index)
+    }
+
+    // Only groups 1 to 9 can be referred to here
+    fn backref(&self, index: usize) -> Result<AstNode, ImportError> {
+        if index > MAX_BACKREF {
+            return Err(self.unsupported(&format!("a backreference to group {}", index)));
+        }
+        Ok(AstNode::Backref(index))
+    }
+
+    fn escape(&mut self) -> Result<Vec<AstNode>, ImportError> {
+        let c = self.next()?;
+        let node = match c {
+            'd' => AstNode::CharClass(CharClass::Digit),
+            'D' => AstNode::CharClass(CharClass::NonDigit),
+            'w' => AstNode::CharClass(CharClass::Word),
+            'W' => AstNode::CharClass(CharClass::NonWord),
+            's' => AstNode::CharClass(CharClass::Whitespace),
+            'S' => AstNode::CharClass(CharClass::NonWhitespace),
+            'h' | 'H' => AstNode::CharClass(CharClass::Set {
+                chars: ranges(HORIZONTAL_SPACE),
+                negated: c == 'H',
+            }),
+            'p' | 'P' => AstNode::CharClass(self.property(c == 'P')?),
+            'b' if self.eat_str("{start}") => AstNode::StartWord,
+            'b' if self.eat_str("{end}") => AstNode::EndWord,
+            'b' if self.peek() == Some('{') => {
+                return Err(self.unsupported("this word boundary"));
+            }
+            'b' => AstNode::WordBoundary,
+            'B' => AstNode::LookAhead {
+                nodes: vec![AstNode::WordBoundary],
+                positive: false,
+            },
+            '<' => AstNode::StartWord,
+            '>' => AstNode::EndWord,
+            'A' if self.global.multiline => AstNode::StartText,
+            'A' => AstNode::StartAnchor,
+            'z' if self.global.multiline => AstNode::EndText,
+            'z' => AstNode::EndAnchor,
+            'Z' => final_newline_or_end(),
+            'K' => AstNode::SetMatchStart,
+            'Q' => {
+                let mut nodes = vec![];
+                while self.peek().is_some() && !self.eat_str("\\E") {
+                    let c = self.next()?;
+                    nodes.push(self.literal(c)?);
+                }
+                return Ok(nodes);
+            }
+            // A stray `\E` ends nothing
+            'E' => return Ok(vec![]),
+            '1'..='9' => {
+                let mut index = c.to_digit(10).unwrap() as usize;
+                while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+                    index = index * 10 + digit as usize;
+                    self.pos += 1;
+                }
+                self.backref(index)?
+            }
+            'g' => self.numbered_backref()?,
+            'k' => {
+                let close = match self.next()? {
+                    '<' => '>',
+                    '{' => '}',
+                    '\'' => '\'',
+                    _ => return Err(self.invalid("expected a group name")),
+                };
+                let name = self.name(close)?;
+                self.named_backref(&name)?
+            }
+            'G' => return Err(self.unsupported("`\\G`")),
+            'R' | 'X' | 'C' | 'N' => {
+                return Err(self.unsupported(&format!("`\\{}`", c)));
+            }
+            'v' => {
+                return Err(
+                    self.unsupported("`\\v`, which means different things in Rust and PCRE")
+                );
+            }
+            c => {
+                let c = self.escaped_char(c)?;
+                self.literal(c)?
+            }
+        };
+        Ok(vec![node])
+    }
+
+    // `\gN`, `\g{N}`, `\g{-N}` or `\g{name}`
+    fn numbered_backref(&mut self) -> Result<AstNode, ImportError> {
+        let braced = self.eat('{');
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if braced && c == '}' {
+                break;
+            }
+            if !(braced || c.is_ascii_digit() || (c == '-' && text.is_empty())) {
+                break;
+            }
+            text.push(c);
+            self.pos += 1;
+        }
+        if braced {
+            self.expect('}')?;
+        }
+        if let Some(relative) = text.strip_prefix('-') {
+            let back: usize = relative
+                .parse()
+                .map_err(|_| self.invalid("invalid backreference"))?;
+            return match (self.groups + 1).checked_sub(back) {
+                Some(index) if back > 0 && index > 0 => self.backref(index),
+                _ => Err(self.invalid("backreference to a group that doesn't exist")),
+            };
+        }
+        match text.parse() {
+            Ok(index) => self.backref(index),
+            Err(_) if braced && !text.is_empty() => self.named_backref(&text),
+            Err(_) => Err(self.invalid("invalid backreference")),
+        }
+    }
+
+    // Escapes that stand for one character, in and out of sets
+    fn escaped_char(&mut self, c: char) -> Result<char, ImportError> {
+        Ok(match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'f' => '\x0C',
+            'e' => '\x1B',
+            'a' => '\x07',
+            '0' => {
+                // Up to two more octal digits, as in `\012`
+                let mut code = 0;
+                for _ in 0..2 {
+                    match self.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            code = code * 8 + digit;
+                            self.pos += 1;
+                        }
+                        None => break,
+                    }
+                }
+                char::from_u32(code).unwrap()
+            }
+            'o' => {
+                self.expect('{')?;
+                self.code_point(8, '}')?
+            }
+            'x' if self.eat('{') => self.code_point(16, '}')?,
+            'x' => self.fixed_code_point(2)?,
+            'u' if self.eat('{') => self.code_point(16, '}')?,
+            'u' => self.fixed_code_point(4)?,
+            'U' => self.fixed_code_point(8)?,
+            'c' => {
+                let letter = self.next()?;
+                if !letter.is_ascii() {
+                    return Err(self.invalid("invalid control escape"));
+                }
+                char::from(letter.to_ascii_uppercase() as u8 ^ 0x40)
+            }
+            c if c.is_ascii_alphanumeric() => {
+                return Err(self.invalid(&format!("unknown escape `\\{}`", c)));
+            }
+            c => c,
+        })
+    }
+
+    fn code_point(&mut self, radix: u32, close: char) -> Result<char, ImportError> {
+        let mut digits = String::new();
+        loop {
+            match self.next()? {
+                c if c == close => break,
+                c => digits.push(c),
+            }
+        }
+        u32::from_str_radix(&digits, radix)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.invalid("invalid code point"))
+    }
+
+    fn fixed_code_point(&mut self, len: usize) -> Result<char, ImportError> {
+        let mut digits = String::new();
+        for _ in 0..len {
+            digits.push(self.next()?);
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.invalid("invalid code point"))
+    }
+
+    // `\pL`, `\p{Lu}` or `\p{^Lu}`, mapped onto the nearest class
+    fn property(&mut self, mut negated: bool) -> Result<CharClass, ImportError> {
+        let name = if self.eat('{') {
+            negated ^= self.eat('^');
+            let mut name = String::new();
+            loop {
+                match self.next()? {
+                    '}' => break,
+                    c => name.push(c),
+                }
+            }
+            name
+        } else {
+            self.next()?.to_string()
+        };
+        let normalized: String = name
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .flat_map(char::to_lowercase)
+            .collect();
+        let (class, complement) = match normalized.as_str() {
+            "ll" | "lowercaseletter" | "lowercase" | "lower" => {
+                (CharClass::Lowercase, CharClass::NonLowercase)
+            }
+            "lu" | "uppercaseletter" | "uppercase" | "upper" => {
+                (CharClass::Uppercase, CharClass::NonUppercase)
+            }
+            "nd" | "decimalnumber" | "digit" => (CharClass::Digit, CharClass::NonDigit),
+            "p" | "punctuation" | "punct" => (CharClass::Punctuation, CharClass::NonPunctuation),
+            "whitespace" | "wspace" | "space" | "xsp" | "xps" => {
+                (CharClass::Whitespace, CharClass::NonWhitespace)
+            }
+            "alnum" | "xan" => (CharClass::Alphanumeric, CharClass::NonAlphanumeric),
+            "xwd" => (CharClass::Word, CharClass::NonWord),
+            "any" => {
+                return Ok(CharClass::Set {
+                    chars: vec![],
+                    negated: !negated,
+                });
+            }
+            _ => return Err(self.unsupported(&format!("the property `{}`", name))),
+        };
+        Ok(if negated { complement } else { class })
+    }
+
+    // Reads a bracket expression after its `[`. Classes and collating elements of
+    // several characters inside it, like `[\w-]` or `[[.ch.]a]`, become an
+    // alternation, or a negative lookahead if the set is negated.
+    fn class(&mut self) -> Result<AstNode, ImportError> {
+        for (text, class) in EXPORTED_CLASSES {
+            if self.eat_str(text) {
+                return Ok(AstNode::CharClass(class.clone()));
+            }
+        }
+        let negated = self.eat('^');
+        let mut chars = vec![];
+        let mut classes = vec![];
+        let mut elements: Vec<String> = vec![];
+        let mut first = true;
+        loop {
+            let c = self.next().map_err(|_| self.invalid("unclosed set"))?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            if c == '[' && self.peek() == Some(':') {
+                chars.extend(self.posix_class()?);
+                continue;
+            }
+            if c == '&' && self.peek() == Some('&') {
+                return Err(self.unsupported("set intersection"));
+            }
+            let start = match c {
+                '[' if matches!(self.peek(), Some('.' | '=')) => {
+                    let (equivalence, element) = self.collating_element()?;
+                    match single_char(&element) {
+                        Some(c) if equivalence => {
+                            chars.extend(equivalents(c).map(|c| CharRange { start: c, end: c }));
+                            continue;
+                        }
+                        Some(c) => c,
+                        None => {
+                            elements.push(element);
+                            continue;
+                        }
+                    }
+                }
+                '\\' => match self.class_escape()? {
+                    ClassItem::Char(c) => c,
+                    ClassItem::Ranges(ranges) => {
+                        chars.extend(ranges);
+                        continue;
+                    }
+                    ClassItem::Class(class) => {
+                        classes.push(class);
+                        continue;
+                    }
+                },
+                c => c,
+            };
+            let end = if self.peek() == Some('-') && self.peek_at(1).is_some_and(|c| c != ']') {
+                self.pos += 1;
+                match self.next()? {
+                    '[' if matches!(self.peek(), Some('.' | '=')) => {
+                        match self.collating_element()? {
+                            (false, element) => single_char(&element),
+                            (true, _) => None,
+                        }
+                        .ok_or_else(|| self.invalid("invalid range in set"))?
+                    }
+                    '\\' => match self.class_escape()? {
+                        ClassItem::Char(c) => c,
+                        _ => return Err(self.invalid("invalid range in set")),
+                    },
+                    c => c,
+                }
+            } else {
+                start
+            };
+            if start > end {
+                return Err(self.invalid("invalid range in set"));
+            }
+            chars.push(CharRange { start, end });
+        }
+
+        if self.mode.ignore_case != self.global.ignore_case {
+            if !elements.is_empty() {
+                return Err(self.unsupported("a collating element in a set with other case rules"));
+            }
+            if !self.mode.ignore_case {
+                return Err(self.unsupported("a case-sensitive set in a case-insensitive pattern"));
+            }
+            chars = self.fold_ranges(chars)?;
+        }
+        let set = AstNode::CharClass(CharClass::Set {
+            chars: chars.clone(),
+            negated: false,
+        });
+        if classes.is_empty() && elements.is_empty() {
+            return Ok(AstNode::CharClass(CharClass::Set { chars, negated }));
+        }
+
+        // The longest collating element matches first, as in POSIX
+        elements.sort_by_key(|element| core::cmp::Reverse(element.chars().count()));
+        let mut alternatives: Vec<Vec<AstNode>> = elements
+            .iter()
+            .map(|element| element.chars().map(AstNode::Literal).collect())
+            .collect();
+        if !chars.is_empty() {
+            alternatives.push(vec![set]);
+        }
+        alternatives.extend(
+            classes
+                .into_iter()
+                .map(|class| vec![AstNode::CharClass(class)]),
+        );
+        let either = if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            vec![AstNode::Alternation(alternatives)]
+        };
+        let nodes = if negated {
+            vec![
+                AstNode::LookAhead {
+                    nodes: either,
+                    positive: false,
+                },
+                any_char(),
+            ]
+        } else {
+            either
+        };
+        Ok(AstNode::Group {
+            nodes,
+            name: None,
+            capture: false,
+            index: None,
+        })
+    }
+
+    // Adds the case variants of every character in the ranges
+    fn fold_ranges(&self, ranges: Vec<CharRange>) -> Result<Vec<CharRange>, ImportError> {
+        let mut folded = vec![];
+        for range in ranges {
+            if u32::from(range.end) - u32::from(range.start) >= MAX_FOLDED_RANGE {
+                return Err(self.unsupported("a wide case-insensitive set"));
+            }
+            for c in range.start..=range.end {
+                folded.extend(
+                    c.to_lowercase()
+                        .chain(c.to_uppercase())
+                        .filter(|&variant| variant < range.start || variant > range.end)
+                        .map(|variant| CharRange {
+                            start: variant,
+                            end: variant,
+                        }),
+                );
+            }
+            folded.push(range);
+        }
+        Ok(folded)
+    }
+
+    // Reads `[:name:]` after its `[`
+    fn posix_class(&mut self) -> Result<Vec<CharRange>, ImportError> {
+        self.pos += 1;
+        if self.peek() == Some('^') {
+            return Err(self.unsupported("a negated POSIX class"));
+        }
+        let mut name = String::new();
+        while !self.eat_str(":]") {
+            name.push(self.next()?);
+        }
+        let table: &[(char, char)] = match name.as_str() {
+            "alpha" => &[('A', 'Z'), ('a', 'z')],
+            "digit" => &[('0', '9')],
+            "alnum" => &[('0', '9'), ('A', 'Z'), ('a', 'z')],
+            "upper" => &[('A', 'Z')],
+            "lower" => &[('a', 'z')],
+            "space" => &[('\t', '\r'), (' ', ' ')],
+            "blank" => &[('\t', '\t'), (' ', ' ')],
+            "punct" => &[('!', '/'), (':', '@'), ('[', '`'), ('{', '~')],
+            "xdigit" => &[('0', '9'), ('A', 'F'), ('a', 'f')],
+            "word" => &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')],
+            "cntrl" => &[('\0', '\x1F'), ('\x7F', '\x7F')],
+            "print" => &[(' ', '~')],
+            "graph" => &[('!', '~')],
+            "ascii" => &[('\0', '\x7F')],
+            _ => return Err(self.invalid(&format!("unknown POSIX class `{}`", name))),
+        };
+        Ok(ranges(table))
+    }
+
+    // Reads `[.element.]` or `[=element=]` after its `[`, with POSIX names replaced by
+    // the character they name. Returns whether it's an equivalence class, and the
+    // element's text.
+    fn collating_element(&mut self) -> Result<(bool, String), ImportError> {
+        let equivalence = self.next()? == '=';
+        let close = if equivalence { "=]" } else { ".]" };
+        let mut element = String::new();
+        while !self.eat_str(close) {
+            let c = self
+                .next()
+                .map_err(|_| self.invalid("unclosed collating element"))?;
+            element.push(c);
+        }
+        if element.is_empty() {
+            return Err(self.invalid("empty collating element"));
+        }
+        Ok(match named(&element) {
+            Some(c) => (equivalence, c.to_string()),
+            None => (equivalence, element),
+        })
+    }
+
+    // Reads an escape inside a set: one character, or the members of a class
+    fn class_escape(&mut self) -> Result<ClassItem, ImportError> {
+        let c = self.next()?;
+        Ok(match c {
+            'd' => ClassItem::Ranges(ranges(&[('0', '9')])),
+            'h' => ClassItem::Ranges(ranges(HORIZONTAL_SPACE)),
+            'D' => ClassItem::Class(CharClass::NonDigit),
+            'w' => ClassItem::Class(CharClass::Word),
+            'W' => ClassItem::Class(CharClass::NonWord),
+            's' => ClassItem::Class(CharClass::Whitespace),
+            'S' => ClassItem::Class(CharClass::NonWhitespace),
+            'H' => ClassItem::Class(CharClass::Set {
+                chars: ranges(HORIZONTAL_SPACE),
+                negated: true,
+            }),
+            'p' | 'P' => ClassItem::Class(self.property(c == 'P')?),
+            'b' => ClassItem::Char('\x08'),
+            'v' => {
+                return Err(
+                    self.unsupported("`\\v`, which means different things in Rust and PCRE")
+                );
+            }
+            c => ClassItem::Char(self.escaped_char(c)?),
+        })
+    }
+
+    // A literal character, spelled out as a set if only this part of the pattern
+    // ignores case
+    fn literal(&self, c: char) -> Result<AstNode, ImportError> {
+        let mut variants: Vec<char> = core::iter::once(c)
+            .chain(c.to_lowercase())
+            .chain(c.to_uppercase())
+            .collect();
+        variants.sort_unstable();
+        variants.dedup();
+        if self.mode.ignore_case == self.global.ignore_case || variants.len() == 1 {
+            return Ok(AstNode::Literal(c));
+        }
+        if !self.mode.ignore_case {
+            return Err(self.unsupported("a case-sensitive letter in a case-insensitive pattern"));
+        }
+        Ok(AstNode::CharClass(CharClass::Set {
+            chars: variants
+                .into_iter()
+                .map(|c| CharRange { start: c, end: c })
+                .collect(),
+            negated: false,
+        }))
+    }
+
+    fn dot(&self) -> AstNode {
+        match (self.mode.dotall, self.global.dotall) {
+            (local, global) if local == global => AstNode::CharClass(CharClass::Dot),
+            (true, _) => any_char(),
+            (false, _) => AstNode::CharClass(CharClass::Set {
+                chars: vec![CharRange {
+                    start: '\n',
+                    end: '\n',
+                }],
+                negated: true,
+            }),
+        }
+    }
+
+    fn line_start(&self) -> AstNode {
+        match (self.mode.multiline, self.global.multiline) {
+            (local, global) if local == global => AstNode::StartAnchor,
+            (true, _) => AstNode::LookBehind {
+                nodes: vec![not_newline()],
+                positive: false,
+            },
+            (false, _) => AstNode::StartText,
+        }
+    }
+
+    fn line_end(&self) -> AstNode {
+        match (self.mode.multiline, self.global.multiline) {
+            (false, _) => final_newline_or_end(),
+            (true, true) => AstNode::EndAnchor,
+            (true, false) => AstNode::LookAhead {
+                nodes: vec![not_newline()],
+                positive: false,
+            },
+        }
+    }
+}
+
+// What an escape inside a set stands for
+enum ClassItem {
+    Char(char),
+    Ranges(Vec<CharRange>),
+    Class(CharClass),
+}
+
+fn ranges(table: &[(char, char)]) -> Vec<CharRange> {
+    table
+        .iter()
+        .map(|&(start, end)| CharRange { start, end })
+        .collect()
+}
+
+fn any_char() -> AstNode {
+    AstNode::CharClass(CharClass::Set {
+        chars: vec![],
+        negated: true,
+    })
+}
+
+// The end, or before a newline that ends the text
+fn final_newline_or_end() -> AstNode {
+    AstNode::LookAhead {
+        nodes: vec![
+            AstNode::Optional {
+                node: Box::new(AstNode::Literal('\n')),
+                greedy: true,
+            },
+            AstNode::EndText,
+        ],
+        positive: true,
+    }
+}
+
+fn not_newline() -> AstNode {
+    AstNode::CharClass(CharClass::Set {
+        chars: vec![CharRange {
+            start: '\n',
+            end: '\n',
+        }],
+        negated: true,
+    })
+}
+
+// The character a collating element consists of, if it's a single one
+fn single_char(element: &str) -> Option<char> {
+    let mut chars = element.chars();
+    chars.next().filter(|_| chars.next().is_none())
+}
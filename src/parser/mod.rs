@@ -1,19 +1,78 @@
-use crate::flags::Flags;
-use std::fmt;
+mod collate;
+
+mod display;
+pub use display::to_pattern;
+
+mod dot;
+pub(crate) use dot::DotGraph;
+pub use dot::to_dot;
+
+mod explain;
+pub use explain::explain;
+
+mod export;
+pub use export::{to_pcre, to_rust_regex};
+
+mod highlight;
+pub use highlight::{Highlight, HighlightKind, highlight, highlight_rift};
+
+mod import;
+pub use import::from_pcre;
+
+#[cfg(feature = "unicode-names")]
+mod names;
+
+mod span;
+pub use span::{Span, SpanMap};
+
+mod visit;
+pub use visit::*;
+
+use crate::flags::{DotMode, Flags};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
 
 /// Represents a node in the Abstract Syntax Tree (AST) of a regular expression.
 #[derive(Debug, Clone, PartialEq)]
 pub enum AstNode {
     /// A literal character match.
     Literal(char),
+    /// A run of literal characters, produced by the optimizer from adjacent `Literal` nodes.
+    LiteralString(String),
 
     /// A character class (e.g., `\d`, `[a-z]`, `.`).
     CharClass(CharClass),
+    /// A whole extended grapheme cluster, which `.` parses to under
+    /// [`DotMode::Grapheme`](crate::DotMode::Grapheme).
+    Grapheme,
 
     /// Start of string (or line in multiline mode) anchor `^`.
     StartAnchor,
     /// End of string (or line in multiline mode) anchor `$`.
     EndAnchor,
+    /// Start of the text `\%^`, even in multiline mode.
+    StartText,
+    /// End of the text `\%$`, even in multiline mode.
+    EndText,
+    /// The position is on the given line, counting from 1: `\%23l`. With `Less` or
+    /// `Greater` it's on an earlier `\%<23l` or later `\%>23l` line instead. Lines
+    /// end at each `\n`.
+    Line(usize, Ordering),
+    /// The position is at the given byte column of its line, counting from 1:
+    /// `\%23c`, or before `\%<23c` or after `\%>23c` it.
+    Column(usize, Ordering),
+    /// The cursor `\%#` of the [`Marks`](crate::Marks) the search was given.
+    Cursor,
+    /// A position inside the visual area `\%V` of the search's `Marks`.
+    Visual,
+    /// The position of a named mark `\%'m` of the search's `Marks`, or one before
+    /// `\%<'m` or after `\%>'m` it.
+    Mark(char, Ordering),
     /// Word boundary anchor `\b`.
     WordBoundary,
     /// Start of word anchor `\<`.
@@ -90,6 +149,9 @@ pub enum AstNode {
         positive: bool,
     },
     /// Lookbehind assertion `(?<=...)` or `(?<!...)`.
+    ///
+    /// The body can match text of any length. It's tried from the nearest start
+    /// back, and has to end right at the assertion.
     LookBehind {
         /// The sequence of nodes to check behind.
         nodes: Vec<AstNode>,
@@ -167,6 +229,14 @@ pub struct CharRange {
     pub end: char,
 }
 
+/// How deeply groups, lookarounds, quantifiers and alternations may be nested. Every
+/// compile pass recurses through the AST, so this keeps pathological patterns from
+/// overflowing the stack.
+pub const MAX_NESTING_DEPTH: usize = 128;
+
+// Backreferences are a single digit, `\1` to `\9`, as in Vim
+pub(crate) const MAX_BACKREF: usize = 9;
+
 /// The recursive descent parser for the regex pattern.
 #[derive(Debug, Clone)]
 pub struct Parser {
@@ -174,6 +244,10 @@ pub struct Parser {
     pos: usize,
     flags: Flags,
     group_count: usize,
+    depth: usize,
+    nest_limit: usize,
+    /// The character range of each node, in the order nodes are finished.
+    finished: Vec<(usize, usize)>,
 }
 
 /// Errors that can occur during parsing.
@@ -185,11 +259,29 @@ pub enum ParseError {
     UnmatchedParen,
     InvalidGroupName(String),
     InvalidEscape(char),
-    InvalidCharClass,
+    /// `\c` followed by something other than a letter.
+    InvalidControlEscape(char),
+    /// `\N{NAME}` with a name no character has, or any name without the
+    /// `unicode-names` feature.
+    UnknownCharName(String),
+    /// A malformed bracket expression, like the reversed range `[z-a]`: what's
+    /// wrong, and the byte range in the pattern it's about.
+    InvalidCharClass {
+        reason: String,
+        span: Span,
+    },
     DuplicateGroupName(String),
     InvalidBackref(usize),
+    /// A `\%l` or `\%c` atom without its number, or with 0.
     InvalidLineNumber(String),
     InvalidGroup(String),
+    NestingTooDeep,
+    /// Syntax from another regex dialect that Rift writes differently, like `(?=`
+    /// for a lookahead: what was written, and the Rift equivalent.
+    ForeignSyntax {
+        found: &'static str,
+        rift: &'static str,
+    },
 }
 
 impl fmt::Display for ParseError {
@@ -209,8 +301,14 @@ impl fmt::Display for ParseError {
             ParseError::InvalidEscape(c) => {
                 write!(f, "Invalid escape sequence: \\{}", c)
             }
-            ParseError::InvalidCharClass => {
-                write!(f, "Invalid character class")
+            ParseError::InvalidControlEscape(c) => {
+                write!(f, "Invalid control escape: \\c{} (expected a letter)", c)
+            }
+            ParseError::UnknownCharName(name) => {
+                write!(f, "Unknown character name: \\N{{{}}}", name)
+            }
+            ParseError::InvalidCharClass { reason, .. } => {
+                write!(f, "Invalid character class: {}", reason)
             }
             ParseError::DuplicateGroupName(s) => {
                 write!(f, "Duplicate group name: {}", s)
@@ -224,11 +322,17 @@ impl fmt::Display for ParseError {
             ParseError::InvalidGroup(s) => {
                 write!(f, "Invalid group syntax: {}", s)
             }
+            ParseError::NestingTooDeep => {
+                write!(f, "Groups nested more than {} deep", MAX_NESTING_DEPTH)
+            }
+            ParseError::ForeignSyntax { found, rift } => {
+                write!(f, "`{}` isn't Rift syntax; did you mean `{}`?", found, rift)
+            }
         }
     }
 }
 
-impl std::error::Error for ParseError {}
+impl core::error::Error for ParseError {}
 
 impl Parser {
     /// Creates a new parser for the given pattern.
@@ -238,16 +342,77 @@ impl Parser {
             pos: 0,
             flags,
             group_count: 0,
+            depth: 0,
+            nest_limit: MAX_NESTING_DEPTH,
+            finished: vec![],
         }
     }
 
+    // Lowers how deeply groups may be nested, so parsing stops as soon as a
+    // compile limit is passed
+    pub(crate) fn set_nest_limit(&mut self, limit: usize) {
+        self.nest_limit = limit.min(MAX_NESTING_DEPTH);
+    }
+
     /// Parses the pattern into an AST.
     pub fn parse(&mut self) -> Result<Vec<AstNode>, ParseError> {
         self.parse_alternation()
     }
 
+    /// Parses the pattern into an AST, along with where in the pattern each node
+    /// was written.
+    pub fn parse_with_spans(&mut self) -> Result<(Vec<AstNode>, SpanMap), ParseError> {
+        self.finished.clear();
+        let nodes = self.parse()?;
+        let mut offsets = Vec::with_capacity(self.input.len() + 1);
+        offsets.push(0);
+        for c in &self.input {
+            offsets.push(offsets[offsets.len() - 1] + c.len_utf8());
+        }
+        let finished: Vec<_> = self
+            .finished
+            .iter()
+            .map(|&(start, end)| Span {
+                start: offsets[start],
+                end: offsets[end],
+            })
+            .collect();
+        let spans = SpanMap::new(&nodes, &finished);
+        Ok((nodes, spans))
+    }
+
+    /// Where in the pattern a failed parse went wrong: the foreign syntax for
+    /// [`ParseError::ForeignSyntax`], the span an [`ParseError::InvalidCharClass`]
+    /// carries, otherwise the character the parser stopped at, or an empty range at
+    /// the end of the pattern.
+    pub(crate) fn error_span(&self, err: &ParseError) -> Span {
+        let pos = self.pos.min(self.input.len());
+        let (start, end) = match err {
+            ParseError::InvalidCharClass { span, .. } => return *span,
+            // The parser stops inside the foreign syntax, so look for where it starts
+            ParseError::ForeignSyntax { found, .. } => {
+                let found: Vec<char> = found.chars().collect();
+                (pos.saturating_sub(found.len())..=pos)
+                    .find(|&start| self.input[start..].starts_with(&found))
+                    .map_or((pos, pos), |start| (start, start + found.len()))
+            }
+            _ => (pos, (pos + 1).min(self.input.len())),
+        };
+        self.byte_span(start, end)
+    }
+
+    // Converts a range of character positions in the input to byte offsets
+    fn byte_span(&self, start: usize, end: usize) -> Span {
+        let offset = |chars: usize| self.input[..chars].iter().map(|c| c.len_utf8()).sum();
+        Span {
+            start: offset(start),
+            end: offset(end),
+        }
+    }
+
     // Top level: handle |
     fn parse_alternation(&mut self) -> Result<Vec<AstNode>, ParseError> {
+        let start = self.pos;
         let mut alternatives = vec![];
         let mut current = self.parse_sequence()?;
 
@@ -261,27 +426,62 @@ impl Parser {
         if alternatives.len() == 1 {
             Ok(alternatives.pop().unwrap())
         } else {
+            self.finished.push((start, self.pos));
             Ok(vec![AstNode::Alternation(alternatives)])
         }
     }
 
-    fn skip_whitespace_and_comments(&mut self) {
-        if !self.flags.verbose {
-            return;
-        }
-        while self.pos < self.input.len() {
-            let ch = self.input[self.pos];
-            if ch.is_whitespace() {
+    // Skips what matches nothing between atoms and before quantifiers: `(?#...)`
+    // comments and `(?x)` or `(?-x)`, which switch verbose mode for the rest of the
+    // enclosing group, and in verbose mode whitespace, `#` comments and a `\` ending
+    // a line, which continues the pattern on the next one. It's only called there,
+    // so whitespace inside a set or after any other `\` is still matched.
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), ParseError> {
+        while let Some(&ch) = self.current() {
+            let verbose = self.flags.verbose;
+            // The `\\` of a continued line goes here, and its newline next time round
+            if verbose && (ch.is_whitespace() || (ch == '\\' && self.line_continues())) {
                 self.pos += 1;
-            } else if ch == '#' {
+            } else if verbose && ch == '#' {
                 self.pos += 1;
                 while self.pos < self.input.len() && self.input[self.pos] != '\n' {
                     self.pos += 1;
                 }
+            } else if ch == '('
+                && self.peek_ahead(1) == Some(&'?')
+                && self.peek_ahead(2) == Some(&'#')
+            {
+                self.pos += 3;
+                // Parentheses inside the comment have to balance
+                let mut depth = 0;
+                loop {
+                    match self.consume()? {
+                        '(' => depth += 1,
+                        ')' if depth == 0 => break,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                }
+            } else if let Some((verbose, len)) = self.inline_verbose(')') {
+                self.flags.verbose = verbose;
+                self.pos += len;
             } else {
                 break;
             }
         }
+        Ok(())
+    }
+
+    // Reads `(?x` or `(?-x` followed by `end` at the current position, returning
+    // whether it switches verbose mode on and its length
+    fn inline_verbose(&self, end: char) -> Option<(bool, usize)> {
+        if self.current() != Some(&'(') || self.peek_ahead(1) != Some(&'?') {
+            return None;
+        }
+        let on = self.peek_ahead(2) != Some(&'-');
+        let letter = if on { 2 } else { 3 };
+        (self.peek_ahead(letter) == Some(&'x') && self.peek_ahead(letter + 1) == Some(&end))
+            .then_some((on, letter + 2))
     }
 
     // Parse sequence of atoms with quantifiers
@@ -289,13 +489,21 @@ impl Parser {
         let mut nodes = vec![];
 
         loop {
-            self.skip_whitespace_and_comments();
+            self.skip_whitespace_and_comments()?;
             match self.current() {
                 Some(&'|') | Some(&')') | None => break,
                 _ => {
+                    let start = self.pos;
                     let node = self.parse_atom()?;
-                    let node = self.apply_quantifier(node)?;
-                    nodes.push(node);
+                    self.finished.push((start, self.pos));
+                    self.skip_whitespace_and_comments()?;
+                    if matches!(self.current(), Some('*' | '+' | '?' | '{')) {
+                        let node = self.apply_quantifier(node)?;
+                        self.finished.push((start, self.pos));
+                        nodes.push(node);
+                    } else {
+                        nodes.push(node);
+                    }
                 }
             }
         }
@@ -309,7 +517,10 @@ impl Parser {
             None => Err(ParseError::UnexpectedEof),
             Some(&'.') => {
                 self.consume()?;
-                Ok(AstNode::CharClass(CharClass::Dot))
+                Ok(match self.flags.dot {
+                    DotMode::Grapheme => AstNode::Grapheme,
+                    _ => AstNode::CharClass(CharClass::Dot),
+                })
             }
             Some(&'^') => {
                 self.consume()?;
@@ -441,11 +652,45 @@ impl Parser {
                     _ => Err(ParseError::InvalidEscape('z')),
                 }
             }
-            Some(&c @ '0'..='9') => {
+            // Groups count from 1, so `\0` is free to be NUL
+            Some(&'0') => {
+                self.consume()?;
+                Ok(AstNode::Literal('\0'))
+            }
+            Some(&c @ '1'..='9') => {
                 self.consume()?;
                 let digit = c.to_digit(10).unwrap() as usize;
                 Ok(AstNode::Backref(digit))
             }
+            Some(&'c') => {
+                self.consume()?;
+                Ok(AstNode::Literal(self.parse_control_letter()?))
+            }
+            // Vim's `\%` atoms; otherwise `\%` is a literal `%`
+            Some(&'%') if self.peek_ahead(1) == Some(&'^') => {
+                self.pos += 2;
+                Ok(AstNode::StartText)
+            }
+            Some(&'%') if self.peek_ahead(1) == Some(&'$') => {
+                self.pos += 2;
+                Ok(AstNode::EndText)
+            }
+            Some(&'%') if self.peek_ahead(1) == Some(&'#') => {
+                self.pos += 2;
+                Ok(AstNode::Cursor)
+            }
+            Some(&'%') if self.peek_ahead(1) == Some(&'V') => {
+                self.pos += 2;
+                Ok(AstNode::Visual)
+            }
+            Some(&'%') if matches!(self.peek_ahead(1), Some('<' | '>' | '\'' | '0'..='9')) => {
+                self.consume()?;
+                self.parse_position_atom()
+            }
+            Some(&'N') if self.peek_ahead(1) == Some(&'{') => {
+                self.consume()?;
+                Ok(AstNode::Literal(self.parse_char_name()?))
+            }
             Some(&'n') => {
                 self.consume()?;
                 Ok(AstNode::Literal('\n'))
@@ -470,6 +715,11 @@ impl Parser {
                 self.consume()?;
                 Ok(AstNode::Literal('\\'))
             }
+            // PCRE's way to reset the match start
+            Some(&'K') => Err(ParseError::ForeignSyntax {
+                found: r"\K",
+                rift: r"\zs",
+            }),
             Some(&ch) => {
                 self.consume()?;
                 // Literal escape (e.g. \*, \[)
@@ -482,6 +732,19 @@ impl Parser {
     fn parse_group(&mut self) -> Result<AstNode, ParseError> {
         self.consume()?; // consume (
 
+        if self.depth == self.nest_limit {
+            return Err(ParseError::NestingTooDeep);
+        }
+        self.depth += 1;
+        // `(?x)` inside the group ends with it
+        let verbose = self.flags.verbose;
+        let group = self.parse_group_contents();
+        self.flags.verbose = verbose;
+        self.depth -= 1;
+        group
+    }
+
+    fn parse_group_contents(&mut self) -> Result<AstNode, ParseError> {
         if self.current() == Some(&'?') {
             self.consume()?;
             self.parse_extended_group()
@@ -501,6 +764,28 @@ impl Parser {
     }
 
     fn parse_extended_group(&mut self) -> Result<AstNode, ParseError> {
+        // Catch the spellings other dialects use before reporting an unknown extension
+        let foreign = match (self.current(), self.peek_ahead(1)) {
+            (Some(&'='), _) => Some(("(?=", "(?>=")),
+            (Some(&'!'), _) => Some(("(?!", "(?>!")),
+            (Some(&'P'), Some(&'<')) => Some(("(?P<", "(?<")),
+            _ => None,
+        };
+        if let Some((found, rift)) = foreign {
+            return Err(ParseError::ForeignSyntax { found, rift });
+        }
+
+        // `(?x:...)` and `(?-x:...)` switch verbose mode for the group alone
+        let scoped = match (self.current(), self.peek_ahead(1), self.peek_ahead(2)) {
+            (Some(&'x'), Some(&':'), _) => Some(true),
+            (Some(&'-'), Some(&'x'), Some(&':')) => Some(false),
+            _ => None,
+        };
+        if let Some(verbose) = scoped {
+            self.flags.verbose = verbose;
+            self.pos += if verbose { 1 } else { 2 };
+        }
+
         match self.current() {
             Some(&':') => {
                 self.consume()?;
@@ -583,6 +868,9 @@ impl Parser {
                     )),
                 }
             }
+            Some(&c) if c.is_ascii_alphabetic() || c == '-' => Err(ParseError::InvalidGroup(
+                "only the `x` flag can be set inside a pattern".to_string(),
+            )),
             _ => Err(ParseError::InvalidGroup("Unknown extension ?".to_string())),
         }
     }
@@ -628,34 +916,39 @@ impl Parser {
                     self.consume()?;
                     break;
                 }
-                Some(&'\\') => {
-                    // Escaped char in class
-                    self.consume()?;
-                    match self.current() {
-                        Some(&c) => {
-                            self.consume()?;
-                            ranges.push(CharRange { start: c, end: c });
-                        }
-                        None => return Err(ParseError::UnexpectedEof),
+                Some(_) => {
+                    if let Some(end) = self.posix_bracket() {
+                        let written: String = self.input[self.pos..end].iter().collect();
+                        return Err(ParseError::InvalidCharClass {
+                            reason: format!(
+                                "POSIX brackets like `{}` aren't supported; `Regex::from_pcre` reads them",
+                                written
+                            ),
+                            span: self.byte_span(self.pos, end),
+                        });
                     }
-                }
-                Some(&c) => {
-                    self.consume()?;
-                    // Check for range
+                    let item = self.pos;
+                    let start = self.parse_class_char()?;
+                    // A `-` after a character makes a range, unless it ends the set.
+                    // One that starts the set or follows a range has nothing to range
+                    // from, so it stands for itself, though it can start a range of
+                    // its own as in `[--/]`
                     if self.current() == Some(&'-')
                         && self.peek_ahead(1).is_some()
                         && self.peek_ahead(1) != Some(&']')
                     {
                         self.consume()?;
-                        match self.current() {
-                            Some(&end) => {
-                                self.consume()?;
-                                ranges.push(CharRange { start: c, end });
-                            }
-                            None => return Err(ParseError::UnexpectedEof),
+                        let end = self.parse_class_char()?;
+                        if start > end {
+                            let written: String = self.input[item..self.pos].iter().collect();
+                            return Err(ParseError::InvalidCharClass {
+                                reason: format!("the range `{}` is reversed", written),
+                                span: self.byte_span(item, self.pos),
+                            });
                         }
+                        ranges.push(CharRange { start, end });
                     } else {
-                        ranges.push(CharRange { start: c, end: c });
+                        ranges.push(CharRange { start, end: start });
                     }
                 }
             }
@@ -667,16 +960,116 @@ impl Parser {
         }))
     }
 
+    // Where a POSIX `[:name:]`, `[=e=]` or `[.ch.]` starting at the current `[` ends,
+    // if it's one. Rift has no syntax for them, and reading one as plain characters
+    // would quietly match something else
+    fn posix_bracket(&self) -> Option<usize> {
+        if self.current() != Some(&'[') {
+            return None;
+        }
+        let kind = *self.peek_ahead(1)?;
+        if !matches!(kind, ':' | '=' | '.') {
+            return None;
+        }
+        let body = self.pos + 2;
+        (body + 1..self.input.len())
+            .find(|&i| self.input[i] == kind && self.input.get(i + 1) == Some(&']'))
+            .map(|i| i + 2)
+    }
+
+    // Parse one character of a bracket expression, interpreting control escapes,
+    // \0, \x{HEX} and \N{NAME}; any other escaped character stands for itself
+    fn parse_class_char(&mut self) -> Result<char, ParseError> {
+        let c = *self.current().ok_or(ParseError::UnexpectedEof)?;
+        self.consume()?;
+        if c != '\\' {
+            return Ok(c);
+        }
+        let escaped = *self.current().ok_or(ParseError::UnexpectedEof)?;
+        self.consume()?;
+        Ok(match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'f' => '\x0C',
+            'v' => '\x0B',
+            '0' => '\0',
+            'c' => self.parse_control_letter()?,
+            'x' if self.current() == Some(&'{') => self.parse_hex_code_point()?,
+            'N' if self.current() == Some(&'{') => self.parse_char_name()?,
+            _ => escaped,
+        })
+    }
+
+    // Parse the letter after \c, naming the control character typed as Ctrl and
+    // that letter, in either case: \cA is U+0001 and \cZ is U+001A
+    fn parse_control_letter(&mut self) -> Result<char, ParseError> {
+        match self.current() {
+            Some(&c) if c.is_ascii_alphabetic() => {
+                self.consume()?;
+                Ok(char::from(c.to_ascii_uppercase() as u8 - b'@'))
+            }
+            Some(&c) => Err(ParseError::InvalidControlEscape(c)),
+            None => Err(ParseError::UnexpectedEof),
+        }
+    }
+
+    // Parse {HEX} after \x, naming a code point
+    fn parse_hex_code_point(&mut self) -> Result<char, ParseError> {
+        self.consume()?; // consume {
+        let mut hex = String::new();
+        loop {
+            match self.current() {
+                Some(&'}') => {
+                    self.consume()?;
+                    break;
+                }
+                Some(&c) if c.is_ascii_hexdigit() && hex.len() < 6 => {
+                    hex.push(c);
+                    self.consume()?;
+                }
+                Some(_) => return Err(ParseError::InvalidEscape('x')),
+                None => return Err(ParseError::UnexpectedEof),
+            }
+        }
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(ParseError::InvalidEscape('x'))
+    }
+
+    // Parse {NAME} after \N, naming a character by its Unicode name. The names
+    // are only known with the `unicode-names` feature.
+    fn parse_char_name(&mut self) -> Result<char, ParseError> {
+        self.consume()?; // consume {
+        let mut name = String::new();
+        loop {
+            match self.current() {
+                Some(&'}') => {
+                    self.consume()?;
+                    break;
+                }
+                Some(&c) => {
+                    name.push(c);
+                    self.consume()?;
+                }
+                None => return Err(ParseError::UnexpectedEof),
+            }
+        }
+        #[cfg(feature = "unicode-names")]
+        if let Some(c) = names::lookup(&name) {
+            return Ok(c);
+        }
+        Err(ParseError::UnknownCharName(name))
+    }
+
     // Apply quantifiers: *, +, ?, {n}, {n,m}, etc
     fn apply_quantifier(&mut self, node: AstNode) -> Result<AstNode, ParseError> {
-        self.skip_whitespace_and_comments();
+        self.skip_whitespace_and_comments()?;
         match self.current() {
             Some(&'*') => {
                 self.consume()?;
-                let greedy = self.current() != Some(&'?');
-                if !greedy {
-                    self.consume()?;
-                }
+                let greedy = self.greediness()?;
                 Ok(AstNode::ZeroOrMore {
                     node: Box::new(node),
                     greedy,
@@ -684,10 +1077,7 @@ impl Parser {
             }
             Some(&'+') => {
                 self.consume()?;
-                let greedy = self.current() != Some(&'?');
-                if !greedy {
-                    self.consume()?;
-                }
+                let greedy = self.greediness()?;
                 Ok(AstNode::OneOrMore {
                     node: Box::new(node),
                     greedy,
@@ -695,10 +1085,7 @@ impl Parser {
             }
             Some(&'?') => {
                 self.consume()?;
-                let greedy = self.current() != Some(&'?');
-                if !greedy {
-                    self.consume()?;
-                }
+                let greedy = self.greediness()?;
                 Ok(AstNode::Optional {
                     node: Box::new(node),
                     greedy,
@@ -709,15 +1096,40 @@ impl Parser {
         }
     }
 
-    // Parse {n}, {n,}, {n,m}, {,m}
+    // Consumes the `?` after a quantifier, if any, and returns whether the quantifier
+    // is greedy. The `U` flag swaps the meaning of the `?`.
+    fn greediness(&mut self) -> Result<bool, ParseError> {
+        let marked = self.current() == Some(&'?');
+        if marked {
+            self.consume()?;
+        }
+        Ok(marked == self.flags.ungreedy)
+    }
+
+    // Parse {n}, {n,}, {n,m}, {,m}, and Vim's lazy {-}, {-n,m} and the like
     fn parse_bounded_quantifier(&mut self, node: AstNode) -> Result<AstNode, ParseError> {
         self.consume()?; // consume {
 
+        // A `-` marks the repeat lazy, as a `?` after the `}` does
+        let lazy = self.current() == Some(&'-');
+        if lazy {
+            self.consume()?;
+            if self.current() == Some(&'}') {
+                self.consume()?;
+                return Ok(AstNode::Range {
+                    node: Box::new(node),
+                    min: 0,
+                    max: None,
+                    greedy: self.flags.ungreedy,
+                });
+            }
+        }
+
         // Parse min
         let min = if self.current() == Some(&',') {
             0
         } else {
-            self.parse_number()?
+            self.parse_number(ParseError::InvalidQuantifier)?
         };
 
         match self.current() {
@@ -727,7 +1139,7 @@ impl Parser {
                 let max = if self.current() == Some(&'}') {
                     None
                 } else {
-                    Some(self.parse_number()?)
+                    Some(self.parse_number(ParseError::InvalidQuantifier)?)
                 };
 
                 if self.current() != Some(&'}') {
@@ -735,10 +1147,11 @@ impl Parser {
                 }
                 self.consume()?;
 
-                let greedy = self.current() != Some(&'?');
-                if !greedy {
-                    self.consume()?;
-                }
+                let greedy = if lazy {
+                    self.flags.ungreedy
+                } else {
+                    self.greediness()?
+                };
 
                 Ok(AstNode::Range {
                     node: Box::new(node),
@@ -760,19 +1173,57 @@ impl Parser {
         }
     }
 
-    // Helper: parse a decimal number
-    fn parse_number(&mut self) -> Result<usize, ParseError> {
-        let mut num = 0;
+    // Parses the rest of `\%23l`, `\%23c` or `\%'m` after the `\%`, with an
+    // optional `<` or `>` before the number or mark
+    fn parse_position_atom(&mut self) -> Result<AstNode, ParseError> {
+        let ordering = match self.current() {
+            Some(&'<') => Ordering::Less,
+            Some(&'>') => Ordering::Greater,
+            _ => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            self.consume()?;
+        }
+        if self.current() == Some(&'\'') {
+            self.consume()?;
+            return Ok(AstNode::Mark(self.consume()?, ordering));
+        }
+        let number = self.parse_number(ParseError::InvalidLineNumber)?;
+        let node = match self.current() {
+            Some(&'l') => AstNode::Line(number, ordering),
+            Some(&'c') => AstNode::Column(number, ordering),
+            _ => {
+                return Err(ParseError::InvalidLineNumber(
+                    "expected 'l' or 'c' after the number".to_string(),
+                ));
+            }
+        };
+        self.consume()?;
+        if number == 0 {
+            return Err(ParseError::InvalidLineNumber(
+                "lines and columns count from 1".to_string(),
+            ));
+        }
+        Ok(node)
+    }
+
+    // Helper: parse a decimal number, reporting a missing or overflowing one with
+    // the caller's error
+    fn parse_number(&mut self, error: fn(String) -> ParseError) -> Result<usize, ParseError> {
+        let mut num: usize = 0;
         let mut found = false;
 
         while let Some(&c @ '0'..='9') = self.current() {
             found = true;
-            num = num * 10 + (c.to_digit(10).unwrap() as usize);
+            num = num
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(c.to_digit(10).unwrap() as usize))
+                .ok_or_else(|| error("number too large".to_string()))?;
             self.consume()?;
         }
 
         if !found {
-            return Err(ParseError::InvalidLineNumber("expected digits".to_string()));
+            return Err(error("expected digits".to_string()));
         }
 
         Ok(num)
@@ -786,6 +1237,15 @@ impl Parser {
         Ok(())
     }
 
+    // Whether the `\\` at the current position ends its line
+    fn line_continues(&self) -> bool {
+        match self.peek_ahead(1) {
+            Some(&'\n') => true,
+            Some(&'\r') => self.peek_ahead(2) == Some(&'\n'),
+            _ => false,
+        }
+    }
+
     // Helper: get current char without advancing
     fn current(&self) -> Option<&char> {
         self.input.get(self.pos)
@@ -0,0 +1,100 @@
+// Characters by Unicode name, for `\N{NAME}`. The names of Unicode 14.0 are kept
+// in `names.txt` as `NAME;HEX` lines sorted by name, except for the ideographs and
+// Hangul syllables, whose names are worked out from the code point instead.
+
+use core::cmp::Ordering;
+
+const NAMES: &str = include_str!("names.txt");
+
+// Ideographs named by a prefix and the code point in hex, with the code points
+// each name applies to
+const NUMBERED: &[(&str, u32, u32)] = &[
+    ("CJK UNIFIED IDEOGRAPH-", 0x3400, 0x4DBF),
+    ("CJK UNIFIED IDEOGRAPH-", 0x4E00, 0x9FFF),
+    ("CJK UNIFIED IDEOGRAPH-", 0x20000, 0x2A6DF),
+    ("CJK UNIFIED IDEOGRAPH-", 0x2A700, 0x2B738),
+    ("CJK UNIFIED IDEOGRAPH-", 0x2B740, 0x2B81D),
+    ("CJK UNIFIED IDEOGRAPH-", 0x2B820, 0x2CEA1),
+    ("CJK UNIFIED IDEOGRAPH-", 0x2CEB0, 0x2EBE0),
+    ("CJK UNIFIED IDEOGRAPH-", 0x30000, 0x3134A),
+    ("CJK COMPATIBILITY IDEOGRAPH-", 0xF900, 0xFA6D),
+    ("CJK COMPATIBILITY IDEOGRAPH-", 0xFA70, 0xFAD9),
+    ("CJK COMPATIBILITY IDEOGRAPH-", 0x2F800, 0x2FA1D),
+    ("KHITAN SMALL SCRIPT CHARACTER-", 0x18B00, 0x18CD5),
+    ("NUSHU CHARACTER-", 0x1B170, 0x1B2FB),
+];
+
+// The jamo a Hangul syllable's name is spelled with: leading consonant, vowel and
+// optional trailing consonant
+const LEADS: [&str; 19] = [
+    "G", "GG", "N", "D", "DD", "R", "M", "B", "BB", "S", "SS", "", "J", "JJ", "C", "K", "T", "P",
+    "H",
+];
+const VOWELS: [&str; 21] = [
+    "A", "AE", "YA", "YAE", "EO", "E", "YEO", "YE", "O", "WA", "WAE", "OE", "YO", "U", "WEO", "WE",
+    "WI", "YU", "EU", "YI", "I",
+];
+const TAILS: [&str; 28] = [
+    "", "G", "GG", "GS", "N", "NJ", "NH", "D", "L", "LG", "LM", "LB", "LS", "LT", "LP", "LH", "M",
+    "B", "BS", "S", "SS", "NG", "J", "C", "K", "T", "P", "H",
+];
+
+/// Returns the character with the Unicode name `name`, ignoring ASCII case.
+pub(crate) fn lookup(name: &str) -> Option<char> {
+    let name = name.to_ascii_uppercase();
+    numbered(&name)
+        .or_else(|| hangul(&name))
+        .or_else(|| listed(&name))
+}
+
+// Binary search over the lines of the table. `low` and `high` are always at the
+// start of a line, so the line around the midpoint lies between them.
+fn listed(name: &str) -> Option<char> {
+    let (mut low, mut high) = (0, NAMES.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let start = NAMES[..mid].rfind('\n').map_or(0, |i| i + 1);
+        let end = start + NAMES[start..].find('\n')?;
+        let (line_name, hex) = NAMES[start..end].split_once(';')?;
+        match line_name.cmp(name) {
+            Ordering::Equal => return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+            Ordering::Less => low = end + 1,
+            Ordering::Greater => high = start,
+        }
+    }
+    None
+}
+
+fn numbered(name: &str) -> Option<char> {
+    NUMBERED.iter().find_map(|&(prefix, first, last)| {
+        let hex = name.strip_prefix(prefix)?;
+        let code = u32::from_str_radix(hex, 16).ok()?;
+        // The name has exactly the digits the code point is written with
+        let digits = if code > 0xFFFF { 5 } else { 4 };
+        let canonical = hex.len() == digits && hex.bytes().all(|b| b.is_ascii_hexdigit());
+        (canonical && (first..=last).contains(&code))
+            .then(|| char::from_u32(code))
+            .flatten()
+    })
+}
+
+fn hangul(name: &str) -> Option<char> {
+    let syllable = name.strip_prefix("HANGUL SYLLABLE ")?;
+    // Jamo names are prefixes of one another, so try every way to split the name;
+    // names are unique, so any split that works is the right one
+    for (lead, lead_name) in LEADS.iter().enumerate() {
+        let Some(rest) = syllable.strip_prefix(lead_name) else {
+            continue;
+        };
+        for (vowel, vowel_name) in VOWELS.iter().enumerate() {
+            let Some(rest) = rest.strip_prefix(vowel_name) else {
+                continue;
+            };
+            if let Some(tail) = TAILS.iter().position(|&tail_name| tail_name == rest) {
+                let index = (lead * VOWELS.len() + vowel) * TAILS.len() + tail;
+                return char::from_u32(0xAC00 + index as u32);
+            }
+        }
+    }
+    None
+}
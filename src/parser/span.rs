@@ -0,0 +1,82 @@
+use super::{AstNode, Visitor, walk};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A byte range in the pattern text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// The byte offset where the range starts (inclusive).
+    pub start: usize,
+    /// The byte offset where the range ends (exclusive).
+    pub end: usize,
+}
+
+/// The span in the pattern of every node of a parsed AST.
+///
+/// Nodes are numbered in the order [`walk`] enters them, starting at zero.
+///
+/// ```
+/// use monster_regex::{Flags, Parser, Span};
+///
+/// let (ast, spans) = Parser::new("ab+", Flags::default()).parse_with_spans().unwrap();
+/// assert_eq!(ast.len(), 2);
+/// // `a`, then `b+`, then the `b` inside it
+/// assert_eq!(spans.get(1), Some(Span { start: 1, end: 3 }));
+/// assert_eq!(spans.get(2), Some(Span { start: 1, end: 2 }));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpanMap {
+    spans: Vec<Span>,
+}
+
+impl SpanMap {
+    /// Pairs the spans the parser recorded, in the order it finished each node,
+    /// with the nodes of the AST.
+    pub(super) fn new(nodes: &[AstNode], finished: &[Span]) -> Self {
+        struct Numbering<'s> {
+            finished: core::slice::Iter<'s, Span>,
+            open: Vec<usize>,
+            spans: Vec<Span>,
+        }
+
+        impl Visitor for Numbering<'_> {
+            fn enter(&mut self, _node: &AstNode) -> bool {
+                self.open.push(self.spans.len());
+                self.spans.push(Span { start: 0, end: 0 });
+                true
+            }
+
+            fn leave(&mut self, _node: &AstNode) {
+                // Children are finished before their parents, as `leave` sees them
+                if let (Some(index), Some(span)) = (self.open.pop(), self.finished.next()) {
+                    self.spans[index] = *span;
+                }
+            }
+        }
+
+        let mut numbering = Numbering {
+            finished: finished.iter(),
+            open: vec![],
+            spans: vec![],
+        };
+        walk(nodes, &mut numbering);
+        SpanMap {
+            spans: numbering.spans,
+        }
+    }
+
+    /// Returns the span of the node at `index`, in [`walk`] order.
+    pub fn get(&self, index: usize) -> Option<Span> {
+        self.spans.get(index).copied()
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns true if the AST has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
@@ -0,0 +1,181 @@
+use super::AstNode;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// A read-only walk over an AST.
+///
+/// Both methods have default implementations, so a visitor only overrides what it needs.
+///
+/// ```
+/// use monster_regex::{AstNode, Flags, Parser, Visitor, walk};
+///
+/// struct CountLiterals(usize);
+///
+/// impl Visitor for CountLiterals {
+///     fn enter(&mut self, node: &AstNode) -> bool {
+///         if let AstNode::Literal(_) = node {
+///             self.0 += 1;
+///         }
+///         true
+///     }
+/// }
+///
+/// let ast = Parser::new("a(b|cd)+", Flags::default()).parse().unwrap();
+/// let mut counter = CountLiterals(0);
+/// walk(&ast, &mut counter);
+/// assert_eq!(counter.0, 4);
+/// ```
+pub trait Visitor {
+    /// Called for every node before its children. Returning `false` skips the children.
+    fn enter(&mut self, _node: &AstNode) -> bool {
+        true
+    }
+
+    /// Called for every node after its children have been visited.
+    fn leave(&mut self, _node: &AstNode) {}
+}
+
+/// Walks a sequence of nodes depth-first, calling the visitor on every node.
+pub fn walk<V: Visitor + ?Sized>(nodes: &[AstNode], visitor: &mut V) {
+    for node in nodes {
+        walk_node(node, visitor);
+    }
+}
+
+/// Walks a single node and its descendants depth-first.
+pub fn walk_node<V: Visitor + ?Sized>(node: &AstNode, visitor: &mut V) {
+    if visitor.enter(node) {
+        match node {
+            AstNode::ZeroOrMore { node: inner, .. }
+            | AstNode::OneOrMore { node: inner, .. }
+            | AstNode::Optional { node: inner, .. }
+            | AstNode::Exact { node: inner, .. }
+            | AstNode::Range { node: inner, .. } => walk_node(inner, visitor),
+            AstNode::Group { nodes, .. }
+            | AstNode::LookAhead { nodes, .. }
+            | AstNode::LookBehind { nodes, .. } => walk(nodes, visitor),
+            AstNode::Alternation(alts) => {
+                for alt in alts {
+                    walk(alt, visitor);
+                }
+            }
+            _ => {}
+        }
+    }
+    visitor.leave(node);
+}
+
+/// Folds over every node in pre-order (parents before children).
+///
+/// ```
+/// use monster_regex::{AstNode, Flags, Parser, fold};
+///
+/// let ast = Parser::new("(a)(?:b)(c)", Flags::default()).parse().unwrap();
+/// let groups = fold(&ast, 0, &mut |count, node| match node {
+///     AstNode::Group { capture: true, .. } => count + 1,
+///     _ => count,
+/// });
+/// assert_eq!(groups, 2);
+/// ```
+pub fn fold<T, F>(nodes: &[AstNode], init: T, f: &mut F) -> T
+where
+    F: FnMut(T, &AstNode) -> T,
+{
+    struct Folder<'f, T, F> {
+        acc: Option<T>,
+        f: &'f mut F,
+    }
+
+    impl<T, F: FnMut(T, &AstNode) -> T> Visitor for Folder<'_, T, F> {
+        fn enter(&mut self, node: &AstNode) -> bool {
+            let acc = self.acc.take().expect("accumulator is always present");
+            self.acc = Some((self.f)(acc, node));
+            true
+        }
+    }
+
+    let mut folder = Folder { acc: Some(init), f };
+    walk(nodes, &mut folder);
+    folder.acc.expect("accumulator is always present")
+}
+
+/// Rewrites every node bottom-up (children before parents).
+///
+/// `f` receives each node after its children have already been rewritten.
+///
+/// ```
+/// use monster_regex::{AstNode, Flags, Parser, map, to_pattern};
+///
+/// let ast = Parser::new("a(b|a)", Flags::default()).parse().unwrap();
+/// let ast = map(ast, &mut |node| match node {
+///     AstNode::Literal('a') => AstNode::Literal('z'),
+///     other => other,
+/// });
+/// assert_eq!(to_pattern(&ast), "z(b|z)");
+/// ```
+pub fn map<F>(nodes: Vec<AstNode>, f: &mut F) -> Vec<AstNode>
+where
+    F: FnMut(AstNode) -> AstNode,
+{
+    nodes.into_iter().map(|node| map_node(node, f)).collect()
+}
+
+/// Rewrites a single node and its descendants bottom-up.
+pub fn map_node<F>(node: AstNode, f: &mut F) -> AstNode
+where
+    F: FnMut(AstNode) -> AstNode,
+{
+    let rebuilt = match node {
+        AstNode::ZeroOrMore { node, greedy } => AstNode::ZeroOrMore {
+            node: Box::new(map_node(*node, f)),
+            greedy,
+        },
+        AstNode::OneOrMore { node, greedy } => AstNode::OneOrMore {
+            node: Box::new(map_node(*node, f)),
+            greedy,
+        },
+        AstNode::Optional { node, greedy } => AstNode::Optional {
+            node: Box::new(map_node(*node, f)),
+            greedy,
+        },
+        AstNode::Exact { node, count } => AstNode::Exact {
+            node: Box::new(map_node(*node, f)),
+            count,
+        },
+        AstNode::Range {
+            node,
+            min,
+            max,
+            greedy,
+        } => AstNode::Range {
+            node: Box::new(map_node(*node, f)),
+            min,
+            max,
+            greedy,
+        },
+        AstNode::Group {
+            nodes,
+            name,
+            capture,
+            index,
+        } => AstNode::Group {
+            nodes: map(nodes, f),
+            name,
+            capture,
+            index,
+        },
+        AstNode::Alternation(alts) => {
+            AstNode::Alternation(alts.into_iter().map(|alt| map(alt, f)).collect())
+        }
+        AstNode::LookAhead { nodes, positive } => AstNode::LookAhead {
+            nodes: map(nodes, f),
+            positive,
+        },
+        AstNode::LookBehind { nodes, positive } => AstNode::LookBehind {
+            nodes: map(nodes, f),
+            positive,
+        },
+        leaf => leaf,
+    };
+    f(rebuilt)
+}
@@ -1,10 +1,24 @@
 use crate::errors::ParseError;
 use crate::flags::Flags;
+use alloc::string::String;
+
+mod range;
+pub use range::{Address, Line, Range, parse_range};
 
 /// Parses a string in the Rift format: `pattern/flags`.
 ///
 /// This format expects the pattern to be terminated by a forward slash `/`,
-/// followed by any number of single-character flags.
+/// followed by any number of single-character flags. A `\/` in the pattern is an
+/// escaped slash and doesn't end it.
+///
+/// # Delimiters
+///
+/// Patterns containing slashes can use another delimiter around the pattern
+/// instead, chosen by the first character: `#a/b#i` is the pattern `a/b` with the
+/// `i` flag. Any character except letters, digits, whitespace, `/` and characters
+/// with a meaning in patterns (`\ ( ) [ ] { } . * + ? ^ $ |`) can be used, and is
+/// escaped with a backslash inside the pattern. Input whose first character is
+/// never repeated is read as `pattern/flags`.
 ///
 /// # Flags
 ///
@@ -15,6 +29,12 @@ use crate::flags::Flags;
 /// * `x`: Verbose mode (whitespace and comments ignored).
 /// * `u`: Unicode support.
 /// * `g`: Global match.
+/// * `A`: Anchored (matches must start where the search starts).
+/// * `U`: Ungreedy (quantifiers are lazy unless followed by `?`).
+/// * `R`: CRLF mode (`\r\n` and `\r` also end lines).
+/// * `n`: Dry run (replacing only counts the matches).
+/// * `C`: Confirm each replacement (left to the caller).
+/// * A number `N`: Replacing starts from the `N`th match.
 ///
 /// # Smartcase
 ///
@@ -24,34 +44,152 @@ use crate::flags::Flags;
 ///
 /// # Errors
 ///
-/// Returns `ParseError::NoDelimiter` if the input string does not contain a `/`.
-/// Returns `ParseError::InvalidFlags` if an unknown flag character is encountered.
+/// Returns `ParseError::NoDelimiter` if the input string does not contain an unescaped `/`.
+/// Returns `ParseError::InvalidFlags` if an unknown flag character is encountered,
+/// or the number is zero, out of range or split by letters.
 pub fn parse_rift_format(input: &str) -> Result<(String, Flags), ParseError> {
-    let last_slash = input.rfind('/').ok_or(ParseError::NoDelimiter)?;
-
-    let pattern = &input[..last_slash];
-    let flag_str = &input[last_slash + 1..];
-
-    let mut flags = Flags::default();
-
-    for ch in flag_str.chars() {
-        match ch {
-            'i' => flags.ignore_case = Some(true),
-            'c' => flags.ignore_case = Some(false),
-            'm' => flags.multiline = true,
-            's' => flags.dotall = true,
-            'x' => flags.verbose = true,
-            'u' => flags.unicode = true,
-            'g' => flags.global = true,
-            _ => return Err(ParseError::InvalidFlags(ch)),
+    let mut chars = input.chars();
+    let delimited = chars
+        .next()
+        .filter(|&c| is_alternate_delimiter(c))
+        .map(|delimiter| read_part(&mut chars, delimiter))
+        .filter(|&(_, closed)| closed);
+    let (pattern, flag_str) = match delimited {
+        Some((pattern, _)) => (pattern, chars.as_str()),
+        None => {
+            let last_slash = last_unescaped(input, '/').ok_or(ParseError::NoDelimiter)?;
+            (
+                unescape(&input[..last_slash], '/'),
+                &input[last_slash + 1..],
+            )
+        }
+    };
+
+    let mut flags: Flags = flag_str.parse()?;
+    resolve_smartcase(&mut flags, &pattern);
+
+    Ok((pattern, flags))
+}
+
+/// Parses a sed/vim-style substitution command: `s/pattern/replacement/flags`.
+///
+/// Any character other than a letter, digit, backslash or whitespace may be used as
+/// the delimiter in place of `/`, as in `s#a/b#c#`. A backslash before the delimiter
+/// makes it part of the pattern or replacement (`s/a\/b/c/` replaces `a/b`); other
+/// escapes are kept as written, for the regex and the replacement to interpret. The
+/// final delimiter may be left out when there are no flags.
+///
+/// Flags are those of [`parse_rift_format`], with smartcase applied the same way.
+/// The returned count is how many matches to replace: `None` for every match
+/// (`g`), otherwise 1. A number in the flags selects the match to start from, as in
+/// sed: `s/a/b/3` replaces the third match, and `s/a/b/3g` the third onward.
+///
+/// # Errors
+///
+/// Returns `ParseError::NotSubstitution` if the input doesn't start with `s` and a
+/// valid delimiter.
+/// Returns `ParseError::NoDelimiter` if the delimiter after the pattern is missing.
+/// Returns `ParseError::InvalidFlags` if an unknown flag character is encountered,
+/// or the number is zero, out of range or split by letters.
+///
+/// ```
+/// use monster_regex::parse_substitution;
+///
+/// let (pattern, replacement, flags, count) = parse_substitution(r"s|a\|b|c|gi").unwrap();
+/// assert_eq!((pattern.as_str(), replacement.as_str()), ("a|b", "c"));
+/// assert_eq!((flags.ignore_case, count), (Some(true), None));
+///
+/// let (_, _, flags, count) = parse_substitution("s/a/b/2").unwrap();
+/// assert_eq!((flags.occurrence, count), (Some(2), Some(1)));
+/// ```
+pub fn parse_substitution(
+    input: &str,
+) -> Result<(String, String, Flags, Option<usize>), ParseError> {
+    let mut chars = input.chars();
+    if chars.next() != Some('s') {
+        return Err(ParseError::NotSubstitution);
+    }
+    let delimiter = match chars.next() {
+        Some(c) if !(c.is_alphanumeric() || c == '\\' || c.is_whitespace()) => c,
+        _ => return Err(ParseError::NotSubstitution),
+    };
+
+    let (pattern, closed) = read_part(&mut chars, delimiter);
+    if !closed {
+        return Err(ParseError::NoDelimiter);
+    }
+    let (replacement, _) = read_part(&mut chars, delimiter);
+
+    let mut flags: Flags = chars.as_str().parse()?;
+    resolve_smartcase(&mut flags, &pattern);
+    let count = (!flags.global).then_some(1);
+
+    Ok((pattern, replacement, flags, count))
+}
+
+// Reads up to an unescaped delimiter, unescaping it. Returns the text and whether
+// the delimiter was found.
+fn read_part(chars: &mut core::str::Chars, delimiter: char) -> (String, bool) {
+    let mut part = String::new();
+    while let Some(ch) = chars.next() {
+        if ch == delimiter {
+            return (part, true);
+        }
+        if ch == '\\' {
+            match chars.next() {
+                Some(next) if next == delimiter => part.push(next),
+                Some(next) => {
+                    part.push(ch);
+                    part.push(next);
+                }
+                None => part.push(ch),
+            }
+        } else {
+            part.push(ch);
+        }
+    }
+    (part, false)
+}
+
+// Whether a leading character can open a pattern in place of `/`
+pub(crate) fn is_alternate_delimiter(c: char) -> bool {
+    !(c.is_alphanumeric() || c.is_whitespace() || "/\\()[]{}.*+?^$|".contains(c))
+}
+
+// Returns the byte offset of the last delimiter not escaped by a backslash
+pub(crate) fn last_unescaped(text: &str, delimiter: char) -> Option<usize> {
+    let mut last = None;
+    let mut escaped = false;
+    for (i, ch) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == delimiter {
+            last = Some(i);
         }
     }
+    last
+}
 
-    // Smartcase: if no explicit case flag, infer from pattern
+// Removes the backslash from escaped delimiters, keeping other escapes
+fn unescape(text: &str, delimiter: char) -> String {
+    let mut chars = text.chars();
+    let mut out = String::new();
+    loop {
+        let (part, closed) = read_part(&mut chars, delimiter);
+        out.push_str(&part);
+        if !closed {
+            return out;
+        }
+        out.push(delimiter);
+    }
+}
+
+// Smartcase: if no explicit case flag, infer from pattern
+fn resolve_smartcase(flags: &mut Flags, pattern: &str) {
     if flags.ignore_case.is_none() {
         let has_uppercase = pattern.chars().any(|c| c.is_uppercase());
         flags.ignore_case = Some(!has_uppercase);
     }
-
-    Ok((pattern.to_string(), flags))
 }
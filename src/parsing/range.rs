@@ -0,0 +1,147 @@
+use crate::errors::ParseError;
+
+/// The lines a command applies to, from a Vim/sed-style address prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Range {
+    /// Every line `%`.
+    All,
+    /// A single line, e.g. `5` or `$`.
+    Line(Address),
+    /// The lines from the first address to the second, inclusive, e.g. `5,20`.
+    Lines(Address, Address),
+}
+
+/// One end of a [`Range`]: a line and how far to move from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address {
+    pub line: Line,
+    /// Lines to move down (or up, if negative) from `line`, as in `.+3` or `$-1`.
+    pub offset: isize,
+}
+
+/// The line an [`Address`] starts from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+    /// A line number, counting from 1.
+    Number(usize),
+    /// The current line `.`.
+    Current,
+    /// The last line `$`.
+    Last,
+}
+
+impl Address {
+    /// Returns the 1-based line number this address refers to, given the current and
+    /// last line numbers.
+    pub fn resolve(&self, current: usize, last: usize) -> Option<usize> {
+        let line = match self.line {
+            Line::Number(n) => n,
+            Line::Current => current,
+            Line::Last => last,
+        };
+        line.checked_add_signed(self.offset)
+    }
+}
+
+impl Range {
+    /// Returns the 1-based line numbers the range covers, given the current and last
+    /// line numbers. Returns `None` if an address is before the first line or past
+    /// the last, or the range runs backwards.
+    ///
+    /// ```
+    /// use monster_regex::parse_range;
+    ///
+    /// let (range, _) = parse_range(".,$-1d").unwrap();
+    /// assert_eq!(range.unwrap().resolve(3, 10), Some(3..=9));
+    /// ```
+    pub fn resolve(&self, current: usize, last: usize) -> Option<core::ops::RangeInclusive<usize>> {
+        let (start, end) = match self {
+            Range::All => (1, last),
+            Range::Line(address) => {
+                let line = address.resolve(current, last)?;
+                (line, line)
+            }
+            Range::Lines(start, end) => {
+                (start.resolve(current, last)?, end.resolve(current, last)?)
+            }
+        };
+        (start >= 1 && start <= end && end <= last).then_some(start..=end)
+    }
+}
+
+/// Splits a Vim/sed-style address off the front of a command, as in
+/// `5,20s/foo/bar/g` or `%s/a/b/`, returning it and the rest of the command.
+///
+/// An address is `%` for every line, or one or two line specifiers separated by a
+/// comma: a number, `.` for the current line or `$` for the last, each optionally
+/// followed by `+N` or `-N` (`N` defaults to 1). A leading `+N` or `-N` is relative to
+/// the current line. Returns `None` for the range if the command has no address,
+/// leaving the default to the caller, since Vim and sed disagree on it.
+///
+/// # Errors
+///
+/// Returns `ParseError::InvalidRange` if a comma isn't followed by a second line
+/// specifier or a number is too large.
+///
+/// ```
+/// use monster_regex::{Address, Line, Range, parse_range, parse_substitution};
+///
+/// let (range, rest) = parse_range("5,$s/foo/bar/g").unwrap();
+/// let last = Address { line: Line::Last, offset: 0 };
+/// let five = Address { line: Line::Number(5), offset: 0 };
+/// assert_eq!(range, Some(Range::Lines(five, last)));
+/// let (pattern, replacement, _, _) = parse_substitution(rest).unwrap();
+/// assert_eq!((pattern.as_str(), replacement.as_str()), ("foo", "bar"));
+/// ```
+pub fn parse_range(input: &str) -> Result<(Option<Range>, &str), ParseError> {
+    if let Some(rest) = input.strip_prefix('%') {
+        return Ok((Some(Range::All), rest));
+    }
+    let Some((start, rest)) = parse_address(input)? else {
+        return Ok((None, input));
+    };
+    let Some(rest) = rest.strip_prefix(',') else {
+        return Ok((Some(Range::Line(start)), rest));
+    };
+    let (end, rest) = parse_address(rest)?.ok_or(ParseError::InvalidRange)?;
+    Ok((Some(Range::Lines(start, end)), rest))
+}
+
+// Reads a line specifier and its offsets, if the input starts with one
+fn parse_address(input: &str) -> Result<Option<(Address, &str)>, ParseError> {
+    let (line, mut rest) = if let Some(rest) = input.strip_prefix('.') {
+        (Line::Current, rest)
+    } else if let Some(rest) = input.strip_prefix('$') {
+        (Line::Last, rest)
+    } else if input.starts_with(|c: char| c.is_ascii_digit()) {
+        let (number, rest) = split_number(input);
+        (Line::Number(number.ok_or(ParseError::InvalidRange)?), rest)
+    } else if input.starts_with(['+', '-']) {
+        (Line::Current, input)
+    } else {
+        return Ok(None);
+    };
+
+    let mut offset: isize = 0;
+    while let Some(sign) = rest.chars().next().filter(|c| matches!(c, '+' | '-')) {
+        let (number, after) = split_number(&rest[1..]);
+        let amount = match number {
+            Some(number) => isize::try_from(number).map_err(|_| ParseError::InvalidRange)?,
+            None if after.len() == rest.len() - 1 => 1,
+            None => return Err(ParseError::InvalidRange),
+        };
+        let amount = if sign == '-' { -amount } else { amount };
+        offset = offset.checked_add(amount).ok_or(ParseError::InvalidRange)?;
+        rest = after;
+    }
+    Ok(Some((Address { line, offset }, rest)))
+}
+
+// Splits leading digits off the input. The number is `None` if there are no digits
+// or they overflow.
+fn split_number(input: &str) -> (Option<usize>, &str) {
+    let end = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    (input[..end].parse().ok(), &input[end..])
+}
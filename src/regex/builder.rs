@@ -0,0 +1,136 @@
+use super::{EmptyMatchPolicy, Regex};
+use crate::compiler::{BackrefPolicy, CompileOptions};
+use crate::errors::CompileError;
+use crate::flags::Flags;
+use alloc::string::{String, ToString};
+
+/// A builder for compiling a `Regex` with non-default compile options.
+///
+/// The limits keep untrusted patterns from using up memory or time while compiling;
+/// patterns over them fail with `CompileError::ExceededLimits`.
+///
+/// ```
+/// use monster_regex::{CompileError, RegexBuilder};
+///
+/// let re = RegexBuilder::new(r"a{2,5000}").repetition_limit(5000).build().unwrap();
+/// assert!(re.is_match("aa"));
+///
+/// let nested = RegexBuilder::new("((a))").nest_limit(1).build();
+/// assert!(matches!(nested, Err(CompileError::ExceededLimits(_))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegexBuilder {
+    pattern: String,
+    flags: Flags,
+    options: CompileOptions,
+}
+
+impl RegexBuilder {
+    /// Creates a builder for the given pattern with default flags and options.
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            flags: Flags::default(),
+            options: CompileOptions::default(),
+        }
+    }
+
+    /// Sets the flags used to compile the pattern.
+    pub fn flags(mut self, flags: Flags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Sets the largest count accepted in a bounded quantifier such as `{n,m}`.
+    ///
+    /// Defaults to [`DEFAULT_REPETITION_LIMIT`](crate::compiler::DEFAULT_REPETITION_LIMIT).
+    pub fn repetition_limit(mut self, limit: usize) -> Self {
+        self.options.repetition_limit = limit;
+        self
+    }
+
+    /// Sets the longest pattern accepted, in bytes.
+    ///
+    /// Defaults to [`DEFAULT_SIZE_LIMIT`](crate::compiler::DEFAULT_SIZE_LIMIT).
+    pub fn size_limit(mut self, limit: usize) -> Self {
+        self.options.size_limit = limit;
+        self
+    }
+
+    /// Sets how deeply groups, lookarounds, quantifiers and alternations may be
+    /// nested. Parsing stops as soon as groups pass the limit, and the rest are
+    /// counted once the pattern is parsed.
+    ///
+    /// Defaults to, and can't be raised above,
+    /// [`MAX_NESTING_DEPTH`](crate::parser::MAX_NESTING_DEPTH).
+    pub fn nest_limit(mut self, limit: usize) -> Self {
+        self.options.nest_limit = limit;
+        self
+    }
+
+    /// Sets the most instructions the pattern may compile to for the backtracking
+    /// matcher.
+    ///
+    /// Defaults to [`DEFAULT_PROGRAM_SIZE_LIMIT`](crate::compiler::DEFAULT_PROGRAM_SIZE_LIMIT).
+    pub fn program_size_limit(mut self, limit: usize) -> Self {
+        self.options.program_size_limit = limit;
+        self
+    }
+
+    /// Sets how [`find_all`](Regex::find_all), [`captures_all`](Regex::captures_all)
+    /// and [`split`](Regex::split) treat empty matches.
+    ///
+    /// [`EmptyMatchPolicy::Skip`] also keeps [`find`](Regex::find),
+    /// [`captures`](Regex::captures), [`replace`](Regex::replace) and
+    /// [`is_match`](Regex::is_match) from reporting an empty match. Searches of a
+    /// range with [`find_in_range`](Regex::find_in_range) or a
+    /// [`Finder`](crate::Finder) report them whatever the policy.
+    ///
+    /// Defaults to [`EmptyMatchPolicy::Allow`].
+    pub fn empty_matches(mut self, policy: EmptyMatchPolicy) -> Self {
+        self.options.empty_matches = policy;
+        self
+    }
+
+    /// Sets whether groups captured inside a lookaround stay set once it has matched,
+    /// so they show in the [`Captures`](crate::Captures) and backreferences see
+    /// them. Groups in a negative lookaround never stay set, as its body has to
+    /// fail for it to match.
+    ///
+    /// Defaults to `true`, as in PCRE.
+    ///
+    /// ```
+    /// use monster_regex::RegexBuilder;
+    ///
+    /// let text = "price: 42";
+    /// let re = RegexBuilder::new(r"(?>=.*(\d+))\w+").build().unwrap();
+    /// assert_eq!(re.captures(text).unwrap().as_str(text, 1), Some("2"));
+    ///
+    /// let re = RegexBuilder::new(r"(?>=.*(\d+))\w+")
+    ///     .lookaround_captures(false)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(re.captures(text).unwrap().as_str(text, 1), None);
+    /// ```
+    pub fn lookaround_captures(mut self, keep: bool) -> Self {
+        self.options.lookaround_captures = keep;
+        self
+    }
+
+    /// Sets how backreferences to groups that can't have matched yet are treated.
+    ///
+    /// Defaults to [`BackrefPolicy::Strict`].
+    pub fn backrefs(mut self, policy: BackrefPolicy) -> Self {
+        self.options.backrefs = policy;
+        self
+    }
+
+    /// Compiles the pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CompileError` if the pattern is invalid or exceeds the configured limits.
+    pub fn build(&self) -> Result<Regex, CompileError> {
+        Regex::compile(&self.pattern, self.flags, &self.options)
+    }
+}
@@ -0,0 +1,550 @@
+// The binary form of a compiled regex, written by `Regex::to_bytes` and read by
+// `Regex::from_bytes`. The bytes hold the optimized AST every other compiled
+// structure is built from, along with the pattern, flags and compile options, so
+// loading skips parsing, optimization and smartcase resolution. Integers are LEB128
+// varints and strings are a length followed by UTF-8.
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use super::{EmptyMatchPolicy, Regex};
+use crate::compiler::{BackrefPolicy, CompileOptions};
+use crate::errors::DecodeError;
+use crate::flags::{DotMode, Flags};
+use crate::parser::{AstNode, CharClass, CharRange, MAX_NESTING_DEPTH};
+
+const MAGIC: &[u8; 4] = b"MRGX";
+const VERSION: u8 = 4;
+
+// Nodes nest at most this deep in decoded ASTs, quantifiers included, so corrupt
+// input can't overflow the stack before validation
+const MAX_DECODE_DEPTH: usize = 4 * MAX_NESTING_DEPTH;
+
+pub(super) fn encode(regex: &Regex) -> Vec<u8> {
+    let mut writer = Writer(MAGIC.to_vec());
+    writer.0.push(VERSION);
+    writer.string(&regex.pattern);
+    writer.string(&regex.flags.to_string());
+    // The flag letters leave out the dot mode
+    writer.0.push(match regex.flags.dot {
+        DotMode::Char => 0,
+        DotMode::NotCr => 1,
+        DotMode::Ascii => 2,
+        DotMode::Grapheme => 3,
+    });
+    writer.0.push(match regex.empty_matches {
+        EmptyMatchPolicy::Allow => 0,
+        EmptyMatchPolicy::SkipAdjacent => 1,
+        EmptyMatchPolicy::Skip => 2,
+    });
+    writer.usize(regex.repetition_limit);
+    writer.0.push(u8::from(regex.program.lookaround_captures));
+    writer
+        .0
+        .push(u8::from(regex.program.unset_backrefs_match_empty));
+    writer.nodes(&regex.ast);
+    writer.0
+}
+
+/// The parts of a compiled regex read back from bytes.
+pub(super) struct Decoded {
+    pub pattern: String,
+    pub flags: Flags,
+    pub options: CompileOptions,
+    pub ast: Vec<AstNode>,
+}
+
+pub(super) fn decode(bytes: &[u8]) -> Result<Decoded, DecodeError> {
+    let body = bytes
+        .strip_prefix(MAGIC)
+        .ok_or(DecodeError::InvalidHeader)?;
+    let mut reader = Reader {
+        bytes: body,
+        pos: 0,
+        depth: 0,
+    };
+    let version = reader.byte()?;
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let pattern = reader.string()?;
+    let at = reader.offset();
+    let mut flags: Flags = reader
+        .string()?
+        .parse()
+        .map_err(|_| DecodeError::InvalidData(at))?;
+    let at = reader.offset();
+    flags.dot = match reader.byte()? {
+        0 => DotMode::Char,
+        1 => DotMode::NotCr,
+        2 => DotMode::Ascii,
+        3 => DotMode::Grapheme,
+        _ => return Err(DecodeError::InvalidData(at)),
+    };
+    let at = reader.offset();
+    let empty_matches = match reader.byte()? {
+        0 => EmptyMatchPolicy::Allow,
+        1 => EmptyMatchPolicy::SkipAdjacent,
+        2 => EmptyMatchPolicy::Skip,
+        _ => return Err(DecodeError::InvalidData(at)),
+    };
+    let repetition_limit = reader.usize()?;
+    let at = reader.offset();
+    let lookaround_captures = match reader.byte()? {
+        0 => false,
+        1 => true,
+        _ => return Err(DecodeError::InvalidData(at)),
+    };
+    let at = reader.offset();
+    let backrefs = match reader.byte()? {
+        0 => BackrefPolicy::Strict,
+        1 => BackrefPolicy::MatchEmpty,
+        _ => return Err(DecodeError::InvalidData(at)),
+    };
+    let ast = reader.nodes()?;
+    if reader.pos != reader.bytes.len() {
+        return Err(DecodeError::InvalidData(reader.offset()));
+    }
+
+    Ok(Decoded {
+        pattern,
+        flags,
+        options: CompileOptions {
+            repetition_limit,
+            empty_matches,
+            lookaround_captures,
+            backrefs,
+            ..CompileOptions::default()
+        },
+        ast,
+    })
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn usize(&mut self, mut value: usize) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.0.push(byte);
+                return;
+            }
+            self.0.push(byte | 0x80);
+        }
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.0.push(value as u8);
+    }
+
+    fn ordering(&mut self, value: Ordering) {
+        self.0.push((value as i8 + 1) as u8);
+    }
+
+    fn char(&mut self, c: char) {
+        self.usize(c as usize);
+    }
+
+    fn string(&mut self, text: &str) {
+        self.usize(text.len());
+        self.0.extend_from_slice(text.as_bytes());
+    }
+
+    fn option(&mut self, value: Option<usize>) {
+        match value {
+            Some(value) => {
+                self.bool(true);
+                self.usize(value);
+            }
+            None => self.bool(false),
+        }
+    }
+
+    fn nodes(&mut self, nodes: &[AstNode]) {
+        self.usize(nodes.len());
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Literal(c) => {
+                self.0.push(0);
+                self.char(*c);
+            }
+            AstNode::LiteralString(s) => {
+                self.0.push(1);
+                self.string(s);
+            }
+            AstNode::CharClass(class) => {
+                self.0.push(2);
+                self.class(class);
+            }
+            AstNode::StartAnchor => self.0.push(3),
+            AstNode::EndAnchor => self.0.push(4),
+            AstNode::WordBoundary => self.0.push(5),
+            AstNode::StartWord => self.0.push(6),
+            AstNode::EndWord => self.0.push(7),
+            AstNode::SetMatchStart => self.0.push(8),
+            AstNode::SetMatchEnd => self.0.push(9),
+            AstNode::ZeroOrMore { node, greedy } => {
+                self.0.push(10);
+                self.node(node);
+                self.bool(*greedy);
+            }
+            AstNode::OneOrMore { node, greedy } => {
+                self.0.push(11);
+                self.node(node);
+                self.bool(*greedy);
+            }
+            AstNode::Optional { node, greedy } => {
+                self.0.push(12);
+                self.node(node);
+                self.bool(*greedy);
+            }
+            AstNode::Exact { node, count } => {
+                self.0.push(13);
+                self.node(node);
+                self.usize(*count);
+            }
+            AstNode::Range {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                self.0.push(14);
+                self.node(node);
+                self.usize(*min);
+                self.option(*max);
+                self.bool(*greedy);
+            }
+            AstNode::Group {
+                nodes,
+                name,
+                capture,
+                index,
+            } => {
+                self.0.push(15);
+                self.nodes(nodes);
+                self.bool(name.is_some());
+                if let Some(name) = name {
+                    self.string(name);
+                }
+                self.bool(*capture);
+                self.option(*index);
+            }
+            AstNode::Alternation(branches) => {
+                self.0.push(16);
+                self.usize(branches.len());
+                for branch in branches {
+                    self.nodes(branch);
+                }
+            }
+            AstNode::Backref(index) => {
+                self.0.push(17);
+                self.usize(*index);
+            }
+            AstNode::LookAhead { nodes, positive } => {
+                self.0.push(18);
+                self.nodes(nodes);
+                self.bool(*positive);
+            }
+            AstNode::StartText => self.0.push(20),
+            AstNode::EndText => self.0.push(21),
+            AstNode::Line(line, ordering) => {
+                self.0.push(22);
+                self.usize(*line);
+                self.ordering(*ordering);
+            }
+            AstNode::Column(column, ordering) => {
+                self.0.push(23);
+                self.usize(*column);
+                self.ordering(*ordering);
+            }
+            AstNode::Cursor => self.0.push(24),
+            AstNode::Visual => self.0.push(25),
+            AstNode::Mark(name, ordering) => {
+                self.0.push(26);
+                self.char(*name);
+                self.ordering(*ordering);
+            }
+            AstNode::Grapheme => self.0.push(27),
+            AstNode::LookBehind { nodes, positive } => {
+                self.0.push(19);
+                self.nodes(nodes);
+                self.bool(*positive);
+            }
+        }
+    }
+
+    fn class(&mut self, class: &CharClass) {
+        let tag = match class {
+            CharClass::Digit => 0,
+            CharClass::NonDigit => 1,
+            CharClass::Word => 2,
+            CharClass::NonWord => 3,
+            CharClass::Whitespace => 4,
+            CharClass::NonWhitespace => 5,
+            CharClass::Lowercase => 6,
+            CharClass::NonLowercase => 7,
+            CharClass::Uppercase => 8,
+            CharClass::NonUppercase => 9,
+            CharClass::Hex => 10,
+            CharClass::NonHex => 11,
+            CharClass::Octal => 12,
+            CharClass::NonOctal => 13,
+            CharClass::WordStart => 14,
+            CharClass::NonWordStart => 15,
+            CharClass::Punctuation => 16,
+            CharClass::NonPunctuation => 17,
+            CharClass::Alphanumeric => 18,
+            CharClass::NonAlphanumeric => 19,
+            CharClass::Dot => 20,
+            CharClass::Set { chars, negated } => {
+                self.0.push(21);
+                self.usize(chars.len());
+                for range in chars {
+                    self.char(range.start);
+                    self.char(range.end);
+                }
+                self.bool(*negated);
+                return;
+            }
+        };
+        self.0.push(tag);
+    }
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Reader<'_> {
+    // The offset of the next byte from the start of the input, header included
+    fn offset(&self) -> usize {
+        MAGIC.len() + self.pos
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self.bytes.get(self.pos).ok_or(DecodeError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn usize(&mut self) -> Result<usize, DecodeError> {
+        let at = self.offset();
+        let mut value: usize = 0;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.byte()?;
+            let bits = usize::from(byte & 0x7f);
+            if bits
+                .checked_shl(shift)
+                .is_none_or(|shifted| shifted >> shift != bits)
+            {
+                return Err(DecodeError::InvalidData(at));
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::InvalidData(at))
+    }
+
+    fn bool(&mut self) -> Result<bool, DecodeError> {
+        let at = self.offset();
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::InvalidData(at)),
+        }
+    }
+
+    fn ordering(&mut self) -> Result<Ordering, DecodeError> {
+        let at = self.offset();
+        match self.byte()? {
+            0 => Ok(Ordering::Less),
+            1 => Ok(Ordering::Equal),
+            2 => Ok(Ordering::Greater),
+            _ => Err(DecodeError::InvalidData(at)),
+        }
+    }
+
+    fn char(&mut self) -> Result<char, DecodeError> {
+        let at = self.offset();
+        u32::try_from(self.usize()?)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(DecodeError::InvalidData(at))
+    }
+
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let at = self.offset();
+        let len = self.usize()?;
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        let text = core::str::from_utf8(&self.bytes[self.pos..end])
+            .map_err(|_| DecodeError::InvalidData(at))?;
+        self.pos = end;
+        Ok(text.to_owned())
+    }
+
+    fn option(&mut self) -> Result<Option<usize>, DecodeError> {
+        Ok(if self.bool()? {
+            Some(self.usize()?)
+        } else {
+            None
+        })
+    }
+
+    // Reads a count of items that each take at least one byte, so a corrupt count
+    // can't reserve more memory than the input could fill
+    fn count(&mut self) -> Result<usize, DecodeError> {
+        let count = self.usize()?;
+        if count > self.bytes.len() - self.pos {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        Ok(count)
+    }
+
+    fn nodes(&mut self) -> Result<Vec<AstNode>, DecodeError> {
+        let count = self.count()?;
+        (0..count).map(|_| self.node()).collect()
+    }
+
+    fn boxed(&mut self) -> Result<Box<AstNode>, DecodeError> {
+        self.node().map(Box::new)
+    }
+
+    fn node(&mut self) -> Result<AstNode, DecodeError> {
+        let at = self.offset();
+        if self.depth == MAX_DECODE_DEPTH {
+            return Err(DecodeError::InvalidData(at));
+        }
+        self.depth += 1;
+        let node = match self.byte()? {
+            0 => AstNode::Literal(self.char()?),
+            1 => AstNode::LiteralString(self.string()?),
+            2 => AstNode::CharClass(self.class()?),
+            3 => AstNode::StartAnchor,
+            4 => AstNode::EndAnchor,
+            5 => AstNode::WordBoundary,
+            6 => AstNode::StartWord,
+            7 => AstNode::EndWord,
+            8 => AstNode::SetMatchStart,
+            9 => AstNode::SetMatchEnd,
+            10 => AstNode::ZeroOrMore {
+                node: self.boxed()?,
+                greedy: self.bool()?,
+            },
+            11 => AstNode::OneOrMore {
+                node: self.boxed()?,
+                greedy: self.bool()?,
+            },
+            12 => AstNode::Optional {
+                node: self.boxed()?,
+                greedy: self.bool()?,
+            },
+            13 => AstNode::Exact {
+                node: self.boxed()?,
+                count: self.usize()?,
+            },
+            14 => AstNode::Range {
+                node: self.boxed()?,
+                min: self.usize()?,
+                max: self.option()?,
+                greedy: self.bool()?,
+            },
+            15 => AstNode::Group {
+                nodes: self.nodes()?,
+                name: if self.bool()? {
+                    Some(self.string()?)
+                } else {
+                    None
+                },
+                capture: self.bool()?,
+                index: self.option()?,
+            },
+            16 => {
+                let count = self.count()?;
+                AstNode::Alternation((0..count).map(|_| self.nodes()).collect::<Result<_, _>>()?)
+            }
+            17 => AstNode::Backref(self.usize()?),
+            18 => AstNode::LookAhead {
+                nodes: self.nodes()?,
+                positive: self.bool()?,
+            },
+            19 => AstNode::LookBehind {
+                nodes: self.nodes()?,
+                positive: self.bool()?,
+            },
+            20 => AstNode::StartText,
+            21 => AstNode::EndText,
+            22 => AstNode::Line(self.usize()?, self.ordering()?),
+            23 => AstNode::Column(self.usize()?, self.ordering()?),
+            24 => AstNode::Cursor,
+            25 => AstNode::Visual,
+            26 => AstNode::Mark(self.char()?, self.ordering()?),
+            27 => AstNode::Grapheme,
+            _ => return Err(DecodeError::InvalidData(at)),
+        };
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    fn class(&mut self) -> Result<CharClass, DecodeError> {
+        let at = self.offset();
+        Ok(match self.byte()? {
+            0 => CharClass::Digit,
+            1 => CharClass::NonDigit,
+            2 => CharClass::Word,
+            3 => CharClass::NonWord,
+            4 => CharClass::Whitespace,
+            5 => CharClass::NonWhitespace,
+            6 => CharClass::Lowercase,
+            7 => CharClass::NonLowercase,
+            8 => CharClass::Uppercase,
+            9 => CharClass::NonUppercase,
+            10 => CharClass::Hex,
+            11 => CharClass::NonHex,
+            12 => CharClass::Octal,
+            13 => CharClass::NonOctal,
+            14 => CharClass::WordStart,
+            15 => CharClass::NonWordStart,
+            16 => CharClass::Punctuation,
+            17 => CharClass::NonPunctuation,
+            18 => CharClass::Alphanumeric,
+            19 => CharClass::NonAlphanumeric,
+            20 => CharClass::Dot,
+            21 => {
+                let count = self.count()?;
+                let chars = (0..count)
+                    .map(|_| {
+                        Ok(CharRange {
+                            start: self.char()?,
+                            end: self.char()?,
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                CharClass::Set {
+                    chars,
+                    negated: self.bool()?,
+                }
+            }
+            _ => return Err(DecodeError::InvalidData(at)),
+        })
+    }
+}
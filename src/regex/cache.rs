@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+use super::Regex;
+use crate::errors::CompileError;
+use crate::flags::Flags;
+
+// How many compiled patterns the cache keeps before evicting the least recently used
+const CAPACITY: usize = 128;
+
+// A compiled pattern and the tick it was last used at
+struct Entry {
+    flags: Flags,
+    regex: Arc<Regex>,
+    used: u64,
+}
+
+// Compiled patterns grouped by pattern, so lookups don't allocate a key
+#[derive(Default)]
+struct PatternCache {
+    entries: HashMap<String, Vec<Entry>>,
+    len: usize,
+    tick: u64,
+}
+
+impl PatternCache {
+    fn get(&mut self, pattern: &str, flags: Flags) -> Option<Arc<Regex>> {
+        self.tick += 1;
+        let entry = self
+            .entries
+            .get_mut(pattern)?
+            .iter_mut()
+            .find(|entry| entry.flags == flags)?;
+        entry.used = self.tick;
+        Some(Arc::clone(&entry.regex))
+    }
+
+    fn insert(&mut self, pattern: &str, flags: Flags, regex: Arc<Regex>) -> Arc<Regex> {
+        // Another thread may have compiled the same pattern meanwhile
+        if let Some(existing) = self.get(pattern, flags) {
+            return existing;
+        }
+        if self.len >= CAPACITY {
+            self.evict();
+        }
+        self.entries
+            .entry(pattern.to_string())
+            .or_default()
+            .push(Entry {
+                flags,
+                regex: Arc::clone(&regex),
+                used: self.tick,
+            });
+        self.len += 1;
+        regex
+    }
+
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .flat_map(|(pattern, entries)| entries.iter().map(move |entry| (entry.used, pattern)))
+            .min()
+            .map(|(used, pattern)| (used, pattern.clone()));
+        if let Some((used, pattern)) = oldest
+            && let Some(entries) = self.entries.get_mut(&pattern)
+        {
+            entries.retain(|entry| entry.used != used);
+            if entries.is_empty() {
+                self.entries.remove(&pattern);
+            }
+            self.len -= 1;
+        }
+    }
+}
+
+// A panic while the lock is held can't leave the cache inconsistent, so a poisoned
+// lock is used as it is
+fn lock() -> MutexGuard<'static, PatternCache> {
+    static CACHE: OnceLock<Mutex<PatternCache>> = OnceLock::new();
+    CACHE
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+pub(super) fn cached(pattern: &str, flags: Flags) -> Result<Arc<Regex>, CompileError> {
+    let found = lock().get(pattern, flags);
+    if let Some(regex) = found {
+        return Ok(regex);
+    }
+    // Compile without holding the lock, so other lookups aren't held up
+    let regex = Arc::new(Regex::new(pattern, flags)?);
+    Ok(lock().insert(pattern, flags, regex))
+}
@@ -0,0 +1,60 @@
+use alloc::vec::Vec;
+
+use super::Regex;
+use crate::errors::CompileError;
+use crate::parsing::parse_rift_format;
+
+/// Searches on strings with patterns in rift format, for one-off searches that
+/// don't need a [`Regex`] kept around.
+///
+/// The `r` in the method names is for rift. Patterns are compiled through
+/// [`Regex::cached`], so calling these in a loop doesn't compile the pattern again.
+///
+/// ```
+/// use monster_regex::RiftStrExt;
+///
+/// assert_eq!("hello World".rfind_pattern(r"w\w+/i").unwrap(), Some("World"));
+/// assert_eq!("a,b;c".rsplit_pattern("[,;]/").unwrap(), ["a", "b", "c"]);
+/// ```
+pub trait RiftStrExt {
+    /// Returns the text of the first match of the pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CompileError` if the pattern doesn't compile, or
+    /// `CompileError::InvalidPattern` if it isn't in rift format.
+    fn rfind_pattern(&self, rift: &str) -> Result<Option<&str>, CompileError>;
+
+    /// Returns the pieces of the text separated by matches of the pattern.
+    ///
+    /// # Errors
+    ///
+    /// As for [`rfind_pattern`](Self::rfind_pattern).
+    fn rsplit_pattern(&self, rift: &str) -> Result<Vec<&str>, CompileError>;
+}
+
+impl RiftStrExt for str {
+    fn rfind_pattern(&self, rift: &str) -> Result<Option<&str>, CompileError> {
+        let regex = compile(rift)?;
+        Ok(regex.find(self).map(|m| m.as_str(self)))
+    }
+
+    fn rsplit_pattern(&self, rift: &str) -> Result<Vec<&str>, CompileError> {
+        // Pieces are sliced from `self` rather than taken from `split`, which would
+        // tie them to the regex
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        for m in compile(rift)?.find_all(self) {
+            pieces.push(&self[start..m.start]);
+            start = m.end;
+        }
+        pieces.push(&self[start..]);
+        Ok(pieces)
+    }
+}
+
+fn compile(rift: &str) -> Result<alloc::sync::Arc<Regex>, CompileError> {
+    let (pattern, flags) = parse_rift_format(rift)
+        .map_err(|err| CompileError::InvalidPattern(err.to_diagnostic().message))?;
+    Regex::cached(&pattern, flags)
+}
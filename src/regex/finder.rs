@@ -0,0 +1,132 @@
+use alloc::vec;
+use core::ops::Range;
+
+use super::Regex;
+use crate::captures::Captures;
+use crate::engine::{Marks, MatchContext, Matcher};
+
+/// A single search with its options set one at a time, returned by
+/// [`Regex::finder`].
+///
+/// By default it finds the first match anywhere in the text with its capture
+/// groups, like [`Regex::captures`].
+///
+/// ```
+/// use monster_regex::{Flags, Regex};
+///
+/// let re = Regex::new(r"(\d+)-(\d+)", Flags::default()).unwrap();
+/// let text = "1-2 30-40 500-600";
+/// let caps = re.finder().range(4..text.len()).run(text).unwrap();
+/// assert_eq!(caps.as_str(text, 2), Some("40"));
+///
+/// assert!(re.finder().anchored(true).range(3..9).run(text).is_none());
+/// let m = re.finder().anchored(true).range(4..9).with_captures(false).run(text);
+/// assert_eq!(m.map(|caps| caps.full_match.end), Some(9));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Finder<'r> {
+    regex: &'r Regex,
+    anchored: bool,
+    range: Option<Range<usize>>,
+    captures: bool,
+    earliest: bool,
+    marks: Option<&'r Marks>,
+}
+
+impl<'r> Finder<'r> {
+    pub(super) fn new(regex: &'r Regex) -> Self {
+        Finder {
+            regex,
+            anchored: regex.flags.anchored,
+            range: None,
+            captures: true,
+            earliest: false,
+            marks: None,
+        }
+    }
+
+    /// Only counts matches starting right where the search does, as if compiled
+    /// with [`Flags::anchored`](crate::Flags::anchored).
+    pub fn anchored(mut self, anchored: bool) -> Self {
+        self.anchored = anchored;
+        self
+    }
+
+    /// Searches only the byte range `range` of the text, like
+    /// [`Regex::find_in_range`]. The rest of the text is still visible to anchors,
+    /// word boundaries and lookarounds.
+    pub fn range(mut self, range: Range<usize>) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// Sets whether to collect the pattern's capture groups. Without them the
+    /// result only holds the full match, which saves the copy.
+    ///
+    /// Defaults to `true`.
+    pub fn with_captures(mut self, captures: bool) -> Self {
+        self.captures = captures;
+        self
+    }
+
+    /// Returns the match that ends first instead of the leftmost one, so the search
+    /// can stop as soon as any match is complete.
+    ///
+    /// `a+` finds `aaa` in `"aaa"` normally, and `a` with this set. Patterns the
+    /// DFA can't run, such as ones with backreferences or lookarounds, try every
+    /// end against every start before it, which is slow on long texts.
+    pub fn earliest(mut self, earliest: bool) -> Self {
+        self.earliest = earliest;
+        self
+    }
+
+    /// Sets the cursor, visual area and named marks that `\%#`, `\%V` and `\%'m`
+    /// match at. Without them those atoms never match.
+    pub fn marks(mut self, marks: &'r Marks) -> Self {
+        self.marks = Some(marks);
+        self
+    }
+
+    /// Runs the search over `text`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range's ends are out of bounds or not on character boundaries.
+    pub fn run(&self, text: &str) -> Option<Captures> {
+        let range = self.range.clone().unwrap_or(0..text.len());
+        assert!(
+            text.is_char_boundary(range.start) && text.is_char_boundary(range.end),
+            "range {:?} is not on character boundaries of the text",
+            range
+        );
+        // Every occurrence of a literal is as long as the others, so the leftmost
+        // one is also the one that ends first
+        if let Some(literal) = &self.regex.literal {
+            return literal
+                .find_at(text, range.start)
+                .filter(|m| m.end <= range.end && (!self.anchored || m.start == range.start))
+                .map(|m| Captures::new(m, vec![], []));
+        }
+        let flags = self.regex.flags.anchored(self.anchored);
+        let mut matcher = Matcher::from_program(&self.regex.program, &flags, text)
+            .with_plan(&self.regex.plan)
+            .with_limit(range.end);
+        if let Some(dfa) = &self.regex.dfa {
+            matcher = matcher.with_dfa(dfa);
+        }
+        if let Some(marks) = self.marks {
+            matcher = matcher.with_marks(marks);
+        }
+        let mut context = MatchContext::new(&self.regex.program);
+        let full_match = if self.earliest {
+            matcher.find_earliest(range.start, &mut context)?
+        } else {
+            matcher.find_at(range.start, &mut context)?
+        };
+        Some(if self.captures {
+            matcher.captures(full_match, &context)
+        } else {
+            Captures::new(full_match, vec![], [])
+        })
+    }
+}
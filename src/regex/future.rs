@@ -0,0 +1,70 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use super::Regex;
+use crate::captures::Match;
+use crate::engine::MatchContext;
+
+/// The steps a [`FindFuture`] takes before yielding, unless set with
+/// [`budget`](FindFuture::budget).
+pub const DEFAULT_STEP_BUDGET: usize = 10_000;
+
+/// A search that runs a little at a time as it's polled, returned by
+/// [`Regex::find_async`].
+///
+/// Each poll tries start positions until the backtracking matcher has taken the
+/// step budget, then wakes the task and yields, so a long search shares an async
+/// executor's worker threads instead of blocking one. The attempt at a single
+/// start position isn't split, so a pathological pattern can still take long
+/// between yields.
+pub struct FindFuture<'a> {
+    regex: &'a Regex,
+    text: &'a str,
+    // Where the next poll starts trying positions
+    pos: usize,
+    budget: usize,
+    context: MatchContext,
+}
+
+impl<'a> FindFuture<'a> {
+    pub(super) fn new(regex: &'a Regex, text: &'a str) -> Self {
+        FindFuture {
+            regex,
+            text,
+            pos: 0,
+            budget: DEFAULT_STEP_BUDGET,
+            context: MatchContext::new(&regex.program),
+        }
+    }
+
+    /// Sets how many steps each poll may take before yielding.
+    ///
+    /// Defaults to [`DEFAULT_STEP_BUDGET`].
+    pub fn budget(mut self, steps: usize) -> Self {
+        self.budget = steps;
+        self
+    }
+}
+
+impl Future for FindFuture<'_> {
+    type Output = Option<Match>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        // A substring search never backtracks, so it's done in one go
+        if let Some(literal) = &this.regex.literal {
+            return Poll::Ready(literal.find_at(this.text, this.pos));
+        }
+        let matcher = this.regex.matcher(this.text).with_step_budget(this.budget);
+        let found = matcher.find_at(this.pos, &mut this.context);
+        match matcher.paused_at() {
+            Some(pos) => {
+                this.pos = pos;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            None => Poll::Ready(found),
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use super::Regex;
+use crate::captures::{Captures, Match};
+use crate::engine::{MatchContext, Matcher};
+use alloc::vec;
+use core::ops::Range;
+
+/// How iteration over all matches treats matches of the empty string.
+///
+/// After an empty match the search always resumes one character later, so the same
+/// empty match is never reported twice and UTF-8 sequences are never split.
+///
+/// ```
+/// use monster_regex::{EmptyMatchPolicy, RegexBuilder};
+///
+/// let spans = |policy| {
+///     let re = RegexBuilder::new("x*").empty_matches(policy).build().unwrap();
+///     re.find_all("axb").map(|m| (m.start, m.end)).collect::<Vec<_>>()
+/// };
+/// assert_eq!(spans(EmptyMatchPolicy::Allow), [(0, 0), (1, 2), (2, 2), (3, 3)]);
+/// assert_eq!(spans(EmptyMatchPolicy::SkipAdjacent), [(0, 0), (1, 2), (3, 3)]);
+/// assert_eq!(spans(EmptyMatchPolicy::Skip), [(1, 2)]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyMatchPolicy {
+    /// Every empty match is reported, including one right where the previous match ended.
+    #[default]
+    Allow,
+    /// An empty match right where the previous match ended is skipped.
+    SkipAdjacent,
+    /// Empty matches are never reported.
+    Skip,
+}
+
+/// An iterator over all non-overlapping matches of a regex in a string.
+///
+/// Yields `Match` objects.
+pub struct FindAllIterator<'a> {
+    search: Search<'a>,
+}
+
+impl<'a> FindAllIterator<'a> {
+    pub(super) fn new(regex: &'a Regex, text: &'a str) -> Self {
+        FindAllIterator {
+            search: Search::new(regex, text),
+        }
+    }
+}
+
+impl<'a> Iterator for FindAllIterator<'a> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.search.next_match()
+    }
+}
+
+/// An iterator over all non-overlapping capture groups of a regex in a string.
+///
+/// Yields `Captures` objects.
+pub struct CapturesIterator<'a> {
+    search: Search<'a>,
+}
+
+impl<'a> CapturesIterator<'a> {
+    pub(super) fn new(regex: &'a Regex, text: &'a str) -> Self {
+        CapturesIterator {
+            search: Search::new(regex, text),
+        }
+    }
+}
+
+impl<'a> Iterator for CapturesIterator<'a> {
+    type Item = Captures;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.search.next_captures()
+    }
+}
+
+/// An iterator over the pieces of a string between the matches of a regex.
+///
+/// Yields string slices.
+pub struct SplitIterator<'a> {
+    search: Search<'a>,
+    piece_start: Option<usize>,
+}
+
+impl<'a> SplitIterator<'a> {
+    pub(super) fn new(regex: &'a Regex, text: &'a str) -> Self {
+        SplitIterator {
+            search: Search::new(regex, text),
+            piece_start: Some(0),
+        }
+    }
+}
+
+impl<'a> Iterator for SplitIterator<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.piece_start?;
+        let text = self.search.text;
+        match self.search.next_match() {
+            Some(m) => {
+                self.piece_start = Some(m.end);
+                Some(&text[start..m.start])
+            }
+            None => {
+                self.piece_start = None;
+                Some(&text[start..])
+            }
+        }
+    }
+}
+
+/// A piece of a text split up by [`Regex::partition`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// A match of the regex.
+    Matched(Match),
+    /// Text between matches, or before the first or after the last. Never empty.
+    Gap(Range<usize>),
+}
+
+impl Segment {
+    /// Returns the byte range of the segment.
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            Segment::Matched(m) => m.range(),
+            Segment::Gap(range) => range.clone(),
+        }
+    }
+
+    /// Returns the text of the segment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds of the text or doesn't lie on UTF-8 boundaries.
+    pub fn as_str<'t>(&self, text: &'t str) -> &'t str {
+        &text[self.range()]
+    }
+}
+
+/// An iterator over a string as alternating matches and the gaps between them,
+/// covering all of it in order.
+///
+/// Yields `Segment` values.
+pub struct PartitionIterator<'a> {
+    search: Search<'a>,
+    // Where the next gap starts
+    pos: usize,
+    // A match found after a gap, to be yielded next
+    pending: Option<Match>,
+}
+
+impl<'a> PartitionIterator<'a> {
+    pub(super) fn new(regex: &'a Regex, text: &'a str) -> Self {
+        PartitionIterator {
+            search: Search::new(regex, text),
+            pos: 0,
+            pending: None,
+        }
+    }
+}
+
+impl<'a> Iterator for PartitionIterator<'a> {
+    type Item = Segment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = match self.pending.take() {
+            Some(m) => m,
+            None => match self.search.next_match() {
+                Some(m) if m.start > self.pos => {
+                    let gap = self.pos..m.start;
+                    self.pos = m.start;
+                    self.pending = Some(m);
+                    return Some(Segment::Gap(gap));
+                }
+                Some(m) => m,
+                None => {
+                    let end = self.search.text.len();
+                    if self.pos >= end {
+                        return None;
+                    }
+                    let gap = self.pos..end;
+                    self.pos = end;
+                    return Some(Segment::Gap(gap));
+                }
+            },
+        };
+        self.pos = self.pos.max(m.end);
+        Some(Segment::Matched(m))
+    }
+}
+
+// The state shared by successive searches of one text. The matcher and its
+// context are built once, and every search sees the whole text, so assertions
+// like `^` and lookbehinds behave as they would for a single search.
+pub(super) struct Search<'a> {
+    text: &'a str,
+    regex: &'a Regex,
+    matcher: Matcher<'a>,
+    context: MatchContext,
+    last_end: usize,
+    previous_end: Option<usize>,
+    // Where the last empty match reported was
+    previous_empty: Option<usize>,
+}
+
+impl<'a> Search<'a> {
+    pub(super) fn new(regex: &'a Regex, text: &'a str) -> Self {
+        Search {
+            text,
+            regex,
+            matcher: regex.matcher(text),
+            context: MatchContext::new(&regex.program),
+            last_end: 0,
+            previous_end: None,
+            previous_empty: None,
+        }
+    }
+
+    pub(super) fn next_match(&mut self) -> Option<Match> {
+        loop {
+            if self.last_end > self.text.len() {
+                return None;
+            }
+            // Advance past the text the match consumed rather than the bounds
+            // `\zs`/`\ze` reported, so those can't make the search go backwards
+            let m = match &self.regex.literal {
+                Some(literal) => {
+                    let m = literal.find_at(self.text, self.last_end)?;
+                    self.advance(&m);
+                    m
+                }
+                None => {
+                    let m = self.matcher.find_at(self.last_end, &mut self.context)?;
+                    let consumed = self.context.consumed.clone();
+                    self.advance(&consumed);
+                    m
+                }
+            };
+            // Moving past the consumed text can find the empty match `\zs`/`\ze`
+            // reported again, so that one is skipped under every policy
+            let skip = m.is_empty()
+                && (self.previous_empty == Some(m.start)
+                    || match self.regex.empty_matches {
+                        EmptyMatchPolicy::Allow => false,
+                        EmptyMatchPolicy::SkipAdjacent => self.previous_end == Some(m.start),
+                        EmptyMatchPolicy::Skip => true,
+                    });
+            if !skip {
+                self.previous_end = Some(m.end);
+                self.previous_empty = m.is_empty().then_some(m.start);
+                return Some(m);
+            }
+        }
+    }
+
+    pub(super) fn next_captures(&mut self) -> Option<Captures> {
+        let full_match = self.next_match()?;
+        Some(match &self.regex.literal {
+            Some(_) => Captures::new(full_match, vec![], []),
+            None => self.matcher.captures(full_match, &self.context),
+        })
+    }
+
+    // Where the search resumes, where the last match it returned ended, and where
+    // the last empty one was
+    #[cfg(feature = "std")]
+    pub(super) fn position(&self) -> (usize, Option<usize>, Option<usize>) {
+        (self.last_end, self.previous_end, self.previous_empty)
+    }
+
+    // Carries on from a `position`, with offsets into this search's text
+    #[cfg(feature = "std")]
+    pub(super) fn resume(
+        &mut self,
+        (last_end, previous_end, previous_empty): (usize, Option<usize>, Option<usize>),
+    ) {
+        self.last_end = last_end;
+        self.previous_end = previous_end;
+        self.previous_empty = previous_empty;
+    }
+
+    // Continues the search from `pos`, which must lie on a character boundary
+    pub(super) fn seek(&mut self, pos: usize) {
+        self.last_end = pos;
+    }
+
+    // Continues after the match, or one character past an empty one
+    fn advance(&mut self, m: &Match) {
+        let next_char = self.text[m.start..]
+            .chars()
+            .next()
+            .map_or(1, char::len_utf8);
+        self.last_end = m.end.max(m.start + next_char);
+    }
+}
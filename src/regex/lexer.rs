@@ -0,0 +1,164 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use super::iter::Search;
+use super::{OverlapPolicy, Regex};
+use crate::captures::Match;
+use crate::errors::CompileError;
+use crate::flags::Flags;
+
+/// Splits text into tokens with an ordered list of named patterns.
+///
+/// At each position every rule is tried there, and the longest match becomes the
+/// next token, with ties going to the rule listed first, as in `lex`. With
+/// [`OverlapPolicy::LeftmostFirst`] the first rule that matches wins instead. Empty
+/// matches never make a token.
+///
+/// Text no rule matches is returned as a token without a name, running up to the
+/// next position where a rule matches, so lexing always reaches the end of the text.
+///
+/// ```
+/// use monster_regex::{Flags, Lexer};
+///
+/// let lexer = Lexer::new(
+///     [("number", r"\d+"), ("name", r"\a\w*"), ("space", r"\s+")],
+///     Flags::default(),
+/// )
+/// .unwrap();
+/// let tokens: Vec<_> = lexer
+///     .tokens("x1 42 ?!")
+///     .map(|token| (token.name, token.span.start))
+///     .collect();
+/// assert_eq!(
+///     tokens,
+///     [
+///         (Some("name"), 0),
+///         (Some("space"), 2),
+///         (Some("number"), 3),
+///         (Some("space"), 5),
+///         (None, 6),
+///     ]
+/// );
+/// ```
+pub struct Lexer {
+    rules: Vec<(String, Regex)>,
+    overlap: OverlapPolicy,
+}
+
+/// A piece of text a [`Lexer`] split off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'l> {
+    /// The name of the rule that matched, or `None` for text no rule matches.
+    pub name: Option<&'l str>,
+    /// Where the token is in the text.
+    pub span: Match,
+}
+
+impl Lexer {
+    /// Compiles the rules, given as `(name, pattern)` pairs in order of priority.
+    /// Every pattern is compiled with `flags`, anchored so it only matches where the
+    /// lexer is.
+    ///
+    /// Uses [`OverlapPolicy::LeftmostLongest`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the `CompileError` of the first pattern that fails to compile.
+    pub fn new<'a>(
+        rules: impl IntoIterator<Item = (&'a str, &'a str)>,
+        flags: Flags,
+    ) -> Result<Self, CompileError> {
+        let rules = rules
+            .into_iter()
+            .map(|(name, pattern)| {
+                Ok((name.to_string(), Regex::new(pattern, flags.anchored(true))?))
+            })
+            .collect::<Result<_, CompileError>>()?;
+        Ok(Lexer {
+            rules,
+            overlap: OverlapPolicy::LeftmostLongest,
+        })
+    }
+
+    /// Sets how rules matching at the same position are chosen between.
+    pub fn overlap(mut self, policy: OverlapPolicy) -> Self {
+        self.overlap = policy;
+        self
+    }
+
+    /// Returns an iterator over the tokens of the text.
+    pub fn tokens<'l>(&'l self, text: &'l str) -> Tokens<'l> {
+        Tokens {
+            lexer: self,
+            text,
+            searches: self
+                .rules
+                .iter()
+                .map(|(_, regex)| Search::new(regex, text))
+                .collect(),
+            pos: 0,
+        }
+    }
+}
+
+/// An iterator over the tokens a [`Lexer`] splits a text into.
+pub struct Tokens<'l> {
+    lexer: &'l Lexer,
+    text: &'l str,
+    searches: Vec<Search<'l>>,
+    pos: usize,
+}
+
+impl<'l> Tokens<'l> {
+    // Returns the rule that makes the token at `pos` and where the token ends
+    fn token_at(&mut self, pos: usize) -> Option<(usize, usize)> {
+        let longest = self.lexer.overlap == OverlapPolicy::LeftmostLongest;
+        self.searches
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(rule, search)| {
+                search.seek(pos);
+                let m = search.next_match()?;
+                (m.start == pos && m.end > pos).then_some((rule, m.end))
+            })
+            // Ties go to the first rule
+            .min_by_key(|&(_, end)| Reverse(if longest { end } else { 0 }))
+    }
+}
+
+impl<'l> Iterator for Tokens<'l> {
+    type Item = Token<'l>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pos;
+        if start >= self.text.len() {
+            return None;
+        }
+        if let Some((rule, end)) = self.token_at(start) {
+            self.pos = end;
+            return Some(Token {
+                name: Some(&self.lexer.rules[rule].0),
+                span: Match { start, end },
+            });
+        }
+
+        // Skip to where a rule matches again
+        loop {
+            self.pos += self.text[self.pos..]
+                .chars()
+                .next()
+                .map_or(1, char::len_utf8);
+            if self.pos >= self.text.len() || self.token_at(self.pos).is_some() {
+                break;
+            }
+        }
+        Some(Token {
+            name: None,
+            span: Match {
+                start,
+                end: self.pos,
+            },
+        })
+    }
+}
@@ -1,82 +1,142 @@
+mod builder;
+mod bytes;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+mod ext;
+mod finder;
+#[cfg(feature = "async")]
+mod future;
+mod iter;
+mod lexer;
+#[cfg(feature = "std")]
+mod reader;
+mod replace;
+mod rewrite;
+mod set;
+mod style;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+pub use builder::RegexBuilder;
+#[cfg(feature = "std")]
+pub use ext::RiftStrExt;
+pub use finder::Finder;
+#[cfg(feature = "async")]
+pub use future::{DEFAULT_STEP_BUDGET, FindFuture};
+use iter::Search;
+pub use iter::{
+    CapturesIterator, EmptyMatchPolicy, FindAllIterator, PartitionIterator, Segment, SplitIterator,
+};
+pub use lexer::{Lexer, Token, Tokens};
+#[cfg(feature = "std")]
+pub use reader::ReaderMatches;
+pub use replace::{Decision, Edit};
+pub use rewrite::{OverlapPolicy, Rewriter};
+pub use set::RegexSet;
+pub use style::MatchStyle;
+
+use alloc::sync::Arc;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
 use crate::captures::{Captures, Match};
-use crate::engine::Matcher;
-use crate::errors::CompileError;
+use crate::compiler::{self, BackrefPolicy, CompileOptions, PatternInfo, ScanPlan};
+use crate::engine::{
+    Automaton, EngineKind, LazyDfa, LiteralSearcher, MatchContext, MatchStats, Matcher,
+    MatchingStrings, Program, Trace, TraceEvent, enumeration_alphabet, product_reaches,
+    representative_alphabet,
+};
+#[cfg(feature = "std")]
+use crate::errors::MatchError;
+use crate::errors::{CompileError, DecodeError, ExportError};
 use crate::flags::Flags;
-use crate::parser::{AstNode, Parser};
+use crate::parser::{
+    AstNode, CharClass, MAX_NESTING_DEPTH, ParseError, Parser, explain, fold, to_pattern, to_pcre,
+    to_rust_regex,
+};
 
-/// An iterator over all non-overlapping matches of a regex in a string.
+/// A compiled regular expression.
+///
+/// This struct represents a parsed and compiled regex pattern, ready to be used for matching against text.
 ///
-/// Yields `Match` objects.
-pub struct FindAllIterator<'a> {
-    text: &'a str,
-    regex: &'a Regex,
-    last_end: usize,
+/// A `Regex` never changes once compiled. It is `Send` and `Sync`, so one instance
+/// can be shared by any number of threads, and cloning it only shares the compiled
+/// program rather than copying it. Regexes compare equal if their patterns and flags
+/// are the same, and display in rift format.
+///
+/// ```
+/// use monster_regex::{Flags, Regex};
+///
+/// let re = Regex::new("a/b", Flags::default().global(true)).unwrap();
+/// let copy = re.clone();
+/// assert_eq!(re, copy);
+/// // The lowercase pattern turned on `i` through smartcase
+/// assert_eq!(re.to_string(), r"a\/b/ig");
+/// std::thread::spawn(move || assert!(copy.is_match("a/b")))
+///     .join()
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Regex {
+    pattern: Arc<str>,
+    flags: Flags,
+    plan: Arc<ScanPlan>,
+    program: Arc<Program>,
+    dfa: Option<Arc<LazyDfa>>,
+    engine: EngineKind,
+    literal: Option<Arc<LiteralSearcher>>,
+    info: Arc<PatternInfo>,
+    empty_matches: EmptyMatchPolicy,
+    // Kept for `to_bytes`, which saves the optimized AST and the options to check it
+    // with, and for translating the pattern to other syntaxes
+    ast: Arc<[AstNode]>,
+    repetition_limit: usize,
 }
 
-impl<'a> Iterator for FindAllIterator<'a> {
-    type Item = Match;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.last_end > self.text.len() {
-            return None;
-        }
-        let m = self.regex.find(&self.text[self.last_end..])?;
-        let adjusted = Match {
-            start: self.last_end + m.start,
-            end: self.last_end + m.end,
-        };
-        self.last_end = adjusted.end.max(adjusted.start + 1);
-        Some(adjusted)
+impl fmt::Debug for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Regex")
+            .field("pattern", &self.pattern)
+            .field("flags", &self.flags)
+            .field("engine", &self.engine)
+            .finish()
     }
 }
 
-/// An iterator over all non-overlapping capture groups of a regex in a string.
-///
-/// Yields `Captures` objects.
-pub struct CapturesIterator<'a> {
-    text: &'a str,
-    regex: &'a Regex,
-    last_end: usize,
-}
-
-impl<'a> Iterator for CapturesIterator<'a> {
-    type Item = Captures;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.last_end > self.text.len() {
-            return None;
-        }
-        let caps = self.regex.captures(&self.text[self.last_end..])?;
-        let offset = self.last_end;
-        self.last_end = offset + caps.full_match.end;
-        self.last_end = self.last_end.max(offset + caps.full_match.start + 1);
-
-        // Adjust all match positions by offset
-        let mut adjusted_caps = caps;
-        adjusted_caps.full_match.start += offset;
-        adjusted_caps.full_match.end += offset;
-        for m in &mut adjusted_caps.groups.iter_mut().flatten() {
-            m.start += offset;
-            m.end += offset;
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Slashes in the pattern are escaped so the last one separates the flags
+        let mut escaped = false;
+        for c in self.pattern.chars() {
+            if c == '/' && !escaped {
+                f.write_str("\\")?;
+            }
+            escaped = c == '\\' && !escaped;
+            write!(f, "{}", c)?;
         }
-        for m in adjusted_caps.named.values_mut() {
-            m.start += offset;
-            m.end += offset;
-        }
-
-        Some(adjusted_caps)
+        write!(f, "/{}", self.flags)
     }
 }
 
-/// A compiled regular expression.
-///
-/// This struct represents a parsed and compiled regex pattern, ready to be used for matching against text.
-pub struct Regex {
-    pattern: String,
-    flags: Flags,
-    ast: Vec<AstNode>,
+// Equal regexes have the same pattern, flags and the builder options that change
+// what they match. Limits only decide whether a pattern compiles, so they aren't compared.
+impl PartialEq for Regex {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+            && self.flags == other.flags
+            && self.empty_matches == other.empty_matches
+            && self.program.lookaround_captures == other.program.lookaround_captures
+            && self.program.unset_backrefs_match_empty == other.program.unset_backrefs_match_empty
+    }
 }
 
+impl Eq for Regex {}
+
 impl Regex {
     /// Compiles a regex pattern with the specified flags.
     ///
@@ -88,72 +148,703 @@ impl Regex {
     /// # Returns
     ///
     /// Returns a `Result` containing the compiled `Regex` or a `CompileError` if the pattern is invalid.
-    pub fn new(pattern: &str, mut flags: Flags) -> Result<Self, CompileError> {
-        // Smartcase: if no explicit case flag, infer from pattern
-        if flags.ignore_case.is_none() {
-            let has_uppercase = pattern.chars().any(|c| c.is_uppercase());
-            flags.ignore_case = Some(!has_uppercase);
-        }
+    ///
+    /// Use [`RegexBuilder`] to compile with non-default options such as the repetition limit.
+    pub fn new(pattern: &str, flags: Flags) -> Result<Self, CompileError> {
+        Self::compile(pattern, flags, &CompileOptions::default())
+    }
 
+    /// Compiles a pattern like [`new`](Self::new), or returns the regex compiled by an
+    /// earlier call with the same pattern and flags.
+    ///
+    /// Compiled regexes are kept in a cache shared by all threads, which holds the
+    /// 128 most recently used. Patterns that fail to compile aren't cached.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let first = Regex::cached(r"\d+", Flags::default()).unwrap();
+    /// let second = Regex::cached(r"\d+", Flags::default()).unwrap();
+    /// assert!(std::sync::Arc::ptr_eq(&first, &second));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn cached(pattern: &str, flags: Flags) -> Result<Arc<Self>, CompileError> {
+        cache::cached(pattern, flags)
+    }
+
+    /// Compiles a regex from an already-built AST, skipping the textual parse.
+    ///
+    /// The AST goes through the same validation as a parsed pattern, and
+    /// [`pattern`](Regex::pattern) returns its serialized form.
+    ///
+    /// ```
+    /// use monster_regex::{AstNode, CharClass, Flags, Regex};
+    ///
+    /// let ast = vec![
+    ///     AstNode::Literal('v'),
+    ///     AstNode::OneOrMore {
+    ///         node: Box::new(AstNode::CharClass(CharClass::Digit)),
+    ///         greedy: true,
+    ///     },
+    /// ];
+    /// let re = Regex::from_ast(ast, Flags::default()).unwrap();
+    /// assert_eq!(re.pattern(), r"v\d+");
+    /// assert!(re.is_match("v42"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CompileError` if the AST fails validation, e.g. a bounded quantifier
+    /// with `min > max` or two capture groups sharing a name or index.
+    pub fn from_ast(nodes: Vec<AstNode>, flags: Flags) -> Result<Self, CompileError> {
+        let options = CompileOptions::default();
+        // Printing the pattern recurses through the tree, so its depth is checked first
+        compiler::validate(&nodes, &options)?;
+        let pattern = to_pattern(&nodes);
+        Self::compile_ast(pattern, nodes, flags, &options)
+    }
+
+    /// Saves the compiled regex as bytes that [`from_bytes`](Self::from_bytes) loads
+    /// without parsing or optimizing the pattern again, so tools can ship patterns
+    /// precompiled.
+    ///
+    /// The bytes keep the options the regex was built with, and are only read by
+    /// builds that use the same format version.
+    ///
+    /// ```
+    /// use monster_regex::{EmptyMatchPolicy, Regex, RegexBuilder};
+    ///
+    /// let re = RegexBuilder::new(r"(?<word>\w+)|x*")
+    ///     .empty_matches(EmptyMatchPolicy::Skip)
+    ///     .build()
+    ///     .unwrap();
+    /// let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+    /// assert_eq!(loaded, re);
+    /// let words: Vec<_> = loaded.find_all("ab, cd").map(|m| (m.start, m.end)).collect();
+    /// assert_eq!(words, [(0, 2), (4, 6)]);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bytes::encode(self)
+    }
+
+    /// Loads a regex saved with [`to_bytes`](Self::to_bytes).
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DecodeError` if the bytes weren't written by `to_bytes` in this
+    /// format version or were corrupted. The loaded AST is validated like one passed
+    /// to [`from_ast`](Self::from_ast).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let decoded = bytes::decode(bytes)?;
+        compiler::validate(&decoded.ast, &decoded.options).map_err(DecodeError::Invalid)?;
+        Ok(Self::assemble(
+            decoded.pattern,
+            decoded.ast,
+            decoded.flags,
+            &decoded.options,
+        ))
+    }
+
+    pub(crate) fn compile(
+        pattern: &str,
+        flags: Flags,
+        options: &CompileOptions,
+    ) -> Result<Self, CompileError> {
+        if pattern.len() > options.size_limit {
+            return Err(CompileError::ExceededLimits(format!(
+                "pattern is {} bytes long, over the limit of {}",
+                pattern.len(),
+                options.size_limit
+            )));
+        }
         let mut parser = Parser::new(pattern, flags);
-        let ast = parser
-            .parse()
-            .map_err(|e| CompileError::InvalidPattern(e.to_string()))?;
+        parser.set_nest_limit(options.nest_limit);
+        let ast = parser.parse().map_err(|e| match e {
+            ParseError::NestingTooDeep => {
+                compiler::nesting_exceeded(options.nest_limit.min(MAX_NESTING_DEPTH))
+            }
+            e => CompileError::InvalidPattern(e.to_string()),
+        })?;
+        Self::compile_ast(pattern.to_string(), ast, flags, options)
+    }
+
+    fn compile_ast(
+        pattern: String,
+        ast: Vec<AstNode>,
+        flags: Flags,
+        options: &CompileOptions,
+    ) -> Result<Self, CompileError> {
+        compiler::validate(&ast, options)?;
+        let flags = Self::resolve_case(&ast, flags);
+        let ast = compiler::optimize(ast);
+        let regex = Self::assemble(pattern, ast, flags, options);
+        let program_size = regex.program.insts.len();
+        if program_size > options.program_size_limit {
+            return Err(CompileError::ExceededLimits(format!(
+                "pattern compiles to {} instructions, over the limit of {}",
+                program_size, options.program_size_limit
+            )));
+        }
+        Ok(regex)
+    }
 
-        Ok(Regex {
-            pattern: pattern.to_string(),
+    // Builds the compiled structures from an optimized AST and resolved flags
+    fn assemble(
+        pattern: String,
+        ast: Vec<AstNode>,
+        flags: Flags,
+        options: &CompileOptions,
+    ) -> Self {
+        let plan = ScanPlan::new(&ast, &flags);
+        let info = PatternInfo::new(&ast, &flags, &plan);
+        let mut program = Program::new(&ast, &flags);
+        program.lookaround_captures = options.lookaround_captures;
+        program.unset_backrefs_match_empty = options.backrefs == BackrefPolicy::MatchEmpty;
+        let dfa = LazyDfa::new(&ast, &flags);
+        // The literal search looks for the text anywhere, so anchored searches skip it
+        let literal = compiler::literal_text(&ast)
+            .filter(|_| !flags.anchored)
+            .map(|text| LiteralSearcher::new(text, &flags));
+        let engine = match (&literal, &dfa) {
+            (Some(_), _) => EngineKind::Literal,
+            (None, Some(_)) => EngineKind::Dfa,
+            (None, None) => EngineKind::Backtracking,
+        };
+
+        Regex {
+            pattern: pattern.into(),
             flags,
-            ast,
-        })
+            plan: Arc::new(plan),
+            program: Arc::new(program),
+            dfa: dfa.map(Arc::new),
+            engine,
+            literal: literal.map(Arc::new),
+            info: Arc::new(info),
+            empty_matches: options.empty_matches,
+            ast: ast.into(),
+            repetition_limit: options.repetition_limit,
+        }
+    }
+
+    // Smartcase: if no explicit case flag, infer from the characters the pattern
+    // matches, however they're written. Escapes like `\L` or `\N{...}` only count
+    // for the character they stand for.
+    pub(crate) fn resolve_case(nodes: &[AstNode], mut flags: Flags) -> Flags {
+        if flags.ignore_case.is_none() {
+            let has_uppercase = fold(nodes, false, &mut |found, node| {
+                found
+                    || match node {
+                        AstNode::Literal(c) => c.is_uppercase(),
+                        AstNode::LiteralString(s) => s.chars().any(char::is_uppercase),
+                        AstNode::CharClass(CharClass::Set { chars, .. }) => chars
+                            .iter()
+                            .any(|range| range.start.is_uppercase() || range.end.is_uppercase()),
+                        _ => false,
+                    }
+            });
+            flags.ignore_case = Some(!has_uppercase);
+        }
+        flags
     }
 
     /// Checks if the regex matches anywhere in the given text.
     ///
     /// Returns `true` if a match is found, `false` otherwise.
+    ///
+    /// Patterns without backreferences, lookarounds or `\zs`/`\ze` are answered by a
+    /// DFA in a single pass over the text, without backtracking.
     pub fn is_match(&self, text: &str) -> bool {
-        self.find(text).is_some()
+        // Neither shortcut tells whether the only matches are empty ones
+        if self.empty_matches == EmptyMatchPolicy::Skip {
+            return self.find(text).is_some();
+        }
+        if let Some(literal) = &self.literal {
+            return literal.find_at(text, 0).is_some();
+        }
+        match &self.dfa {
+            Some(dfa) if self.flags.anchored => dfa.matches_at(text, 0),
+            Some(dfa) => dfa.earliest_end(text, 0).is_some(),
+            None => self.find(text).is_some(),
+        }
+    }
+
+    /// Checks if the regex matches the whole text, as if the pattern were wrapped in
+    /// `^(?:` and `)$` with multiline off, for validation like "is this a valid
+    /// identifier".
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let ident = Regex::new(r"[a-z_]\w*", Flags::default()).unwrap();
+    /// assert!(ident.is_full_match("snake_case"));
+    /// assert!(!ident.is_full_match("two words"));
+    /// // Any way through the pattern counts, not only the one `find` takes
+    /// let re = Regex::new("a|ab", Flags::default()).unwrap();
+    /// assert!(re.is_full_match("ab"));
+    /// ```
+    pub fn is_full_match(&self, text: &str) -> bool {
+        self.full_match(text).is_some()
+    }
+
+    /// Returns the match covering the whole text, if the regex matches all of it.
+    /// See [`is_full_match`](Self::is_full_match).
+    ///
+    /// The match spans the whole text unless the pattern moves its bounds with
+    /// `\zs` or `\ze`.
+    pub fn full_match(&self, text: &str) -> Option<Match> {
+        if let Some(literal) = &self.literal {
+            return literal
+                .find_at(text, 0)
+                .filter(|m| m.start == 0 && m.end == text.len());
+        }
+        Matcher::from_program(&self.program, &self.flags, text)
+            .find_whole(&mut MatchContext::new(&self.program))
     }
 
     /// Finds the first occurrence of the regex in the text.
     ///
-    /// Returns `Some(Match)` if a match is found, or `None` otherwise.
+    /// Returns `Some(Match)` if a match is found, or `None` otherwise. Under
+    /// [`EmptyMatchPolicy::Skip`] an empty match isn't one, as in
+    /// [`find_all`](Self::find_all).
     pub fn find(&self, text: &str) -> Option<Match> {
-        let matcher = Matcher::new(&self.ast, &self.flags, text);
-        matcher.find()
+        Search::new(self, text).next_match()
+    }
+
+    /// Finds the first match lying entirely within the byte range `range` of the text.
+    ///
+    /// Only the range is searched, but the rest of the text is still visible to
+    /// anchors, word boundaries and lookarounds, so `^` only matches at the range's
+    /// start if a line starts there, and a lookbehind can see what comes before it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range's ends are out of bounds or not on character boundaries.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"(?<=\$)\d+", Flags::default()).unwrap();
+    /// let text = "$12 $345";
+    /// assert_eq!(re.find_in_range(text, 5..8).map(|m| (m.start, m.end)), Some((5, 8)));
+    /// // The match is cut short at the range's end
+    /// assert_eq!(re.find_in_range(text, 1..2).map(|m| (m.start, m.end)), Some((1, 2)));
+    /// // Nothing before 2 is a `$`, though the range starts on a digit
+    /// assert_eq!(re.find_in_range(text, 2..4), None);
+    /// ```
+    pub fn find_in_range(&self, text: &str, range: core::ops::Range<usize>) -> Option<Match> {
+        assert!(
+            text.is_char_boundary(range.start) && text.is_char_boundary(range.end),
+            "range {:?} is not on character boundaries of the text",
+            range
+        );
+        if let Some(literal) = &self.literal {
+            return literal
+                .find_at(text, range.start)
+                .filter(|m| m.end <= range.end);
+        }
+        self.matcher(text)
+            .with_limit(range.end)
+            .find_at(range.start, &mut MatchContext::new(&self.program))
+    }
+
+    /// Starts a search whose options are set one at a time, for when the plain
+    /// methods don't cover the combination needed. See [`Finder`].
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"\w+", Flags::default()).unwrap();
+    /// let text = "foo bar";
+    /// let m = re.finder().anchored(true).range(4..7).earliest(true).run(text);
+    /// assert_eq!(m.map(|caps| caps.full_match.as_str(text)), Some("b"));
+    /// ```
+    pub fn finder(&self) -> Finder<'_> {
+        Finder::new(self)
+    }
+
+    fn matcher<'t>(&'t self, text: &'t str) -> Matcher<'t> {
+        let matcher = Matcher::from_program(&self.program, &self.flags, text).with_plan(&self.plan);
+        match &self.dfa {
+            Some(dfa) => matcher.with_dfa(dfa),
+            None => matcher,
+        }
+    }
+
+    /// Finds the first match like [`find`](Self::find), and counts the work the
+    /// search took. See [`MatchStats`].
+    ///
+    /// Patterns searched as plain strings never run the backtracking matcher and
+    /// report all counters as zero.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// // The lookbehind can never match, so both patterns fail everywhere
+    /// let text = "aaaaaaaaaaaa!";
+    /// let nested = Regex::new("(?:a|aa)+(?<=b)", Flags::default()).unwrap();
+    /// let flat = Regex::new("a+(?<=b)", Flags::default()).unwrap();
+    /// let (_, nested_stats) = nested.find_with_stats(text);
+    /// let (_, flat_stats) = flat.find_with_stats(text);
+    /// assert_eq!(nested_stats.positions_attempted, flat_stats.positions_attempted);
+    /// assert!(nested_stats.backtracks > 10 * flat_stats.backtracks);
+    /// ```
+    pub fn find_with_stats(&self, text: &str) -> (Option<Match>, MatchStats) {
+        if let Some(literal) = &self.literal {
+            return (literal.find_at(text, 0), MatchStats::default());
+        }
+        let matcher = self.matcher(text).with_stats();
+        let found = matcher.find();
+        (found, matcher.stats())
+    }
+
+    /// Finds the first match like [`find`](Self::find), as a future that yields to
+    /// the executor between bursts of work. See [`FindFuture`].
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// # async fn run() {
+    /// let re = Regex::new(r"(\w)\1", Flags::default()).unwrap();
+    /// let found = re.find_async("abccd").await;
+    /// assert_eq!(found.map(|m| m.start), Some(2));
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn find_async<'a>(&'a self, text: &'a str) -> FindFuture<'a> {
+        FindFuture::new(self, text)
+    }
+
+    /// Finds the first match like [`find`](Self::find), giving up once the search
+    /// has run for `timeout`, so a pathological pattern can't freeze an interactive
+    /// program.
+    ///
+    /// The clock is read every so many steps of the backtracking matcher, which is
+    /// the only part of a search that can take more than linear time, so a search
+    /// that finishes quickly succeeds even with a zero timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MatchError::Timeout` if the search ran past the timeout.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use monster_regex::{Flags, MatchError, Regex};
+    ///
+    /// let re = Regex::new("(?:a|aa)+(?<=b)", Flags::default()).unwrap();
+    /// let text = "a".repeat(60);
+    /// assert_eq!(
+    ///     re.find_with_timeout(&text, Duration::from_millis(10)),
+    ///     Err(MatchError::Timeout)
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn find_with_timeout(
+        &self,
+        text: &str,
+        timeout: Duration,
+    ) -> Result<Option<Match>, MatchError> {
+        if let Some(literal) = &self.literal {
+            return Ok(literal.find_at(text, 0));
+        }
+        let matcher = self.matcher(text);
+        // A timeout too long to represent never runs out
+        let matcher = match Instant::now().checked_add(timeout) {
+            Some(deadline) => matcher.with_deadline(deadline),
+            None => matcher,
+        };
+        let found = matcher.find();
+        match matcher.timed_out() {
+            true => Err(MatchError::Timeout),
+            false => Ok(found),
+        }
+    }
+
+    /// Finds the first match like [`find`](Self::find), reporting each step of the
+    /// backtracking matcher to `on_event`.
+    ///
+    /// Traced searches skip the literal search, DFA and start-position filters, so
+    /// every start position is attempted in turn. The match found is the same.
+    pub fn find_traced(&self, text: &str, mut on_event: impl FnMut(TraceEvent)) -> Option<Match> {
+        let matcher =
+            Matcher::from_program(&self.program, &self.flags, text).with_tracer(&mut on_event);
+        matcher.find_at(0, &mut MatchContext::new(&self.program))
+    }
+
+    /// Finds the first match and records every step the matcher took on the way.
+    /// See [`find_traced`](Self::find_traced).
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex, TraceEvent};
+    ///
+    /// let re = Regex::new("a+b", Flags::default()).unwrap();
+    /// let trace = re.trace("xaab");
+    /// assert_eq!(trace.result.map(|m| (m.start, m.end)), Some((1, 4)));
+    /// assert_eq!(trace.events[0], TraceEvent::Attempt { start: 0 });
+    /// assert!(trace.events.contains(&TraceEvent::Attempt { start: 1 }));
+    /// ```
+    pub fn trace(&self, text: &str) -> Trace {
+        let mut events = vec![];
+        let result = self.find_traced(text, |event| events.push(event));
+        Trace { events, result }
     }
 
     /// Returns an iterator over all non-overlapping matches in the text.
     pub fn find_all<'a>(&'a self, text: &'a str) -> FindAllIterator<'a> {
-        FindAllIterator {
-            text,
-            regex: self,
-            last_end: 0,
+        FindAllIterator::new(self, text)
+    }
+
+    /// Returns an iterator over the text of all non-overlapping matches, like
+    /// [`find_all`](Self::find_all) with each match turned into its substring.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"\d+", Flags::default()).unwrap();
+    /// let numbers: Vec<_> = re.matches_str("1 22 x 333").collect();
+    /// assert_eq!(numbers, ["1", "22", "333"]);
+    /// ```
+    pub fn matches_str<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.find_all(text).map(|m| m.as_str(text))
+    }
+
+    /// Returns the number of non-overlapping matches in the text, the ones
+    /// [`find_all`](Self::find_all) would return. Only match bounds are found, not
+    /// capture groups.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new("error", Flags::default()).unwrap();
+    /// assert_eq!(re.count("Error: disk error\nok\nerror"), 3);
+    /// ```
+    pub fn count(&self, text: &str) -> usize {
+        let mut search = Search::new(self, text);
+        let mut count = 0;
+        while search.next_match().is_some() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns an iterator over the byte offsets where the non-overlapping matches
+    /// in the text start.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new("ab", Flags::default()).unwrap();
+    /// let starts: Vec<_> = re.positions("abcab").collect();
+    /// assert_eq!(starts, [0, 3]);
+    /// ```
+    pub fn positions<'a>(&'a self, text: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.find_all(text).map(|m| m.start)
+    }
+
+    /// Returns an iterator over all non-overlapping matches in text read from
+    /// `reader`, with byte offsets from the start of the input.
+    ///
+    /// Text is read a chunk at a time and discarded once no match can start in it,
+    /// so large inputs don't have to fit in memory, and matches spanning chunks are
+    /// found as they would be in the whole text. Whether more input could still
+    /// change a match is decided by the DFA; patterns it can't handle
+    /// (backreferences, lookarounds and `\zs`/`\ze`) are searched once the whole
+    /// input is read.
+    ///
+    /// The iterator yields an error and stops if reading fails or the input isn't
+    /// valid UTF-8.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"\d+", Flags::default()).unwrap();
+    /// // A tiny buffer, so matches cross the chunks it reads
+    /// let reader = std::io::BufReader::with_capacity(2, "ab 1234 c 56".as_bytes());
+    /// let spans: Vec<_> = re
+    ///     .find_reader(reader)
+    ///     .map(|m| m.map(|m| (m.start, m.end)))
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(spans, [(3, 7), (10, 12)]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn find_reader<R: BufRead>(&self, reader: R) -> ReaderMatches<'_, R> {
+        ReaderMatches::new(self, reader)
+    }
+
+    /// Finds all matches in each of the haystacks, searching them on several
+    /// threads at once. Returns the matches of each haystack, in the same order as
+    /// the haystacks.
+    ///
+    /// The haystacks are split into one batch per available core, each searched on
+    /// a scoped thread started for this call, so this pays off for many haystacks
+    /// or long ones. The regex is shared between the threads rather than copied. A
+    /// panic on one of them is resumed on the calling thread.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"\d+", Flags::default()).unwrap();
+    /// let found = re.find_in_all(&["a1 b22", "none", "333"]);
+    /// let counts: Vec<_> = found.iter().map(Vec::len).collect();
+    /// assert_eq!(counts, [2, 0, 1]);
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn find_in_all<S: AsRef<str> + Sync>(&self, haystacks: &[S]) -> Vec<Vec<Match>> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let batch = haystacks.len().div_ceil(threads).max(1);
+        let search = |batch: &[S]| -> Vec<Vec<Match>> {
+            batch
+                .iter()
+                .map(|text| self.find_all(text.as_ref()).collect())
+                .collect()
+        };
+        if haystacks.len() <= batch {
+            return search(haystacks);
         }
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = haystacks
+                .chunks(batch)
+                .map(|batch| scope.spawn(move || search(batch)))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        })
     }
 
     /// Finds the first match and returns the capture groups.
     ///
     /// Returns `Some(Captures)` if a match is found, containing the full match and any captured groups.
     /// Returns `None` if no match is found.
-    pub fn captures(&self, _text: &str) -> Option<Captures> {
-        // TODO: Implement with group extraction in Matcher
-        None
+    pub fn captures(&self, text: &str) -> Option<Captures> {
+        Search::new(self, text).next_captures()
+    }
+
+    /// Finds the first match and returns its text along with the text of each of the
+    /// pattern's `N` capture groups, for parsing a line in one go.
+    ///
+    /// A group that didn't take part in the match gives an empty string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern doesn't have exactly `N` capture groups.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"(\d+)-(\d+)-(\d+)", Flags::default()).unwrap();
+    /// let (date, [year, month, day]) = re.extract("due 2024-06-01").unwrap();
+    /// assert_eq!((date, year, month, day), ("2024-06-01", "2024", "06", "01"));
+    /// ```
+    pub fn extract<'t, const N: usize>(&self, text: &'t str) -> Option<(&'t str, [&'t str; N])> {
+        assert_eq!(
+            self.program.group_count, N,
+            "the pattern has {} capture groups, not {}",
+            self.program.group_count, N
+        );
+        let captures = self.captures(text)?;
+        let groups = core::array::from_fn(|i| captures.as_str(text, i + 1).unwrap_or(""));
+        Some((captures.full_match.as_str(text), groups))
     }
 
     /// Returns an iterator over all non-overlapping matches, yielding capture groups for each match.
     pub fn captures_all<'a>(&'a self, text: &'a str) -> CapturesIterator<'a> {
-        CapturesIterator {
-            text,
-            regex: self,
-            last_end: 0,
+        CapturesIterator::new(self, text)
+    }
+
+    /// Returns an iterator over the text as alternating matches and the gaps
+    /// between them, covering all of it in order, for highlighting matches or
+    /// marking them up.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex, Segment};
+    ///
+    /// let re = Regex::new(r"\d+", Flags::default()).unwrap();
+    /// let text = "a1b22";
+    /// let marked: String = re
+    ///     .partition(text)
+    ///     .map(|segment| match segment {
+    ///         Segment::Matched(m) => format!("[{}]", m.as_str(text)),
+    ///         Segment::Gap(range) => text[range].to_string(),
+    ///     })
+    ///     .collect();
+    /// assert_eq!(marked, "a[1]b[22]");
+    /// ```
+    pub fn partition<'a>(&'a self, text: &'a str) -> PartitionIterator<'a> {
+        PartitionIterator::new(self, text)
+    }
+
+    /// Returns the text with every match wrapped in the style's ANSI color codes or
+    /// markers, for showing matches in a terminal or markup. Empty matches are left
+    /// unmarked.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, MatchStyle, Regex};
+    ///
+    /// let re = Regex::new(r"\d+", Flags::default()).unwrap();
+    /// assert_eq!(
+    ///     re.highlight("a1b22", &MatchStyle::markers("<mark>", "</mark>")),
+    ///     "a<mark>1</mark>b<mark>22</mark>"
+    /// );
+    /// assert_eq!(
+    ///     re.highlight("a1", &MatchStyle::default()),
+    ///     "a\x1b[1;31m1\x1b[0m"
+    /// );
+    /// ```
+    pub fn highlight(&self, text: &str, style: &MatchStyle) -> String {
+        let open = style.open();
+        let mut result = String::with_capacity(text.len());
+        for segment in self.partition(text) {
+            match segment {
+                Segment::Matched(m) if !m.is_empty() => {
+                    result.push_str(&open);
+                    result.push_str(m.as_str(text));
+                    result.push_str(style.close());
+                }
+                segment => result.push_str(segment.as_str(text)),
+            }
         }
+        result
     }
 
-    /// Replaces the first match in the text with the replacement string.
+    /// Returns an iterator over the pieces of the text separated by matches.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"\s*,\s*", Flags::default()).unwrap();
+    /// let pieces: Vec<_> = re.split("a , b,c").collect();
+    /// assert_eq!(pieces, ["a", "b", "c"]);
+    /// ```
+    pub fn split<'a>(&'a self, text: &'a str) -> SplitIterator<'a> {
+        SplitIterator::new(self, text)
+    }
+
+    /// Replaces the first match in the text with the replacement string, or the
+    /// match the flags' [`occurrence`](Flags::occurrence) selects.
+    ///
+    /// If no match is found, or the `n` flag is set, returns the original text.
     ///
-    /// If no match is found, returns the original text.
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new("o", Flags::new().occurrence(3)).unwrap();
+    /// assert_eq!(re.replace("foo boo", "0"), "foo b0o");
+    /// ```
     pub fn replace(&self, text: &str, replacement: &str) -> String {
-        if let Some(m) = self.find(text) {
+        if self.flags.dry_run {
+            return text.to_string();
+        }
+        let found = match self.skipped_matches() {
+            0 => self.find(text),
+            skipped => self.find_all(text).nth(skipped),
+        };
+        if let Some(m) = found {
             let mut result = String::with_capacity(text.len());
             result.push_str(&text[..m.start]);
             result.push_str(replacement);
@@ -165,11 +856,26 @@ impl Regex {
     }
 
     /// Replaces all non-overlapping matches in the text with the replacement string.
+    /// If the flags select an [`occurrence`](Flags::occurrence), the matches before
+    /// it are left alone.
+    ///
+    /// If the `n` flag is set, returns the original text.
     pub fn replace_all(&self, text: &str, replacement: &str) -> String {
+        if self.flags.dry_run {
+            return text.to_string();
+        }
+        let skipped = self.skipped_matches();
+        if let Some(literal) = &self.literal
+            && skipped == 0
+            && !literal.ignore_case()
+            && !literal.needle().is_empty()
+        {
+            return text.replace(literal.needle(), replacement);
+        }
         let mut result = String::with_capacity(text.len() * 2);
         let mut last_end = 0;
 
-        for m in self.find_all(text) {
+        for m in self.replaced_matches(text) {
             result.push_str(&text[last_end..m.start]);
             result.push_str(replacement);
             last_end = m.end;
@@ -179,6 +885,421 @@ impl Regex {
         result
     }
 
+    /// Replaces matches like [`replace_all`](Self::replace_all), asking `decide`
+    /// about each match first. This is what a frontend needs for a confirm flag like
+    /// Vim's `c` (`C` here, as `c` is taken for case sensitivity).
+    ///
+    /// The decision can accept the replacement, skip the match, replace it with
+    /// other text, or stop, leaving the rest of the text as it is.
+    ///
+    /// ```
+    /// use monster_regex::{Decision, Flags, Regex};
+    ///
+    /// let text = "cat cot cut";
+    /// let re = Regex::new(r"c(\w)t", Flags::default()).unwrap();
+    /// let result = re.replace_all_with_decision(text, "dog", |caps| {
+    ///     match caps.as_str(text, 1) {
+    ///         Some("a") => Decision::Accept,
+    ///         Some("o") => Decision::Edit("cog".to_string()),
+    ///         _ => Decision::Stop,
+    ///     }
+    /// });
+    /// assert_eq!(result, "dog cog cut");
+    /// ```
+    pub fn replace_all_with_decision(
+        &self,
+        text: &str,
+        replacement: &str,
+        mut decide: impl FnMut(&Captures) -> Decision,
+    ) -> String {
+        if self.flags.dry_run {
+            return text.to_string();
+        }
+        let mut result = String::with_capacity(text.len());
+        let mut last_end = 0;
+
+        for caps in self.captures_all(text).skip(self.skipped_matches()) {
+            let m = &caps.full_match;
+            let decision = decide(&caps);
+            let new_text = match &decision {
+                Decision::Accept => replacement,
+                Decision::Edit(edited) => edited,
+                Decision::Skip => continue,
+                Decision::Stop => break,
+            };
+            result.push_str(&text[last_end..m.start]);
+            result.push_str(new_text);
+            last_end = m.end;
+        }
+
+        result.push_str(&text[last_end..]);
+        result
+    }
+
+    /// Returns the changes [`replace_all`](Self::replace_all) makes, in order, rather
+    /// than the rebuilt text, so editors can apply them to their own buffers.
+    ///
+    /// Edits don't overlap, and offsets refer to the original text, so applying them
+    /// from last to first keeps the earlier offsets valid. With the `n` flag there
+    /// are none.
+    ///
+    /// ```
+    /// use monster_regex::{Edit, Flags, Regex};
+    ///
+    /// let re = Regex::new(r"\d+", Flags::default()).unwrap();
+    /// let edits = re.replacement_edits("a1 b22", "N");
+    /// assert_eq!(
+    ///     edits,
+    ///     [
+    ///         Edit { start: 1, end: 2, new_text: "N".to_string() },
+    ///         Edit { start: 4, end: 6, new_text: "N".to_string() },
+    ///     ]
+    /// );
+    /// ```
+    pub fn replacement_edits(&self, text: &str, replacement: &str) -> Vec<Edit> {
+        if self.flags.dry_run {
+            return vec![];
+        }
+        self.replaced_matches(text)
+            .map(|m| Edit {
+                start: m.start,
+                end: m.end,
+                new_text: replacement.to_string(),
+            })
+            .collect()
+    }
+
+    /// Replaces matches the way the flags ask, returning the new text and how many
+    /// replacements were made.
+    ///
+    /// With the `g` flag every match is replaced, otherwise only the first. An
+    /// [`occurrence`](Flags::occurrence) starts from that match instead, so `2`
+    /// replaces only the second match and `g2` the second and every one after it.
+    /// With the `n` flag the text is returned unchanged, along with how many
+    /// replacements would have been made.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new("o", Flags::new().global(true)).unwrap();
+    /// assert_eq!(re.substitute("foo boo", "0"), ("f00 b00".to_string(), 4));
+    ///
+    /// let dry_run = Regex::new("o", Flags::new().global(true).dry_run(true)).unwrap();
+    /// assert_eq!(dry_run.substitute("foo boo", "0"), ("foo boo".to_string(), 4));
+    ///
+    /// let from_third = Regex::new("o", "g3".parse().unwrap()).unwrap();
+    /// assert_eq!(from_third.substitute("foo boo", "0"), ("foo b00".to_string(), 2));
+    /// ```
+    pub fn substitute(&self, text: &str, replacement: &str) -> (String, usize) {
+        let limit = if self.flags.global { usize::MAX } else { 1 };
+        let mut result = String::with_capacity(text.len());
+        let mut last_end = 0;
+        let mut count = 0;
+
+        for m in self.find_all(text).skip(self.skipped_matches()).take(limit) {
+            count += 1;
+            if !self.flags.dry_run {
+                result.push_str(&text[last_end..m.start]);
+                result.push_str(replacement);
+                last_end = m.end;
+            }
+        }
+
+        result.push_str(&text[last_end..]);
+        (result, count)
+    }
+
+    // How many matches replacing passes over before the selected occurrence
+    fn skipped_matches(&self) -> usize {
+        self.flags.occurrence.map_or(0, |n| n.saturating_sub(1))
+    }
+
+    // The matches `replace_all` replaces
+    fn replaced_matches<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Match> + 'a {
+        self.find_all(text).skip(self.skipped_matches())
+    }
+
+    /// Returns the fewest characters any match of this regex consumes.
+    ///
+    /// Zero-width assertions count as zero, and a backreference counts as zero
+    /// since the group it refers to may be empty.
+    pub fn min_match_len(&self) -> usize {
+        self.plan.min_len
+    }
+
+    /// Returns the most characters any match of this regex consumes, or `None` if unbounded.
+    pub fn max_match_len(&self) -> Option<usize> {
+        self.plan.max_len
+    }
+
+    /// Checks whether the pattern is a plain string, optionally matched case-insensitively.
+    ///
+    /// Such patterns are searched for directly, bypassing the regex engine.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new("(?:foo)bar", Flags::default()).unwrap();
+    /// assert!(re.is_literal());
+    /// assert_eq!(re.replace_all("FooBar foobar", "x"), "x x");
+    ///
+    /// assert!(!Regex::new("fo+", Flags::default()).unwrap().is_literal());
+    /// ```
+    pub fn is_literal(&self) -> bool {
+        self.engine == EngineKind::Literal
+    }
+
+    /// Returns structural facts about the compiled pattern, such as whether it is
+    /// anchored or uses backreferences. See [`PatternInfo`].
+    pub fn info(&self) -> &PatternInfo {
+        &self.info
+    }
+
+    /// Returns the bracket expressions of the pattern as the compiled regex tests
+    /// them, in the order they appear. This is meant for debugging.
+    ///
+    /// With `ignore_case`, each set is expanded when compiling to every character
+    /// connected to one of its members by simple case mappings, ones to a single
+    /// character, so matching it needs no case folding.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new("[a-ck]x[^0-9]", Flags::new().ignore_case(true)).unwrap();
+    /// let sets: Vec<String> = re.compiled_sets().iter().map(|set| set.to_string()).collect();
+    /// assert_eq!(sets, ["[A-CKa-ck\u{212A}]", "[^0-9]"]);
+    /// ```
+    pub fn compiled_sets(&self) -> Vec<CharClass> {
+        self.program
+            .classes()
+            .filter(|class| matches!(class, CharClass::Set { .. }))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the search strategy chosen for this regex when it was compiled.
+    ///
+    /// This is meant for debugging and benchmarking; every strategy returns the same
+    /// matches.
+    ///
+    /// ```
+    /// use monster_regex::{EngineKind, Flags, Regex};
+    ///
+    /// let re = Regex::new("needle", Flags::default()).unwrap();
+    /// assert_eq!(re.engine_kind(), EngineKind::Literal);
+    ///
+    /// let re = Regex::new(r"(\w)\1", Flags::default()).unwrap();
+    /// assert_eq!(re.engine_kind(), EngineKind::Backtracking);
+    /// ```
+    pub fn engine_kind(&self) -> EngineKind {
+        self.engine
+    }
+
+    /// Returns every string the pattern matches in full, up to `max_len` characters,
+    /// shortest first. See [`MatchingStrings`] for the characters considered.
+    ///
+    /// Returns `None` if the pattern uses backreferences, lookarounds or `\zs`/`\ze`,
+    /// or its automaton is too large to build.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new("[ab]C?|D{2}", Flags::default()).unwrap();
+    /// let strings: Vec<_> = re.matching_strings(3).unwrap().collect();
+    /// assert_eq!(strings, ["a", "b", "DD", "aC", "bC"]);
+    /// ```
+    pub fn matching_strings(&self, max_len: usize) -> Option<MatchingStrings> {
+        let nfa = self.dfa.as_ref()?.nfa();
+        let automaton = Automaton::new(nfa, enumeration_alphabet(nfa))?;
+        Some(MatchingStrings::new(automaton, max_len))
+    }
+
+    /// Checks whether the pattern matches no string at all, in full.
+    ///
+    /// Returns `None` if the pattern uses backreferences, lookarounds or `\zs`/`\ze`,
+    /// or its automaton is too large to build.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"a\bb|\>x", Flags::default()).unwrap();
+    /// assert_eq!(re.is_empty_language(), Some(true));
+    /// let re = Regex::new(r"a\b!", Flags::default()).unwrap();
+    /// assert_eq!(re.is_empty_language(), Some(false));
+    /// ```
+    pub fn is_empty_language(&self) -> Option<bool> {
+        let nfa = self.dfa.as_ref()?.nfa();
+        let automaton = Automaton::new(nfa, representative_alphabet(&[nfa]))?;
+        Some(!automaton.accepting.contains(&true))
+    }
+
+    /// Checks whether some string is matched in full by both patterns, e.g. to find
+    /// rules that overlap. Each pattern keeps its own flags.
+    ///
+    /// Returns `None` if either pattern uses backreferences, lookarounds or
+    /// `\zs`/`\ze`, or the automata are too large to build.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let ids = Regex::new(r"[a-z]+\d*", Flags::default()).unwrap();
+    /// let versions = Regex::new(r"v\d+", Flags::default()).unwrap();
+    /// let numbers = Regex::new(r"\d+", Flags::default()).unwrap();
+    /// assert_eq!(ids.intersects(&versions), Some(true));
+    /// assert_eq!(ids.intersects(&numbers), Some(false));
+    /// ```
+    pub fn intersects(&self, other: &Regex) -> Option<bool> {
+        let (a, b) = self.automata_with(other)?;
+        Some(product_reaches(&a, &b, |a, b| a && b))
+    }
+
+    /// Checks whether every string this pattern matches in full is also matched in
+    /// full by `other`.
+    ///
+    /// Returns `None` if either pattern uses backreferences, lookarounds or
+    /// `\zs`/`\ze`, or the automata are too large to build.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let hex = Regex::new(r"\x+", Flags::default()).unwrap();
+    /// let word = Regex::new(r"\w+", Flags::default()).unwrap();
+    /// assert_eq!(hex.is_subset_of(&word), Some(true));
+    /// assert_eq!(word.is_subset_of(&hex), Some(false));
+    /// ```
+    pub fn is_subset_of(&self, other: &Regex) -> Option<bool> {
+        let (a, b) = self.automata_with(other)?;
+        Some(!product_reaches(&a, &b, |a, b| a && !b))
+    }
+
+    /// Checks whether both patterns match exactly the same strings in full, e.g. to
+    /// confirm a rewritten pattern still behaves like the original.
+    ///
+    /// Returns `None` if either pattern uses backreferences, lookarounds or
+    /// `\zs`/`\ze`, or the automata are too large to build.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let original = Regex::new("(?:a|b)(?:a|b)*", Flags::default()).unwrap();
+    /// let rewritten = Regex::new("[ab]+", Flags::default()).unwrap();
+    /// assert_eq!(original.is_equivalent(&rewritten), Some(true));
+    /// ```
+    pub fn is_equivalent(&self, other: &Regex) -> Option<bool> {
+        let (a, b) = self.automata_with(other)?;
+        Some(!product_reaches(&a, &b, |a, b| a != b))
+    }
+
+    // Builds both patterns' automata over one alphabet that tells apart every
+    // character either of them does
+    fn automata_with(&self, other: &Regex) -> Option<(Automaton, Automaton)> {
+        let (nfa, other_nfa) = (self.dfa.as_ref()?.nfa(), other.dfa.as_ref()?.nfa());
+        let alphabet = representative_alphabet(&[nfa, other_nfa]);
+        Some((
+            Automaton::new(nfa, alphabet.clone())?,
+            Automaton::new(other_nfa, alphabet)?,
+        ))
+    }
+
+    /// Translates the pattern, with its flags, into the syntax of the Rust `regex`
+    /// crate. See [`to_rust_regex`](crate::to_rust_regex) for what can be translated.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"(?<key>\h\w*)=.", Flags::default()).unwrap();
+    /// assert_eq!(
+    ///     re.to_rust_regex().unwrap(),
+    ///     r"(?i)(?P<key>[\p{Alphabetic}_][\p{Alphabetic}\p{N}_]*)=[^\n]"
+    /// );
+    /// assert!(Regex::new(r"(a)\1", Flags::default()).unwrap().to_rust_regex().is_err());
+    /// ```
+    pub fn to_rust_regex(&self) -> Result<String, ExportError> {
+        to_rust_regex(&self.ast, &self.flags)
+    }
+
+    /// Translates the pattern, with its flags, into PCRE2 syntax. See
+    /// [`to_pcre`](crate::to_pcre) for what can be translated.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"(\w)\1(?<!x)", Flags::default()).unwrap();
+    /// assert_eq!(
+    ///     re.to_pcre().unwrap(),
+    ///     r"(*UTF)(*UCP)(?i)([\p{Alphabetic}\p{N}_])\g{1}(?<!x)"
+    /// );
+    /// ```
+    pub fn to_pcre(&self) -> Result<String, ExportError> {
+        to_pcre(&self.ast, &self.flags)
+    }
+
+    /// Describes the pattern in plain English, one indented step per line.
+    /// See [`explain`](crate::explain) for the format.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let re = Regex::new(r"^(?<year>\d{4})-(?:0|1)", Flags::default()).unwrap();
+    /// assert_eq!(
+    ///     re.explain(),
+    ///     "start of the text
+    /// group 1 (year):
+    ///   exactly 4 of: a digit
+    /// literal '-'
+    /// group:
+    ///   either:
+    ///     literal '0'
+    ///   or:
+    ///     literal '1'"
+    /// );
+    /// ```
+    pub fn explain(&self) -> String {
+        // The stored AST is optimized, and the explanation follows the pattern as
+        // written, so it's parsed again. Compiling already parsed it, so that succeeds.
+        let ast = Parser::new(&self.pattern, self.flags)
+            .parse()
+            .expect("a compiled pattern parses");
+        explain(&ast, &self.flags)
+    }
+
+    /// Renders the compiled program as a Graphviz DOT graph, one node per
+    /// instruction of the backtracking matcher, with edges for where each one can
+    /// continue. Use [`to_dot`](crate::to_dot) to draw the AST instead.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let dot = Regex::new("a|b", Flags::default()).unwrap().to_dot();
+    /// assert!(dot.starts_with("digraph program {"));
+    /// assert!(dot.contains("n0 [label=\"0: split\"];"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        self.program.to_dot()
+    }
+
+    /// Writes the pattern in a normalized form, so rule sets can be deduplicated
+    /// and configs diffed by what their patterns match rather than how they're
+    /// written.
+    ///
+    /// Non-capturing groups that don't change the match are removed, bracket
+    /// expressions are sorted with overlapping ranges merged, quantifiers use their
+    /// shortest form, duplicate alternation branches are dropped and shared branch
+    /// prefixes are hoisted. The result is written for this regex's
+    /// [`flags`](Self::flags) with `x` and `U` off, which it has already applied.
+    ///
+    /// ```
+    /// use monster_regex::{Flags, Regex};
+    ///
+    /// let canonical = |pattern| Regex::new(pattern, Flags::default()).unwrap().canonical_pattern();
+    /// assert_eq!(canonical("(?:ab){1}[x-zc-fa-d]{0,}"), "ab[a-fx-z]*");
+    /// assert_eq!(canonical("(?:ab)[a-fzyx]*"), canonical("ab[x-za-f]{0,}"));
+    /// assert_eq!(canonical("(cat|cow|cat)+?"), "(c(?:at|ow))+?");
+    /// ```
+    pub fn canonical_pattern(&self) -> String {
+        to_pattern(&compiler::canonicalize(self.ast.to_vec()))
+    }
+
     /// Returns the original pattern string used to compile this regex.
     pub fn pattern(&self) -> &str {
         &self.pattern
@@ -0,0 +1,171 @@
+use std::io::{self, BufRead};
+
+use super::Regex;
+use super::iter::Search;
+use crate::captures::Match;
+
+/// An iterator over the matches of a regex in text read from a [`BufRead`].
+///
+/// Created by [`Regex::find_reader`]. Yields matches with byte offsets from the
+/// start of the input, or the error that stopped reading.
+pub struct ReaderMatches<'r, R> {
+    regex: &'r Regex,
+    reader: R,
+    // The text read and not yet discarded, which starts `base` bytes into the input
+    buffer: String,
+    base: usize,
+    // Bytes of a character split between reads
+    partial: Vec<u8>,
+    // Where the search resumes in `buffer`, where the last match ended and where the
+    // last empty match was
+    position: (usize, Option<usize>, Option<usize>),
+    eof: bool,
+    failed: bool,
+}
+
+impl<'r, R: BufRead> ReaderMatches<'r, R> {
+    pub(super) fn new(regex: &'r Regex, reader: R) -> Self {
+        ReaderMatches {
+            regex,
+            reader,
+            buffer: String::new(),
+            base: 0,
+            partial: vec![],
+            position: (0, None, None),
+            eof: false,
+            failed: false,
+        }
+    }
+
+    // Searches the buffer, returning the match and where the search got to
+    fn search(&self) -> (Option<Match>, (usize, Option<usize>, Option<usize>)) {
+        let mut search = Search::new(self.regex, &self.buffer);
+        search.resume(self.position);
+        let found = search.next_match();
+        (found, search.position())
+    }
+
+    // Whether reading more could change the leftmost match starting at or before
+    // `last_start`. Patterns without a DFA are only searched once the input is read.
+    fn undecided(&self, last_start: usize) -> bool {
+        !self.eof
+            && self
+                .regex
+                .dfa
+                .as_ref()
+                .is_none_or(|dfa| dfa.is_live_at_end(&self.buffer, self.position.0, last_start))
+    }
+
+    // Drops the text before the earliest position a match can still start at, except
+    // the character before it, which assertions look at
+    fn discard(&mut self) {
+        let start = self.position.0;
+        let Some(dfa) = &self.regex.dfa else {
+            return;
+        };
+        // Find the first start position whose attempt is still running. Those usually
+        // started near the end, so look back from it before bisecting.
+        let live = |last_start| dfa.is_live_at_end(&self.buffer, start, last_start);
+        let (mut low, mut high) = (start, self.buffer.len());
+        let mut step = 1;
+        while low < high {
+            let probe = high.saturating_sub(step).max(low);
+            if !live(probe) {
+                low = probe + 1;
+                break;
+            }
+            high = probe;
+            step *= 2;
+        }
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if live(mid) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        let keep = self.buffer[..low]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i);
+        self.buffer.drain(..keep);
+        self.base += keep;
+        self.position = (
+            low - keep,
+            self.position.1.and_then(|end| end.checked_sub(keep)),
+            self.position.2.and_then(|pos| pos.checked_sub(keep)),
+        );
+    }
+
+    // Appends the next chunk of input to the buffer
+    fn read(&mut self) -> io::Result<()> {
+        let chunk = loop {
+            match self.reader.fill_buf() {
+                Ok(chunk) => break chunk,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        };
+        if chunk.is_empty() {
+            self.eof = true;
+            if !self.partial.is_empty() {
+                return Err(invalid_utf8());
+            }
+            return Ok(());
+        }
+        self.partial.extend_from_slice(chunk);
+        let len = chunk.len();
+        self.reader.consume(len);
+
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(text) => text.len(),
+            // A character cut off at the end is completed by the next read
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        let text = std::str::from_utf8(&self.partial[..valid]).map_err(|_| invalid_utf8())?;
+        self.buffer.push_str(text);
+        self.partial.drain(..valid);
+        Ok(())
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+impl<'r, R: BufRead> Iterator for ReaderMatches<'r, R> {
+    type Item = io::Result<Match>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            let (found, position) = self.search();
+            match found {
+                Some(m) if !self.undecided(m.start) => {
+                    self.position = position;
+                    return Some(Ok(Match {
+                        start: self.base + m.start,
+                        end: self.base + m.end,
+                    }));
+                }
+                None if self.eof => return None,
+                _ => {}
+            }
+
+            if found.is_none() {
+                self.discard();
+            }
+            if let Err(err) = self.read() {
+                self.failed = true;
+                return Some(Err(err));
+            }
+        }
+    }
+}
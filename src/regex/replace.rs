@@ -0,0 +1,24 @@
+use alloc::string::String;
+
+/// What to do with one match during
+/// [`replace_all_with_decision`](super::Regex::replace_all_with_decision).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Replace the match with the replacement string.
+    Accept,
+    /// Leave the match as it is and move on to the next one.
+    Skip,
+    /// Replace the match with this text instead of the replacement string.
+    Edit(String),
+    /// Leave this match and every later one as they are.
+    Stop,
+}
+
+/// One change [`replacement_edits`](super::Regex::replacement_edits) asks for: the
+/// text between the byte offsets `start` and `end` becomes `new_text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub new_text: String,
+}
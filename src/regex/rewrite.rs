@@ -0,0 +1,116 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use super::Regex;
+use super::iter::Search;
+use crate::captures::Match;
+
+/// How a [`Rewriter`] chooses between rules matching at the same position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlapPolicy {
+    /// The rule added first wins, as alternatives in a single pattern would.
+    #[default]
+    LeftmostFirst,
+    /// The longest match wins, and the rule added first among equally long ones.
+    LeftmostLongest,
+}
+
+/// Rewrites text with several regexes and their replacements in one left-to-right
+/// pass.
+///
+/// At each point the leftmost match of any rule is replaced, and scanning goes on
+/// after it, so replaced text is never matched again and the result doesn't depend
+/// on running separate passes in the right order. Matches that overlap a
+/// replacement are dropped in favour of the rule's next match after it.
+///
+/// Every match of every rule is a candidate: the `g`, `n` and occurrence flags of
+/// the regexes don't apply.
+///
+/// ```
+/// use monster_regex::{Flags, Regex, Rewriter};
+///
+/// let rewriter = Rewriter::new()
+///     .rule(Regex::new("cat", Flags::default()).unwrap(), "dog")
+///     .rule(Regex::new("dog", Flags::default()).unwrap(), "cat");
+/// assert_eq!(rewriter.rewrite("cat chases dog"), "dog chases cat");
+/// ```
+#[derive(Default)]
+pub struct Rewriter {
+    rules: Vec<(Regex, String)>,
+    overlap: OverlapPolicy,
+}
+
+impl Rewriter {
+    /// Creates a rewriter with no rules, using [`OverlapPolicy::LeftmostFirst`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule replacing matches of `regex` with `replacement`. Rules added
+    /// earlier win ties under [`OverlapPolicy::LeftmostFirst`].
+    pub fn rule(mut self, regex: Regex, replacement: &str) -> Self {
+        self.rules.push((regex, replacement.to_string()));
+        self
+    }
+
+    /// Sets how rules matching at the same position are chosen between.
+    ///
+    /// Defaults to [`OverlapPolicy::LeftmostFirst`].
+    pub fn overlap(mut self, policy: OverlapPolicy) -> Self {
+        self.overlap = policy;
+        self
+    }
+
+    /// Rewrites the text, returning the result.
+    pub fn rewrite(&self, text: &str) -> String {
+        let mut searches: Vec<_> = self
+            .rules
+            .iter()
+            .map(|(regex, _)| Search::new(regex, text))
+            .collect();
+        // The next match of each rule, or `None` once it has no more
+        let mut next: Vec<_> = searches.iter_mut().map(Search::next_match).collect();
+        let mut result = String::with_capacity(text.len());
+        let mut last_end = 0;
+        let mut pos = 0;
+
+        loop {
+            // Matches overlapping the last replacement are looked for again after it
+            for (search, m) in searches.iter_mut().zip(&mut next) {
+                if m.as_ref().is_some_and(|m| m.start < pos) {
+                    search.seek(pos);
+                    *m = search.next_match();
+                }
+            }
+            let Some((rule, m)) = self.pick(&mut next) else {
+                break;
+            };
+            result.push_str(&text[last_end..m.start]);
+            result.push_str(&self.rules[rule].1);
+            last_end = m.end;
+            // An empty match still uses up its position, so no other rule matches there
+            pos = if m.is_empty() {
+                m.end + text[m.end..].chars().next().map_or(1, char::len_utf8)
+            } else {
+                m.end
+            };
+            next[rule] = searches[rule].next_match();
+        }
+
+        result.push_str(&text[last_end..]);
+        result
+    }
+
+    // Takes the match to replace next out of the rules' next matches
+    fn pick(&self, next: &mut [Option<Match>]) -> Option<(usize, Match)> {
+        let longest = self.overlap == OverlapPolicy::LeftmostLongest;
+        let (rule, _) = next
+            .iter()
+            .enumerate()
+            .filter_map(|(rule, m)| Some((rule, m.as_ref()?)))
+            // Ties go to the first rule
+            .min_by_key(|(_, m)| (m.start, Reverse(if longest { m.end } else { 0 })))?;
+        Some((rule, next[rule].take()?))
+    }
+}
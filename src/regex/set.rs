@@ -0,0 +1,101 @@
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use super::iter::Search;
+use super::{OverlapPolicy, Regex};
+use crate::captures::Match;
+use crate::errors::CompileError;
+use crate::flags::Flags;
+
+/// A list of patterns searched together, reporting which one matched.
+///
+/// [`matches_at`](RegexSet::matches_at) finds the leftmost match of any pattern.
+/// When several match at the same position, the first pattern wins with
+/// [`OverlapPolicy::LeftmostFirst`], and the longest match with
+/// [`OverlapPolicy::LeftmostLongest`], ties going to the first pattern. Compiled
+/// with [`Flags::anchored`], only matches starting right at the position count,
+/// which is what hand-written lexers and routers need.
+///
+/// ```
+/// use monster_regex::{Flags, OverlapPolicy, RegexSet};
+///
+/// let set = RegexSet::new(["GET", r"GET /\w+", "POST"], Flags::default().anchored(true))
+///     .unwrap();
+/// let (pattern, m) = set.matches_at("GET /users", 0).unwrap();
+/// assert_eq!((pattern, m.end), (0, 3));
+///
+/// let set = set.overlap(OverlapPolicy::LeftmostLongest);
+/// let (pattern, m) = set.matches_at("GET /users", 0).unwrap();
+/// assert_eq!((pattern, m.end), (1, 10));
+/// assert_eq!(set.matches_at("GET /users", 1), None);
+/// ```
+pub struct RegexSet {
+    regexes: Vec<Regex>,
+    overlap: OverlapPolicy,
+}
+
+impl RegexSet {
+    /// Compiles every pattern with `flags`, in order of priority.
+    ///
+    /// Uses [`OverlapPolicy::LeftmostFirst`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the `CompileError` of the first pattern that fails to compile.
+    pub fn new<'a>(
+        patterns: impl IntoIterator<Item = &'a str>,
+        flags: Flags,
+    ) -> Result<Self, CompileError> {
+        let regexes = patterns
+            .into_iter()
+            .map(|pattern| Regex::new(pattern, flags))
+            .collect::<Result<_, CompileError>>()?;
+        Ok(RegexSet {
+            regexes,
+            overlap: OverlapPolicy::LeftmostFirst,
+        })
+    }
+
+    /// Sets how patterns matching at the same position are chosen between.
+    pub fn overlap(mut self, policy: OverlapPolicy) -> Self {
+        self.overlap = policy;
+        self
+    }
+
+    /// Returns the number of patterns in the set.
+    pub fn len(&self) -> usize {
+        self.regexes.len()
+    }
+
+    /// Returns `true` if the set has no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.regexes.is_empty()
+    }
+
+    /// Returns the compiled patterns, in order.
+    pub fn regexes(&self) -> &[Regex] {
+        &self.regexes
+    }
+
+    /// Finds the leftmost match of any pattern starting at or after byte offset
+    /// `pos`, returning the index of the pattern that made it along with the match.
+    ///
+    /// Returns `None` if no pattern matches, or if `pos` is past the end of the
+    /// text or not on a character boundary.
+    pub fn matches_at(&self, text: &str, pos: usize) -> Option<(usize, Match)> {
+        if !text.is_char_boundary(pos) {
+            return None;
+        }
+        let longest = self.overlap == OverlapPolicy::LeftmostLongest;
+        self.regexes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, regex)| {
+                let mut search = Search::new(regex, text);
+                search.seek(pos);
+                Some((index, search.next_match()?))
+            })
+            // Ties go to the first pattern
+            .min_by_key(|(_, m)| (m.start, Reverse(if longest { m.end } else { 0 })))
+    }
+}
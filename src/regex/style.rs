@@ -0,0 +1,41 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+
+/// How [`Regex::highlight`](super::Regex::highlight) marks matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchStyle {
+    /// Sets these ANSI SGR parameters before each match, like `"1;31"` for bold
+    /// red, and resets them after it.
+    Ansi(String),
+    /// Puts the first text before each match and the second after it, like
+    /// `<mark>` and `</mark>`.
+    Markers(String, String),
+}
+
+impl MatchStyle {
+    /// Wraps matches in the given opening and closing text.
+    pub fn markers(open: &str, close: &str) -> Self {
+        MatchStyle::Markers(open.to_string(), close.to_string())
+    }
+
+    pub(super) fn open(&self) -> String {
+        match self {
+            MatchStyle::Ansi(params) => format!("\x1b[{}m", params),
+            MatchStyle::Markers(open, _) => open.clone(),
+        }
+    }
+
+    pub(super) fn close(&self) -> &str {
+        match self {
+            MatchStyle::Ansi(_) => "\x1b[0m",
+            MatchStyle::Markers(_, close) => close,
+        }
+    }
+}
+
+/// Bold red, as `grep --color` shows matches.
+impl Default for MatchStyle {
+    fn default() -> Self {
+        MatchStyle::Ansi("1;31".to_string())
+    }
+}
@@ -15,27 +15,918 @@ fn test_regex_methods_existence() {
     let re = Regex::new("abc", flags).unwrap();
     let text = "abc def abc";
 
-    assert_eq!(re.is_match(text), true);
+    assert!(re.is_match(text));
     assert!(re.find(text).is_some());
-    assert!(re.captures(text).is_none());
+    assert!(re.captures(text).is_some());
 
     let matches: Vec<_> = re.find_all(text).collect();
     assert!(matches.len() == 2);
 
     let captures: Vec<_> = re.captures_all(text).collect();
-    assert!(captures.is_empty());
+    assert!(captures.len() == 2);
 
     assert_eq!(re.replace(text, "XYZ"), "XYZ def abc");
     assert_eq!(re.replace_all(text, "XYZ"), "XYZ def XYZ");
 }
 
+#[test]
+fn test_matches_str() {
+    let re = Regex::new(r"\a+", Flags::default()).unwrap();
+    let words: Vec<_> = re.matches_str("héllo, wörld!").collect();
+    assert_eq!(words, ["héllo", "wörld"]);
+    assert_eq!(re.matches_str("?! -").count(), 0);
+
+    let empty = Regex::new("x*", Flags::default()).unwrap();
+    let parts: Vec<_> = empty.matches_str("axx").collect();
+    assert_eq!(parts, ["", "xx", ""]);
+}
+
+#[test]
+fn test_count_and_positions() {
+    let re = Regex::new(r"\d+", Flags::default()).unwrap();
+    let text = "1 22 x 333 4";
+    assert_eq!(re.count(text), 4);
+    assert_eq!(re.positions(text).collect::<Vec<_>>(), [0, 2, 7, 11]);
+    assert_eq!(re.count(""), 0);
+
+    // Both follow find_all, empty matches included
+    for pattern in ["x*", "(a)\\1", "ab|b", "(?<=a)b"] {
+        let re = Regex::new(pattern, Flags::default()).unwrap();
+        let text = "xaab abxx b";
+        assert_eq!(re.count(text), re.find_all(text).count(), "{}", pattern);
+        assert!(
+            re.positions(text).eq(re.find_all(text).map(|m| m.start)),
+            "{}",
+            pattern
+        );
+    }
+}
+
+#[test]
+fn test_full_match() {
+    let full = |pattern: &str, text: &str| {
+        let re = Regex::new(pattern, Flags::default()).unwrap();
+        assert_eq!(re.is_full_match(text), re.full_match(text).is_some());
+        re.full_match(text).map(|m| (m.start, m.end))
+    };
+    assert_eq!(full(r"\d+", "123"), Some((0, 3)));
+    assert_eq!(full(r"\d+", "123a"), None);
+    assert_eq!(full(r"\d+", "a123"), None);
+    assert_eq!(full("a|ab|abc", "abc"), Some((0, 3)));
+    assert_eq!(full("(a+)b\\1", "aabaa"), Some((0, 5)));
+    assert_eq!(full("(a+)b\\1", "aaba"), None);
+    assert_eq!(full("x*", ""), Some((0, 0)));
+    assert_eq!(full("x+", ""), None);
+    assert_eq!(full("a.*(?<=z)", "abcz"), Some((0, 4)));
+    assert_eq!(full(r"foo\zsbar", "foobar"), Some((3, 6)));
+
+    // Literal patterns, case-insensitive ones included
+    assert_eq!(full("hello", "HeLLo"), Some((0, 5)));
+    assert_eq!(full("hello", "hello!"), None);
+    assert_eq!(full("Hello", "hello"), None);
+
+    // Line anchors in the pattern still only see line boundaries
+    let re = Regex::new("^a$", Flags::default().multiline(true)).unwrap();
+    assert!(re.is_full_match("a"));
+    assert!(!re.is_full_match("a\na"));
+}
+
+#[test]
+fn test_finder() {
+    let re = Regex::new(r"(\d+)-(\d+)", Flags::default()).unwrap();
+    let text = "1-2 30-40";
+
+    // With no options it agrees with `captures`
+    let caps = re.finder().run(text).unwrap();
+    assert_eq!(caps.full_match, re.captures(text).unwrap().full_match);
+    assert_eq!(caps.as_str(text, 2), Some("2"));
+
+    let caps = re.finder().range(1..text.len()).run(text).unwrap();
+    assert_eq!(caps.as_str(text, 0), Some("30-40"));
+    assert!(re.finder().range(4..6).run(text).is_none());
+    assert!(re.finder().anchored(true).range(3..9).run(text).is_none());
+
+    let caps = re.finder().with_captures(false).run(text).unwrap();
+    assert_eq!(caps.as_str(text, 0), Some("1-2"));
+    assert_eq!(caps.get(1), None);
+
+    // The match that ends first, with or without the DFA to find it
+    for pattern in [r"b+", r"(b)\1*", r"(?<=a)b+"] {
+        let re = Regex::new(pattern, Flags::default()).unwrap();
+        let m = re.finder().earliest(true).run("abbb").unwrap().full_match;
+        assert_eq!((m.start, m.end), (1, 2), "{}", pattern);
+    }
+    let re = Regex::new(r"xyz|y", Flags::default()).unwrap();
+    let m = re.finder().earliest(true).run("xyz").unwrap().full_match;
+    assert_eq!((m.start, m.end), (1, 2));
+    let m = re
+        .finder()
+        .earliest(true)
+        .anchored(true)
+        .run("xyz")
+        .unwrap()
+        .full_match;
+    assert_eq!((m.start, m.end), (0, 3));
+    assert!(re.finder().earliest(true).range(0..1).run("xyz").is_none());
+
+    // Literal patterns take the substring search
+    let re = Regex::new("ab", Flags::default()).unwrap();
+    assert!(re.finder().anchored(true).run("xab").is_none());
+    let m = re.finder().range(1..3).run("xab").unwrap().full_match;
+    assert_eq!((m.start, m.end), (1, 3));
+}
+
+#[test]
+fn test_finder_marks() {
+    use crate::Marks;
+
+    let text = "one two three two";
+    let find = |pattern: &str, marks: &Marks| {
+        let re = Regex::new(pattern, Flags::default()).unwrap();
+        re.finder()
+            .marks(marks)
+            .run(text)
+            .map(|caps| &text[caps.full_match.start..caps.full_match.end])
+    };
+    let marks = Marks::new()
+        .cursor(8)
+        .visual(4..13)
+        .mark('a', 4)
+        .mark('b', 14);
+
+    assert_eq!(find(r"\%#\w+", &marks), Some("three"));
+    assert_eq!(find(r"\w+ \%#", &marks), Some("two "));
+    // `\%V` checks the character after it, so `\%V` before the last character
+    // keeps the whole match inside the area
+    assert_eq!(find(r"\%Vt\w+", &marks), Some("two"));
+    assert_eq!(find(r"\%V\w*\%V\w", &marks), Some("two"));
+    assert_eq!(find(r"\%Vth\w*\%V\w e", &marks), None);
+    assert_eq!(find(r"e\%V", &marks), Some("e"));
+    assert_eq!(find(r"\%'b\w+", &marks), Some("two"));
+    assert_eq!(find(r"\%>'a\%<'b\w+", &marks), Some("wo"));
+    assert_eq!(find(r"\%<'a\w+", &marks), Some("one"));
+
+    // Positions that aren't set never match, and neither do searches without marks
+    assert_eq!(find(r"\%'z", &marks), None);
+    assert_eq!(find(r"\%#", &Marks::new()), None);
+    let re = Regex::new(r"\%Vo", Flags::default()).unwrap();
+    assert!(!re.is_match(text));
+
+    let pattern = r"\%#\%V\%'a\%<'b\%>'<";
+    let ast = crate::Parser::new(pattern, Flags::default())
+        .parse()
+        .unwrap();
+    assert_eq!(crate::parser::to_pattern(&ast), pattern);
+    let re = Regex::new(r"\%V\%#\w\%'a", Flags::default()).unwrap();
+    assert!(re.to_pcre().is_err());
+    let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+    let marks = Marks::new().cursor(1).visual(0..3).mark('a', 2);
+    let m = loaded.finder().marks(&marks).run("abc").unwrap().full_match;
+    assert_eq!((m.start, m.end), (1, 2));
+}
+
+#[test]
+fn test_partition() {
+    use crate::Segment;
+
+    let re = Regex::new(r"\d+", Flags::default()).unwrap();
+    assert_eq!(
+        re.partition("a1b22").collect::<Vec<_>>(),
+        [
+            Segment::Gap(0..1),
+            Segment::Matched(crate::Match { start: 1, end: 2 }),
+            Segment::Gap(2..3),
+            Segment::Matched(crate::Match { start: 3, end: 5 }),
+        ]
+    );
+    assert_eq!(
+        re.partition("abc").collect::<Vec<_>>(),
+        [Segment::Gap(0..3)]
+    );
+    assert!(re.partition("").next().is_none());
+
+    // The segments cover the text in order, whatever the matches look like
+    for pattern in ["x*", r"\a+", "é", r"foo\zsbar", r"foo\zebar", "^"] {
+        let re = Regex::new(pattern, Flags::default()).unwrap();
+        let text = "xfoobar éé xx";
+        let segments: Vec<_> = re.partition(text).collect();
+        let mut pos = 0;
+        for segment in &segments {
+            assert_eq!(segment.range().start, pos, "{}: {:?}", pattern, segments);
+            pos = segment.range().end;
+        }
+        assert_eq!(pos, text.len(), "{}", pattern);
+        let joined: String = segments.iter().map(|s| s.as_str(text)).collect();
+        assert_eq!(joined, text);
+        let matched = segments.iter().filter_map(|s| match s {
+            Segment::Matched(m) => Some(m.clone()),
+            Segment::Gap(range) => {
+                assert!(!range.is_empty());
+                None
+            }
+        });
+        assert!(matched.eq(re.find_all(text)), "{}", pattern);
+    }
+}
+
+#[test]
+fn test_highlight_matches() {
+    use crate::MatchStyle;
+
+    let re = Regex::new("o+", Flags::default()).unwrap();
+    let brackets = MatchStyle::markers("[", "]");
+    assert_eq!(re.highlight("foo boo", &brackets), "f[oo] b[oo]");
+    assert_eq!(re.highlight("xyz", &brackets), "xyz");
+    assert_eq!(re.highlight("", &brackets), "");
+    assert_eq!(
+        re.highlight("oé", &MatchStyle::Ansi("4".to_string())),
+        "\x1b[4mo\x1b[0mé"
+    );
+
+    // Empty matches aren't marked
+    let empty = Regex::new("o*", Flags::default()).unwrap();
+    assert_eq!(empty.highlight("xo", &brackets), "x[o]");
+}
+
+#[test]
+fn test_rift_str_ext() {
+    use crate::{CompileError, RiftStrExt};
+
+    assert_eq!(
+        "hello World".rfind_pattern(r"w\w+/i").unwrap(),
+        Some("World")
+    );
+    assert_eq!("hello World".rfind_pattern(r"w\w+/c").unwrap(), None);
+    assert_eq!("x1y22".rfind_pattern(r"#\d+#").unwrap(), Some("1"));
+    assert_eq!(
+        "a,b;;c".rsplit_pattern("[,;]/").unwrap(),
+        ["a", "b", "", "c"]
+    );
+    assert_eq!("abc".rsplit_pattern("x/").unwrap(), ["abc"]);
+
+    assert!(matches!(
+        "abc".rfind_pattern("a"),
+        Err(CompileError::InvalidPattern(_))
+    ));
+    assert!(matches!(
+        "abc".rsplit_pattern("a/q"),
+        Err(CompileError::InvalidPattern(_))
+    ));
+    assert!("abc".rfind_pattern("(/").is_err());
+}
+
 #[test]
 fn test_flags_default() {
     let flags = Flags::default();
     assert_eq!(flags.ignore_case, None);
-    assert_eq!(flags.multiline, false);
-    assert_eq!(flags.dotall, false);
-    assert_eq!(flags.verbose, false);
-    assert_eq!(flags.unicode, false);
-    assert_eq!(flags.global, false);
+    assert!(!flags.multiline);
+    assert!(!flags.dotall);
+    assert!(!flags.verbose);
+    assert!(!flags.unicode);
+    assert!(!flags.global);
+}
+
+#[test]
+fn test_empty_match_policy() {
+    use crate::{EmptyMatchPolicy, RegexBuilder};
+
+    let build = |pattern: &str, policy| {
+        RegexBuilder::new(pattern)
+            .empty_matches(policy)
+            .build()
+            .unwrap()
+    };
+
+    // Empty matches step over whole characters
+    let re = build("", EmptyMatchPolicy::Allow);
+    let starts: Vec<_> = re.find_all("aé😀").map(|m| m.start).collect();
+    assert_eq!(starts, vec![0, 1, 3, 7]);
+    assert_eq!(re.split("aé").collect::<Vec<_>>(), vec!["", "a", "é", ""]);
+    assert_eq!(re.replace_all("aé", "-"), "-a-é-");
+
+    let re = build("x*", EmptyMatchPolicy::SkipAdjacent);
+    assert_eq!(re.replace_all("axxb", "-"), "-a-b-");
+    assert_eq!(re.split("axxb").collect::<Vec<_>>(), vec!["", "a", "b", ""]);
+
+    let re = build(r"(\d*)", EmptyMatchPolicy::Skip);
+    let groups: Vec<_> = re
+        .captures_all("a12b3")
+        .map(|c| c.as_str("a12b3", 1).unwrap().to_string())
+        .collect();
+    assert_eq!(groups, vec!["12", "3"]);
+    assert_eq!(re.split("a12b3").collect::<Vec<_>>(), vec!["a", "b", ""]);
+
+    // Single searches skip them too
+    let re = build("x*", EmptyMatchPolicy::Skip);
+    assert_eq!(re.find("ab"), None);
+    assert_eq!(re.find("axxb").map(|m| (m.start, m.end)), Some((1, 3)));
+    assert!(re.captures("ab").is_none());
+    assert_eq!(re.replace("ab", "-"), "ab");
+    assert_eq!(re.replace("abx", "-"), "ab-");
+    assert!(!re.is_match("ab"));
+    assert!(re.is_match("abx"));
+    let re = build("", EmptyMatchPolicy::Skip);
+    assert!(!re.is_match("ab"));
+    assert_eq!(re.replace("ab", "-"), "ab");
+    // The other policies only ever skip an empty match after another match
+    let re = build("x*", EmptyMatchPolicy::SkipAdjacent);
+    assert_eq!(re.find("ab").map(|m| (m.start, m.end)), Some((0, 0)));
+}
+
+#[test]
+fn test_explain() {
+    let explain = |pattern: &str, flags: Flags| Regex::new(pattern, flags).unwrap().explain();
+
+    assert_eq!(
+        explain(r"(\w+)\s\1", Flags::default()),
+        "group 1:\n  one or more of: a word character\na whitespace character\nthe text matched by group 1"
+    );
+    assert_eq!(
+        explain("[^a-c_]+?x?(?:ab)*", Flags::default()),
+        "one or more (as few as possible) of: any character except 'a' to 'c', '_'\n\
+         optionally: literal 'x'\n\
+         zero or more times:\n  literal \"ab\""
+    );
+    assert_eq!(
+        explain(
+            "(?<=a)b(?>!c).{2,}$",
+            Flags {
+                multiline: true,
+                ..Flags::default()
+            }
+        ),
+        "preceded by:\n  literal 'a'\nliteral 'b'\nnot followed by:\n  literal 'c'\n\
+         at least 2 of: any character except a newline\nend of a line"
+    );
+    assert_eq!(
+        explain("a|", Flags::default()),
+        "either:\n  literal 'a'\nor:\n  nothing"
+    );
+}
+
+#[test]
+fn test_replacement_edits() {
+    use crate::{Edit, EmptyMatchPolicy, RegexBuilder};
+
+    // Applying the edits from the back gives what replace_all returns
+    let apply = |text: &str, edits: &[Edit]| {
+        let mut result = text.to_string();
+        for edit in edits.iter().rev() {
+            result.replace_range(edit.start..edit.end, &edit.new_text);
+        }
+        result
+    };
+    let cases = [
+        (r"\d+", Flags::default(), "a1 b22 c333"),
+        ("é", Flags::default(), "café é"),
+        ("a", Flags::new().occurrence(2), "banana"),
+    ];
+    for (pattern, flags, text) in cases {
+        let re = Regex::new(pattern, flags).unwrap();
+        let edits = re.replacement_edits(text, "<>");
+        assert_eq!(apply(text, &edits), re.replace_all(text, "<>"));
+    }
+
+    let re = RegexBuilder::new("x*")
+        .empty_matches(EmptyMatchPolicy::SkipAdjacent)
+        .build()
+        .unwrap();
+    let spans: Vec<_> = re
+        .replacement_edits("axxb", "-")
+        .iter()
+        .map(|edit| (edit.start, edit.end))
+        .collect();
+    assert_eq!(spans, [(0, 0), (1, 3), (4, 4)]);
+
+    let re = Regex::new("a", Flags::new().dry_run(true)).unwrap();
+    assert!(re.replacement_edits("banana", "o").is_empty());
+}
+
+#[test]
+fn test_rewriter() {
+    use crate::{OverlapPolicy, Rewriter};
+
+    let re = |pattern: &str| Regex::new(pattern, Flags::new().ignore_case(false)).unwrap();
+
+    // Replacements aren't rewritten again by later rules
+    let rewriter = Rewriter::new().rule(re("a"), "b").rule(re("b"), "a");
+    assert_eq!(rewriter.rewrite("abba"), "baab");
+
+    // Rules matching at the same place: the first rule, or the longest match
+    let text = "foobar foo";
+    let first = Rewriter::new().rule(re("foo"), "1").rule(re("foobar"), "2");
+    assert_eq!(first.rewrite(text), "1bar 1");
+    let longest = Rewriter::new()
+        .rule(re("foo"), "1")
+        .rule(re("foobar"), "2")
+        .overlap(OverlapPolicy::LeftmostLongest);
+    assert_eq!(longest.rewrite(text), "2 1");
+
+    // A match overlapping an earlier replacement gives way to the rule's next one
+    let rewriter = Rewriter::new().rule(re("ab"), "X").rule(re("bc"), "Y");
+    assert_eq!(rewriter.rewrite("abc bcab"), "Xc YX");
+    let rewriter = Rewriter::new().rule(re("ab"), "X").rule(re("bc|c"), "Y");
+    assert_eq!(rewriter.rewrite("abc"), "XY");
+
+    // Matches are found as for a single pattern with the rules as alternatives, so
+    // the empty matches of `y*` keep `é` from being reached
+    let text = "x1 yy22 zé3";
+    let rules = [(r"\d", "#"), ("y*", "-"), ("é", "e")];
+    let rewriter = rules
+        .iter()
+        .fold(Rewriter::new(), |rewriter, (pattern, _)| {
+            rewriter.rule(re(pattern), "-")
+        });
+    assert_eq!(
+        rewriter.rewrite(text),
+        re(r"\d|y*|é").replace_all(text, "-")
+    );
+    let rewriter = rules
+        .iter()
+        .fold(Rewriter::new(), |rewriter, (pattern, replacement)| {
+            rewriter.rule(re(pattern), replacement)
+        });
+    assert_eq!(rewriter.rewrite(text), "-x#- -##- -z-é#-");
+
+    assert_eq!(Rewriter::new().rewrite("abc"), "abc");
+}
+
+#[test]
+fn test_lexer() {
+    use crate::{CompileError, Lexer, OverlapPolicy};
+
+    let rules = [
+        ("if", "if"),
+        ("ident", r"\a\w*"),
+        ("assign", "="),
+        ("eq", "=="),
+        ("space", " +"),
+    ];
+    let lexer = Lexer::new(rules, Flags::default()).unwrap();
+    fn lex<'l>(lexer: &'l Lexer, text: &'l str) -> Vec<(Option<&'l str>, &'l str)> {
+        lexer
+            .tokens(text)
+            .map(|token| (token.name, token.span.as_str(text)))
+            .collect()
+    }
+
+    // The longest match wins, and the first rule among equally long ones
+    assert_eq!(
+        lex(&lexer, "if iffy == x"),
+        [
+            (Some("if"), "if"),
+            (Some("space"), " "),
+            (Some("ident"), "iffy"),
+            (Some("space"), " "),
+            (Some("eq"), "=="),
+            (Some("space"), " "),
+            (Some("ident"), "x"),
+        ]
+    );
+
+    // Unmatched text is grouped into one token up to the next match
+    assert_eq!(
+        lex(&lexer, "a€!? b"),
+        [
+            (Some("ident"), "a"),
+            (None, "€!?"),
+            (Some("space"), " "),
+            (Some("ident"), "b"),
+        ]
+    );
+    assert_eq!(lex(&lexer, "?!"), [(None, "?!")]);
+    assert!(lex(&lexer, "").is_empty());
+
+    // The first rule that matches wins
+    let first = Lexer::new(rules, Flags::default())
+        .unwrap()
+        .overlap(OverlapPolicy::LeftmostFirst);
+    assert_eq!(
+        lex(&first, "iffy=="),
+        [
+            (Some("if"), "if"),
+            (Some("ident"), "fy"),
+            (Some("assign"), "="),
+            (Some("assign"), "=")
+        ]
+    );
+
+    // Rules only match where the lexer is, and empty matches are no tokens
+    let lexer = Lexer::new([("digits", r"\d*"), ("x", "x")], Flags::default()).unwrap();
+    assert_eq!(
+        lex(&lexer, "x1y"),
+        [(Some("x"), "x"), (Some("digits"), "1"), (None, "y")]
+    );
+
+    assert!(matches!(
+        Lexer::new([("bad", "(")], Flags::default()),
+        Err(CompileError::InvalidPattern(_))
+    ));
+}
+
+#[test]
+fn test_regex_set() {
+    use crate::{CompileError, OverlapPolicy, RegexSet};
+
+    let patterns = ["b+", "ab", "a", r"\d"];
+    let set = RegexSet::new(patterns, Flags::default()).unwrap();
+    assert_eq!(set.len(), 4);
+    fn found(set: &RegexSet, text: &str, pos: usize) -> Option<(usize, usize, usize)> {
+        set.matches_at(text, pos).map(|(i, m)| (i, m.start, m.end))
+    }
+
+    // The leftmost match wins, then the first pattern matching there
+    assert_eq!(found(&set, "xxabb", 0), Some((1, 2, 4)));
+    assert_eq!(found(&set, "xxabb", 3), Some((0, 3, 5)));
+    assert_eq!(found(&set, "xxabb", 5), None);
+    assert_eq!(found(&set, "xxabb", 6), None);
+    assert_eq!(found(&set, "€1", 1), None);
+    assert_eq!(found(&set, "€1", 3), Some((3, 3, 4)));
+
+    // The longest match at the leftmost position wins
+    let longest = RegexSet::new(["a", "ab", "a."], Flags::default())
+        .unwrap()
+        .overlap(OverlapPolicy::LeftmostLongest);
+    assert_eq!(found(&longest, "xab", 0), Some((1, 1, 3)));
+
+    // Anchored patterns only match at the position
+    let anchored = RegexSet::new(patterns, Flags::default().anchored(true)).unwrap();
+    assert_eq!(found(&anchored, "xxabb", 0), None);
+    assert_eq!(found(&anchored, "xxabb", 2), Some((1, 2, 4)));
+    assert_eq!(found(&anchored, "xxabb", 3), Some((0, 3, 5)));
+
+    assert!(RegexSet::new([], Flags::default()).unwrap().is_empty());
+    assert!(matches!(
+        RegexSet::new(["a", "("], Flags::default()),
+        Err(CompileError::InvalidPattern(_))
+    ));
+}
+
+#[test]
+fn test_find_reader() {
+    use std::io::{BufReader, Cursor};
+
+    let text = "ab aab\nxaaab b\n€é ab€ aa\n\nfoo-bar 12 345";
+    let patterns = [
+        "a+b",
+        r"\<\w+\>",
+        "^a*b?$",
+        r"b\n",
+        "x*",
+        r"(a)\1",
+        r"\d+(?>= )",
+        r"[^\n]*",
+        "€é",
+        "foo",
+    ];
+    for pattern in patterns {
+        let flags = Flags::new().multiline(true);
+        let re = Regex::new(pattern, flags).unwrap();
+        let expected: Vec<_> = re.find_all(text).collect();
+        // Chunks of every size split matches and characters in every way
+        for capacity in 1..8 {
+            let reader = BufReader::with_capacity(capacity, text.as_bytes());
+            let found: Vec<_> = re.find_reader(reader).map(Result::unwrap).collect();
+            assert_eq!(found, expected, "{pattern} with {capacity} byte chunks");
+        }
+    }
+
+    // Without multiline mode `$` only matches at the very end
+    let re = Regex::new("b$", Flags::default()).unwrap();
+    let reader = BufReader::with_capacity(1, "ab\nab".as_bytes());
+    let found: Vec<_> = re.find_reader(reader).map(|m| m.unwrap().start).collect();
+    assert_eq!(found, [4]);
+
+    // Invalid UTF-8 stops the search, including a character cut off at the end
+    let re = Regex::new("a", Flags::default()).unwrap();
+    for input in [&b"a\xffa"[..], b"a\xe2\x82"] {
+        let mut matches = re.find_reader(Cursor::new(input));
+        assert!(matches.next().unwrap().is_err());
+        assert!(matches.next().is_none());
+    }
+    let reader = BufReader::with_capacity(1, "a€a".as_bytes());
+    assert_eq!(re.find_reader(reader).count(), 2);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_find_in_all() {
+    let re = Regex::new(r"[a-z]+\d", Flags::default()).unwrap();
+    let haystacks: Vec<String> = (0..100)
+        .map(|i| format!("{} ab{} -- x{}", i, i % 10, i))
+        .collect();
+    let found = re.find_in_all(&haystacks);
+    assert_eq!(found.len(), haystacks.len());
+    for (text, matches) in haystacks.iter().zip(&found) {
+        assert_eq!(matches, &re.find_all(text).collect::<Vec<_>>());
+    }
+
+    assert!(re.find_in_all::<&str>(&[]).is_empty());
+    assert_eq!(re.find_in_all(&["", "?"]), [vec![], vec![]]);
+}
+
+#[test]
+fn test_find_with_timeout() {
+    use crate::MatchError;
+    use std::time::{Duration, Instant};
+
+    let slow = Regex::new("(?:a|aa)+(?<=b)", Flags::default()).unwrap();
+    let text = "a".repeat(60);
+    let started = Instant::now();
+    assert_eq!(
+        slow.find_with_timeout(&text, Duration::from_millis(20)),
+        Err(MatchError::Timeout)
+    );
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    // Quick searches finish before the clock is read
+    assert_eq!(slow.find_with_timeout("aab", Duration::ZERO), Ok(None));
+    let re = Regex::new(r"(\w)\1", Flags::default()).unwrap();
+    for timeout in [Duration::ZERO, Duration::MAX] {
+        assert_eq!(re.find_with_timeout("abccd", timeout), Ok(re.find("abccd")));
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_find_async() {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    // Polls to completion, counting the times the search yielded
+    fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut yields = 0;
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return (output, yields),
+                Poll::Pending => yields += 1,
+            }
+        }
+    }
+
+    let re = Regex::new(r"(a+)\1b", Flags::default()).unwrap();
+    let text = "a".repeat(20) + "c aab";
+    let (found, yields) = block_on(re.find_async(&text).budget(100));
+    assert_eq!(found, re.find(&text));
+    assert!(yields > 1);
+    let (found, yields) = block_on(re.find_async(&text).budget(usize::MAX));
+    assert_eq!((found, yields), (re.find(&text), 0));
+
+    // Even a zero budget makes progress, and anchored searches stay anchored
+    let (found, _) = block_on(re.find_async("xaab").budget(0));
+    assert_eq!(found, re.find("xaab"));
+    let anchored = Regex::new(r"(a)\1", Flags::default().anchored(true)).unwrap();
+    assert_eq!(block_on(anchored.find_async("xaa").budget(0)).0, None);
+    assert_eq!(
+        block_on(anchored.find_async("aax").budget(0)).0,
+        anchored.find("aax")
+    );
+
+    let literal = Regex::new("cd", Flags::default()).unwrap();
+    assert_eq!(
+        block_on(literal.find_async("abcd")),
+        (literal.find("abcd"), 0)
+    );
+}
+
+#[test]
+fn test_find_in_range() {
+    let span = |pattern: &str, text: &str, range: std::ops::Range<usize>| {
+        Regex::new(pattern, Flags::new().ignore_case(false))
+            .unwrap()
+            .find_in_range(text, range)
+            .map(|m| (m.start, m.end))
+    };
+
+    // Matches are cut short at the range's end, and start no earlier than its start
+    assert_eq!(span("a+", "aaaaa", 1..3), Some((1, 3)));
+    assert_eq!(span("ab", "abab", 1..4), Some((2, 4)));
+    assert_eq!(span("ab", "abab", 1..3), None);
+    // Plain strings take the literal search
+    assert_eq!(span("needle", "needle needle", 1..13), Some((7, 13)));
+    assert_eq!(span("needle", "needle needle", 1..12), None);
+
+    // Anchors and word boundaries see the text around the range
+    assert_eq!(span("^a", "ba", 1..2), None);
+    assert_eq!(span("a$", "ab", 0..1), None);
+    assert_eq!(span(r"\bcat\b", "concat cat", 3..10), Some((7, 10)));
+    assert_eq!(span("^b", "a\nb", 2..3), None);
+    let multiline = Regex::new("^b", Flags::new().multiline(true)).unwrap();
+    assert_eq!(
+        multiline
+            .find_in_range("a\nb", 2..3)
+            .map(|m| (m.start, m.end)),
+        Some((2, 3))
+    );
+
+    // Lookarounds read past both ends
+    assert_eq!(span(r"(?<=x)\d", "x1 2", 1..4), Some((1, 2)));
+    assert_eq!(span(r"\d(?>=y)", "1y 2", 0..1), Some((0, 1)));
+    assert_eq!(span(r"\d(?>!y)", "1y 2", 0..1), None);
+
+    // An empty range still finds empty matches at its position
+    assert_eq!(span("x*", "abc", 2..2), Some((2, 2)));
+    assert_eq!(span(r"(a)\1", "aa", 0..1), None);
+}
+
+#[test]
+fn test_cached() {
+    use std::sync::Arc;
+
+    // Patterns no other test caches, since the cache is shared
+    let flags = Flags::new().ignore_case(false);
+    let first = Regex::cached("cached-[a-z]+", flags).unwrap();
+    assert!(Arc::ptr_eq(
+        &first,
+        &Regex::cached("cached-[a-z]+", flags).unwrap()
+    ));
+    let other_flags = Regex::cached("cached-[a-z]+", flags.global(true)).unwrap();
+    assert!(!Arc::ptr_eq(&first, &other_flags));
+    assert!(other_flags.flags().global);
+    assert!(Regex::cached("cached-(", flags).is_err());
+
+    // Threads share the cache
+    let from_thread = std::thread::spawn(move || Regex::cached("cached-[a-z]+", flags).unwrap())
+        .join()
+        .unwrap();
+    assert!(Arc::ptr_eq(&first, &from_thread));
+
+    // Recently used patterns outlive the rest
+    let recent = Regex::cached("cached-recent", flags).unwrap();
+    for i in 0..300 {
+        Regex::cached(&format!("cached-{}", i), flags).unwrap();
+        if i % 50 == 0 {
+            assert!(Arc::ptr_eq(
+                &recent,
+                &Regex::cached("cached-recent", flags).unwrap()
+            ));
+        }
+    }
+    assert!(Arc::ptr_eq(
+        &recent,
+        &Regex::cached("cached-recent", flags).unwrap()
+    ));
+    assert!(!Arc::ptr_eq(
+        &first,
+        &Regex::cached("cached-[a-z]+", flags).unwrap()
+    ));
+}
+
+#[test]
+fn test_regex_traits() {
+    fn assert_send_sync<T: Send + Sync + Clone + std::fmt::Debug>() {}
+    assert_send_sync::<Regex>();
+
+    let flags = Flags::new().ignore_case(true).multiline(true);
+    let re = Regex::new(r"(\d+)/x", flags).unwrap();
+    let copy = re.clone();
+    assert_eq!(copy.find("a 12/x").map(|m| (m.start, m.end)), Some((2, 6)));
+    assert_eq!(copy, re);
+    assert_ne!(Regex::new(r"(\d+)/x", Flags::default()).unwrap(), re);
+    assert_ne!(Regex::new(r"\d+", flags).unwrap(), re);
+    // Options that change what matches count, limits don't
+    let build = |builder: crate::RegexBuilder| builder.flags(flags).build().unwrap();
+    let builder = || crate::RegexBuilder::new(r"(\d+)/x");
+    assert_eq!(build(builder().repetition_limit(10)), re);
+    assert_ne!(
+        build(builder().empty_matches(crate::EmptyMatchPolicy::Skip)),
+        re
+    );
+    assert_ne!(build(builder().lookaround_captures(false)), re);
+    assert_ne!(
+        build(builder().backrefs(crate::BackrefPolicy::MatchEmpty)),
+        re
+    );
+
+    // Displayed regexes read back as the same pattern and flags
+    for (pattern, shown) in [
+        (r"(\d+)/x", r"(\d+)\/x/im"),
+        (r"a\/b", r"a\/b/im"),
+        (r"a\\/b", r"a\\\/b/im"),
+        (r"a\\", r"a\\/im"),
+    ] {
+        let re = Regex::new(pattern, flags).unwrap();
+        assert_eq!(re.to_string(), shown);
+        let (parsed, parsed_flags) = crate::parse_rift_format(&re.to_string()).unwrap();
+        let reparsed = Regex::new(&parsed, parsed_flags).unwrap();
+        assert_eq!(reparsed.flags(), re.flags());
+        assert_eq!(reparsed.find("a/b a\\/b"), re.find("a/b a\\/b"));
+    }
+
+    let debug = format!("{:?}", re);
+    assert!(
+        debug.starts_with(r#"Regex { pattern: "(\\d+)/x", flags: "#),
+        "{}",
+        debug
+    );
+}
+
+#[test]
+fn test_to_bytes_round_trip() {
+    use crate::{DecodeError, RegexBuilder};
+
+    let text = "Foo foo\r\nbar 12 baz_9 aaa (x) é";
+    for (pattern, flags) in [
+        ("foo", Flags::default()),
+        ("Foo", Flags::default()),
+        (r"(?<word>\w+)\s(\d+)?", Flags::default()),
+        (r"^\a+$", Flags::new().multiline(true).crlf(true)),
+        (r"(a|b)\1*(?<=a)", Flags::default()),
+        (r"[^a-z\d]+?", Flags::new().ignore_case(false)),
+        (r"\(x\zs)\ze", Flags::default()),
+        (r"a{2}|z{1,3}|é", Flags::new().global(true).occurrence(2)),
+        ("", Flags::default()),
+    ] {
+        let re = Regex::new(pattern, flags).unwrap();
+        let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+        assert_eq!(loaded, re, "{}", pattern);
+        assert_eq!(loaded.engine_kind(), re.engine_kind(), "{}", pattern);
+        assert_eq!(loaded.to_bytes(), re.to_bytes(), "{}", pattern);
+        let spans = |re: &Regex| {
+            re.find_all(text)
+                .map(|m| (m.start, m.end))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(spans(&loaded), spans(&re), "{}", pattern);
+    }
+
+    // Builder options are kept
+    let limited = RegexBuilder::new("a{5000}")
+        .repetition_limit(10_000)
+        .build()
+        .unwrap();
+    assert!(Regex::from_bytes(&limited.to_bytes()).is_ok());
+
+    let bytes = Regex::new(r"(a|bc)+[x-z]", Flags::default())
+        .unwrap()
+        .to_bytes();
+    assert!(matches!(
+        Regex::from_bytes(b"nope"),
+        Err(DecodeError::InvalidHeader)
+    ));
+    let mut newer = bytes.clone();
+    newer[4] = 99;
+    assert!(matches!(
+        Regex::from_bytes(&newer),
+        Err(DecodeError::UnsupportedVersion(99))
+    ));
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert!(matches!(
+        Regex::from_bytes(&longer),
+        Err(DecodeError::InvalidData(_))
+    ));
+    for len in 4..bytes.len() {
+        assert!(
+            Regex::from_bytes(&bytes[..len]).is_err(),
+            "prefix of {}",
+            len
+        );
+    }
+    // Corrupt bytes give an error or a working regex, never a panic
+    for i in 5..bytes.len() {
+        for value in [0, 1, 2, 21, 0x7f, 0x80, 0xff] {
+            let mut corrupt = bytes.clone();
+            corrupt[i] = value;
+            if let Ok(re) = Regex::from_bytes(&corrupt) {
+                re.find_all(text).count();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_canonical_pattern() {
+    let cases = [
+        ("a{1,1}b{2,2}?c{0,1}?", Flags::default(), "ab{2}c??"),
+        ("[a-cb-eg-hf]", Flags::default(), "[a-h]"),
+        ("[^zyx]{1,}", Flags::default(), "[^x-z]+"),
+        ("x{0,}?(?:(?:y))", Flags::default(), "x*?y"),
+        // Greediness is already applied, so `U` is off for the result
+        ("a+b*?", "U".parse().unwrap(), "a+?b*"),
+        // As is `x`, so spaces are written out
+        (r"a \  b # comment", "x".parse().unwrap(), r"a b"),
+    ];
+    let texts = ["", "abbc", "aab zyx", "xxy", "a b", "abcdefgh"];
+    for (pattern, flags, expected) in cases {
+        let re = Regex::new(pattern, flags).unwrap();
+        let canonical = re.canonical_pattern();
+        assert_eq!(canonical, expected, "{pattern}");
+
+        let mut canonical_flags = *re.flags();
+        canonical_flags.verbose = false;
+        canonical_flags.ungreedy = false;
+        let reparsed = Regex::new(&canonical, canonical_flags).unwrap();
+        assert_eq!(reparsed.canonical_pattern(), canonical);
+        for text in texts {
+            assert_eq!(
+                reparsed.find_all(text).collect::<Vec<_>>(),
+                re.find_all(text).collect::<Vec<_>>(),
+                "{pattern} on {text:?}"
+            );
+        }
+    }
 }
@@ -1,5 +1,4 @@
 use crate::captures::{Captures, Match};
-use std::collections::HashMap;
 
 #[test]
 fn test_match_methods() {
@@ -14,6 +13,19 @@ fn test_match_methods() {
     assert_eq!(empty_m.len(), 0);
     assert!(empty_m.is_empty());
     assert_eq!(empty_m.as_str(text), "");
+
+    assert_eq!(m.range(), 5..10);
+    assert_eq!(&text[m.range()], "56789");
+    assert_eq!(core::ops::Range::from(m.clone()), 5..10);
+    let range: core::ops::Range<usize> = empty_m.into();
+    assert!(range.is_empty());
+
+    assert_eq!(m.try_as_str(text), Some("56789"));
+    assert_eq!(m.try_as_str("012345"), None);
+    let split = Match { start: 1, end: 2 };
+    assert_eq!(split.try_as_str("é"), None);
+    assert_eq!(split.try_as_str("aé"), None);
+    assert_eq!(Match { start: 1, end: 3 }.try_as_str("aé"), Some("é"));
 }
 
 #[test]
@@ -23,15 +35,11 @@ fn test_captures_access() {
     let group1 = Match { start: 0, end: 5 }; // hello
     let group2 = Match { start: 6, end: 11 }; // world
 
-    let mut named = HashMap::new();
-    named.insert("greeting".to_string(), group1.clone());
-    named.insert("object".to_string(), group2.clone());
-
-    let captures = Captures {
-        full_match: full_match.clone(),
-        groups: vec![Some(group1.clone()), Some(group2.clone())],
-        named,
-    };
+    let captures = Captures::new(
+        full_match.clone(),
+        vec![Some(group1.clone()), Some(group2.clone())],
+        [("greeting".to_string(), 1), ("object".to_string(), 2)],
+    );
 
     // Test get()
     assert_eq!(captures.get(0), Some(&full_match));
@@ -62,11 +70,8 @@ fn test_captures_optional_groups() {
     let full_match = Match { start: 0, end: 5 };
     let group1 = Match { start: 0, end: 5 };
 
-    let captures = Captures {
-        full_match: full_match.clone(),
-        groups: vec![Some(group1.clone()), None], // Second group didn't match
-        named: HashMap::new(),
-    };
+    // Second group didn't match
+    let captures = Captures::new(full_match.clone(), vec![Some(group1.clone()), None], []);
 
     assert_eq!(captures.get(1), Some(&group1));
     assert_eq!(captures.get(2), None);
@@ -74,3 +79,182 @@ fn test_captures_optional_groups() {
     assert_eq!(captures.as_str(text, 1), Some("hello"));
     assert_eq!(captures.as_str(text, 2), None);
 }
+
+#[test]
+fn test_regex_captures() {
+    use crate::{Flags, Regex};
+
+    let re = Regex::new(r"(?<key>\w+)=(\d+)?", Flags::default()).unwrap();
+    let text = "a=1 b= c=3";
+    let caps = re.captures(text).unwrap();
+    assert_eq!(caps.as_str(text, 0), Some("a=1"));
+    assert_eq!(caps.as_str_named(text, "key"), Some("a"));
+    assert_eq!(caps.as_str(text, 2), Some("1"));
+
+    let all: Vec<_> = re.captures_all(text).collect();
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[1].as_str_named(text, "key"), Some("b"));
+    assert_eq!(all[1].get(2), None);
+    assert_eq!(all[2].get(1), Some(&Match { start: 7, end: 8 }));
+}
+
+#[test]
+fn test_named_groups_use_the_name_table() {
+    use crate::{Flags, Regex};
+
+    let re = Regex::new(
+        r"(?<year>\d+)-(?<month>\d+)(-(?<day>\d+))?",
+        Flags::default(),
+    )
+    .unwrap();
+    let text = "2024-05";
+    let caps = re.captures(text).unwrap();
+    assert_eq!(caps.as_str_named(text, "month"), Some("05"));
+    assert_eq!(caps.get_named("day"), None);
+    assert_eq!(caps.get_named("hour"), None);
+    let named: Vec<_> = caps
+        .iter_named()
+        .map(|(name, m)| (name, m.as_str(text)))
+        .collect();
+    assert_eq!(named, vec![("year", "2024"), ("month", "05")]);
+
+    let map = caps.named();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["year"], &crate::captures::Match { start: 0, end: 4 });
+    assert!(!map.contains_key("day"));
+}
+
+#[test]
+fn test_iterators_search_the_whole_text() {
+    use crate::{Flags, Regex};
+
+    // Later searches don't treat the previous match's end as the start of the text
+    let re = Regex::new("^a", Flags::default()).unwrap();
+    assert_eq!(re.find_all("aaa").count(), 1);
+    let re = Regex::new(r"\<a", Flags::default()).unwrap();
+    assert_eq!(re.find_all("aa a").count(), 2);
+    let re = Regex::new("(?<!a)(b)", Flags::default()).unwrap();
+    assert_eq!(re.captures_all("bab").count(), 1);
+
+    // Empty matches advance by a whole character
+    let re = Regex::new("x*", Flags::default()).unwrap();
+    let starts: Vec<_> = re.find_all("éx").map(|m| m.start).collect();
+    assert_eq!(starts, vec![0, 2, 3]);
+}
+
+#[test]
+fn test_quantified_group_captures() {
+    use crate::{Flags, Regex};
+
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    // Expected groups follow PCRE
+    for (pattern, text, expected) in [
+        // A repeated group reports its last iteration
+        (r"(\w)+", "abc", vec![Some("c")]),
+        ("(a|b)*c", "abac", vec![Some("a")]),
+        ("(a?)+?b", "aab", vec![Some("a")]),
+        ("((a)|b)+", "ab", vec![Some("b"), Some("a")]),
+        // A group keeps its value from an earlier iteration it took part in
+        ("(?:(a)|b)+", "ab", vec![Some("a")]),
+        ("(?:a(b)?)+", "aba", vec![Some("b")]),
+        // Groups that never took part are unset, even if a failed path set them
+        ("(a)?b", "b", vec![None]),
+        ("(a)*", "b", vec![None]),
+        ("(?:(a)c|ab)", "ab", vec![None]),
+        // An empty iteration is kept, and ends the loop
+        ("(a*)+", "b", vec![Some("")]),
+        ("(a*)*", "b", vec![Some("")]),
+        ("(a*)+", "aa", vec![Some("")]),
+        (
+            "(a|ab)(c|bcd)(d*)",
+            "abcd",
+            vec![Some("a"), Some("bcd"), Some("")],
+        ),
+    ] {
+        let re = Regex::new(pattern, sensitive).unwrap();
+        let caps = re.captures(text).unwrap();
+        let groups: Vec<_> = (1..=expected.len()).map(|i| caps.as_str(text, i)).collect();
+        assert_eq!(groups, expected, "groups of '{}' on '{}'", pattern, text);
+    }
+}
+
+#[test]
+fn test_lookaround_captures() {
+    use crate::{Regex, RegexBuilder};
+
+    let groups = |re: &Regex, text: &str, count: usize| {
+        let caps = re.captures(text).unwrap();
+        let full = caps.as_str(text, 0).unwrap().to_string();
+        let groups: Vec<_> = (1..=count)
+            .map(|i| caps.as_str(text, i).map(str::to_string))
+            .collect();
+        (full, groups)
+    };
+    let some = |s: &str| Some(s.to_string());
+
+    for (pattern, text, expected) in [
+        // A lookaround that matched keeps what it captured
+        (r"(?>=(\w+)!)\w", "hey!", ("h", vec![some("hey")])),
+        (r"(?<=(\d)\d)x", "12x", ("x", vec![some("1")])),
+        ("(?<=(a)|(b))c", "bc", ("c", vec![None, some("b")])),
+        // Later parts of the pattern can refer back to them
+        (r"(?>=\w(\w))\w\1", "abc", ("ab", vec![some("b")])),
+        // A negative lookaround only matches when its body fails
+        ("(?>!(x))a", "a", ("a", vec![None])),
+        // A path that fails after the lookaround drops its captures
+        ("(?:(?>=(a))x|ab)", "ab", ("ab", vec![None])),
+    ] {
+        let re = Regex::new(pattern, Default::default()).unwrap();
+        let (full, found) = groups(&re, text, expected.1.len());
+        assert_eq!(
+            (full.as_str(), found),
+            expected,
+            "'{}' on '{}'",
+            pattern,
+            text
+        );
+    }
+
+    let dropped = RegexBuilder::new(r"(?>=(\w+)!)\w")
+        .lookaround_captures(false)
+        .build()
+        .unwrap();
+    assert_eq!(groups(&dropped, "hey!", 1), ("h".to_string(), vec![None]));
+    let backref = RegexBuilder::new(r"(?>=\w(\w))\w\1")
+        .lookaround_captures(false)
+        .build()
+        .unwrap();
+    assert!(!backref.is_match("abc"));
+
+    // The setting survives a round trip through bytes
+    let loaded = Regex::from_bytes(&dropped.to_bytes()).unwrap();
+    assert_eq!(groups(&loaded, "hey!", 1).1, vec![None]);
+}
+
+#[test]
+fn test_extract() {
+    use crate::{Flags, Regex};
+
+    let re = Regex::new(r"(?<key>\w+)=(\w*)(;)?", Flags::default()).unwrap();
+    let (full, [key, value, end]) = re.extract("x: a=1; b=").unwrap();
+    assert_eq!((full, key, value, end), ("a=1;", "a", "1", ";"));
+    // Groups that didn't take part are empty
+    let (full, [_, value, end]) = re.extract("b=").unwrap();
+    assert_eq!((full, value, end), ("b=", "", ""));
+    assert!(re.extract::<3>("nothing").is_none());
+
+    let plain = Regex::new("abc", Flags::default()).unwrap();
+    assert_eq!(plain.extract("xabc"), Some(("abc", [])));
+}
+
+#[test]
+#[should_panic(expected = "the pattern has 1 capture groups, not 2")]
+fn test_extract_wrong_group_count() {
+    use crate::{Flags, Regex};
+
+    let re = Regex::new("(a)b", Flags::default()).unwrap();
+    let _ = re.extract::<2>("ab");
+}
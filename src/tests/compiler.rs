@@ -0,0 +1,597 @@
+use crate::compiler::prefilter::{Prefilter, RequiredLiteral};
+use crate::compiler::{Anchor, ScanPlan, optimize};
+use crate::{
+    AstNode, CharClass, CompileError, ComplexityKind, Flags, LintKind, Parser, Regex, RegexBuilder,
+    Severity, check_complexity, diagnose, lint, parse_rift_format,
+};
+
+#[test]
+fn test_reversed_bounds_rejected() {
+    assert!(matches!(
+        Regex::new("a{5,2}", Flags::default()),
+        Err(CompileError::InvalidQuantifier(_))
+    ));
+    assert!(Regex::new("a{2,2}", Flags::default()).is_ok());
+}
+
+#[test]
+fn test_repetition_limit() {
+    assert!(matches!(
+        Regex::new("a{100000}", Flags::default()),
+        Err(CompileError::InvalidQuantifier(_))
+    ));
+    assert!(matches!(
+        Regex::new("(a{1,99999})", Flags::default()),
+        Err(CompileError::InvalidQuantifier(_))
+    ));
+
+    // The limit is configurable
+    let re = RegexBuilder::new("a{3,}").repetition_limit(2).build();
+    assert!(matches!(re, Err(CompileError::InvalidQuantifier(_))));
+    let re = RegexBuilder::new("a{1500}").repetition_limit(2000).build();
+    assert!(re.is_ok());
+}
+
+#[test]
+fn test_compile_limits() {
+    fn exceeded(result: Result<Regex, CompileError>) -> bool {
+        matches!(result, Err(CompileError::ExceededLimits(_)))
+    }
+
+    // Pattern size, in bytes
+    assert!(exceeded(RegexBuilder::new("abcde").size_limit(4).build()));
+    assert!(RegexBuilder::new("abcd").size_limit(4).build().is_ok());
+    let huge = "a".repeat(crate::compiler::DEFAULT_SIZE_LIMIT + 1);
+    assert!(exceeded(Regex::new(&huge, Flags::default())));
+
+    // Nesting, counting lookarounds, and never deeper than the parser allows
+    assert!(exceeded(
+        RegexBuilder::new("(a(?<=b))").nest_limit(1).build()
+    ));
+    assert!(RegexBuilder::new("(a)(b)").nest_limit(1).build().is_ok());
+    assert!(RegexBuilder::new("a").nest_limit(0).build().is_ok());
+    let deep = format!("{}a{}", "(".repeat(10_000), ")".repeat(10_000));
+    assert!(exceeded(Regex::new(&deep, Flags::default())));
+    assert!(exceeded(
+        RegexBuilder::new(&deep).nest_limit(20_000).build()
+    ));
+
+    // Program size
+    let size = |pattern| RegexBuilder::new(pattern).program_size_limit(8).build();
+    assert!(size("abc").is_ok());
+    assert!(exceeded(size("(a)(b)(c)(d)(e)")));
+
+    let err = RegexBuilder::new("((a))")
+        .nest_limit(1)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.to_diagnostic().code, "exceeded-limits");
+}
+
+#[test]
+fn test_backref_policy() {
+    use crate::BackrefPolicy;
+
+    let strict = |pattern| RegexBuilder::new(pattern).build();
+    let lenient = |pattern| {
+        RegexBuilder::new(pattern)
+            .backrefs(BackrefPolicy::MatchEmpty)
+            .build()
+            .unwrap()
+    };
+
+    // Self, forward and missing references are rejected up front
+    for pattern in [r"(a\1)", r"\1(a)", r"(a(b\1))", r"(a)\2", r"(?:\1b|(a))+"] {
+        let err = strict(pattern).unwrap_err();
+        assert!(
+            matches!(err, CompileError::InvalidBackref(_)),
+            "{}: {:?}",
+            pattern,
+            err
+        );
+        assert_eq!(err.to_diagnostic().code, "invalid-backref");
+    }
+    assert!(matches!(
+        strict(r"(a\1)"),
+        Err(CompileError::InvalidBackref(message)) if message == r"\1 refers to a group it's inside of"
+    ));
+    // A group that closed earlier is fine, though it may not be set when matching
+    let re = strict(r"(a)?b\1").unwrap();
+    assert!(re.is_match("aba"));
+    assert!(!re.is_match("b"));
+    assert!(strict(r"(a)(b\1)").is_ok());
+
+    // Leniently, references to unset groups match nothing
+    assert_eq!(lenient(r"(a\1)").find("a").map(|m| m.end), Some(1));
+    assert_eq!(lenient(r"\1(a)").find("a").map(|m| m.end), Some(1));
+    assert!(lenient(r"(a)?b\1").is_match("b"));
+    assert_eq!(lenient(r"x\3").find("x").map(|m| m.end), Some(1));
+    // A forward reference in a loop sees the group's previous iteration
+    let re = lenient(r"^(?:\1b|(a))+$");
+    assert!(re.is_match("aab"));
+
+    // The policy survives a round trip through bytes
+    let loaded = Regex::from_bytes(&lenient(r"(a\1)").to_bytes()).unwrap();
+    assert!(loaded.is_match("a"));
+
+    // Only `\1` to `\9` can be written, so a tree can't refer to a later group
+    let mut ast: Vec<_> = (0..12)
+        .map(|i| AstNode::Group {
+            nodes: vec![AstNode::Literal('a')],
+            name: None,
+            capture: true,
+            index: Some(i + 1),
+        })
+        .collect();
+    ast.push(AstNode::Backref(9));
+    assert!(Regex::from_ast(ast.clone(), Flags::default()).is_ok());
+    ast.push(AstNode::Backref(12));
+    assert!(matches!(
+        Regex::from_ast(ast, Flags::default()),
+        Err(CompileError::InvalidBackref(message)) if message.starts_with(r"\12 can't be written")
+    ));
+    let twelve = format!("{}\\12", "(a)".repeat(12));
+    for pattern in [twelve.as_str(), r"(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\g{10}"] {
+        assert!(matches!(
+            crate::from_pcre(pattern),
+            Err(crate::ImportError::Unsupported(_))
+        ));
+    }
+}
+
+#[test]
+fn test_count_overflow_is_an_error() {
+    assert!(Regex::new("a{99999999999999999999999}", Flags::default()).is_err());
+}
+
+#[test]
+fn test_zero_count_matches_empty() {
+    let re = Regex::new("ba{0}c", Flags::default()).unwrap();
+    assert!(re.is_match("bc"));
+    assert!(!re.is_match("bac"));
+
+    let re = Regex::new("ba{0,0}c", Flags::default()).unwrap();
+    assert!(re.is_match("bc"));
+}
+
+#[test]
+fn test_duplicate_group_names_rejected() {
+    assert!(matches!(
+        Regex::new("(?<a>x)(?<a>y)", Flags::default()),
+        Err(CompileError::DuplicateGroupName(name)) if name == "a"
+    ));
+}
+
+fn group(nodes: Vec<AstNode>, index: Option<usize>) -> AstNode {
+    AstNode::Group {
+        nodes,
+        name: None,
+        capture: true,
+        index,
+    }
+}
+
+#[test]
+fn test_from_ast() {
+    let ast = vec![
+        group(vec![AstNode::Literal('a')], Some(1)),
+        AstNode::Range {
+            node: Box::new(AstNode::CharClass(CharClass::Digit)),
+            min: 1,
+            max: Some(2),
+            greedy: true,
+        },
+        AstNode::Backref(1),
+    ];
+    let re = Regex::from_ast(ast, Flags::default()).unwrap();
+    assert_eq!(re.pattern(), r"(a)\d{1,2}\1");
+    assert!(re.is_match("a12a"));
+    assert!(!re.is_match("a123"));
+}
+
+#[test]
+fn test_from_ast_is_validated() {
+    let reversed = vec![AstNode::Range {
+        node: Box::new(AstNode::Literal('a')),
+        min: 3,
+        max: Some(1),
+        greedy: true,
+    }];
+    assert!(matches!(
+        Regex::from_ast(reversed, Flags::default()),
+        Err(CompileError::InvalidQuantifier(_))
+    ));
+
+    let missing_index = vec![group(vec![AstNode::Literal('a')], None)];
+    assert!(matches!(
+        Regex::from_ast(missing_index, Flags::default()),
+        Err(CompileError::InvalidGroup(_))
+    ));
+
+    let repeated_index = vec![
+        group(vec![AstNode::Literal('a')], Some(1)),
+        group(vec![AstNode::Literal('b')], Some(1)),
+    ];
+    assert!(matches!(
+        Regex::from_ast(repeated_index, Flags::default()),
+        Err(CompileError::InvalidGroup(_))
+    ));
+}
+
+fn prefilter_for(pattern: &str, flags: Flags) -> Option<Prefilter> {
+    let ast = optimize(Parser::new(pattern, flags).parse().unwrap());
+    Prefilter::new(&ast, &flags)
+}
+
+#[test]
+fn test_prefilter_selection() {
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    assert!(matches!(
+        prefilter_for("^http://", sensitive),
+        Some(Prefilter::Prefix(p)) if p == "http://"
+    ));
+    assert!(matches!(
+        prefilter_for(r"\bfoo(bar)\d", sensitive),
+        Some(Prefilter::Prefix(p)) if p == "foobar"
+    ));
+    assert!(matches!(
+        prefilter_for(r"cat|dog", sensitive),
+        Some(Prefilter::FirstChars(_))
+    ));
+    assert!(matches!(
+        prefilter_for(r"\d*\.\d+", sensitive),
+        Some(Prefilter::FirstChars(_))
+    ));
+
+    // Patterns that can match empty or start with anything have no prefilter
+    assert!(prefilter_for("a*", sensitive).is_none());
+    assert!(prefilter_for("a|", sensitive).is_none());
+    assert!(prefilter_for(r"\w+", sensitive).is_none());
+    assert!(prefilter_for(r"(a)\1", sensitive).is_some());
+    assert!(prefilter_for(r"\1", sensitive).is_none());
+}
+
+#[test]
+fn test_prefilter_candidates() {
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    let prefilter = prefilter_for("ab", sensitive).unwrap();
+    assert_eq!(prefilter.next_candidate("xxabyab", 0), Some(2));
+    assert_eq!(prefilter.next_candidate("xxabyab", 3), Some(5));
+    assert_eq!(prefilter.next_candidate("xxabyab", 6), None);
+
+    let prefilter = prefilter_for("[xé]", sensitive).unwrap();
+    assert_eq!(prefilter.next_candidate("aaéx", 0), Some(2));
+    assert_eq!(prefilter.next_candidate("aaéx", 4), Some(4));
+}
+
+#[test]
+fn test_prefilter_respects_case_folding() {
+    let insensitive = Flags {
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
+    let re = Regex::new("kb", insensitive).unwrap();
+    assert_eq!(re.find("xxKB").map(|m| m.start), Some(2));
+    assert_eq!(re.find("x\u{212A}b").map(|m| m.start), Some(1));
+
+    let re = Regex::new("[a-c]x", insensitive).unwrap();
+    assert!(re.is_match("Bx"));
+}
+
+#[test]
+fn test_prefilter_find_results() {
+    let re = Regex::new("^http://", Flags::default()).unwrap();
+    assert!(re.find("xhttp://").is_none());
+    assert_eq!(re.find("http://x").map(|m| m.end), Some(7));
+
+    let re = Regex::new(r"\d*\.\d+", Flags::default()).unwrap();
+    let m = re.find("v 12.5").unwrap();
+    assert_eq!((m.start, m.end), (2, 6));
+
+    let re = Regex::new("b(?<=ab)c", Flags::default()).unwrap();
+    assert_eq!(re.find("bc abc").map(|m| m.start), Some(4));
+}
+
+fn required_for(pattern: &str, flags: Flags) -> Option<RequiredLiteral> {
+    let ast = optimize(Parser::new(pattern, flags).parse().unwrap());
+    RequiredLiteral::new(&ast, &flags)
+}
+
+#[test]
+fn test_required_literal_selection() {
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    let required = required_for(r"\w+@\w+\.com", sensitive).unwrap();
+    assert_eq!(required.find("a@b.com", 0), Some(3));
+    assert_eq!(required.earliest_start(3), 0);
+
+    // The preceding part is bounded, so distant starts can be skipped
+    let required = required_for(r"\d{2}-x", sensitive).unwrap();
+    assert_eq!(required.find("1234-x", 0), Some(4));
+    assert_eq!(required.earliest_start(20), 12);
+
+    // A leading literal is left to the start prefilter
+    assert!(required_for("abc", sensitive).is_none());
+    // Literals under quantifiers or alternations aren't required
+    assert!(required_for(r"\w(ab)*", sensitive).is_none());
+    assert!(required_for(r"\w(ab|cd)", sensitive).is_none());
+
+    // Case-folded search only covers ASCII text without 'k'
+    let insensitive = Flags {
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
+    let required = required_for(r"\w+@ex\.", insensitive).unwrap();
+    assert_eq!(required.find("USER@EX.ORG", 0), Some(4));
+    assert!(required_for(r"\w+ok", insensitive).is_none());
+}
+
+#[test]
+fn test_required_literal_find_results() {
+    let re = Regex::new(r"\w+@\w+\.com", Flags::default()).unwrap();
+    let text = "contact: someone@example.com, other@test.org";
+    let m = re.find(text).unwrap();
+    assert_eq!(m.as_str(text), "someone@example.com");
+    assert!(!re.is_match("no address here at all"));
+
+    let re = Regex::new(r"(\d{1,3})x", Flags::default()).unwrap();
+    let text = "1234567x";
+    assert_eq!(re.find(text).unwrap().as_str(text), "567x");
+
+    let all: Vec<_> = re.find_all("9x 10x 123x").map(|m| m.start).collect();
+    assert_eq!(all, vec![0, 3, 7]);
+}
+
+fn plan_for(pattern: &str, flags: Flags) -> ScanPlan {
+    let ast = optimize(Parser::new(pattern, flags).parse().unwrap());
+    ScanPlan::new(&ast, &flags)
+}
+
+#[test]
+fn test_anchor_analysis() {
+    let multiline = Flags {
+        multiline: true,
+        ..Flags::default()
+    };
+    assert_eq!(
+        plan_for("^ab", Flags::default()).start_anchor,
+        Some(Anchor::Text)
+    );
+    assert_eq!(plan_for("^ab", multiline).start_anchor, Some(Anchor::Line));
+    assert_eq!(
+        plan_for("(^a|^b)c", Flags::default()).start_anchor,
+        Some(Anchor::Text)
+    );
+    assert_eq!(plan_for("^a|b", Flags::default()).start_anchor, None);
+    assert_eq!(plan_for("a^", Flags::default()).start_anchor, None);
+
+    assert_eq!(
+        plan_for(r"\d{1,3}$", Flags::default()).end_anchor,
+        Some((Anchor::Text, 3))
+    );
+    assert_eq!(plan_for(r"(a|bc$)", Flags::default()).end_anchor, None);
+    // Unbounded patterns can't use the end anchor
+    assert_eq!(plan_for(r"\d+$", Flags::default()).end_anchor, None);
+}
+
+#[test]
+fn test_anchored_find_results() {
+    let multiline = Flags {
+        multiline: true,
+        ..Flags::default()
+    };
+
+    let re = Regex::new(r"^\w+", Flags::default()).unwrap();
+    assert_eq!(re.find("  abc").map(|m| m.start), None);
+    assert_eq!(re.find("abc def").map(|m| m.end), Some(3));
+
+    let re = Regex::new(r"^b\w", multiline).unwrap();
+    let text = "abc\nax\nbz\nby";
+    assert_eq!(re.find(text).map(|m| m.start), Some(7));
+    let starts: Vec<_> = re.find_all(text).map(|m| m.start).collect();
+    assert_eq!(starts, vec![7, 10]);
+
+    let re = Regex::new(r"\d{1,3}$", Flags::default()).unwrap();
+    let text = "12345 and 6789";
+    assert_eq!(re.find(text).unwrap().as_str(text), "789");
+
+    let re = Regex::new(r"x\d$", multiline).unwrap();
+    let text = "x1 x2\nx3 y\nx4";
+    let found: Vec<_> = re.find_all(text).map(|m| m.as_str(text)).collect();
+    assert_eq!(found, vec!["x2", "x4"]);
+
+    let re = Regex::new(r"é$", Flags::default()).unwrap();
+    assert!(re.is_match("ééé"));
+}
+
+#[test]
+fn test_match_length_bounds() {
+    for (pattern, min, max) in [
+        ("abc", 3, Some(3)),
+        (r"a\d?b", 2, Some(3)),
+        ("a|bcd", 1, Some(3)),
+        ("(ab){2,4}", 4, Some(8)),
+        ("x+", 1, None),
+        ("x*y", 1, None),
+        (r"^\bfoo(?>=bar)$", 3, Some(3)),
+        (r"(a)\1", 1, None),
+        ("(?:){3,}", 0, Some(0)),
+    ] {
+        let re = Regex::new(pattern, Flags::default()).unwrap();
+        assert_eq!(re.min_match_len(), min, "min length of '{}'", pattern);
+        assert_eq!(re.max_match_len(), max, "max length of '{}'", pattern);
+    }
+}
+
+#[test]
+fn test_min_length_ends_scan() {
+    let re = Regex::new(r"\w{3}", Flags::default()).unwrap();
+    assert!(re.find("ab").is_none());
+    assert_eq!(re.find("a abc").map(|m| m.start), Some(2));
+    assert!(re.find("é").is_none());
+}
+
+#[test]
+fn test_pattern_info() {
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    let info = |pattern: &str| Regex::new(pattern, sensitive).unwrap().info().clone();
+
+    let plain = info("foo(bar|baz)+");
+    assert!(!plain.anchored_start && !plain.anchored_end);
+    assert!(!plain.has_backrefs && !plain.has_lookaround && !plain.has_match_bounds);
+    assert_eq!(plain.literal_prefix, "foo");
+    assert_eq!((plain.min_len, plain.max_len), (6, None));
+
+    let anchored = info("^(?:ab|cd)$");
+    assert!(anchored.anchored_start && anchored.anchored_end);
+    assert_eq!(anchored.literal_prefix, "");
+    assert_eq!((anchored.min_len, anchored.max_len), (2, Some(2)));
+
+    assert!(info(r"(a)x\1").has_backrefs);
+    assert!(info("a(?<=b)").has_lookaround);
+    assert!(info("(?:x(?>!y))?").has_lookaround);
+    assert!(info(r"foo\zsbar").has_match_bounds);
+
+    // Folded patterns have no usable prefix
+    let folded = Regex::new("hello", Flags::default()).unwrap();
+    assert_eq!(folded.info().literal_prefix, "");
+}
+
+#[test]
+fn test_check_complexity() {
+    let kinds = |pattern: &str| -> Vec<ComplexityKind> {
+        check_complexity(pattern, Flags::default())
+            .unwrap()
+            .iter()
+            .map(|w| w.kind)
+            .collect()
+    };
+
+    assert_eq!(kinds("(a+)+"), [ComplexityKind::NestedQuantifier]);
+    assert_eq!(kinds(r"^(\w+\s?)*$"), [ComplexityKind::NestedQuantifier]);
+    assert_eq!(kinds("(x*y*)*z"), [ComplexityKind::NestedQuantifier; 2]);
+    assert_eq!(kinds("(a|a)*"), [ComplexityKind::OverlappingAlternation]);
+    assert_eq!(
+        kinds(r"(?:\w|\d)+$"),
+        [ComplexityKind::OverlappingAlternation]
+    );
+
+    // Each iteration is pinned by a character the inner loop can't take
+    assert!(kinds("(ab+)+").is_empty());
+    assert!(kinds("(a+b)+").is_empty());
+    assert!(kinds(r"(\w+\s)*").is_empty());
+    assert!(kinds("(a|b)*").is_empty());
+    assert!(kinds("a+b+").is_empty());
+    assert!(kinds("(a+){3}").is_empty());
+
+    let warnings = check_complexity("x(?<n>a*)+", Flags::default()).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!((warnings[0].span.start, warnings[0].span.end), (6, 8));
+
+    assert!(matches!(
+        check_complexity("(a", Flags::default()),
+        Err(CompileError::InvalidPattern(_))
+    ));
+}
+
+#[test]
+fn test_lint() {
+    let found = |pattern: &str| -> Vec<(LintKind, String)> {
+        lint(pattern, Flags::default())
+            .unwrap()
+            .iter()
+            .map(|w| (w.kind, pattern[w.span.start..w.span.end].to_string()))
+            .collect()
+    };
+    let one = |kind, text: &str| vec![(kind, text.to_string())];
+
+    assert_eq!(found("ab{1}c"), one(LintKind::RedundantQuantifier, "b{1}"));
+    assert_eq!(
+        found("x{1,1}"),
+        one(LintKind::RedundantQuantifier, "x{1,1}")
+    );
+    assert_eq!(found("cat|dog|cat"), one(LintKind::DuplicateBranch, "cat"));
+    assert_eq!(found("(a||)"), one(LintKind::DuplicateBranch, "a||"));
+    assert_eq!(found("[a-zc-fé-]"), one(LintKind::RedundantSetItem, "c-f"));
+    assert_eq!(found("[xx]"), one(LintKind::RedundantSetItem, "x"));
+    assert_eq!(found("[a-c-e]"), one(LintKind::AmbiguousHyphen, "-"));
+    assert_eq!(found("(?:abc)d"), one(LintKind::UselessGroup, "(?:abc)"));
+    assert_eq!(found("(?:a)*"), one(LintKind::UselessGroup, "(?:a)"));
+
+    // Nothing to report
+    for pattern in [
+        "(?:ab)+", "x(?:a|b)", "[-a-c]", r"[a\-c]", "[a-]", "a{2}", "(a)", "a|b",
+    ] {
+        assert!(found(pattern).is_empty(), "{pattern}");
+    }
+}
+
+#[test]
+fn test_diagnose() {
+    fn one(pattern: &str) -> (&'static str, Severity, Option<&str>) {
+        let diagnostics = diagnose(pattern, Flags::default());
+        assert_eq!(diagnostics.len(), 1, "{pattern}");
+        let d = diagnostics.into_iter().next().unwrap();
+        (
+            d.code,
+            d.severity,
+            d.span.map(|span| &pattern[span.start..span.end]),
+        )
+    }
+
+    // Spans are byte ranges, so they stay on character boundaries
+    assert_eq!(one(r"é\zé"), ("invalid-escape", Severity::Error, Some("é")));
+    assert_eq!(one("[a"), ("unexpected-end", Severity::Error, Some("")));
+    assert_eq!(
+        one("[0-9é-a]"),
+        ("invalid-char-class", Severity::Error, Some("é-a"))
+    );
+    // Errors found after parsing have no span
+    assert_eq!(
+        one("a{2000}"),
+        ("invalid-quantifier", Severity::Error, None)
+    );
+    assert_eq!(
+        one("x[aa]"),
+        ("redundant-set-item", Severity::Warning, Some("a"))
+    );
+}
+
+#[test]
+fn test_diagnostic_json() {
+    let err = parse_rift_format("abc").unwrap_err();
+    assert_eq!(
+        err.to_json(),
+        r#"{"code":"no-delimiter","message":"Missing `/` between the pattern and its flags","severity":"error","span":null,"suggestion":"end the pattern with `/`, followed by any flags"}"#
+    );
+
+    let err = Regex::new("(?<x>a)(?<x>b)", Flags::default()).unwrap_err();
+    let json = err.to_diagnostic().to_json();
+    assert!(
+        json.starts_with(r#"{"code":"duplicate-group-name","#),
+        "{json}"
+    );
+    assert!(
+        json.contains(r#""suggestion":"rename one of the groups named `x`"}"#),
+        "{json}"
+    );
+
+    let mut diagnostic = err.to_diagnostic();
+    diagnostic.message = "quote \" backslash \\ newline \n bell \u{7}".to_string();
+    assert!(
+        diagnostic
+            .to_json()
+            .contains(r#""message":"quote \" backslash \\ newline \n bell \u0007""#)
+    );
+}
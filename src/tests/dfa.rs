@@ -0,0 +1,260 @@
+use crate::compiler::optimize;
+use crate::engine::{LazyDfa, Matcher};
+use crate::{EngineKind, Flags, Parser, Regex};
+
+fn backtracking_find(pattern: &str, flags: Flags, text: &str) -> Option<(usize, usize)> {
+    let ast = Parser::new(pattern, flags).parse().unwrap();
+    Matcher::new(&ast, &flags, text)
+        .find()
+        .map(|m| (m.start, m.end))
+}
+
+#[test]
+fn test_dfa_agrees_with_backtracking() {
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    let multiline = Flags {
+        multiline: true,
+        ..sensitive
+    };
+    let cases = [
+        ("abc", "xxabcxx"),
+        ("a|ab|abc", "zabcz"),
+        (r"\d+\.\d*", "v 12. 3.45"),
+        ("(ab)*c", "ababab ababc"),
+        ("a{2,3}b", "ab aab aaaab"),
+        ("x?y??z", "yz xz z"),
+        (r"\bcat\b", "concat cat"),
+        (r"\<is\>", "this is"),
+        (r"^$", ""),
+        (r"^\w+$", "word"),
+        (r"^\w+$", "two words"),
+        ("[^a-c]+", "abcdef"),
+        ("é+", "aééb"),
+        (".*", "line\nnext"),
+        ("(a|)+b", "aab"),
+        ("(a*)*c", "aaaa"),
+    ];
+    for flags in [sensitive, multiline] {
+        for (pattern, text) in cases {
+            let re = Regex::new(pattern, flags).unwrap();
+            let expected = backtracking_find(pattern, flags, text);
+            let actual = re.find(text).map(|m| (m.start, m.end));
+            assert_eq!(actual, expected, "find '{}' on '{:?}'", pattern, text);
+            assert_eq!(re.is_match(text), expected.is_some());
+        }
+    }
+
+    let re = Regex::new(r"^b$", multiline).unwrap();
+    assert_eq!(re.find("a\nb\nc").map(|m| m.start), Some(2));
+    let re = Regex::new(
+        "K",
+        Flags {
+            ignore_case: Some(true),
+            ..Flags::default()
+        },
+    )
+    .unwrap();
+    assert!(re.is_match("\u{212A}"));
+}
+
+#[test]
+fn test_folded_sets_across_engines() {
+    let flags = Flags {
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
+    let cases = [
+        ("[a-ck]+", "xAbK\u{212A}cz", Some((1, 8))),
+        ("[^k]+", "k\u{212A}ab", Some((4, 6))),
+        ("[σ]", "ΣΣ", Some((0, 2))),
+        ("x[A-Z]", "xé xÉ xq", Some((8, 10))),
+        ("[K]", "\u{212A}", Some((0, 3))),
+        ("[A-Z]", "\u{212A}", Some((0, 3))),
+        ("[A-Z]+", "ßﬀİ", None),
+        ("[a-z]", "İ", None),
+        ("[ẞ]", "ß", Some((0, 2))),
+        ("[ß]", "ẞ", Some((0, 3))),
+    ];
+    for (pattern, text, expected) in cases {
+        let re = Regex::new(pattern, flags).unwrap();
+        assert_eq!(
+            re.find(text).map(|m| (m.start, m.end)),
+            expected,
+            "{}",
+            pattern
+        );
+        assert_eq!(
+            backtracking_find(pattern, flags, text),
+            expected,
+            "{}",
+            pattern
+        );
+    }
+}
+
+#[test]
+fn test_dfa_eligibility() {
+    let flags = Flags::default();
+    let dfa_for = |pattern: &str| {
+        let ast = Parser::new(pattern, flags).parse().unwrap();
+        LazyDfa::new(&ast, &flags)
+    };
+    assert!(dfa_for(r"(a|b)+\d{2,5}$").is_some());
+    assert!(dfa_for(r"(a)\1").is_none());
+    assert!(dfa_for(r"a(?>=b)").is_none());
+    assert!(dfa_for(r"(?<!a)b").is_none());
+    assert!(dfa_for(r"foo\zsbar").is_none());
+    // Counted repetitions too large to expand fall back to backtracking
+    assert!(dfa_for(r"(\w{1,100}){1,100}").is_none());
+}
+
+#[test]
+fn test_one_pass_detection() {
+    let flags = Flags::default().ignore_case(false);
+    for (pattern, one_pass) in [
+        (r"\d+-\d+", true),
+        (r"\w+@\w+", true),
+        ("(cat|dog)s?$", true),
+        ("(a|ab)c", true),
+        ("(x)", true),
+        (r"\w+\d", false),
+        (r"(ab|a)\w", false),
+        ("(a*)*b", false),
+    ] {
+        let ast = optimize(Parser::new(pattern, flags).parse().unwrap());
+        let dfa = LazyDfa::new(&ast, &flags).unwrap();
+        assert_eq!(dfa.is_one_pass(), one_pass, "{}", pattern);
+    }
+}
+
+#[test]
+fn test_dfa_start_and_end_positions() {
+    let flags = Flags::default();
+    let ast = Parser::new("ab+", flags).parse().unwrap();
+    let dfa = LazyDfa::new(&ast, &flags).unwrap();
+    assert_eq!(dfa.earliest_end("xabbb", 0), Some(3));
+    assert_eq!(dfa.earliest_end("xabbb ab", 3), Some(8));
+    assert!(dfa.matches_at("xabbb", 1));
+    assert!(!dfa.matches_at("xabbb", 0));
+    assert!(!dfa.matches_at("xabbb", 2));
+    assert_eq!(dfa.leftmost_start("xabbb ab", 0, 8), Some(1));
+    assert_eq!(dfa.leftmost_start("xabbb ab", 2, 8), Some(6));
+    assert_eq!(dfa.leftmost_start("xabbb ab", 2, 6), None);
+
+    // The leftmost match can end after the earliest one does
+    let ast = Parser::new(r"a.*z|b\b", flags).parse().unwrap();
+    let dfa = LazyDfa::new(&ast, &flags).unwrap();
+    assert_eq!(dfa.leftmost_start("xa b z", 0, 6), Some(1));
+    assert_eq!(dfa.leftmost_start("xa b z", 0, 5), Some(3));
+}
+
+#[test]
+fn test_find_tries_one_start_position() {
+    // Probing every start before the match would make this quadratic
+    let re = Regex::new("[a-z]+ing", Flags::default()).unwrap();
+    let text = format!("{} walking", "a".repeat(20_000));
+    let (found, stats) = re.find_with_stats(&text);
+    assert_eq!(found.map(|m| m.as_str(&text)), Some("walking"));
+    assert_eq!(stats.positions_attempted, 1);
+    assert!(stats.steps < 100, "{:?}", stats);
+
+    let re = Regex::new(r"\w+\s+\d", Flags::default()).unwrap();
+    let text = format!("{} 7", "x ".repeat(10_000));
+    let (found, stats) = re.find_with_stats(&text);
+    assert_eq!(found.map(|m| m.start), Some(text.len() - 4));
+    assert_eq!(stats.positions_attempted, 1);
+}
+
+#[test]
+fn test_no_catastrophic_backtracking() {
+    // Without the DFA every start position backtracks exponentially
+    let re = Regex::new("(a*)*b", Flags::default()).unwrap();
+    let text = "a".repeat(40);
+    assert!(!re.is_match(&text));
+    assert!(re.find(&text).is_none());
+
+    let re = Regex::new(r"(\w+\s?)+$", Flags::default()).unwrap();
+    let text = format!("{}!", "word ".repeat(20));
+    assert!(!re.is_match(&text));
+}
+
+#[test]
+fn test_engine_selection() {
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    for (pattern, kind) in [
+        ("hello", EngineKind::Literal),
+        ("a(?:bc)d", EngineKind::Literal),
+        ("", EngineKind::Literal),
+        (r"\d+-\d+", EngineKind::Dfa),
+        (r"\w+\d", EngineKind::Dfa),
+        ("(a*)*b", EngineKind::Dfa),
+        (r"(\w)\1", EngineKind::Backtracking),
+        ("a(?>=b)", EngineKind::Backtracking),
+    ] {
+        let re = Regex::new(pattern, sensitive).unwrap();
+        assert_eq!(re.engine_kind(), kind, "engine for '{}'", pattern);
+    }
+
+    // Case-insensitive literals are searched for directly too
+    let re = Regex::new("abc", Flags::default()).unwrap();
+    assert_eq!(re.engine_kind(), EngineKind::Literal);
+    assert!(re.is_literal());
+}
+
+#[test]
+fn test_engines_agree() {
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    for (pattern, text) in [
+        ("needle", "haystack with a needle in it"),
+        ("", "abc"),
+        ("é", "caféé"),
+        (r"\d+-\d+", "1- 12-34"),
+        (r"\w+@\w+", "@ a@ b@c"),
+        ("(cat|dog)s?$", "dogs cats"),
+        ("a+b", "aaaa aab"),
+    ] {
+        let re = Regex::new(pattern, sensitive).unwrap();
+        let expected = backtracking_find(pattern, sensitive, text);
+        assert_eq!(re.find(text).map(|m| (m.start, m.end)), expected);
+        assert_eq!(re.is_match(text), expected.is_some());
+    }
+}
+
+#[test]
+fn test_folded_literals() {
+    let folded = Flags {
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
+    for (pattern, text) in [
+        ("needle", "a NeEdLe and a needle"),
+        ("kilo", "the \u{212A}ILO sign"),
+        ("café", "CAFÉ café"),
+        ("ß", "STRASSE straße"),
+        ("", "abc"),
+        ("xyz", "xy"),
+    ] {
+        let re = Regex::new(pattern, folded).unwrap();
+        assert!(re.is_literal(), "'{}' should be literal", pattern);
+        let expected = backtracking_find(pattern, folded, text);
+        assert_eq!(re.find(text).map(|m| (m.start, m.end)), expected);
+        assert_eq!(re.is_match(text), expected.is_some());
+    }
+
+    let re = Regex::new("ab", folded).unwrap();
+    let spans: Vec<_> = re.find_all("AB ab aB").map(|m| (m.start, m.end)).collect();
+    assert_eq!(spans, vec![(0, 2), (3, 5), (6, 8)]);
+    assert_eq!(re.replace_all("AB ab aB", "-"), "- - -");
+
+    let re = Regex::new("Ab", Flags::default()).unwrap();
+    assert_eq!(re.replace_all("Ab ab Ab", "-"), "- ab -");
+}
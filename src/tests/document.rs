@@ -0,0 +1,57 @@
+use crate::{Document, Flags, Position, Regex};
+
+fn spans(doc: &Document, pattern: &str, flags: Flags) -> Vec<(usize, usize, usize, usize)> {
+    let re = Regex::new(pattern, flags).unwrap();
+    doc.find_all(&re)
+        .map(|m| (m.start.line, m.start.column, m.end.line, m.end.column))
+        .collect()
+}
+
+#[test]
+fn test_document_lines() {
+    let doc = Document::new("ab\r\n\ncd\n");
+    assert_eq!(doc.line_count(), 4);
+    let lines: Vec<_> = (0..5).map(|i| doc.line(i)).collect();
+    assert_eq!(lines, [Some("ab"), Some(""), Some("cd"), Some(""), None]);
+
+    let from_lines = Document::from_lines(&["ab", "", "cd"]);
+    assert_eq!(from_lines.text(), "ab\n\ncd");
+    assert_eq!(from_lines.line_count(), 3);
+    assert_eq!(Document::from_lines::<&str>(&[]).line_count(), 1);
+}
+
+#[test]
+fn test_document_positions() {
+    let doc = Document::new("ab\r\n\ncd");
+    let at = |line, column| Position { line, column };
+    assert_eq!(doc.position(0), at(0, 0));
+    assert_eq!(doc.position(3), at(0, 3));
+    assert_eq!(doc.position(4), at(1, 0));
+    assert_eq!(doc.position(7), at(2, 2));
+    for offset in 0..=doc.text().len() {
+        assert_eq!(doc.offset(doc.position(offset)), Some(offset));
+    }
+    assert_eq!(doc.offset(at(1, 1)), None);
+    assert_eq!(doc.offset(at(3, 0)), None);
+}
+
+#[test]
+fn test_document_search() {
+    let doc = Document::from_lines(&["foo bar", "bar foo", "baz"]);
+    let flags = Flags::new().ignore_case(false);
+    assert_eq!(spans(&doc, "foo", flags), [(0, 0, 0, 3), (1, 4, 1, 7)]);
+    // Matches can span lines
+    assert_eq!(spans(&doc, r"bar\nbar", flags), [(0, 4, 1, 3)]);
+    assert_eq!(spans(&doc, r"o\s+b", flags), [(0, 2, 0, 5), (1, 6, 2, 1)]);
+    // Anchors follow the flags, as for any text
+    assert_eq!(spans(&doc, "^b", flags), []);
+    assert_eq!(
+        spans(&doc, "^b", flags.multiline(true)),
+        [(1, 0, 1, 1), (2, 0, 2, 1)]
+    );
+
+    let re = Regex::new("baz", flags).unwrap();
+    let m = doc.find(&re).unwrap();
+    assert_eq!(m.start, Position { line: 2, column: 0 });
+    assert_eq!(doc.line(m.start.line), Some("baz"));
+}
@@ -99,8 +99,10 @@ fn test_dot() {
     assert_no_match(".", "\n"); // Default doesn't match newline
 
     // Dotall flag
-    let mut flags = Flags::default();
-    flags.dotall = true;
+    let flags = Flags {
+        dotall: true,
+        ..Flags::default()
+    };
     let re = Regex::new(".", flags).unwrap();
     assert!(re.is_match("\n"));
 }
@@ -163,6 +165,12 @@ fn test_quantifiers_lazy() {
 
     // {n,m}?
     assert_find("a{2,4}?", "aaaaa", "aa"); // Minimal 2
+
+    // Vim's {-n,m}
+    assert_find("ba{-}", "baaaa", "b");
+    assert_find("a{-2,4}", "aaaaa", "aa");
+    assert_find("a{-2,}b", "aaaab", "aaaab");
+    assert_find("<.{-}>", "<a><b>", "<a>");
 }
 
 // --- 3. Character Classes ---
@@ -225,15 +233,19 @@ fn test_extended_classes() {
     assert_no_match(r"\a", "!");
 
     // \L Non-lowercase
-    assert_match(r"\L", "A");
+    assert_no_match(r"\L", "A"); // Smartcase
     assert_match(r"\L", "0");
     assert_no_match(r"\L", "a");
 
     // \U Non-uppercase
-    assert_match(r"\U", "a");
+    assert_no_match(r"\U", "a"); // Smartcase
     assert_match(r"\U", "0");
     assert_no_match(r"\U", "A");
 
+    let sensitive = Flags::new().ignore_case(false);
+    assert!(Regex::new(r"\L", sensitive).unwrap().is_match("A"));
+    assert!(Regex::new(r"\U", sensitive).unwrap().is_match("a"));
+
     // \X Non-hex digit
     assert_match(r"\X", "g");
     assert_no_match(r"\X", "a");
@@ -290,8 +302,10 @@ fn test_custom_sets() {
     assert_match("[a-z]", "M"); // Smartcase
 
     // Force case sensitive
-    let mut flags = Flags::default();
-    flags.ignore_case = Some(false);
+    let flags = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
     let re = Regex::new("[a-z]", flags).unwrap();
     assert!(!re.is_match("M"));
 
@@ -353,8 +367,10 @@ fn test_match_boundaries_zs_ze() {
 
 #[test]
 fn test_flags_multiline() {
-    let mut flags = Flags::default();
-    flags.multiline = true;
+    let flags = Flags {
+        multiline: true,
+        ..Flags::default()
+    };
     let re = Regex::new("^bar", flags).unwrap();
 
     assert!(re.is_match("foo\nbar"));
@@ -365,15 +381,19 @@ fn test_flags_multiline() {
 #[test]
 fn test_flags_case_sensitivity() {
     // i flag (ignore-case)
-    let mut flags = Flags::default();
-    flags.ignore_case = Some(true);
+    let flags = Flags {
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
     let re = Regex::new("abc", flags).unwrap();
     assert!(re.is_match("ABC"));
     assert!(re.is_match("AbC"));
 
     // c flag (case-sensitive)
-    let mut flags = Flags::default();
-    flags.ignore_case = Some(false);
+    let flags = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
     let re = Regex::new("abc", flags).unwrap();
     assert!(re.is_match("abc"));
     assert!(!re.is_match("ABC"));
@@ -381,20 +401,22 @@ fn test_flags_case_sensitivity() {
 
 #[test]
 fn test_flags_verbose() {
-    let mut flags = Flags::default();
-    flags.verbose = true;
+    let flags = Flags {
+        verbose: true,
+        ..Flags::default()
+    };
 
     // Spaces ignored
-    let re = Regex::new("foo bar", flags.clone()).unwrap();
+    let re = Regex::new("foo bar", flags).unwrap();
     assert!(re.is_match("foobar"));
     assert!(!re.is_match("foo bar"));
 
     // Escaped space matches space
-    let re = Regex::new(r"foo\ bar", flags.clone()).unwrap();
+    let re = Regex::new(r"foo\ bar", flags).unwrap();
     assert!(re.is_match("foo bar"));
 
     // Space in brackets matches space
-    let re = Regex::new(r"foo[ ]bar", flags.clone()).unwrap();
+    let re = Regex::new(r"foo[ ]bar", flags).unwrap();
     assert!(re.is_match("foo bar"));
 
     // Comments
@@ -405,6 +427,29 @@ bar",
     )
     .unwrap();
     assert!(re.is_match("foobar"));
+
+    // Whitespace and `#` are kept in sets, and after a `\`
+    assert_eq!(
+        Regex::new("[a b#]+", flags)
+            .unwrap()
+            .find("x a#b")
+            .map(|m| m.start),
+        Some(1)
+    );
+    let re = Regex::new("a\\\tb \\# c", flags).unwrap();
+    assert!(re.is_match("a\tb#c") && !re.is_match("ab#c"));
+
+    // A `\` at the end of a line joins it to the next, even before a quantifier
+    for pattern in ["^fo\\\no$", "^fo\\\r\n  o$", "^fo\\\n +$"] {
+        let re = Regex::new(pattern, flags).unwrap();
+        assert!(re.is_match("foo") && !re.is_match("fo\no"), "{:?}", pattern);
+    }
+    // Without the flag it's an escaped newline
+    assert!(
+        Regex::new("fo\\\no", Flags::default())
+            .unwrap()
+            .is_match("fo\no")
+    );
 }
 
 // --- 6. Alternation & Grouping ---
@@ -519,8 +564,10 @@ fn test_ipv4() {
 
 #[test]
 fn test_unicode_flag() {
-    let mut flags = Flags::default();
-    flags.unicode = true;
+    let flags = Flags {
+        unicode: true,
+        ..Flags::default()
+    };
 
     // \w should match unicode letters
     let _re = Regex::new(r"\w+", flags).unwrap();
@@ -531,3 +578,586 @@ fn test_unicode_flag() {
     let _re_ascii = Regex::new(r"\w+", Flags::default()).unwrap();
     assert!(_re_ascii.is_match("über"));
 }
+
+#[test]
+fn test_lookbehind_non_ascii() {
+    assert_find("(?<=é)b", "aéb", "b");
+    assert_find("(?<=ü+)x", "üüx", "x");
+    assert_no_match("(?<!é)b", "éb");
+}
+
+#[test]
+fn test_backtracking_into_groups() {
+    // A group's earlier choice is revisited when what follows it fails
+    assert_find("(a|ab)c", "abc", "abc");
+    assert_find("(a|ab)(c|bcd)(d*)", "abcd", "abcd");
+    assert_find(r"(\w+)(\d)", "abc123", "abc123");
+    assert_find("(?:ab|a)*b", "aab", "aab");
+    assert_find("(a{1,3})a", "aaa", "aaa");
+    assert_find("x(a+?)+b", "xaaab", "xaaab");
+
+    // Captures from abandoned branches don't leak into backreferences
+    assert_no_match(r"(?:(a)x|ab)\1", "ab");
+    assert_find(r"(?:(a)x|ab)\1", "axa", "axa");
+    assert_find(r"(a+)\1", "aaaa", "aaaa");
+}
+
+#[test]
+fn test_backref_ignore_case() {
+    // Smartcase makes these patterns case-insensitive
+    assert_find(r"(\w+) \1", "say Hello HELLO", "Hello HELLO");
+    assert_find(r"(k)\1", "k\u{212A}", "k\u{212A}");
+    assert_find(r"(é+)-\1", "éÉ-Éé", "éÉ-Éé");
+    assert_no_match(r"(ab)\1", "abac");
+
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    let re = Regex::new(r"(\w+) \1", sensitive).unwrap();
+    assert!(!re.is_match("Hello HELLO"));
+    assert!(re.is_match("Hello Hello"));
+}
+
+#[test]
+fn test_lookbehind_backtracks_to_position() {
+    // The body's greedy match is shortened to end exactly where the assertion sits
+    assert_find("(?<=a+)a", "aaa", "a");
+    let re = Regex::new("(?<=a+)a", Flags::default()).unwrap();
+    assert_eq!(re.find("aaa").map(|m| m.start), Some(1));
+}
+
+#[test]
+fn test_lookbehind_window() {
+    // Bounds are counted in characters, not bytes
+    assert_find("(?<=éé)x", "éx ééx", "x");
+    let re = Regex::new("(?<=éé)x", Flags::default()).unwrap();
+    assert_eq!(re.find("éx ééx").map(|m| m.start), Some(8));
+    assert_find("(?<=ab|é)c", "éc", "c");
+    assert_no_match("(?<!ab|é)c", "éc");
+
+    // A bounded body is only tried a few characters back from each position
+    let text = format!("{}abc", "x".repeat(100_000));
+    assert_find("(?<=ab)c", &text, "c");
+    assert_no_match("(?<=x{2,3}y)c", &text);
+}
+#[test]
+fn test_variable_length_lookbehind() {
+    // A lookbehind holds at a position when some text ending there matches its
+    // body on its own. Check that against every position of a few texts.
+    let texts = ["abbbc", "aabbabc", "xabcabbc", "cbaabbc", "", "éabbçc"];
+    for body in [
+        "ab+",
+        "ab*",
+        "a|bb",
+        "(?:ab|b)+",
+        "a(?:b|bb)*c?",
+        "(a(b+))",
+        "(?:(a)|(b+))c",
+        "[ab]{2,}",
+        "b{1,3}?",
+        "é?ab",
+        "x*",
+    ] {
+        let whole = Regex::new(&format!("^(?:{})$", body), Flags::default()).unwrap();
+        for positive in [true, false] {
+            let op = if positive { "=" } else { "!" };
+            let re = Regex::new(&format!("(?<{}{})", op, body), Flags::default()).unwrap();
+            for text in texts {
+                for (pos, _) in text.char_indices().chain([(text.len(), ' ')]) {
+                    let holds = text[..pos]
+                        .char_indices()
+                        .map(|(i, _)| i)
+                        .chain([pos])
+                        .any(|start| whole.is_match(&text[start..pos]));
+                    let found = re.finder().anchored(true).range(pos..text.len()).run(text);
+                    assert_eq!(
+                        found.is_some(),
+                        holds == positive,
+                        "(?<{}{}) at {} of '{}'",
+                        op,
+                        body,
+                        pos,
+                        text
+                    );
+                }
+            }
+        }
+    }
+
+    // Lookarounds nest inside the body, and see the text around it
+    assert_find("(?<=(?<=x)ab+)c", "yabc xabbc", "c");
+    let re = Regex::new("(?<=(?<=x)ab+)c", Flags::default()).unwrap();
+    assert_eq!(re.find("yabc xabbc").map(|m| m.start), Some(9));
+    assert_find("(?<=a(?>=b+c)b+)c", "abbc", "c");
+    assert_no_match("(?<=a(?>!b+c)b+)c", "abbc");
+    assert_find(r"(?<=^\w+ )\w+", "one two three", "two");
+    assert_no_match(r"(?<=\bb+)c", "abbc");
+
+    // The body is tried from the nearest start back, so of the texts it could
+    // match the shortest wins, and that's what its groups capture
+    let text = "aaab";
+    let re = Regex::new("(?<=(a+))b", Flags::default()).unwrap();
+    assert_eq!(re.captures(text).unwrap().as_str(text, 1), Some("a"));
+    let re = Regex::new("(?<=(a+?a*))b", Flags::default()).unwrap();
+    assert_eq!(re.captures(text).unwrap().as_str(text, 1), Some("a"));
+    // From one start, the body's own order of preference decides
+    let text = "xaab";
+    let re = Regex::new("(?<=x(a|aa|a(a))(a?))b", Flags::default()).unwrap();
+    let caps = re.captures(text).unwrap();
+    assert_eq!(
+        [1, 2, 3].map(|i| caps.as_str(text, i)),
+        [Some("a"), None, Some("a")]
+    );
+}
+
+#[test]
+fn test_text_anchors() {
+    let multiline = Flags {
+        multiline: true,
+        ..Flags::default()
+    };
+    let find = |pattern: &str, text: &str| {
+        Regex::new(pattern, multiline)
+            .unwrap()
+            .find(text)
+            .map(|m| (m.start, m.end))
+    };
+    // `^` and `$` match at every line, `\%^` and `\%$` only at the ends of the text
+    assert_eq!(find("^b", "a\nb"), Some((2, 3)));
+    assert_eq!(find(r"\%^b", "a\nb"), None);
+    assert_eq!(find(r"\%^a", "a\nb"), Some((0, 1)));
+    assert_eq!(find("a$", "a\nb"), Some((0, 1)));
+    assert_eq!(find(r"a\%$", "a\nb"), None);
+    assert_eq!(find(r"b\%$", "a\nb"), Some((2, 3)));
+    assert_eq!(find(r"\%^\%$", ""), Some((0, 0)));
+    assert_eq!(find(r"\%^\w+\%$", "a\nb"), None);
+    // They're the plain anchors' meaning without multiline, and ignore ranges
+    assert_find(r"\%^a|b\%$", "ab", "a");
+    let re = Regex::new(r"\%^b", Flags::default()).unwrap();
+    assert_eq!(re.find_in_range("ab", 1..2), None);
+    // Any other `\%` is still a percent sign
+    assert_find(r"100\%", "100%", "100%");
+
+    let re = Regex::new(r"\%^a\%$", multiline).unwrap();
+    let ast = crate::Parser::new(r"\%^a\%$", multiline).parse().unwrap();
+    assert_eq!(crate::parser::to_pattern(&ast), r"\%^a\%$");
+    assert_eq!(re.to_pcre().unwrap(), r"(*UTF)(*UCP)(?i)\Aa\z");
+    let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+    assert!(loaded.is_match("a") && !loaded.is_match("a\na"));
+}
+
+#[test]
+fn test_line_and_column_atoms() {
+    let text = "one two\nthree four\nfive";
+    let find_all = |pattern: &str| {
+        Regex::new(pattern, Flags::default())
+            .unwrap()
+            .find_all(text)
+            .map(|m| &text[m.start..m.end])
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(find_all(r"\%2l\w+"), ["three", "four"]);
+    assert_eq!(find_all(r"\%<2l\w+"), ["one", "two"]);
+    assert_eq!(find_all(r"\%>1l\w+"), ["three", "four", "five"]);
+    assert_eq!(find_all(r"\%>9l\w+"), Vec::<&str>::new());
+    // Columns count bytes from 1 on each line
+    assert_eq!(find_all(r"\%1c\w+"), ["one", "three", "five"]);
+    assert_eq!(find_all(r"\%5c\w+"), ["two", "e"]);
+    assert_eq!(find_all(r"\w\%>4c\w*"), ["two", "ee", "four", "e"]);
+    assert_eq!(find_all(r"\%<3c\w"), ["o", "n", "t", "h", "f", "i"]);
+    // The position just after a newline is on the next line
+    assert_eq!(find_all(r"\n\%3l"), ["\n"]);
+    assert_find(r"\%2l\%1c", "a\nb", "");
+
+    // Without digits, or without the `l` or `c`, it's an error; other `\%` is `%`
+    for pattern in [r"\%<l", r"\%12", r"\%0l", r"\%>3x"] {
+        assert!(matches!(
+            crate::Parser::new(pattern, Flags::default()).parse(),
+            Err(crate::parser::ParseError::InvalidLineNumber(_))
+        ));
+    }
+    assert_find(r"\%l", "%l", "%l");
+
+    let re = Regex::new(r"x\%<12l\%>3c", Flags::default()).unwrap();
+    let ast = crate::Parser::new(r"x\%<12l\%>3c", Flags::default())
+        .parse()
+        .unwrap();
+    assert_eq!(crate::parser::to_pattern(&ast), r"x\%<12l\%>3c");
+    assert!(re.to_pcre().is_err());
+    let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+    assert_eq!(loaded.find("x\nabcx").map(|m| m.start), Some(5));
+}
+
+#[test]
+fn test_long_repetitions() {
+    let text = format!("{}!", "a".repeat(100_000));
+    assert_find(r"(\w)+!", &text, &text);
+    assert_find(r"a*?!", &text, &text);
+    assert_find(r".*a", &text, &text[..100_000]);
+}
+
+#[test]
+fn test_deep_patterns() {
+    // Long patterns don't grow the matcher's stack (the backreference keeps the DFA out)
+    let pattern = r"(a)\1".to_string() + &"a??".repeat(5000) + &"a".repeat(4998);
+    let text = "a".repeat(5000);
+    assert_find(&pattern, &text, &text);
+
+    // Nesting is bounded at parse time, and quantifiers count toward it too
+    let depth = crate::parser::MAX_NESTING_DEPTH;
+    let nested = |n: usize| format!("{}a{}", "(?:".repeat(n), ")*".repeat(n));
+    assert_match(&nested(depth / 2), "aaa");
+    assert!(Regex::new(&nested(depth / 2 + 1), Flags::default()).is_err());
+    assert!(Regex::new(&nested(depth + 1), Flags::default()).is_err());
+
+    // Hand-built trees get the same check
+    let mut ast = vec![crate::AstNode::Literal('a')];
+    for _ in 0..=depth {
+        ast = vec![crate::AstNode::Group {
+            nodes: ast,
+            name: None,
+            capture: false,
+            index: None,
+        }];
+    }
+    assert!(Regex::from_ast(ast, Flags::default()).is_err());
+
+    // However deep they go without a group
+    let too_deep = |wrap: fn(crate::AstNode) -> crate::AstNode, n| {
+        let node = (0..n).fold(crate::AstNode::Literal('a'), |node, _| wrap(node));
+        let err = Regex::from_ast(vec![node], Flags::default()).unwrap_err();
+        matches!(err, crate::CompileError::ExceededLimits(_))
+    };
+    assert!(too_deep(
+        |node| crate::AstNode::Optional {
+            node: Box::new(node),
+            greedy: true,
+        },
+        20_000
+    ));
+    assert!(too_deep(
+        |node| crate::AstNode::Exact {
+            node: Box::new(node),
+            count: 1,
+        },
+        20_000
+    ));
+    assert!(too_deep(
+        |node| crate::AstNode::Alternation(vec![vec![node], vec![]]),
+        depth + 1
+    ));
+}
+
+#[test]
+fn test_class_tables_agree_with_classes() {
+    use crate::engine::class_matches;
+    use crate::engine::program::ClassTable;
+    use crate::parser::{CharClass, CharRange};
+
+    let classes = [
+        CharClass::Word,
+        CharClass::NonWhitespace,
+        CharClass::Dot,
+        CharClass::Uppercase,
+        CharClass::NonLowercase,
+        CharClass::Punctuation,
+        CharClass::WordStart,
+        CharClass::Set {
+            chars: vec![
+                CharRange {
+                    start: 'a',
+                    end: 'f',
+                },
+                CharRange {
+                    start: 'é',
+                    end: 'é',
+                },
+                CharRange {
+                    start: 'Ā',
+                    end: 'ſ',
+                },
+            ],
+            negated: false,
+        },
+        CharClass::Set {
+            chars: vec![CharRange {
+                start: 'K',
+                end: 'K',
+            }],
+            negated: true,
+        },
+    ];
+    let flag_sets = [
+        Flags {
+            ignore_case: Some(false),
+            ..Flags::default()
+        },
+        Flags {
+            ignore_case: Some(true),
+            dotall: true,
+            ..Flags::default()
+        },
+    ];
+    for flags in flag_sets {
+        for class in &classes {
+            let table = ClassTable::new(class, &flags);
+            for c in ('\0'..='\u{2FF}').chain(['\u{212A}', '\u{1F600}']) {
+                assert_eq!(
+                    table.matches(c, &flags),
+                    class_matches(class, c, &flags),
+                    "{:?} on {:?}",
+                    class,
+                    c
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_case_closures_agree_with_folding() {
+    use crate::compiler::case_closure;
+    use crate::engine::class_matches;
+    use crate::parser::{CharClass, CharRange};
+
+    let flags = Flags {
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
+    let range = |start, end| CharRange { start, end };
+    let sets = [
+        vec![range('a', 'f'), range('K', 'K')],
+        vec![range('\0', '\u{7F}')],
+        vec![range('σ', 'σ'), range('ß', 'ß'), range('İ', 'İ')],
+        vec![range('\u{400}', '\u{52F}'), range('\u{1E900}', '\u{1E943}')],
+        vec![range('\u{2C00}', '\u{2CFF}'), range('ǅ', 'ǅ')],
+    ];
+    for chars in sets {
+        let closure = case_closure(&chars);
+        assert!(
+            closure
+                .windows(2)
+                .all(|pair| u32::from(pair[0].end) + 1 < u32::from(pair[1].start))
+        );
+        let class = CharClass::Set {
+            chars,
+            negated: false,
+        };
+        for c in (0..=0x10FFFF).filter_map(char::from_u32) {
+            assert_eq!(
+                closure.iter().any(|r| r.start <= c && c <= r.end),
+                class_matches(&class, c, &flags),
+                "{} on {:?}",
+                class,
+                c
+            );
+        }
+    }
+}
+
+#[test]
+fn test_fold_orbits_are_closed() {
+    use crate::compiler::{case_closure, fold_orbit};
+    use crate::parser::CharRange;
+
+    let single = |chars: Vec<char>| (chars.len() == 1).then(|| chars[0]);
+    let mut cased = vec![];
+    let mut keys = vec![];
+    for c in (0..=0x10FFFF).filter_map(char::from_u32) {
+        let orbit: Vec<char> = fold_orbit(c).collect();
+        assert!(orbit.contains(&c), "{:?}", c);
+        let lower = single(c.to_lowercase().collect());
+        let upper = single(c.to_uppercase().collect());
+        for mapped in lower.into_iter().chain(upper) {
+            assert!(orbit.contains(&mapped), "{:?} maps to {:?}", c, mapped);
+        }
+        for &member in &orbit {
+            let mut other: Vec<char> = fold_orbit(member).collect();
+            let mut orbit = orbit.clone();
+            other.sort_unstable();
+            orbit.sort_unstable();
+            assert_eq!(other, orbit, "{:?} and {:?}", c, member);
+        }
+        if orbit.len() > 1 {
+            cased.push(c);
+            keys.push(CharRange {
+                start: orbit[0],
+                end: orbit[0],
+            });
+        }
+    }
+    // Each character with a case variant is found from its orbit's key
+    keys.sort_unstable_by_key(|r| r.start);
+    keys.dedup_by_key(|r| r.start);
+    let closure: Vec<char> = case_closure(&keys)
+        .iter()
+        .flat_map(|r| r.start..=r.end)
+        .collect();
+    assert_eq!(closure, cased);
+}
+
+#[test]
+fn test_folded_chars_agree_with_literals() {
+    use crate::engine::literal_matches;
+    use crate::engine::program::FoldedChar;
+
+    let flags = Flags {
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
+    let samples: Vec<char> = ('\0'..='\u{2FF}')
+        .chain([
+            '\u{130}',
+            '\u{212A}',
+            '\u{1E9E}',
+            'Σ',
+            'ς',
+            'σ',
+            '\u{1F600}',
+        ])
+        .collect();
+    for &literal in &samples {
+        let folded = FoldedChar::new(literal);
+        for &c in &samples {
+            assert_eq!(
+                folded.matches(c, &flags),
+                literal_matches(literal, c, &flags),
+                "{:?} against {:?}",
+                literal,
+                c
+            );
+        }
+    }
+    assert_find("strasse", "STRASSE", "STRASSE");
+    assert_find("ǅ", "ǆ ǅ", "ǆ");
+}
+
+#[test]
+fn test_program_to_dot() {
+    let dot = Regex::new("(?:ab)+?", Flags::default()).unwrap().to_dot();
+    assert_eq!(
+        dot,
+        [
+            "digraph program {",
+            "  node [shape=box];",
+            r#"  n0 [label="0: repeat start (r0)"];"#,
+            r#"  n1 [label="1: repeat 1..inf, lazy"];"#,
+            r#"  n2 [label="2: iteration start"];"#,
+            r#"  n3 [label="3: \"ab\" (any case)"];"#,
+            r#"  n4 [label="4: repeat next"];"#,
+            r#"  n5 [label="5: match"];"#,
+            "  n0 -> n1;",
+            r#"  n1 -> n2 [label="again"];"#,
+            r#"  n1 -> n5 [label="exit"];"#,
+            "  n2 -> n3;",
+            "  n3 -> n4;",
+            r#"  n4 -> n1 [label="loop"];"#,
+            r#"  n4 -> n5 [label="exit"];"#,
+            "}\n",
+        ]
+        .join("\n")
+    );
+
+    let dot = Regex::new("a|b", Flags::default()).unwrap().to_dot();
+    assert!(dot.contains(r#"n0 -> n1 [label="1"];"#));
+    assert!(dot.contains(r#"n0 -> n3 [label="2"];"#));
+}
+
+#[test]
+fn test_trace() {
+    use crate::{Match, TraceEvent};
+
+    let re = Regex::new("(a)c|ab", Flags::default()).unwrap();
+    let trace = re.trace("ab");
+    assert_eq!(
+        trace.events,
+        [
+            TraceEvent::Attempt { start: 0 },
+            TraceEvent::Step { pc: 0, pos: 0 },
+            TraceEvent::Step { pc: 1, pos: 0 },
+            TraceEvent::Step { pc: 2, pos: 0 },
+            TraceEvent::Step { pc: 3, pos: 1 },
+            TraceEvent::CaptureSaved {
+                group: 1,
+                span: Match { start: 0, end: 1 },
+            },
+            TraceEvent::Step { pc: 4, pos: 1 },
+            TraceEvent::Backtrack { pc: 6, pos: 0 },
+            TraceEvent::Step { pc: 6, pos: 0 },
+            TraceEvent::Step { pc: 7, pos: 2 },
+        ]
+    );
+    assert_eq!(trace.result, Some(Match { start: 0, end: 2 }));
+    assert_eq!(trace.backtracks(), 1);
+
+    // Every start is attempted, even ones the filters would skip
+    let re = Regex::new("^z", Flags::default()).unwrap();
+    let mut attempts = 0;
+    let found = re.find_traced("abc", |event| {
+        if matches!(event, TraceEvent::Attempt { .. }) {
+            attempts += 1;
+        }
+    });
+    assert_eq!((found, attempts), (None, 4));
+}
+
+#[test]
+fn test_find_with_stats() {
+    use crate::{Match, MatchStats};
+
+    let re = Regex::new("ab|ac", Flags::default()).unwrap();
+    assert_eq!(
+        re.find_with_stats("xxac"),
+        (
+            Some(Match { start: 2, end: 4 }),
+            MatchStats {
+                positions_attempted: 1,
+                steps: 5,
+                backtracks: 1,
+                peak_backtrack_depth: 1,
+            }
+        )
+    );
+
+    // Start positions are only counted once the filters let them through
+    let re = Regex::new("a+(?<=b)", Flags::default()).unwrap();
+    let (found, stats) = re.find_with_stats("xaaa");
+    assert_eq!(found, None);
+    assert_eq!(stats.positions_attempted, 3);
+    assert_eq!(stats.peak_backtrack_depth, 3);
+
+    let re = Regex::new("b", Flags::default()).unwrap();
+    assert_eq!(re.find_with_stats("abc").1, MatchStats::default());
+}
+
+#[test]
+fn test_byte_scans() {
+    use crate::engine::scan::{find_any_byte, find_ignore_ascii_case};
+
+    // Long enough for several 16-byte chunks and a remainder
+    let text = "the quick brown fox jumps over the lazy dog, then Naps: Z!";
+    let bytes = text.as_bytes();
+    for needles in [&b"q"[..], b"xz", b"!Z,", b"#", b"N@Z"] {
+        for from in 0..bytes.len() {
+            assert_eq!(
+                find_any_byte(needles, &bytes[from..]),
+                bytes[from..].iter().position(|b| needles.contains(b)),
+                "{:?} from {}",
+                needles,
+                from
+            );
+        }
+    }
+
+    assert_eq!(find_ignore_ascii_case(b"NAPS", bytes), Some(50));
+    assert_eq!(find_ignore_ascii_case(b"the", &bytes[1..]), Some(30));
+    assert_eq!(find_ignore_ascii_case(b"z!", bytes), Some(56));
+    assert_eq!(find_ignore_ascii_case(b"z!?", bytes), None);
+    assert_eq!(find_ignore_ascii_case(b"", bytes), Some(0));
+
+    // The scans back the prefilter and literal search
+    assert_find("naps", text, "Naps");
+    assert_find(r"[xz]\a+", text, "zy");
+    assert_find(r"[!Z]\W", text, "Z!");
+}
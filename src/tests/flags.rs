@@ -1,33 +1,25 @@
-use crate::{Flags, Regex, parse_rift_format};
+use crate::{Decision, DotMode, Flags, Regex, parse_rift_format};
 
 // Helper to assert flag parsing
 fn assert_flags_parsed(pattern_with_flags: &str, expected_flags: Flags) {
     let (_, flags) = parse_rift_format(pattern_with_flags).expect("Failed to parse rift format");
 
-    // Compare fields
-    assert_eq!(
-        flags.ignore_case, expected_flags.ignore_case,
-        "ignore_case mismatch"
-    );
-    assert_eq!(
-        flags.multiline, expected_flags.multiline,
-        "multiline mismatch"
-    );
-    assert_eq!(flags.dotall, expected_flags.dotall, "dotall mismatch");
-    assert_eq!(flags.verbose, expected_flags.verbose, "verbose mismatch");
-    assert_eq!(flags.unicode, expected_flags.unicode, "unicode mismatch");
-    assert_eq!(flags.global, expected_flags.global, "global mismatch");
+    assert_eq!(flags, expected_flags);
 }
 
 #[test]
 fn test_ignore_case_flag() {
     // 1. Parsing
-    let mut expected = Flags::default();
-    expected.ignore_case = Some(true);
+    let expected = Flags {
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
     assert_flags_parsed("abc/i", expected);
 
-    let mut expected_c = Flags::default();
-    expected_c.ignore_case = Some(false);
+    let expected_c = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
     assert_flags_parsed("abc/c", expected_c);
 
     // 2. Behavior
@@ -50,6 +42,18 @@ fn test_ignore_case_flag() {
     let re = Regex::new("Abc", Flags::default()).unwrap();
     assert!(!re.is_match("abc"));
 
+    // Only the characters matched count, not how escapes are spelled
+    for pattern in [r"\cAb", r"\Lb", r"\W\S+", r"[\d\s]"] {
+        let re = Regex::new(pattern, Flags::default()).unwrap();
+        assert_eq!(re.flags().ignore_case, Some(true), "{}", pattern);
+    }
+    for pattern in ["[A-C]", "(?:x|Y)+", "a{2}B"] {
+        let re = Regex::new(pattern, Flags::default()).unwrap();
+        assert_eq!(re.flags().ignore_case, Some(false), "{}", pattern);
+    }
+    let re = Regex::new(r"\cAb", Flags::default()).unwrap();
+    assert!(re.is_match("\x01B"));
+
     // Character class with ignore case
     let re = Regex::new("[a-z]", expected).unwrap();
     assert!(re.is_match("A"));
@@ -58,10 +62,12 @@ fn test_ignore_case_flag() {
 #[test]
 fn test_multiline_flag() {
     // 1. Parsing
-    let mut expected = Flags::default();
-    expected.multiline = true;
-    // parse_rift_format infers smartcase (Some(true) for "abc")
-    expected.ignore_case = Some(true);
+    let expected = Flags {
+        multiline: true,
+        // parse_rift_format infers smartcase (Some(true) for "abc")
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
     assert_flags_parsed("abc/m", expected);
 
     // 2. Behavior
@@ -72,8 +78,10 @@ fn test_multiline_flag() {
     assert!(!re.is_match(text));
 
     // With multiline
-    let mut flags = Flags::default();
-    flags.multiline = true;
+    let flags = Flags {
+        multiline: true,
+        ..Flags::default()
+    };
     let re = Regex::new("^bar", flags).unwrap();
     assert!(re.is_match(text));
 
@@ -84,9 +92,11 @@ fn test_multiline_flag() {
 #[test]
 fn test_dotall_flag() {
     // 1. Parsing
-    let mut expected = Flags::default();
-    expected.dotall = true;
-    expected.ignore_case = Some(true);
+    let expected = Flags {
+        dotall: true,
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
     assert_flags_parsed("abc/s", expected);
 
     // 2. Behavior
@@ -97,8 +107,10 @@ fn test_dotall_flag() {
     assert!(!re.is_match(text));
 
     // With dotall
-    let mut flags = Flags::default();
-    flags.dotall = true;
+    let flags = Flags {
+        dotall: true,
+        ..Flags::default()
+    };
     let re = Regex::new("a.b", flags).unwrap();
     assert!(re.is_match(text));
 }
@@ -106,9 +118,11 @@ fn test_dotall_flag() {
 #[test]
 fn test_verbose_flag() {
     // 1. Parsing
-    let mut expected = Flags::default();
-    expected.verbose = true;
-    expected.ignore_case = Some(true);
+    let expected = Flags {
+        verbose: true,
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
     assert_flags_parsed("abc/x", expected);
 
     // 2. Behavior
@@ -123,8 +137,10 @@ fn test_verbose_flag() {
     assert!(!re.is_match("abc"));
 
     // With verbose
-    let mut flags = Flags::default();
-    flags.verbose = true;
+    let flags = Flags {
+        verbose: true,
+        ..Flags::default()
+    };
     let re = Regex::new(pattern, flags).unwrap();
     assert!(re.is_match("abc"));
 }
@@ -132,14 +148,18 @@ fn test_verbose_flag() {
 #[test]
 fn test_unicode_flag() {
     // 1. Parsing
-    let mut expected = Flags::default();
-    expected.unicode = true;
-    expected.ignore_case = Some(true);
+    let expected = Flags {
+        unicode: true,
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
     assert_flags_parsed("abc/u", expected);
 
     // 2. Behavior
-    let mut flags = Flags::default();
-    flags.unicode = true;
+    let flags = Flags {
+        unicode: true,
+        ..Flags::default()
+    };
 
     // \w matching unicode
     let re = Regex::new(r"\w", flags).unwrap();
@@ -150,15 +170,19 @@ fn test_unicode_flag() {
 #[test]
 fn test_global_flag() {
     // 1. Parsing
-    let mut expected = Flags::default();
-    expected.global = true;
-    expected.ignore_case = Some(true);
+    let expected = Flags {
+        global: true,
+        ignore_case: Some(true),
+        ..Flags::default()
+    };
     assert_flags_parsed("abc/g", expected);
 
     // 2. Behavior
     // The global flag is preserved in the struct
-    let mut flags = Flags::default();
-    flags.global = true;
+    let flags = Flags {
+        global: true,
+        ..Flags::default()
+    };
     let re = Regex::new("a", flags).unwrap();
     assert!(re.flags().global);
 }
@@ -172,3 +196,281 @@ fn test_combined_flags() {
     assert!(flags.multiline);
     assert!(flags.dotall);
 }
+
+#[test]
+fn test_flags_builder_and_round_trip() {
+    let flags = Flags::new().ignore_case(false).dotall(true).global(true);
+    assert_eq!(
+        flags,
+        Flags {
+            ignore_case: Some(false),
+            dotall: true,
+            global: true,
+            ..Flags::default()
+        }
+    );
+    assert_eq!(flags.to_string(), "csg");
+    assert_eq!(Flags::new().smartcase().to_string(), "");
+
+    let parsed: Flags = "gmi".parse().unwrap();
+    assert_eq!(
+        parsed,
+        Flags::new().ignore_case(true).multiline(true).global(true)
+    );
+    assert_eq!(parsed.to_string(), "img");
+    for letters in [
+        "", "i", "c", "msxug", "imsxug", "cgAURn", "nC", "g2", "mA15",
+    ] {
+        let flags: Flags = letters.parse().unwrap();
+        assert_eq!(flags.to_string().parse::<Flags>().unwrap(), flags);
+    }
+    assert!(matches!(
+        "iz".parse::<Flags>(),
+        Err(crate::ParseError::InvalidFlags('z'))
+    ));
+}
+
+#[test]
+fn test_anchored_flag() {
+    assert_flags_parsed("abc/A", Flags::new().ignore_case(true).anchored(true));
+
+    let flags = Flags::new().ignore_case(false).anchored(true);
+    let re = Regex::new(r"\d+", flags).unwrap();
+    assert!(re.is_match("12ab"));
+    assert!(!re.is_match("ab12"));
+    assert_eq!(re.find("ab12"), None);
+    // Successive matches must follow on from each other
+    let matches: Vec<_> = Regex::new(r"\d,", flags)
+        .unwrap()
+        .find_all("1,2,x3,")
+        .map(|m| m.start)
+        .collect();
+    assert_eq!(matches, [0, 2]);
+
+    // Plain strings aren't searched for anywhere in the text either
+    let re = Regex::new("foo", flags).unwrap();
+    assert!(!re.is_literal());
+    assert!(!re.is_match("a foo"));
+    assert!(re.is_match("foo"));
+}
+
+#[test]
+fn test_ungreedy_flag() {
+    let flags = Flags::new().ignore_case(false).ungreedy(true);
+    let re = Regex::new("a+", flags).unwrap();
+    assert_eq!(re.find("aaa").map(|m| m.end), Some(1));
+    let re = Regex::new("a+?", flags).unwrap();
+    assert_eq!(re.find("aaa").map(|m| m.end), Some(3));
+    let re = Regex::new("<.*>", flags).unwrap();
+    assert_eq!(re.find("<a><b>").map(|m| m.end), Some(3));
+    let re = Regex::new("a{1,3}", flags).unwrap();
+    assert_eq!(re.find("aaa").map(|m| m.end), Some(1));
+    let re = Regex::new("a{-1,3}", flags).unwrap();
+    assert_eq!(re.find("aaa").map(|m| m.end), Some(3));
+}
+
+#[test]
+fn test_crlf_flag() {
+    let flags = Flags::new().ignore_case(false).multiline(true).crlf(true);
+    let text = "ab\r\ncd\rx\ny";
+    let starts: Vec<_> = Regex::new("^", flags)
+        .unwrap()
+        .find_all(text)
+        .map(|m| m.start)
+        .collect();
+    assert_eq!(starts, [0, 4, 7, 9]);
+    let ends: Vec<_> = Regex::new("$", flags)
+        .unwrap()
+        .find_all(text)
+        .map(|m| m.start)
+        .collect();
+    assert_eq!(ends, [2, 6, 8, 10]);
+
+    let re = Regex::new("^b$", flags).unwrap();
+    assert!(re.is_match("a\r\nb\r\nc"));
+    assert!(
+        !Regex::new("^b$", flags.crlf(false))
+            .unwrap()
+            .is_match("a\r\nb\r\nc")
+    );
+
+    // `.` stops at `\r` unless dotall is on
+    let re = Regex::new("a.b", flags).unwrap();
+    assert!(!re.is_match("a\rb"));
+    assert!(
+        Regex::new("a.b", flags.dotall(true))
+            .unwrap()
+            .is_match("a\rb")
+    );
+    assert!(
+        Regex::new("a.b", flags.crlf(false))
+            .unwrap()
+            .is_match("a\rb")
+    );
+}
+
+#[test]
+fn test_dot_modes() {
+    let matches = |pattern: &str, flags: Flags, text: &str| -> Vec<String> {
+        Regex::new(pattern, flags)
+            .unwrap()
+            .find_all(text)
+            .map(|m| text[m.start..m.end].to_string())
+            .collect()
+    };
+    let flags = Flags::new().ignore_case(false);
+
+    // `\r` is excluded without the `R` flag's line anchors
+    let not_cr = flags.dot(DotMode::NotCr);
+    assert_eq!(matches(".+", not_cr, "ab\rcd\nef"), ["ab", "cd", "ef"]);
+    assert_eq!(matches(".+", not_cr.dotall(true), "ab\rcd"), ["ab\rcd"]);
+    assert_eq!(matches("^c", not_cr.multiline(true), "ab\rcd").len(), 0);
+
+    let ascii = flags.dot(DotMode::Ascii);
+    assert_eq!(
+        matches(".+", ascii, "naïve café\nok"),
+        ["na", "ve caf", "ok"]
+    );
+    assert_eq!(matches(".+", ascii.dotall(true), "a\nb€"), ["a\nb"]);
+
+    // A cluster takes its combining marks, joined emoji and flag pairs with it
+    let grapheme = flags.dot(DotMode::Grapheme);
+    assert_eq!(
+        matches(".", grapheme, "e\u{301}👩\u{200d}👦🇫🇷🇩🇪"),
+        ["e\u{301}", "👩\u{200d}👦", "🇫🇷", "🇩🇪"]
+    );
+    assert_eq!(matches("^.$", grapheme, "각"), ["각"]);
+    assert_eq!(
+        matches("^.{3}$", grapheme, "a\u{300}\u{301}bc"),
+        ["a\u{300}\u{301}bc"]
+    );
+    assert!(Regex::new("^.{2}$", flags).unwrap().is_match("e\u{301}"));
+    // Line endings stay out unless dotall is on, `\r\n` whole
+    assert_eq!(matches(".", grapheme, "a\r\nb"), ["a", "b"]);
+    assert_eq!(
+        matches(".", grapheme.dotall(true), "a\r\nb"),
+        ["a", "\r\n", "b"]
+    );
+    assert_eq!(matches(".", grapheme, "a\rb"), ["a", "\r", "b"]);
+    assert_eq!(matches(".", grapheme.crlf(true), "a\rb"), ["a", "b"]);
+    // Lookbehinds reach back over a whole cluster
+    assert_eq!(matches("(?<=^.)x", grapheme, "e\u{301}x"), ["x"]);
+
+    // The mode isn't a flag letter, so saved regexes keep it separately
+    let re = Regex::new(".", grapheme).unwrap();
+    assert_eq!(re.flags().to_string(), "c");
+    let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+    assert_eq!(loaded.flags().dot, DotMode::Grapheme);
+    assert_eq!(loaded.find("e\u{301}").unwrap().end, 3);
+}
+
+#[test]
+fn test_dry_run_flag() {
+    let flags = Flags::new().ignore_case(false);
+    let re = Regex::new("o+", flags).unwrap();
+    assert_eq!(re.substitute("foo boo", "0"), ("f0 boo".to_string(), 1));
+    let re = Regex::new("o+", flags.global(true)).unwrap();
+    assert_eq!(re.substitute("foo boo", "0"), ("f0 b0".to_string(), 2));
+
+    let re = Regex::new("o+", flags.global(true).dry_run(true)).unwrap();
+    assert_eq!(re.substitute("foo boo", "0"), ("foo boo".to_string(), 2));
+    assert_eq!(re.replace("foo boo", "0"), "foo boo");
+    assert_eq!(re.replace_all("foo boo", "0"), "foo boo");
+    let re = Regex::new("o+", flags.dry_run(true)).unwrap();
+    assert_eq!(re.substitute("foo boo", "0"), ("foo boo".to_string(), 1));
+    assert_eq!(re.substitute("xyz", "0"), ("xyz".to_string(), 0));
+
+    let (pattern, flags) = parse_rift_format("o/gn").unwrap();
+    assert!(flags.dry_run && flags.global);
+    let re = Regex::new(&pattern, flags).unwrap();
+    assert_eq!(re.substitute("foo", "0").1, 2);
+}
+
+#[test]
+fn test_occurrence_flag() {
+    assert_flags_parsed(
+        "abc/g2",
+        Flags::new().ignore_case(true).global(true).occurrence(2),
+    );
+    assert_flags_parsed(
+        "abc/12m",
+        Flags::new()
+            .ignore_case(true)
+            .multiline(true)
+            .occurrence(12),
+    );
+    assert_eq!(Flags::new().global(true).occurrence(2).to_string(), "g2");
+    // `0` selects no occurrence, so the flags still round-trip
+    let flags = Flags::new().global(true).occurrence(0);
+    assert_eq!(flags.occurrence, None);
+    assert_eq!(flags.to_string().parse::<Flags>().unwrap(), flags);
+    let flags = Flags {
+        occurrence: Some(0),
+        ..Flags::new()
+    };
+    assert_eq!(flags.to_string(), "");
+    assert!(matches!(
+        parse_rift_format("a/0"),
+        Err(crate::ParseError::InvalidFlags('0'))
+    ));
+    assert!(matches!(
+        parse_rift_format("a/99999999999999999999999"),
+        Err(crate::ParseError::InvalidFlags('9'))
+    ));
+
+    let flags = Flags::new().ignore_case(false);
+    let text = "a1 a2 a3 a4";
+    let re = Regex::new(r"a\d", flags.occurrence(2)).unwrap();
+    assert_eq!(re.replace(text, "x"), "a1 x a3 a4");
+    assert_eq!(re.replace_all(text, "x"), "a1 x x x");
+    assert_eq!(re.substitute(text, "x"), ("a1 x a3 a4".to_string(), 1));
+    let re = Regex::new(r"a\d", flags.occurrence(3).global(true)).unwrap();
+    assert_eq!(re.substitute(text, "x"), ("a1 a2 x x".to_string(), 2));
+
+    // Past the last match nothing is replaced, including for plain strings
+    let re = Regex::new("a", flags.occurrence(5)).unwrap();
+    assert!(re.is_literal());
+    assert_eq!(re.replace(text, "x"), text);
+    assert_eq!(re.replace_all(text, "x"), text);
+    assert_eq!(re.substitute(text, "x"), (text.to_string(), 0));
+}
+
+#[test]
+fn test_replace_with_decision() {
+    assert_flags_parsed(
+        "abc/gC",
+        Flags::new().ignore_case(true).global(true).confirm(true),
+    );
+
+    let text = "a1 a2 a3 a4";
+    let re = Regex::new(r"a(\d)", Flags::default()).unwrap();
+    let mut seen = vec![];
+    let result = re.replace_all_with_decision(text, "x", |caps| {
+        seen.push(caps.full_match.start);
+        match caps.as_str(text, 1) {
+            Some("1") => Decision::Skip,
+            Some("2") => Decision::Accept,
+            Some("3") => Decision::Edit("<3>".to_string()),
+            _ => Decision::Stop,
+        }
+    });
+    assert_eq!(result, "a1 x <3> a4");
+    assert_eq!(seen, [0, 3, 6, 9]);
+
+    // Stopping leaves the rest alone, and later matches aren't asked about
+    let mut asked = 0;
+    let result = re.replace_all_with_decision(text, "x", |_| {
+        asked += 1;
+        if asked == 1 {
+            Decision::Accept
+        } else {
+            Decision::Stop
+        }
+    });
+    assert_eq!((result.as_str(), asked), ("x a2 a3 a4", 2));
+
+    // Matches before the selected occurrence aren't offered
+    let re = Regex::new(r"a\d", Flags::new().occurrence(3)).unwrap();
+    let result = re.replace_all_with_decision(text, "x", |_| Decision::Accept);
+    assert_eq!(result, "a1 a2 x x");
+}
@@ -0,0 +1,320 @@
+use core::cmp::Ordering;
+
+use crate::compiler::optimize;
+use crate::engine::{LazyDfa, MatchContext, Matcher, Program};
+use crate::fuzz::{check_consistency, check_round_trip, shrink, shrink_pattern};
+use crate::{AstNode, CharClass, CharRange, Flags, Regex, to_pattern};
+
+// Deterministic pseudo-random inputs, standing in for a fuzzer's corpus
+fn inputs(count: usize) -> impl Iterator<Item = Vec<u8>> {
+    let mut state = 0x2545_f491_u32;
+    (0..count).map(move |i| {
+        (0..8 + i % 40)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state >> 8) as u8
+            })
+            .collect()
+    })
+}
+
+// Mostly a few letters, so generated patterns often match generated text
+const CHARS: &[char] = &['a', 'b', 'c', 'A', ' ', '\n', '-', '.', 'é', '中'];
+
+// Nodes nest at most this deep in generated ASTs
+const MAX_DEPTH: usize = 6;
+
+// Builds a valid AST from bytes. Group indices run in order, names are unique,
+// backreferences only refer to groups closed before them and lookbehinds have
+// fixed lengths, so the AST compiles
+fn ast_from_bytes(data: &[u8]) -> Vec<AstNode> {
+    let mut builder = AstBuilder {
+        data,
+        pos: 0,
+        groups: 0,
+        closed: vec![],
+    };
+    builder.nodes(0)
+}
+
+struct AstBuilder<'d> {
+    data: &'d [u8],
+    pos: usize,
+    groups: usize,
+    closed: Vec<usize>,
+}
+
+impl AstBuilder<'_> {
+    // The next byte, or 0 once the input runs out
+    fn byte(&mut self) -> u8 {
+        let byte = self.data.get(self.pos).copied().unwrap_or(0);
+        self.pos += 1;
+        byte
+    }
+
+    fn below(&mut self, bound: u8) -> u8 {
+        self.byte() % bound
+    }
+
+    fn ordering(&mut self) -> Ordering {
+        [Ordering::Less, Ordering::Equal, Ordering::Greater][usize::from(self.below(3))]
+    }
+
+    fn nodes(&mut self, depth: usize) -> Vec<AstNode> {
+        let mut nodes = vec![];
+        while self.pos < self.data.len() && nodes.len() < 8 {
+            // A zero byte ends the sequence early
+            if self.byte() == 0 {
+                break;
+            }
+            nodes.push(self.node(depth));
+        }
+        nodes
+    }
+
+    fn node(&mut self, depth: usize) -> AstNode {
+        let choices = if depth < MAX_DEPTH { 16 } else { 8 };
+        match self.below(choices) {
+            0..=2 => AstNode::Literal(self.char()),
+            3 => AstNode::LiteralString((0..=self.below(4)).map(|_| self.char()).collect()),
+            4 | 5 => AstNode::CharClass(self.class()),
+            6 => match usize::from(self.below(11)) {
+                9 => AstNode::Line(usize::from(self.below(3)) + 1, self.ordering()),
+                10 => AstNode::Column(usize::from(self.below(4)) + 1, self.ordering()),
+                anchor => [
+                    AstNode::StartAnchor,
+                    AstNode::EndAnchor,
+                    AstNode::StartText,
+                    AstNode::EndText,
+                    AstNode::WordBoundary,
+                    AstNode::StartWord,
+                    AstNode::EndWord,
+                    AstNode::SetMatchStart,
+                    AstNode::SetMatchEnd,
+                ][anchor]
+                    .clone(),
+            },
+            7 => match self.closed.len() {
+                0 => AstNode::Literal(self.char()),
+                closed => {
+                    let index = usize::from(self.byte()) % closed;
+                    AstNode::Backref(self.closed[index])
+                }
+            },
+            8..=10 => {
+                let node = Box::new(self.node(depth + 1));
+                let greedy = self.below(2) == 0;
+                match self.below(5) {
+                    0 => AstNode::ZeroOrMore { node, greedy },
+                    1 => AstNode::OneOrMore { node, greedy },
+                    2 => AstNode::Optional { node, greedy },
+                    3 => AstNode::Exact {
+                        node,
+                        count: usize::from(self.below(4)),
+                    },
+                    _ => {
+                        let min = usize::from(self.below(3));
+                        let max = (self.below(2) == 0).then(|| min + usize::from(self.below(3)));
+                        AstNode::Range {
+                            node,
+                            min,
+                            max,
+                            greedy,
+                        }
+                    }
+                }
+            }
+            11 | 12 => {
+                let capture = self.below(3) != 0;
+                let index = capture.then(|| {
+                    self.groups += 1;
+                    self.groups
+                });
+                let name = index
+                    .filter(|_| self.below(2) == 0)
+                    .map(|index| format!("g{}", index));
+                let nodes = self.nodes(depth + 1);
+                self.closed.extend(index);
+                AstNode::Group {
+                    nodes,
+                    name,
+                    capture,
+                    index,
+                }
+            }
+            13 => AstNode::Alternation(
+                (0..2 + self.below(2))
+                    .map(|_| self.nodes(depth + 1))
+                    .collect(),
+            ),
+            _ => {
+                // Lookarounds hold single characters, so lookbehinds have a fixed length
+                let nodes = (0..=self.below(3))
+                    .map(|_| AstNode::Literal(self.char()))
+                    .collect();
+                let positive = self.below(2) == 0;
+                if self.below(2) == 0 {
+                    AstNode::LookAhead { nodes, positive }
+                } else {
+                    AstNode::LookBehind { nodes, positive }
+                }
+            }
+        }
+    }
+
+    fn char(&mut self) -> char {
+        CHARS[usize::from(self.byte()) % CHARS.len()]
+    }
+
+    fn class(&mut self) -> CharClass {
+        const CLASSES: &[CharClass] = &[
+            CharClass::Digit,
+            CharClass::NonDigit,
+            CharClass::Word,
+            CharClass::NonWord,
+            CharClass::Whitespace,
+            CharClass::NonWhitespace,
+            CharClass::Lowercase,
+            CharClass::Uppercase,
+            CharClass::Hex,
+            CharClass::Alphanumeric,
+            CharClass::Punctuation,
+            CharClass::Dot,
+        ];
+        let choice = usize::from(self.byte()) % (CLASSES.len() + 1);
+        match CLASSES.get(choice) {
+            Some(class) => class.clone(),
+            None => {
+                let chars = (0..=self.below(3))
+                    .map(|_| {
+                        let (a, b) = (self.char(), self.char());
+                        CharRange {
+                            start: a.min(b),
+                            end: a.max(b),
+                        }
+                    })
+                    .collect();
+                CharClass::Set {
+                    chars,
+                    negated: self.below(2) == 0,
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ast_from_bytes() {
+    assert_eq!(ast_from_bytes(b""), []);
+    assert_eq!(ast_from_bytes(b"xyz"), ast_from_bytes(b"xyz"));
+
+    for input in inputs(300) {
+        let ast = ast_from_bytes(&input);
+        if let Err(err) = Regex::from_ast(ast.clone(), Flags::default()) {
+            panic!("{} doesn't compile: {:?}", to_pattern(&ast), err);
+        }
+    }
+}
+
+#[test]
+fn test_generated_patterns_round_trip() {
+    for input in inputs(300) {
+        let pattern = to_pattern(&ast_from_bytes(&input));
+        check_round_trip(&pattern);
+        for text in ["", "abc", "a-b\nc", "AAé 中 ba"] {
+            check_consistency(&pattern, Flags::default(), text);
+        }
+    }
+}
+
+// Checks the DFA-guided matcher against the plain backtracking one on generated
+// regular patterns, from every start position of generated texts, the way a
+// differential fuzzer would
+#[test]
+fn test_dfa_agrees_with_backtracking_on_generated_cases() {
+    let flag_sets = [
+        Flags::new().ignore_case(false),
+        Flags::new().ignore_case(true),
+        Flags::new().ignore_case(false).multiline(true).dotall(true),
+        Flags::new().ignore_case(true).crlf(true),
+    ];
+    let mut inputs = inputs(3000);
+    let mut compared = 0;
+    while let Some(input) = inputs.next() {
+        let ast = optimize(ast_from_bytes(&input));
+        let texts: Vec<String> = inputs
+            .by_ref()
+            .take(4)
+            .map(|bytes| {
+                bytes[..bytes.len() % 16]
+                    .iter()
+                    .map(|&byte| CHARS[usize::from(byte) % CHARS.len()])
+                    .collect()
+            })
+            .collect();
+        for flags in flag_sets {
+            let Some(dfa) = LazyDfa::new(&ast, &flags) else {
+                continue;
+            };
+            let program = Program::new(&ast, &flags);
+            for text in &texts {
+                for from in (0..=text.len()).filter(|&i| text.is_char_boundary(i)) {
+                    let plain = Matcher::from_program(&program, &flags, text)
+                        .find_at(from, &mut MatchContext::new(&program));
+                    let guided = Matcher::from_program(&program, &flags, text)
+                        .with_dfa(&dfa)
+                        .find_at(from, &mut MatchContext::new(&program));
+                    assert_eq!(
+                        guided,
+                        plain,
+                        "'{}' with flags '{}' on {:?} from {}",
+                        to_pattern(&ast),
+                        flags,
+                        text,
+                        from
+                    );
+                    compared += 1;
+                }
+                check_consistency(&to_pattern(&ast), flags, text);
+            }
+        }
+    }
+    assert!(compared > 10_000, "only {} comparisons", compared);
+}
+
+#[test]
+fn test_checks_ignore_invalid_patterns() {
+    check_round_trip("(abc");
+    check_consistency("a{3,1}", Flags::default(), "aaa");
+}
+
+#[test]
+fn test_shrink_pattern() {
+    // Still matches the "g" in "abcfgx"
+    let matches_g = |pattern: &str| {
+        Regex::new(pattern, Flags::default()).is_ok_and(|re| {
+            re.find("abcfgx")
+                .is_some_and(|m| m.start <= 4 && m.end >= 5)
+        })
+    };
+    assert_eq!(
+        shrink_pattern(r"abc(de|fg)+x", Flags::default(), matches_g),
+        "g"
+    );
+    assert_eq!(
+        shrink_pattern(r"x[a-cg]*y", Flags::default(), |p| p.contains('[')),
+        "[g]"
+    );
+    assert_eq!(shrink_pattern("(abc", Flags::default(), |_| true), "(abc");
+}
+
+#[test]
+fn test_shrink_keeps_failing_ast() {
+    let ast = crate::Parser::new("(a|b)c*(?>=d)", Flags::default())
+        .parse()
+        .unwrap();
+    let has_lookahead = |nodes: &[AstNode]| to_pattern(nodes).contains("(?>=");
+    assert_eq!(to_pattern(&shrink(ast, has_lookahead)), "(?>=)");
+}
@@ -0,0 +1,49 @@
+use crate::{Flags, Generator, Regex};
+
+#[test]
+fn test_generated_strings_match() {
+    let patterns = [
+        r"^[a-f]{2}-\d+(?:x|yz)?$",
+        r"(\w{1,3})-\1",
+        r"\<\a+\>",
+        r"[^a-z\n]+",
+        r"[α-ω😀]{3}",
+        r"foo(?>=bar)\w+",
+        r"(?<=\d)x",
+        r"^a$\n^b$",
+        r"\bword\b",
+        r"\d{2,4}?\s*\S",
+    ];
+    for pattern in patterns {
+        let flags = Flags {
+            multiline: true,
+            ..Flags::default()
+        };
+        let re = Regex::new(pattern, flags).unwrap();
+        let mut generator = Generator::new(&re, 42);
+        for _ in 0..50 {
+            let text = generator.generate().unwrap_or_else(|| panic!("{pattern}"));
+            assert!(re.is_match(&text), "{pattern} {text:?}");
+        }
+    }
+}
+
+#[test]
+fn test_generator_seeds_and_bounds() {
+    let re = Regex::new(r"a*b+", Flags::default()).unwrap();
+    let sample = |seed| {
+        let mut generator = Generator::new(&re, seed).max_repeat(3);
+        (0..20)
+            .map(|_| generator.generate().unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sample(1), sample(1));
+    assert_ne!(sample(1), sample(2));
+    assert!(sample(3).iter().all(|text| text.len() <= 7));
+
+    let mut generator = Generator::new(&re, 0).max_repeat(0);
+    assert_eq!(generator.generate().as_deref(), Some("b"));
+
+    let impossible = Regex::new("a(?<=b)", Flags::default()).unwrap();
+    assert_eq!(Generator::new(&impossible, 0).generate(), None);
+}
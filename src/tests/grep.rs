@@ -0,0 +1,104 @@
+use crate::{Flags, GrepOptions, Regex, grep, grep_reader};
+
+// Line numbers and whether each is context
+fn report(pattern: &str, text: &str, options: GrepOptions) -> Vec<(usize, bool)> {
+    let re = Regex::new(pattern, Flags::default()).unwrap();
+    grep(&re, text, options)
+        .map(|line| (line.line_number, line.context))
+        .collect()
+}
+
+#[test]
+fn test_grep_lines_and_matches() {
+    let re = Regex::new(r"o\w", Flags::default()).unwrap();
+    let lines: Vec<_> = grep(&re, "foo bob\r\nbar\nox\n", GrepOptions::new()).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!((lines[0].line_number, lines[0].line), (1, "foo bob"));
+    let spans: Vec<_> = lines[0].matches.iter().map(|m| (m.start, m.end)).collect();
+    assert_eq!(spans, [(1, 3), (5, 7)]);
+    assert_eq!((lines[1].line_number, lines[1].line), (3, "ox"));
+
+    // Anchors see each line on its own, and the line ending isn't part of it
+    assert_eq!(
+        report("^b.*r$", "foo\r\nbar\r\n", GrepOptions::new()),
+        [(2, false)]
+    );
+    assert_eq!(
+        report("a$", "a\nba\nab", GrepOptions::new()),
+        [(1, false), (2, false)]
+    );
+}
+
+#[test]
+fn test_grep_invert_and_context() {
+    let text = "1\n2\nx\n4\n5\n6\n7\nx\nx\n10";
+    assert_eq!(
+        report("x", text, GrepOptions::new().invert(true)),
+        [1, 2, 4, 5, 6, 7, 10].map(|n| (n, false))
+    );
+    assert_eq!(
+        report("x", text, GrepOptions::new().context(1)),
+        [
+            (2, true),
+            (3, false),
+            (4, true),
+            (7, true),
+            (8, false),
+            (9, false),
+            (10, true)
+        ]
+    );
+    // Context lines are reported once, even where selected lines' contexts overlap
+    assert_eq!(
+        report("x", text, GrepOptions::new().before_context(3)),
+        [
+            (1, true),
+            (2, true),
+            (3, false),
+            (5, true),
+            (6, true),
+            (7, true),
+            (8, false),
+            (9, false)
+        ]
+    );
+    assert_eq!(
+        report("x", text, GrepOptions::new().after_context(2).invert(true)),
+        [
+            (1, false),
+            (2, false),
+            (3, true),
+            (4, false),
+            (5, false),
+            (6, false),
+            (7, false),
+            (8, true),
+            (9, true),
+            (10, false)
+        ]
+    );
+    assert!(report("y", text, GrepOptions::new().context(5)).is_empty());
+}
+
+#[test]
+fn test_grep_reader() {
+    let re = Regex::new("b", Flags::default()).unwrap();
+    let reader = std::io::Cursor::new("a\nb\r\nc\n");
+    let lines: Vec<_> = grep_reader(&re, reader, GrepOptions::new().context(1))
+        .map(|line| line.unwrap())
+        .map(|line| (line.line_number, line.line, line.context))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (1, "a".to_string(), true),
+            (2, "b".to_string(), false),
+            (3, "c".to_string(), true)
+        ]
+    );
+
+    let reader = std::io::Cursor::new(b"b\n\xff\n".to_vec());
+    let results: Vec<_> = grep_reader(&re, reader, GrepOptions::new()).collect();
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+}
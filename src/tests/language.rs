@@ -0,0 +1,104 @@
+use crate::{Flags, Regex};
+
+const SENSITIVE: Flags = Flags::new().ignore_case(false);
+
+fn strings(pattern: &str, max_len: usize) -> Option<Vec<String>> {
+    let re = Regex::new(pattern, SENSITIVE).unwrap();
+    Some(re.matching_strings(max_len)?.collect())
+}
+
+#[test]
+fn test_matching_strings() {
+    assert_eq!(strings("a*", 3).unwrap(), ["", "a", "aa", "aaa"]);
+    let folded = Regex::new("ab", Flags::default()).unwrap();
+    let all: Vec<_> = folded.matching_strings(2).unwrap().collect();
+    assert_eq!(all, ["AB", "Ab", "aB", "ab"]);
+    assert_eq!(strings("x|yz", 1).unwrap(), ["x"]);
+    assert_eq!(strings("[α-γ]", 5).unwrap(), ["α", "β", "γ"]);
+    assert_eq!(strings(r"\d", 1).unwrap().len(), 10);
+    assert_eq!(strings(r"\d{2}", 5).unwrap().len(), 100);
+    // Printable ASCII and tab, but not newline
+    assert_eq!(strings(".", 1).unwrap().len(), 96);
+
+    // Assertions are decided by the characters around them
+    assert_eq!(
+        strings(r"\<[a-c]\>!?", 2).unwrap(),
+        ["a", "b", "c", "a!", "b!", "c!"]
+    );
+    assert_eq!(strings(r"a\bb", 3).unwrap(), Vec::<String>::new());
+
+    // Every string really matches in full
+    let re = Regex::new(r"^(?:[x-z]+\d?|q{2,3})$", SENSITIVE).unwrap();
+    let all: Vec<_> = re.matching_strings(4).unwrap().collect();
+    assert!(all.iter().all(|s| re.is_match(s)));
+    assert_eq!(all.len(), 3 + 9 + 30 + 27 + 90 + 81 + 270 + 2);
+
+    assert_eq!(strings(r"(a)\1", 4), None);
+    assert_eq!(strings("a(?>=b)", 4), None);
+}
+
+fn regex(pattern: &str) -> Regex {
+    Regex::new(pattern, SENSITIVE).unwrap()
+}
+
+#[test]
+fn test_is_empty_language() {
+    assert_eq!(regex(r"a\bb").is_empty_language(), Some(true));
+    assert_eq!(regex(r"x\<y|\>z").is_empty_language(), Some(true));
+    assert_eq!(regex("").is_empty_language(), Some(false));
+    assert_eq!(regex(r"\<\d+\>").is_empty_language(), Some(false));
+    assert_eq!(regex(r"(a)\1").is_empty_language(), None);
+}
+
+#[test]
+fn test_intersects() {
+    assert_eq!(regex("ab*").intersects(&regex("a*b")), Some(true));
+    assert_eq!(regex("[a-m]+").intersects(&regex("[n-z]+")), Some(false));
+    assert_eq!(
+        regex(r"\d{3}").intersects(&regex(r"\d{2}|\d{4}")),
+        Some(false)
+    );
+    assert_eq!(regex(r"\s").intersects(&regex("\u{3000}")), Some(true));
+    assert_eq!(regex(r"\w+").intersects(&regex("é")), Some(true));
+    assert_eq!(regex(r"[^a-z]").intersects(&regex("q")), Some(false));
+
+    // Each side keeps its own flags
+    let folded = Regex::new("abc", Flags::default()).unwrap();
+    assert_eq!(regex("ABC").intersects(&folded), Some(true));
+    assert_eq!(regex("ABC").intersects(&regex("abc")), Some(false));
+
+    assert_eq!(regex("a").intersects(&regex("(?<=x)a")), None);
+}
+
+#[test]
+fn test_is_subset_of() {
+    assert_eq!(regex("ab").is_subset_of(&regex("a[a-z]")), Some(true));
+    assert_eq!(regex("a[a-z]").is_subset_of(&regex("ab")), Some(false));
+    assert_eq!(regex(r"\d{2,3}").is_subset_of(&regex(r"\d+")), Some(true));
+    assert_eq!(regex(r"\d*").is_subset_of(&regex(r"\d+")), Some(false));
+    assert_eq!(regex(r"\<a").is_subset_of(&regex("a")), Some(true));
+    assert_eq!(
+        regex(r"[^x]").is_subset_of(&regex("[a-w]|[y-z]")),
+        Some(false)
+    );
+    assert_eq!(regex("a").is_subset_of(&regex(r"(a)\1")), None);
+}
+
+#[test]
+fn test_is_equivalent() {
+    assert_eq!(regex("a+a*").is_equivalent(&regex("a+")), Some(true));
+    assert_eq!(
+        regex("(?:ab)*a").is_equivalent(&regex("a(?:ba)*")),
+        Some(true)
+    );
+    assert_eq!(regex(r"[0-9]").is_equivalent(&regex(r"\d")), Some(true));
+    assert_eq!(regex("a{2,4}").is_equivalent(&regex("aa?a?")), Some(false));
+    assert_eq!(regex("a{2,4}").is_equivalent(&regex("aaa?a?")), Some(true));
+    assert_eq!(
+        regex(r"\w").is_equivalent(&regex("[a-zA-Z0-9_]")),
+        Some(false)
+    );
+
+    let folded = Regex::new("ab", Flags::default()).unwrap();
+    assert_eq!(folded.is_equivalent(&regex("[aA][bB]")), Some(true));
+}
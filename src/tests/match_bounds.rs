@@ -0,0 +1,72 @@
+use crate::{Flags, Regex};
+
+fn spans(pattern: &str, text: &str) -> Vec<(usize, usize)> {
+    Regex::new(pattern, Flags::default())
+        .unwrap()
+        .find_all(text)
+        .map(|m| (m.start, m.end))
+        .collect()
+}
+
+fn found<'t>(pattern: &str, text: &'t str) -> Option<&'t str> {
+    let re = Regex::new(pattern, Flags::default()).unwrap();
+    re.find(text).map(|m| m.as_str(text))
+}
+
+#[test]
+fn test_last_executed_wins() {
+    assert_eq!(found(r"a\zsb\zsc", "abc"), Some("c"));
+    assert_eq!(found(r"a\zeb\zec", "abc"), Some("ab"));
+    assert_eq!(found(r"(?:x\zs)+y", "xxxy"), Some("y"));
+    assert_eq!(found(r"(?:x\ze)+y", "xxxy"), Some("xxx"));
+    assert_eq!(found(r"(?:x\zs)*y", "y"), Some("y"));
+}
+
+#[test]
+fn test_inside_groups_and_alternation() {
+    let re = Regex::new(r"(foo\zsbar)", Flags::default()).unwrap();
+    let caps = re.captures("xfoobar").unwrap();
+    assert_eq!(caps.as_str("xfoobar", 0), Some("bar"));
+    assert_eq!(caps.as_str("xfoobar", 1), Some("foobar"));
+
+    assert_eq!(found(r"foo\zsbar|baz", "foobar"), Some("bar"));
+    assert_eq!(found(r"foo\zsbar|baz", "baz"), Some("baz"));
+    // Only the branch that matched sets the bounds
+    assert_eq!(found(r"a\zsx|ab", "ab"), Some("ab"));
+    assert_eq!(found(r"(?:a\zeb)?abc", "abc"), Some("abc"));
+}
+
+#[test]
+fn test_crossed_bounds_are_empty() {
+    let re = Regex::new(r"a\zeb\zsc", Flags::default()).unwrap();
+    let m = re.find("abc").unwrap();
+    assert_eq!((m.start, m.end), (2, 2));
+}
+
+#[test]
+fn test_iteration_follows_the_consumed_text() {
+    assert_eq!(spans(r"a\zeb", "abab"), vec![(0, 1), (2, 3)]);
+    assert_eq!(spans(r"a\zs", "aaa"), vec![(1, 1), (2, 2), (3, 3)]);
+    assert_eq!(spans(r"\zea", "aaa"), vec![(0, 0), (1, 1), (2, 2)]);
+    // The text after `\ze` is still consumed, so matches don't overlap
+    assert_eq!(spans(r"a\zeaa", "aaaaaa"), vec![(0, 1), (3, 4)]);
+    assert_eq!(spans(r"foo\zebar", "foobarfoobar"), vec![(0, 3), (6, 9)]);
+
+    let re = Regex::new(r"\d\zs,", Flags::default()).unwrap();
+    assert_eq!(re.replace_all("1,2,a,3", ";"), "1;2;a,3");
+}
+
+#[test]
+fn test_empty_bounds_are_reported_once() {
+    assert_eq!(spans(r"a*\zs", "aab"), vec![(2, 2), (3, 3)]);
+    assert_eq!(spans(r"(?:a\zs)*", "aaa"), vec![(3, 3)]);
+
+    let re = Regex::new(r"a*\zs", Flags::default()).unwrap();
+    assert_eq!(re.replace_all("aab", "X"), "aaXbX");
+    assert_eq!(re.split("aab").collect::<Vec<_>>(), ["aa", "b", ""]);
+    assert_eq!(re.count("aab"), 2);
+
+    let re = Regex::new(r"(?:a\zs)*", Flags::default()).unwrap();
+    assert_eq!(re.replace_all("aaa", "X"), "aaaX");
+    assert_eq!(re.split("aaa").collect::<Vec<_>>(), ["aaa", ""]);
+}
@@ -27,3 +27,39 @@ mod engine;
 #[cfg(test)]
 #[path = "flags.rs"]
 mod flags;
+
+#[cfg(test)]
+#[path = "compiler.rs"]
+mod compiler;
+
+#[cfg(test)]
+#[path = "optimizer.rs"]
+mod optimizer;
+
+#[cfg(test)]
+#[path = "dfa.rs"]
+mod dfa;
+
+#[cfg(test)]
+#[path = "match_bounds.rs"]
+mod match_bounds;
+
+#[cfg(test)]
+#[path = "generate.rs"]
+mod generate;
+
+#[cfg(test)]
+#[path = "language.rs"]
+mod language;
+
+#[cfg(test)]
+#[path = "grep.rs"]
+mod grep;
+
+#[cfg(test)]
+#[path = "document.rs"]
+mod document;
+
+#[cfg(test)]
+#[path = "fuzz.rs"]
+mod fuzz;
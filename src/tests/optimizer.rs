@@ -0,0 +1,91 @@
+use crate::compiler::optimize;
+use crate::engine::Matcher;
+use crate::{AstNode, Flags, Parser, to_pattern};
+
+fn optimized(pattern: &str) -> Vec<AstNode> {
+    optimize(Parser::new(pattern, Flags::default()).parse().unwrap())
+}
+
+fn assert_optimizes_to(pattern: &str, expected: &str) {
+    assert_eq!(
+        to_pattern(&optimized(pattern)),
+        expected,
+        "Unexpected optimization of '{}'",
+        pattern
+    );
+}
+
+#[test]
+fn test_literal_runs_merged() {
+    assert_eq!(
+        optimized("abc"),
+        vec![AstNode::LiteralString("abc".to_string())]
+    );
+    assert_eq!(
+        optimized(r"ab\dc"),
+        vec![
+            AstNode::LiteralString("ab".to_string()),
+            AstNode::CharClass(crate::CharClass::Digit),
+            AstNode::Literal('c'),
+        ]
+    );
+    // Quantifiers bind to the last literal only
+    assert_optimizes_to("abc+", "abc+");
+}
+
+#[test]
+fn test_snapshots() {
+    for (pattern, expected) in [
+        // Group flattening
+        ("a(?:bc)d", "abcd"),
+        ("(?:(?:ab))", "ab"),
+        ("(?:a)*", "a*"),
+        ("(?:ab)*", "(?:ab)*"),
+        ("(a)(?:b)", "(a)b"),
+        ("x(?:a|b)y", "x(?:a|b)y"),
+        // Redundant branches
+        ("a|a", "a"),
+        ("a|b|a", "a|b"),
+        ("(a|a)", "(a)"),
+        // Prefix hoisting
+        ("abc|abd", "ab(?:c|d)"),
+        ("a|ab", "a(?:|b)"),
+        ("abc|abd|x", "ab(?:c|d)|x"),
+        ("x|abc|abd", "x|ab(?:c|d)"),
+        ("ab|x|ac", "ab|x|ac"),
+        ("foo1|foo2|bar", "foo(?:1|2)|bar"),
+        ("abcx|abcy|abz", "ab(?:c(?:x|y)|z)"),
+        // Nested structures
+        (r"(?<=ab|ac)d", "(?<=a(?:b|c))d"),
+        (r"(?>=(?:ab))", "(?>=ab)"),
+    ] {
+        assert_optimizes_to(pattern, expected);
+    }
+}
+
+#[test]
+fn test_optimized_matches_unoptimized() {
+    let flags = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    let cases = [
+        ("abc|abd", "xxabdxx"),
+        ("a|ab", "ab"),
+        ("(a|ab)(c|bcd)", "abcd"),
+        ("(?:ab|ac)+d", "abacabd"),
+        ("x(?:a|b)*y", "xababy"),
+        ("x(?:ab)*y", "xababy"),
+        (r"(\w)(?:\1)", "hello"),
+        ("foo1|foo2|bar", "foo2bar"),
+        ("(?<=ab|ac)d", "acd"),
+        ("ab\\zscd|abce", "abce"),
+    ];
+    for (pattern, text) in cases {
+        let ast = Parser::new(pattern, flags).parse().unwrap();
+        let expected = Matcher::new(&ast, &flags, text).find();
+        let optimized = optimize(ast.clone());
+        let actual = Matcher::new(&optimized, &flags, text).find();
+        assert_eq!(actual, expected, "Mismatch for '{}' on '{}'", pattern, text);
+    }
+}
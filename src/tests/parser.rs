@@ -23,6 +23,58 @@ fn test_quantifiers() {
     assert!(matches!(ast[0], AstNode::OneOrMore { .. }));
 }
 
+#[test]
+fn test_vim_lazy_quantifiers() {
+    let bounds = |pattern: &str| match &Parser::new(pattern, Flags::default()).parse().unwrap()[..]
+    {
+        [
+            AstNode::Range {
+                min, max, greedy, ..
+            },
+        ] => (*min, *max, *greedy),
+        other => panic!("'{}' parsed to {:?}", pattern, other),
+    };
+    assert_eq!(bounds("a{-}"), (0, None, false));
+    assert_eq!(bounds("a{-1,3}"), (1, Some(3), false));
+    assert_eq!(bounds("a{-2,}"), (2, None, false));
+    assert_eq!(bounds("a{-,4}"), (0, Some(4), false));
+    // An exact count is the same either way
+    assert!(matches!(
+        Parser::new("a{-3}", Flags::default()).parse().unwrap()[0],
+        AstNode::Exact { count: 3, .. }
+    ));
+    assert!(matches!(
+        Parser::new("a{-x}", Flags::default()).parse(),
+        Err(crate::parser::ParseError::InvalidQuantifier(_))
+    ));
+    // It prints as the PCRE-style lazy quantifier, which parses the same
+    let ast = Parser::new("a{-1,3}", Flags::default()).parse().unwrap();
+    assert_eq!(to_pattern(&ast), "a{1,3}?");
+}
+
+#[test]
+fn test_number_errors() {
+    use crate::parser::ParseError;
+
+    let parse = |pattern| Parser::new(pattern, Flags::default()).parse();
+    assert!(matches!(
+        parse("a{99999999999999999999}"),
+        Err(ParseError::InvalidQuantifier(_))
+    ));
+    assert!(matches!(
+        parse("a{1,99999999999999999999}"),
+        Err(ParseError::InvalidQuantifier(_))
+    ));
+    assert!(matches!(
+        parse(r"\%99999999999999999999l"),
+        Err(ParseError::InvalidLineNumber(_))
+    ));
+    assert!(matches!(
+        parse(r"\%<99999999999999999999c"),
+        Err(ParseError::InvalidLineNumber(_))
+    ));
+}
+
 #[test]
 fn test_char_class() {
     let mut p = Parser::new("[a-z]", Flags::default());
@@ -30,6 +82,123 @@ fn test_char_class() {
     assert_eq!(ast.len(), 1);
 }
 
+#[test]
+fn test_char_class_escapes() {
+    let set = |pattern: &str| match Parser::new(pattern, Flags::default()).parse() {
+        Ok(ast) => match &ast[..] {
+            [AstNode::CharClass(CharClass::Set { chars, .. })] => {
+                Ok(chars.iter().map(|r| (r.start, r.end)).collect::<Vec<_>>())
+            }
+            other => panic!("'{}' parsed to {:?}", pattern, other),
+        },
+        Err(e) => Err(e.to_string()),
+    };
+    assert_eq!(
+        set(r"[\n\t\r\f\v]"),
+        Ok(vec![
+            ('\n', '\n'),
+            ('\t', '\t'),
+            ('\r', '\r'),
+            ('\x0C', '\x0C'),
+            ('\x0B', '\x0B')
+        ])
+    );
+    assert_eq!(set(r"[\x{20}-\x{7E}]"), Ok(vec![(' ', '~')]));
+    assert_eq!(set(r"[\x{1F600}é]"), Ok(vec![('😀', '😀'), ('é', 'é')]));
+    assert_eq!(set(r"[\t-\r]"), Ok(vec![('\t', '\r')]));
+    // Other escapes stand for the character itself
+    assert_eq!(
+        set(r"[\]\-\x]"),
+        Ok(vec![(']', ']'), ('-', '-'), ('x', 'x')])
+    );
+
+    assert_eq!(
+        set("[z-a]"),
+        Err("Invalid character class: the range `z-a` is reversed".to_string())
+    );
+    assert_eq!(
+        set(r"[\x{110000}]"),
+        Err(crate::parser::ParseError::InvalidEscape('x').to_string())
+    );
+    assert_eq!(
+        set(r"[\x{zz}]"),
+        Err(crate::parser::ParseError::InvalidEscape('x').to_string())
+    );
+    assert_eq!(
+        set(r"[\x{41"),
+        Err(crate::parser::ParseError::UnexpectedEof.to_string())
+    );
+}
+
+#[test]
+fn test_char_class_hyphens() {
+    let set = |pattern: &str| match Parser::new(pattern, Flags::default()).parse() {
+        Ok(ast) => match &ast[..] {
+            [AstNode::CharClass(CharClass::Set { chars, .. })] => {
+                chars.iter().map(|r| (r.start, r.end)).collect::<Vec<_>>()
+            }
+            other => panic!("'{}' parsed to {:?}", pattern, other),
+        },
+        Err(e) => panic!("'{}' failed: {}", pattern, e),
+    };
+
+    // First or last in the set, `-` stands for itself
+    assert_eq!(set("[-a]"), [('-', '-'), ('a', 'a')]);
+    assert_eq!(set("[^-a]"), [('-', '-'), ('a', 'a')]);
+    assert_eq!(set("[a-]"), [('a', 'a'), ('-', '-')]);
+    assert_eq!(set("[a-c-]"), [('a', 'c'), ('-', '-')]);
+    assert_eq!(set("[-]"), [('-', '-')]);
+    // After a range it can't extend it, so it's literal too
+    assert_eq!(set("[a-c-e]"), [('a', 'c'), ('-', '-'), ('e', 'e')]);
+    // It can be a range's end, or start one of its own
+    assert_eq!(set("[!--]"), [('!', '-')]);
+    assert_eq!(set("[--/]"), [('-', '/')]);
+    assert_eq!(set(r"[a\-z]"), [('a', 'a'), ('-', '-'), ('z', 'z')]);
+
+    // Reversed ranges are errors that point at the range
+    for (pattern, range) in [
+        ("[z-a]", "z-a"),
+        ("[xé-a]", "é-a"),
+        ("[a--]", "a--"),
+        (r"[\x{7A}-a]", r"\x{7A}-a"),
+    ] {
+        let mut parser = Parser::new(pattern, Flags::default());
+        let err = parser.parse().unwrap_err();
+        let crate::parser::ParseError::InvalidCharClass { span, .. } = err else {
+            panic!("'{}' failed with {:?}", pattern, err);
+        };
+        assert_eq!(&pattern[span.start..span.end], range);
+        assert_eq!(parser.error_span(&err), span);
+    }
+}
+
+#[test]
+fn test_posix_brackets_in_sets() {
+    // Rift has no syntax for these, so they're errors rather than plain characters
+    for (pattern, bracket) in [
+        ("[[=e=]]", "[=e=]"),
+        ("x[a[.ch.]]", "[.ch.]"),
+        ("[^[:alpha:]_]", "[:alpha:]"),
+    ] {
+        let mut parser = Parser::new(pattern, Flags::default());
+        let err = parser.parse().unwrap_err();
+        let crate::parser::ParseError::InvalidCharClass { span, .. } = err else {
+            panic!("'{}' failed with {:?}", pattern, err);
+        };
+        assert_eq!(&pattern[span.start..span.end], bracket);
+    }
+    // A `[` that doesn't start one is still a character
+    assert!(Regex::new("[[=]", Flags::default()).unwrap().is_match("="));
+    assert!(
+        Regex::new("[[..]]", Flags::default())
+            .unwrap()
+            .is_match(".]")
+    );
+    // Sets holding `[` are written so they don't read back as one
+    let ast = Parser::new(r"[\[=e=]", Flags::default()).parse().unwrap();
+    assert_eq!(to_pattern(&ast), r"[\[=e=]");
+}
+
 #[test]
 fn test_group() {
     let mut p = Parser::new("(abc)", Flags::default());
@@ -45,6 +214,118 @@ fn test_escape_classes() {
     assert_eq!(ast.len(), 3);
 }
 
+#[test]
+fn test_control_escapes() {
+    use crate::parser::ParseError;
+
+    let parse = |pattern: &str| Parser::new(pattern, Flags::default()).parse();
+    let literals = |pattern: &str| {
+        parse(pattern)
+            .unwrap()
+            .iter()
+            .map(|node| match node {
+                AstNode::Literal(c) => *c,
+                other => panic!("'{}' parsed to {:?}", pattern, other),
+            })
+            .collect::<String>()
+    };
+    assert_eq!(literals(r"\cA\cz\cC\0"), "\x01\x1A\x03\0");
+    // `\0` is NUL and the digit after it is its own character
+    assert_eq!(literals(r"\01"), "\x001");
+    assert!(matches!(parse(r"(a)\1").unwrap()[1], AstNode::Backref(1)));
+
+    assert!(matches!(
+        parse(r"\c1"),
+        Err(ParseError::InvalidControlEscape('1'))
+    ));
+    assert!(matches!(parse(r"\c"), Err(ParseError::UnexpectedEof)));
+    assert_eq!(
+        ParseError::InvalidControlEscape('[').to_diagnostic().code,
+        "invalid-control-escape"
+    );
+
+    // The same escapes work in sets, and show as written
+    let ast = parse(r"[\cA-\cZ\0]").unwrap();
+    assert!(
+        matches!(&ast[0], AstNode::CharClass(CharClass::Set { chars, .. })
+        if chars.iter().map(|r| (r.start, r.end)).eq([('\x01', '\x1A'), ('\0', '\0')]))
+    );
+    let ast = parse(r"\cA\cZ\0\e").unwrap();
+    assert_eq!(crate::parser::to_pattern(&ast), r"\cA\cZ\0e");
+
+    let re = crate::Regex::new(r"\cC+\0", Flags::default()).unwrap();
+    assert_eq!(re.find("x\x03\x03\0").map(|m| m.start), Some(1));
+}
+
+#[cfg(feature = "unicode-names")]
+#[test]
+fn test_char_names() {
+    use crate::parser::ParseError;
+
+    let parse = |pattern: &str| Parser::new(pattern, Flags::default()).parse();
+    let literal = |pattern: &str| match &parse(pattern).unwrap()[..] {
+        [AstNode::Literal(c)] => *c,
+        other => panic!("'{}' parsed to {:?}", pattern, other),
+    };
+    assert_eq!(literal(r"\N{LATIN SMALL LETTER A}"), 'a');
+    assert_eq!(literal(r"\N{greek small letter alpha}"), 'α');
+    assert_eq!(literal(r"\N{ZERO WIDTH SPACE}"), '\u{200B}');
+    assert_eq!(literal(r"\N{CJK UNIFIED IDEOGRAPH-4E00}"), '一');
+    assert_eq!(literal(r"\N{CJK UNIFIED IDEOGRAPH-20000}"), '\u{20000}');
+    assert_eq!(literal(r"\N{HANGUL SYLLABLE GAG}"), '\u{AC01}');
+    assert_eq!(literal(r"\N{HANGUL SYLLABLE A}"), '\u{C544}');
+    assert_eq!(literal(r"\N{HANGUL SYLLABLE HIH}"), '\u{D7A3}');
+
+    assert!(matches!(
+        parse(r"\N{CJK UNIFIED IDEOGRAPH-04E00}"),
+        Err(ParseError::UnknownCharName(_))
+    ));
+    assert!(matches!(
+        parse(r"\N{LATIN SMALL LETTER}"),
+        Err(ParseError::UnknownCharName(name)) if name == "LATIN SMALL LETTER"
+    ));
+    assert!(matches!(
+        parse(r"\N{ABACUS"),
+        Err(ParseError::UnexpectedEof)
+    ));
+    // Without braces it's still an escaped `N`
+    assert_eq!(literal(r"\N"), 'N');
+
+    let re = crate::Regex::new(r"[\N{EM DASH}\N{EN DASH}]+", Flags::default()).unwrap();
+    assert_eq!(re.find("a–—b").map(|m| (m.start, m.end)), Some((1, 7)));
+
+    // Every listed name finds its character
+    for line in include_str!("../parser/names.txt").lines() {
+        let (name, hex) = line.split_once(';').unwrap();
+        let expected = char::from_u32(u32::from_str_radix(hex, 16).unwrap()).unwrap();
+        assert_eq!(literal(&format!(r"\N{{{}}}", name)), expected, "{}", name);
+    }
+
+    // Smartcase goes by the character named, not the capitals in its name
+    let re = crate::Regex::new(r"\N{LATIN SMALL LETTER A}b", Flags::default()).unwrap();
+    assert!(re.is_match("AB"));
+    let re = crate::Regex::new(r"\N{LATIN CAPITAL LETTER A}b", Flags::default()).unwrap();
+    assert!(!re.is_match("ab"));
+}
+
+#[cfg(not(feature = "unicode-names"))]
+#[test]
+fn test_char_names_need_feature() {
+    let err = Parser::new(r"\N{LATIN SMALL LETTER A}", Flags::default())
+        .parse()
+        .unwrap_err();
+    assert!(
+        matches!(&err, crate::parser::ParseError::UnknownCharName(name)
+        if name == "LATIN SMALL LETTER A")
+    );
+    assert!(
+        err.to_diagnostic()
+            .suggestion
+            .unwrap()
+            .contains("unicode-names")
+    );
+}
+
 #[test]
 fn test_lookarounds() {
     // Positive lookahead (?>=...)
@@ -79,3 +360,576 @@ fn test_lookarounds() {
         }
     ));
 }
+
+fn assert_round_trip(pattern: &str) {
+    let ast = Parser::new(pattern, Flags::default()).parse().unwrap();
+    let printed = to_pattern(&ast);
+    let reparsed = Parser::new(&printed, Flags::default()).parse().unwrap();
+    assert_eq!(
+        ast, reparsed,
+        "Pattern '{}' printed as '{}' did not round-trip",
+        pattern, printed
+    );
+}
+
+#[test]
+fn test_to_pattern_round_trip() {
+    for pattern in [
+        "abc",
+        r"a.c\.d",
+        r"\d+\w*?\s?\S??",
+        "a{3}b{2,4}c{2,}?d{,5}",
+        "[a-z_]",
+        r"[^\]\\\-x]",
+        r"[\n\t\x{1}-\x{1F}\x{7F}]",
+        "(a|b)(?:cd)+(?<word>e)",
+        r"^foo\zsbar\ze$",
+        r"\<\bword\b\>",
+        r"(a)\1",
+        "(?>=x)(?>!y)(?<=z)(?<!w)",
+        r"\n\t\r\f\v",
+        r"\(\)\[\]\{\}\*\+\?\^\$\|",
+        "cat|dog|",
+        r"\l\L\u\U\x\X\o\O\h\H\p\P\a\A\D\W",
+    ] {
+        assert_round_trip(pattern);
+    }
+}
+
+#[test]
+fn test_to_pattern_output() {
+    let ast = Parser::new("(?:a|b)c{2,}?", Flags::default())
+        .parse()
+        .unwrap();
+    assert_eq!(to_pattern(&ast), "(?:a|b)c{2,}?");
+    assert_eq!(ast[1].to_pattern(), "c{2,}?");
+}
+
+#[test]
+fn test_to_pattern_wraps_built_nodes() {
+    // An alternation sharing a sequence with other nodes needs a group
+    let ast = vec![
+        AstNode::Alternation(vec![
+            vec![AstNode::Literal('a')],
+            vec![AstNode::Literal('b')],
+        ]),
+        AstNode::Literal('c'),
+    ];
+    assert_eq!(to_pattern(&ast), "(?:a|b)c");
+
+    // A repeated quantifier needs a group
+    let ast = vec![AstNode::OneOrMore {
+        node: Box::new(AstNode::Optional {
+            node: Box::new(AstNode::Literal('a')),
+            greedy: true,
+        }),
+        greedy: true,
+    }];
+    assert_eq!(to_pattern(&ast), "(?:a?)+");
+}
+
+#[test]
+fn test_visitor_order_and_skipping() {
+    struct Recorder {
+        events: Vec<String>,
+    }
+
+    impl Visitor for Recorder {
+        fn enter(&mut self, node: &AstNode) -> bool {
+            self.events.push(format!("enter {}", node));
+            // Don't descend into lookarounds
+            !matches!(node, AstNode::LookAhead { .. })
+        }
+
+        fn leave(&mut self, node: &AstNode) {
+            self.events.push(format!("leave {}", node));
+        }
+    }
+
+    let ast = Parser::new("(a)+(?>=b)", Flags::default()).parse().unwrap();
+    let mut recorder = Recorder { events: vec![] };
+    walk(&ast, &mut recorder);
+    assert_eq!(
+        recorder.events,
+        vec![
+            "enter (a)+",
+            "enter (a)",
+            "enter a",
+            "leave a",
+            "leave (a)",
+            "leave (a)+",
+            "enter (?>=b)",
+            "leave (?>=b)",
+        ]
+    );
+}
+
+#[test]
+fn test_map_rewrites_bottom_up() {
+    let ast = Parser::new("(?:(?:ab))", Flags::default()).parse().unwrap();
+    // Unwrap non-capturing groups holding a single node
+    let ast = map(ast, &mut |node| match node {
+        AstNode::Group {
+            capture: false,
+            mut nodes,
+            ..
+        } if nodes.len() == 1 => nodes.pop().unwrap(),
+        other => other,
+    });
+    assert_eq!(to_pattern(&ast), "(?:ab)");
+}
+
+#[test]
+fn test_parse_with_spans() {
+    let span = |start, end| Some(Span { start, end });
+
+    let (ast, spans) = Parser::new("(é|b)*c", Flags::default())
+        .parse_with_spans()
+        .unwrap();
+    assert_eq!(ast.len(), 2);
+    // `(é|b)*`, the group, the alternation, `é`, `b`, then `c`
+    assert_eq!(spans.len(), 6);
+    assert_eq!(spans.get(0), span(0, 7));
+    assert_eq!(spans.get(1), span(0, 6));
+    assert_eq!(spans.get(2), span(1, 5));
+    assert_eq!(spans.get(3), span(1, 3));
+    assert_eq!(spans.get(4), span(4, 5));
+    assert_eq!(spans.get(5), span(7, 8));
+    assert_eq!(spans.get(6), None);
+
+    let (_, spans) = Parser::new(r"a{2,3}\d", Flags::default())
+        .parse_with_spans()
+        .unwrap();
+    assert_eq!(spans.get(0), span(0, 6));
+    assert_eq!(spans.get(1), span(0, 1));
+    assert_eq!(spans.get(2), span(6, 8));
+}
+
+#[test]
+fn test_to_dot() {
+    let ast = Parser::new(r#"(?<q>"|\d)"#, Flags::default())
+        .parse()
+        .unwrap();
+    assert_eq!(
+        to_dot(&ast),
+        [
+            "digraph ast {",
+            "  node [shape=box];",
+            r#"  n0 [label="pattern"];"#,
+            r#"  n1 [label="group 1 <q>"];"#,
+            "  n0 -> n1;",
+            r#"  n2 [label="|"];"#,
+            "  n1 -> n2;",
+            r#"  n3 [label="branch"];"#,
+            "  n2 -> n3;",
+            r#"  n4 [label="\""];"#,
+            "  n3 -> n4;",
+            r#"  n5 [label="branch"];"#,
+            "  n2 -> n5;",
+            r#"  n6 [label="\\d"];"#,
+            "  n5 -> n6;",
+            "}\n",
+        ]
+        .join("\n")
+    );
+    assert_eq!(ast[0].to_dot(), to_dot(&ast));
+}
+
+fn export(
+    pattern: &str,
+    flags: Flags,
+) -> (Result<String, ExportError>, Result<String, ExportError>) {
+    let ast = Parser::new(pattern, flags).parse().unwrap();
+    (to_rust_regex(&ast, &flags), to_pcre(&ast, &flags))
+}
+
+#[test]
+fn test_export_dialects() {
+    let sensitive = Flags {
+        ignore_case: Some(false),
+        ..Flags::default()
+    };
+    let multiline = Flags {
+        multiline: true,
+        dotall: true,
+        ..sensitive
+    };
+
+    let (rust, pcre) = export(r"^a.b|[x\]^-]+?$", sensitive);
+    assert_eq!(rust.unwrap(), r"\Aa[^\n]b|[x\]\^\-]+?\z");
+    assert_eq!(pcre.unwrap(), r"(*UTF)(*UCP)\Aa[^\n]b|[x\]\^\-]+?\z");
+    let (rust, _) = export("a[]", sensitive);
+    assert_eq!(rust.unwrap(), r"a[^\x{0}-\x{10FFFF}]");
+
+    let (rust, pcre) = export(r"^.$", multiline);
+    assert_eq!(rust.unwrap(), r"(?m:^)(?s:.)(?m:$)");
+    assert_eq!(pcre.unwrap(), r"(*UTF)(*UCP)(?<![^\n])(?s:.)(?![^\n])");
+    let crlf = Flags {
+        dotall: false,
+        crlf: true,
+        ..multiline
+    };
+    let (rust, pcre) = export(r"^.$", crlf);
+    assert_eq!(rust.unwrap(), r"(?mR:^)[^\n\r](?mR:$)");
+    assert_eq!(
+        pcre.unwrap(),
+        r"(*UTF)(*UCP)(?<![^\n\r])(?!(?<=\r)\n)[^\n\r](?![^\n\r])(?!(?<=\r)\n)"
+    );
+
+    let (rust, pcre) = export("a.+", sensitive.dot(DotMode::Ascii));
+    assert_eq!(rust.unwrap(), r"a[^\n\x{80}-\x{10FFFF}]+");
+    assert_eq!(pcre.unwrap(), r"(*UTF)(*UCP)a[^\n\x{80}-\x{10FFFF}]+");
+    let (rust, pcre) = export("a.+", sensitive.dot(DotMode::Grapheme));
+    assert!(rust.is_err());
+    assert_eq!(pcre.unwrap(), r"(*UTF)(*UCP)a(?:(?!\r?\n)\X)+");
+
+    let (rust, pcre) = export(
+        r"\<\l{2}\>\p",
+        Flags {
+            ignore_case: Some(true),
+            ..Flags::default()
+        },
+    );
+    assert_eq!(
+        rust.unwrap(),
+        r"(?i)\b{start}[\p{Lowercase}\p{Uppercase}]{2}\b{end}[!-/:-@\[-`{-~]"
+    );
+    assert_eq!(
+        pcre.unwrap(),
+        r"(*UTF)(*UCP)(?i)\b(?=\w)[\p{Lowercase}\p{Uppercase}]{2}\b(?<=\w)[!-/:-@\[-`{-~]"
+    );
+
+    // `\ze` turns the rest of the pattern into a lookahead, through alternations
+    let (rust, pcre) = export(r"x(?:a\zeb|c)", sensitive);
+    assert!(rust.is_err());
+    assert_eq!(pcre.unwrap(), r"(*UTF)(*UCP)x(?:a(?=b)|c)");
+    let (_, pcre) = export(r"(a\zeb)", sensitive);
+    assert!(pcre.is_err());
+    let (_, pcre) = export(r"(?:a\ze)*b", sensitive);
+    assert!(pcre.is_err());
+
+    let (rust, pcre) = export(r"(?<=a+)b", sensitive);
+    assert_eq!(
+        rust.unwrap_err(),
+        ExportError::Unsupported("a lookbehind has no equivalent in the Rust regex crate".into())
+    );
+    assert!(pcre.is_err());
+    let (_, pcre) = export(r"(?<!ab?)(?>!\zsc)", sensitive);
+    assert!(pcre.is_err());
+    let (_, pcre) = export(r"(?<!ab?)c", sensitive);
+    assert_eq!(pcre.unwrap(), r"(*UTF)(*UCP)(?<!ab?)c");
+}
+
+fn import(pattern: &str) -> Result<String, ImportError> {
+    from_pcre(pattern).map(|(ast, _)| to_pattern(&ast))
+}
+
+#[test]
+fn test_from_pcre() {
+    assert_eq!(
+        import(r"\Aa+?\.\d{2,}(?:x|y)\z").unwrap(),
+        r"^a+?\.\d{2,}(?:x|y)$"
+    );
+    assert_eq!(
+        import(r"(?P<n>\w)(?'m'b)\k<n>\g{-1}(?P=m)").unwrap(),
+        r"(?<n>\w)(?<m>b)\1\2\2"
+    );
+    assert_eq!(
+        import(r"(?<=x)(?!y)\K\b{start}\B").unwrap(),
+        r"(?<=x)(?>!y)\zs\<(?>!\b)"
+    );
+    assert_eq!(
+        import(r"\p{Lu}\P{Ll}\pN").unwrap_err(),
+        ImportError::Unsupported("the property `N` at byte 12 has no equivalent here".into())
+    );
+    assert_eq!(import(r"\p{Lu}\P{Ll}\p{Nd}").unwrap(), r"\u\L\d");
+    assert_eq!(import(r"\Q.*\E\x41\u{3B1}\0").unwrap(), r"\.\*Aα\0");
+    assert_eq!(import("a{,3}b{2}c{x}").unwrap(), r"a{0,3}b{2}c\{x\}");
+
+    // Sets, including classes and POSIX classes inside them
+    assert_eq!(import(r"[[:digit:]_a-c]").unwrap(), "[0-9_a-c]");
+    assert_eq!(import(r"[\w.]").unwrap(), r"(?:[.]|\w)");
+    assert_eq!(import(r"[^\s\d]").unwrap(), r"(?:(?>![0-9]|\s)[^])");
+    // Equivalence classes and collating symbols
+    assert_eq!(import("[[=n=]x]").unwrap(), "[nñńņňǹṅṇṉṋx]");
+    assert_eq!(import("[[=ñ=]]").unwrap(), import("[[=n=]]").unwrap());
+    assert_eq!(import("[[=-=]]").unwrap(), r"[\-]");
+    assert_eq!(import("[[.a.]-[.c.][.hyphen.]]").unwrap(), r"[a-c\-]");
+    assert_eq!(import("[[.ch.]a[.ll.][.rr.]]").unwrap(), "(?:ch|ll|rr|[a])");
+    assert_eq!(import("[^[.ch.]]").unwrap(), "(?:(?>!ch)[^])");
+    assert!(matches!(
+        import("[[.ab"),
+        Err(ImportError::InvalidPattern(_))
+    ));
+    assert!(matches!(
+        import("[[..]]"),
+        Err(ImportError::InvalidPattern(_))
+    ));
+    assert!(matches!(
+        import("[a-[=e=]]"),
+        Err(ImportError::InvalidPattern(_))
+    ));
+
+    // Flags at the start become the pattern's flags, later ones are written out
+    let (ast, flags) = from_pcre(r"(*UCP)(?is)a.(?-s:.)").unwrap();
+    assert_eq!((flags.ignore_case, flags.dotall), (Some(true), true));
+    assert_eq!(to_pattern(&ast), r"a.(?:[^\n])");
+    let (ast, flags) = from_pcre(r"a(?i:b)(?m)^").unwrap();
+    assert_eq!(flags.ignore_case, Some(false));
+    assert_eq!(to_pattern(&ast), r"a(?:[Bb])(?<![^\n])");
+    assert_eq!(import("(?x) a b # comment\n c").unwrap(), "abc");
+
+    // `$` matches before a final newline, as in PCRE, unless it's multiline
+    assert_eq!(import("a$").unwrap(), import(r"a\Z").unwrap());
+    let (ast, flags) = from_pcre("a$").unwrap();
+    let re = Regex::from_ast(ast, flags).unwrap();
+    assert_eq!(re.find("a\n").map(|m| (m.start, m.end)), Some((0, 1)));
+    assert!(re.is_match("a"));
+    assert!(!re.is_match("a\nb"));
+    assert!(!re.is_match("a\n\n"));
+    assert_eq!(import("(?m)a$").unwrap(), "a$");
+    assert_eq!(import("(?m)a(?-m:$)").unwrap(), r"a(?:(?>=\n?\%$))");
+
+    assert!(matches!(import("(?>a)"), Err(ImportError::Unsupported(_))));
+    assert!(matches!(import("a++"), Err(ImportError::Unsupported(_))));
+    assert!(matches!(
+        import("(?i)a(?-i:b)"),
+        Err(ImportError::Unsupported(_))
+    ));
+    assert!(matches!(import("(a"), Err(ImportError::InvalidPattern(_))));
+    assert!(matches!(import("a)"), Err(ImportError::InvalidPattern(_))));
+    assert!(matches!(import("*a"), Err(ImportError::InvalidPattern(_))));
+    assert!(matches!(
+        import(r"\k<nope>"),
+        Err(ImportError::InvalidPattern(_))
+    ));
+}
+
+#[test]
+fn test_pcre_round_trip() {
+    for pattern in [
+        r"^(?<y>\d{4})-\x+?\s*$",
+        r"foo\zsbar(?>=\w)",
+        r"[^a-c]|\<x\>",
+    ] {
+        let flags = Flags {
+            ignore_case: Some(false),
+            ..Flags::default()
+        };
+        let re = Regex::new(pattern, flags).unwrap();
+        let (ast, flags) = from_pcre(&re.to_pcre().unwrap()).unwrap();
+        let imported = Regex::from_ast(ast, flags).unwrap();
+        for text in ["2024-ff ", "foobar!", "b", "a x y", "1999-0"] {
+            assert_eq!(
+                imported.find(text),
+                re.find(text),
+                "{} on {:?}",
+                pattern,
+                text
+            );
+        }
+    }
+}
+
+#[test]
+fn test_foreign_syntax() {
+    fn error(pattern: &str) -> (String, &str) {
+        let mut parser = Parser::new(pattern, Flags::default());
+        let err = parser.parse().unwrap_err();
+        let span = parser.error_span(&err);
+        (err.to_string(), &pattern[span.start..span.end])
+    }
+    assert_eq!(
+        error("a(?=b)"),
+        (
+            "`(?=` isn't Rift syntax; did you mean `(?>=`?".to_string(),
+            "(?="
+        )
+    );
+    assert_eq!(error("é(?!b)").1, "(?!");
+    assert_eq!(error("(?P<year>\\d+)").1, "(?P<");
+    assert_eq!(
+        error(r"foo\Kbar"),
+        (
+            r"`\K` isn't Rift syntax; did you mean `\zs`?".to_string(),
+            r"\K"
+        )
+    );
+
+    // The Rift spellings, and a `P` that doesn't start a named group
+    for pattern in ["a(?>=b)", "(?>!b)", "(?<year>x)", r"foo\zsbar", r"\k"] {
+        assert!(
+            Parser::new(pattern, Flags::default()).parse().is_ok(),
+            "{pattern}"
+        );
+    }
+    assert!(matches!(
+        Parser::new("(?Px)", Flags::default()).parse(),
+        Err(crate::parser::ParseError::InvalidGroup(_))
+    ));
+
+    let diagnostic = &crate::diagnose("(?P<n>a)", Flags::default())[0];
+    assert_eq!(diagnostic.code, "foreign-syntax");
+    assert_eq!(
+        diagnostic.suggestion.as_deref(),
+        Some("write `(?<` instead")
+    );
+}
+
+#[test]
+fn test_highlight() {
+    use crate::{HighlightKind::*, highlight, highlight_rift};
+
+    fn spans(input: &str, rift: bool, flags: Flags) -> Vec<(crate::HighlightKind, &str)> {
+        let highlights = match rift {
+            true => highlight_rift(input),
+            false => highlight(input, flags),
+        };
+        highlights
+            .into_iter()
+            .map(|h| (h.kind, &input[h.span.start..h.span.end]))
+            .collect()
+    }
+    let pattern = |input| spans(input, false, Flags::default());
+
+    assert_eq!(
+        pattern(r"^\<é[^\]a-z]+?\1\zs.|\K(?=x)$"),
+        [
+            (Anchor, "^"),
+            (Anchor, r"\<"),
+            (Literal, "é"),
+            (Class, r"[^\]a-z]"),
+            (Quantifier, "+?"),
+            (Backref, r"\1"),
+            (Anchor, r"\zs"),
+            (Class, "."),
+            (Alternation, "|"),
+            (Invalid, r"\K"),
+            (Invalid, "(?="),
+            (Literal, "x"),
+            (GroupClose, ")"),
+            (Anchor, "$"),
+        ]
+    );
+    // Quantifier characters with nothing to repeat are literals
+    assert_eq!(
+        pattern("*a{2,}?b{,3}c{x(?<=\\.)"),
+        [
+            (Literal, "*a"),
+            (Quantifier, "{2,}?"),
+            (Literal, "b"),
+            (Quantifier, "{,3}"),
+            (Literal, "c"),
+            (Invalid, "{"),
+            (Literal, "x"),
+            (GroupOpen, "(?<="),
+            (Escape, r"\."),
+            (GroupClose, ")"),
+        ]
+    );
+    assert_eq!(pattern("[ab"), [(Invalid, "[ab")]);
+
+    let verbose = spans("a b # note\n+", false, "x".parse().unwrap());
+    assert_eq!(
+        verbose,
+        [
+            (Literal, "a"),
+            (Literal, "b"),
+            (Comment, "# note"),
+            (Quantifier, "+")
+        ]
+    );
+    let continued = spans("a\\\n b", false, "x".parse().unwrap());
+    assert_eq!(continued, [(Literal, "a"), (Literal, "b")]);
+
+    assert_eq!(
+        spans("#a/b#i2z", true, Flags::default()),
+        [
+            (Delimiter, "#"),
+            (Literal, "a/b"),
+            (Delimiter, "#"),
+            (Flag, "i"),
+            (Flag, "2"),
+            (Invalid, "z"),
+        ]
+    );
+    assert_eq!(
+        spans(r"a\/b # x/x", true, Flags::default()),
+        [
+            (Literal, "a"),
+            (Escape, r"\/"),
+            (Literal, "b"),
+            (Comment, "# x"),
+            (Delimiter, "/"),
+            (Flag, "x"),
+        ]
+    );
+    assert_eq!(spans("ab", true, Flags::default()), [(Literal, "ab")]);
+
+    // `(?x)` switches verbose mode until the group closes, and `(?#...)` is a comment
+    assert_eq!(
+        pattern("(a(?x) b)(?#note) c*"),
+        [
+            (GroupOpen, "("),
+            (Literal, "a"),
+            (Flag, "(?x)"),
+            (Literal, "b"),
+            (GroupClose, ")"),
+            (Comment, "(?#note)"),
+            (Literal, " c"),
+            (Quantifier, "*"),
+        ]
+    );
+    assert_eq!(
+        pattern("a(?#c(d))b"),
+        [(Literal, "a"), (Comment, "(?#c(d))"), (Literal, "b")]
+    );
+    assert_eq!(
+        pattern("(?-x: a)"),
+        [(GroupOpen, "(?-x:"), (Literal, " a"), (GroupClose, ")")]
+    );
+}
+
+#[test]
+fn test_inline_verbose() {
+    let parse = |pattern: &str, verbose: bool| {
+        let flags = Flags {
+            verbose,
+            ..Flags::default()
+        };
+        Parser::new(pattern, flags)
+            .parse()
+            .map(|ast| to_pattern(&ast))
+    };
+    assert_eq!(parse("a b(?x) c # d", false).unwrap(), "a bc");
+    assert_eq!(parse("a b(?-x) c", true).unwrap(), "ab c");
+    // It lasts until the enclosing group closes
+    assert_eq!(parse("(a(?x) b) c", false).unwrap(), "(ab) c");
+    assert_eq!(parse("(?x: a b ) c", false).unwrap(), "(?:ab) c");
+    assert_eq!(parse("(?-x: a b ) c", true).unwrap(), "(?: a b )c");
+    assert_eq!(parse("(?x)a(?-x: b)(?#c) d", false).unwrap(), "a(?: b)d");
+    // A `#` comment ends at the line, so it can't hide the `(?-x)` after it
+    assert_eq!(parse("(?x)a # b\n(?-x) c", false).unwrap(), "a c");
+
+    // `(?#...)` comments work in any mode, and a quantifier after one applies to
+    // the atom before it
+    assert_eq!(parse("a(?#one)b(?# two )*", false).unwrap(), "ab*");
+    assert!(matches!(
+        parse("a(?#open", false),
+        Err(crate::parser::ParseError::UnexpectedEof)
+    ));
+    // Parentheses inside one balance, so the comment doesn't end early
+    assert_eq!(parse("a(?#c(d))b", false).unwrap(), "ab");
+    assert!(matches!(
+        parse("a(?#c(d)b", false),
+        Err(crate::parser::ParseError::UnexpectedEof)
+    ));
+    let re = Regex::new("a(?#c(d))b", Flags::default()).unwrap();
+    assert!(re.is_match("ab"));
+    assert!(!re.is_match("a"));
+    // No other flag can be set inline
+    assert!(matches!(
+        parse("(?i)a", false),
+        Err(crate::parser::ParseError::InvalidGroup(_))
+    ));
+}
@@ -10,7 +10,9 @@ fn test_stub_find() {
 #[test]
 fn test_stub_captures() {
     let re = Regex::new("abc", Flags::default()).unwrap();
-    assert!(re.captures("abc").is_none());
+    let caps = re.captures("abc").unwrap();
+    assert_eq!(caps.full_match, crate::captures::Match { start: 0, end: 3 });
+    assert!(caps.groups.is_empty());
 }
 
 #[test]
@@ -36,5 +38,5 @@ fn test_stub_iterators() {
     assert!(matches.len() == 2);
 
     let captures: Vec<_> = re.captures_all(text).collect();
-    assert!(captures.is_empty());
+    assert!(captures.len() == 2);
 }
@@ -61,3 +61,149 @@ fn test_parse_rift_format_errors() {
         Err(ParseError::InvalidFlags('z'))
     ));
 }
+
+#[test]
+fn test_parse_substitution() {
+    let (pattern, replacement, flags, count) = parse_substitution("s/foo/bar/").unwrap();
+    assert_eq!((pattern.as_str(), replacement.as_str()), ("foo", "bar"));
+    assert_eq!(flags.ignore_case, Some(true)); // inferred smartcase (lowercase)
+    assert_eq!(count, Some(1));
+
+    // Escaped delimiters are unescaped, other escapes are kept
+    let (pattern, replacement, _, _) = parse_substitution(r"s/a\/\d+/\0\/x/").unwrap();
+    assert_eq!(pattern, r"a/\d+");
+    assert_eq!(replacement, r"\0/x");
+
+    // Another delimiter, flags and counts
+    let (pattern, replacement, flags, count) = parse_substitution("s#a/b#c#gmc").unwrap();
+    assert_eq!((pattern.as_str(), replacement.as_str()), ("a/b", "c"));
+    assert!(flags.multiline);
+    assert_eq!(count, None);
+    let (_, _, flags, count) = parse_substitution("s/Foo/bar/12").unwrap();
+    assert_eq!(flags.ignore_case, Some(false)); // has uppercase
+    assert_eq!((flags.occurrence, count), (Some(12), Some(1)));
+    let (_, _, flags, count) = parse_substitution("s/a/b/g2").unwrap();
+    assert_eq!((flags.occurrence, count), (Some(2), None));
+    let (_, _, flags, _) = parse_substitution("s/a/b/3gi").unwrap();
+    assert_eq!(flags.occurrence, Some(3));
+
+    // The final delimiter is optional without flags
+    let (_, replacement, _, _) = parse_substitution("s/a/b").unwrap();
+    assert_eq!(replacement, "b");
+    let (_, replacement, _, _) = parse_substitution("s/a/").unwrap();
+    assert_eq!(replacement, "");
+}
+
+#[test]
+fn test_parse_substitution_errors() {
+    assert!(matches!(
+        parse_substitution("x/a/b/"),
+        Err(ParseError::NotSubstitution)
+    ));
+    assert!(matches!(
+        parse_substitution("sa/b/"),
+        Err(ParseError::NotSubstitution)
+    ));
+    assert!(matches!(
+        parse_substitution("s/a"),
+        Err(ParseError::NoDelimiter)
+    ));
+    assert!(matches!(
+        parse_substitution("s/a/b/z"),
+        Err(ParseError::InvalidFlags('z'))
+    ));
+    assert!(matches!(
+        parse_substitution("s/a/b/2g3"),
+        Err(ParseError::InvalidFlags('3'))
+    ));
+    assert!(matches!(
+        parse_substitution("s/a/b/0"),
+        Err(ParseError::InvalidFlags('0'))
+    ));
+}
+
+#[test]
+fn test_parse_rift_format_delimiters() {
+    // The first character can choose another delimiter
+    let (pattern, flags) = parse_rift_format("#a/b#i").unwrap();
+    assert_eq!(pattern, "a/b");
+    assert_eq!(flags.ignore_case, Some(true));
+    let (pattern, flags) = parse_rift_format(r"!x\!y!m").unwrap();
+    assert_eq!(pattern, "x!y");
+    assert!(flags.multiline);
+
+    // Escaped slashes don't end the pattern
+    let (pattern, flags) = parse_rift_format(r"a\/B/").unwrap();
+    assert_eq!(pattern, "a/B");
+    assert_eq!(flags.ignore_case, Some(false));
+    let (pattern, _) = parse_rift_format(r"a\\/").unwrap();
+    assert_eq!(pattern, r"a\\");
+    assert!(matches!(
+        parse_rift_format(r"a\/"),
+        Err(ParseError::NoDelimiter)
+    ));
+
+    // Without a second delimiter, or with a pattern character first, it's `pattern/flags`
+    let (pattern, _) = parse_rift_format("#a/").unwrap();
+    assert_eq!(pattern, "#a");
+    let (pattern, _) = parse_rift_format("(a)(b)/").unwrap();
+    assert_eq!(pattern, "(a)(b)");
+}
+
+#[test]
+fn test_parse_range() {
+    let number = |n| Address {
+        line: Line::Number(n),
+        offset: 0,
+    };
+    assert_eq!(
+        parse_range("%s/a/b/").unwrap(),
+        (Some(Range::All), "s/a/b/")
+    );
+    assert_eq!(
+        parse_range("5,20s/foo/bar/g").unwrap(),
+        (Some(Range::Lines(number(5), number(20))), "s/foo/bar/g")
+    );
+    assert_eq!(
+        parse_range("$s/a/b/").unwrap(),
+        (
+            Some(Range::Line(Address {
+                line: Line::Last,
+                offset: 0
+            })),
+            "s/a/b/"
+        )
+    );
+    assert_eq!(parse_range("s/a/b/").unwrap(), (None, "s/a/b/"));
+
+    // Offsets, with a bare sign meaning one line
+    let (range, rest) = parse_range(".+2,$--3s").unwrap();
+    assert_eq!(rest, "s");
+    assert_eq!(
+        range,
+        Some(Range::Lines(
+            Address {
+                line: Line::Current,
+                offset: 2
+            },
+            Address {
+                line: Line::Last,
+                offset: -4
+            }
+        ))
+    );
+    let (range, _) = parse_range("-,+s").unwrap();
+    assert_eq!(range.unwrap().resolve(5, 10), Some(4..=6));
+
+    // Resolving against the current and last lines
+    assert_eq!(Range::All.resolve(1, 7), Some(1..=7));
+    assert_eq!(Range::Lines(number(3), number(2)).resolve(1, 7), None);
+    assert_eq!(Range::Line(number(8)).resolve(1, 7), None);
+    assert_eq!(Range::Line(number(0)).resolve(1, 7), None);
+
+    assert!(matches!(parse_range("5,s"), Err(ParseError::InvalidRange)));
+    assert!(matches!(
+        parse_range("99999999999999999999999s"),
+        Err(ParseError::InvalidRange)
+    ));
+}
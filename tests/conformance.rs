@@ -0,0 +1,341 @@
+//! Runs the behavioral cases in `tests/data/*.json`.
+//!
+//! Each file holds an array of cases, so new behavior can be pinned down without
+//! writing Rust. A case is an object with these keys:
+//!
+//! - `name`: describes the case in failure messages.
+//! - `pattern`, and optionally `flags` as written after a rift pattern (`"i"`).
+//! - `error: true` if the pattern must fail to compile; nothing else is checked.
+//! - `haystack`: the text searched.
+//! - `matches`: every match of `find_all` as `[start, end]` byte offsets.
+//! - `groups`: the first match's capture groups, group 0 first, each `[start, end]`
+//!   or `null` if the group didn't take part, or `null` if nothing matches.
+//! - `replace` and `replaced`: `replaced` is the result of `replace_all` with the
+//!   replacement `replace`.
+
+use std::fs;
+use std::path::Path;
+
+use monster_regex::{Flags, Regex};
+
+#[test]
+fn conformance() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .expect("tests/data exists")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no case files in {}", dir.display());
+
+    let mut failures = vec![];
+    let mut count = 0;
+    for path in &paths {
+        let file = path.file_name().unwrap().to_string_lossy();
+        let source = fs::read_to_string(path).unwrap();
+        let cases = match Json::parse(&source) {
+            Ok(Json::Array(cases)) => cases,
+            Ok(_) => panic!("{}: expected an array of cases", file),
+            Err(err) => panic!("{}: {}", file, err),
+        };
+        for (i, case) in cases.iter().enumerate() {
+            count += 1;
+            let name = case.get("name").and_then(Json::as_str).unwrap_or("unnamed");
+            if let Err(err) = run_case(case) {
+                failures.push(format!("{} #{} ({}): {}", file, i, name, err));
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} cases failed:\n{}",
+        failures.len(),
+        count,
+        failures.join("\n")
+    );
+}
+
+fn run_case(case: &Json) -> Result<(), String> {
+    let pattern = case
+        .get("pattern")
+        .and_then(Json::as_str)
+        .ok_or("missing `pattern`")?;
+    let flags: Flags = case
+        .get("flags")
+        .and_then(Json::as_str)
+        .unwrap_or("")
+        .parse()
+        .map_err(|err| format!("invalid flags: {:?}", err))?;
+    let compiled = Regex::new(pattern, flags);
+    if case.get("error") == Some(&Json::Bool(true)) {
+        return match compiled {
+            Ok(_) => Err("compiled, but should be an error".to_string()),
+            Err(_) => Ok(()),
+        };
+    }
+    let regex = compiled.map_err(|err| format!("doesn't compile: {:?}", err))?;
+    let haystack = case
+        .get("haystack")
+        .and_then(Json::as_str)
+        .ok_or("missing `haystack`")?;
+
+    if let Some(expected) = case.get("matches") {
+        let expected = spans(expected).ok_or("`matches` should be a list of spans")?;
+        let found: Vec<_> = regex
+            .find_all(haystack)
+            .map(|m| Some((m.start, m.end)))
+            .collect();
+        if found != expected {
+            return Err(format!("matches {:?}, expected {:?}", found, expected));
+        }
+    }
+
+    if let Some(expected) = case.get("groups") {
+        let expected = match expected {
+            Json::Null => None,
+            other => Some(spans(other).ok_or("`groups` should be a list of spans or nulls")?),
+        };
+        let found = regex.captures(haystack).map(|captures| {
+            (0..=captures.groups.len())
+                .map(|i| captures.get(i).map(|m| (m.start, m.end)))
+                .collect::<Vec<_>>()
+        });
+        if found != expected {
+            return Err(format!("groups {:?}, expected {:?}", found, expected));
+        }
+    }
+
+    if let Some(replacement) = case.get("replace") {
+        let replacement = replacement.as_str().ok_or("`replace` should be a string")?;
+        let expected = case
+            .get("replaced")
+            .and_then(Json::as_str)
+            .ok_or("`replace` needs `replaced`")?;
+        let found = regex.replace_all(haystack, replacement);
+        if found != expected {
+            return Err(format!("replaced to {:?}, expected {:?}", found, expected));
+        }
+    }
+    Ok(())
+}
+
+// Reads a list of `[start, end]` spans, where `null` entries stand for groups that
+// didn't take part
+fn spans(value: &Json) -> Option<Vec<Option<(usize, usize)>>> {
+    let Json::Array(items) = value else {
+        return None;
+    };
+    items
+        .iter()
+        .map(|item| match item {
+            Json::Null => Some(None),
+            Json::Array(pair) => match pair.as_slice() {
+                [Json::Number(start), Json::Number(end)] => Some(Some((*start, *end))),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+// Just enough JSON for the case files: numbers are non-negative integers
+#[derive(Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(usize),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn parse(source: &str) -> Result<Json, String> {
+        let mut parser = JsonParser { source, pos: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        match parser.pos == source.len() {
+            true => Ok(value),
+            false => Err(parser.error("trailing characters")),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+struct JsonParser<'s> {
+    source: &'s str,
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: &str) -> String {
+        let line = self.source[..self.pos].matches('\n').count() + 1;
+        format!("line {}: {}", line, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(found) if found == c => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected `{}`", c))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        let rest = &self.source[self.pos..];
+        for (word, value) in [
+            ("null", Json::Null),
+            ("true", Json::Bool(true)),
+            ("false", Json::Bool(false)),
+        ] {
+            if rest.starts_with(word) {
+                self.pos += word.len();
+                return Ok(value);
+            }
+        }
+        match self.peek() {
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                self.list(']', |parser| {
+                    items.push(parser.value()?);
+                    Ok(())
+                })?;
+                Ok(Json::Array(items))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = vec![];
+                self.list('}', |parser| {
+                    parser.skip_whitespace();
+                    let key = parser.string()?;
+                    parser.expect(':')?;
+                    entries.push((key, parser.value()?));
+                    Ok(())
+                })?;
+                Ok(Json::Object(entries))
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let digits =
+                    rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                self.pos += digits;
+                rest[..digits]
+                    .parse()
+                    .map(Json::Number)
+                    .map_err(|_| self.error("number out of range"))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    // Reads comma-separated items up to `close`, after the opening bracket
+    fn list(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<(), String>,
+    ) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.error(&format!("expected `,` or `{}`", close))),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(text),
+                '\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    match escape {
+                        '"' | '\\' | '/' => text.push(escape),
+                        'n' => text.push('\n'),
+                        'r' => text.push('\r'),
+                        't' => text.push('\t'),
+                        'b' => text.push('\u{8}'),
+                        'f' => text.push('\u{c}'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // A surrogate pair spells out a character outside the BMP
+                            if (0xd800..0xdc00).contains(&code) {
+                                self.expect('\\')?;
+                                self.expect('u')?;
+                                let low = self.hex4()?;
+                                code = 0x10000
+                                    + ((code - 0xd800) << 10)
+                                    + (low.wrapping_sub(0xdc00) & 0x3ff);
+                            }
+                            text.push(
+                                char::from_u32(code)
+                                    .ok_or_else(|| self.error("invalid \\u escape"))?,
+                            );
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                _ => text.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .source
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid \\u escape"))
+    }
+}